@@ -0,0 +1,33 @@
+//! Compare and diff two arbitrary documents under a few configurations and
+//! check that the comparer never panics.
+//!
+//! Run with `cargo +nightly fuzz run compare`.
+#![no_main]
+
+use html_compare_rs::{presets, HtmlCompareOptions, HtmlComparer};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte picks the configuration, the rest splits in two
+    let Some((&config, data)) = data.split_first() else {
+        return;
+    };
+    let (expected, actual) = data.split_at(data.len() / 2);
+    let expected = String::from_utf8_lossy(expected);
+    let actual = String::from_utf8_lossy(actual);
+
+    let mut options = match config % 4 {
+        0 => HtmlCompareOptions::default(),
+        1 => presets::relaxed(),
+        2 => presets::strict(),
+        _ => presets::markdown(),
+    };
+    options.ignore_sibling_order = config & 0x10 != 0;
+    options.exhaustive = config & 0x20 != 0;
+    let comparer = HtmlComparer::with_options(options);
+    let _ = comparer.verify(&expected, &actual);
+    let _ = comparer.is_equal(&expected, &expected);
+    let diff = comparer.diff(&expected, &actual);
+    let _ = diff.edit_script();
+    let _ = diff.summary();
+});
@@ -0,0 +1,231 @@
+//! Counting differences across many comparisons.
+//!
+//! A nightly job comparing hundreds of pages wants one report of how far
+//! they drifted, not hundreds of diffs. A [`DiffSummary`] takes each
+//! [`HtmlDiff`] as it comes and keeps only counts: documents compared and
+//! differing, differences per [`DifferenceCategory`] and per path prefix,
+//! e.g. `/html/body/footer`, with indices dropped so that the same region
+//! of different pages adds up. [`HtmlDiff::merge`] keeps the differences
+//! themselves instead.
+//!
+//! ```text
+//! 412 differences in 37 of 250 documents: 12 structural, 380 attribute, 20 text
+//!   /html/body/footer: 300
+//!   /html/body/main: 112
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::diff::{DifferenceCategory, SUMMARY_LIMIT};
+use crate::HtmlDiff;
+
+/// Number of path steps, counting `html`, that prefixes keep by default
+pub const DEFAULT_PREFIX_DEPTH: usize = 3;
+
+/// Counts of the differences of many diffs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffSummary {
+    prefix_depth: usize,
+    documents: usize,
+    differing: usize,
+    categories: BTreeMap<DifferenceCategory, usize>,
+    prefixes: BTreeMap<String, usize>,
+}
+
+impl Default for DiffSummary {
+    fn default() -> Self {
+        DiffSummary::new()
+    }
+}
+
+impl DiffSummary {
+    /// An empty summary grouping paths by their first
+    /// [`DEFAULT_PREFIX_DEPTH`] steps
+    pub fn new() -> Self {
+        DiffSummary::with_prefix_depth(DEFAULT_PREFIX_DEPTH)
+    }
+
+    /// An empty summary grouping paths by their first `depth` steps
+    pub fn with_prefix_depth(depth: usize) -> Self {
+        DiffSummary {
+            prefix_depth: depth,
+            documents: 0,
+            differing: 0,
+            categories: BTreeMap::new(),
+            prefixes: BTreeMap::new(),
+        }
+    }
+
+    /// Count the differences of one more compared document
+    pub fn add(&mut self, diff: &HtmlDiff) {
+        self.documents += 1;
+        if !diff.is_empty() {
+            self.differing += 1;
+        }
+        for difference in diff {
+            *self.categories.entry(difference.category()).or_default() += 1;
+            *self
+                .prefixes
+                .entry(path_prefix(difference.path(), self.prefix_depth))
+                .or_default() += 1;
+        }
+    }
+
+    /// Number of documents added
+    pub fn documents(&self) -> usize {
+        self.documents
+    }
+
+    /// Number of documents added with at least one difference
+    pub fn differing_documents(&self) -> usize {
+        self.differing
+    }
+
+    /// Number of differences over all documents
+    pub fn total(&self) -> usize {
+        self.categories.values().sum()
+    }
+
+    /// Number of differences in `category` over all documents
+    pub fn count(&self, category: DifferenceCategory) -> usize {
+        self.categories.get(&category).copied().unwrap_or_default()
+    }
+
+    /// Path prefixes with their numbers of differences, most first
+    pub fn by_prefix(&self) -> Vec<(&str, usize)> {
+        let mut prefixes: Vec<_> = self
+            .prefixes
+            .iter()
+            .map(|(prefix, count)| (prefix.as_str(), *count))
+            .collect();
+        prefixes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        prefixes
+    }
+
+    /// A bounded report: the totals per category followed by the
+    /// [`SUMMARY_LIMIT`] prefixes with the most differences
+    pub fn report(&self) -> String {
+        let counts: Vec<_> = self
+            .categories
+            .iter()
+            .map(|(category, count)| format!("{} {}", count, category))
+            .collect();
+        let mut report = format!(
+            "{} difference{} in {} of {} document{}",
+            self.total(),
+            if self.total() == 1 { "" } else { "s" },
+            self.differing,
+            self.documents,
+            if self.documents == 1 { "" } else { "s" }
+        );
+        if !counts.is_empty() {
+            report.push_str(&format!(": {}", counts.join(", ")));
+        }
+        let prefixes = self.by_prefix();
+        for (prefix, count) in prefixes.iter().take(SUMMARY_LIMIT) {
+            report.push_str(&format!("\n  {}: {}", prefix, count));
+        }
+        if prefixes.len() > SUMMARY_LIMIT {
+            report.push_str(&format!(
+                "\n  ... and {} more prefixes",
+                prefixes.len() - SUMMARY_LIMIT
+            ));
+        }
+        report
+    }
+}
+
+impl fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.report())
+    }
+}
+
+impl<'a> Extend<&'a HtmlDiff> for DiffSummary {
+    fn extend<I: IntoIterator<Item = &'a HtmlDiff>>(&mut self, diffs: I) {
+        for diff in diffs {
+            self.add(diff);
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a HtmlDiff> for DiffSummary {
+    fn from_iter<I: IntoIterator<Item = &'a HtmlDiff>>(diffs: I) -> Self {
+        let mut summary = DiffSummary::new();
+        summary.extend(diffs);
+        summary
+    }
+}
+
+/// The first `depth` steps of `path`, without their indices
+fn path_prefix(path: &str, depth: usize) -> String {
+    path.split('/')
+        .filter(|step| !step.is_empty())
+        .take(depth)
+        .map(|step| step.split_once('[').map_or(step, |(name, _)| name))
+        .fold(String::new(), |prefix, step| prefix + "/" + step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlComparer;
+
+    fn page(main: &str, footer: &str) -> String {
+        format!(
+            r#"<main><p>{}</p></main><div class="x"></div><div><footer><p>{}</p></footer></div>"#,
+            main, footer
+        )
+    }
+
+    #[test]
+    fn test_diff_summary() {
+        let comparer = HtmlComparer::new();
+        let expected = page("Hello", "2024");
+        let diffs = [
+            comparer.diff(&expected, &page("Hello", "2025")),
+            comparer.diff(&expected, &page("Hi", "2025")),
+            comparer.diff(&expected, &expected),
+        ];
+
+        let summary: DiffSummary = diffs.iter().collect();
+        assert_eq!(summary.documents(), 3);
+        assert_eq!(summary.differing_documents(), 2);
+        assert_eq!(summary.total(), 3);
+        assert_eq!(summary.count(DifferenceCategory::Text), 3);
+        assert_eq!(
+            summary.by_prefix(),
+            [("/html/body/div", 2), ("/html/body/main", 1)]
+        );
+        assert_eq!(
+            summary.to_string(),
+            "3 differences in 2 of 3 documents: 3 text\n  /html/body/div: 2\n  /html/body/main: 1"
+        );
+
+        let mut deep = DiffSummary::with_prefix_depth(4);
+        deep.extend(&diffs);
+        assert_eq!(deep.by_prefix()[0], ("/html/body/div/footer", 2));
+        assert_eq!(
+            DiffSummary::new().report(),
+            "0 differences in 0 of 0 documents"
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let comparer = HtmlComparer::new();
+        let merged = HtmlDiff::merge([
+            comparer.diff("<p>a</p>", "<p>b</p>"),
+            comparer.diff("<p>a</p>", "<p>a</p>"),
+            comparer.diff("<p class='x'>c</p>", "<p>d</p>"),
+        ]);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged.count(DifferenceCategory::Attribute), 1);
+        assert!(merged.edit_script().is_empty());
+        assert!(merged
+            .summary()
+            .starts_with("3 differences: 1 attribute, 2 text"));
+        assert!(HtmlDiff::merge([]).is_empty());
+    }
+}
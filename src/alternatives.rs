@@ -0,0 +1,164 @@
+//! Alternative expected content within one fixture.
+//!
+//! Output that depends on the locale or a feature flag differs in a few
+//! places only. Instead of one fixture per variant, the expected document
+//! lists the acceptable variants of such a place:
+//!
+//! ```html
+//! <p>
+//!   <html-compare-alt>
+//!     <html-compare-variant>Hello</html-compare-variant>
+//!     <html-compare-variant>Bonjour</html-compare-variant>
+//!   </html-compare-alt>
+//! </p>
+//! ```
+//!
+//! The `<html-compare-alt>` element stands for the content of whichever of
+//! its variants matches the actual document, preferring the longest, and for
+//! the first variant if none does, so mismatches are reported against it. A
+//! variant may be empty to make content optional. Only expected documents
+//! are searched for alternatives. Like any element, `<html-compare-alt>` is
+//! moved out of tables by the HTML parser, so it cannot stand for rows or
+//! cells.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::{ChildNodes, HeadingLevels, HtmlComparer};
+
+/// Tag of the element listing alternatives
+pub const ALTERNATIVES_TAG: &str = "html-compare-alt";
+/// Tag of one alternative
+pub const VARIANT_TAG: &str = "html-compare-variant";
+
+impl HtmlComparer {
+    /// The compared children of `expected` and `actual`, with alternatives
+    /// among the expected children replaced by their matching variant
+    pub(crate) fn compared_children<'a>(
+        &self,
+        expected: ElementRef<'a>,
+        actual: ElementRef<'a>,
+        levels: HeadingLevels,
+    ) -> (ChildNodes<'a>, ChildNodes<'a>) {
+        let expected = self.child_nodes(expected);
+        let actual = self.child_nodes(actual);
+        if !expected.iter().any(|node| alternatives(*node).is_some()) {
+            return (expected, actual);
+        }
+        let mut resolved = ChildNodes::with_capacity(actual.len());
+        for node in expected {
+            let Some(alternatives) = alternatives(node) else {
+                resolved.push(node);
+                continue;
+            };
+            let variants: Vec<_> = alternatives
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|variant| variant.value().name() == VARIANT_TAG)
+                .map(|variant| self.child_nodes(variant))
+                .collect();
+            let start = resolved.len();
+            let chosen = variants
+                .iter()
+                .filter(|variant| self.variant_matches(variant, &actual, start, levels))
+                .max_by_key(|variant| variant.len())
+                .or(variants.first());
+            resolved.extend(chosen.into_iter().flatten().copied());
+        }
+        (resolved, actual)
+    }
+
+    /// Whether the nodes of `variant` match the actual children, starting at
+    /// `start` or, without sibling order, anywhere
+    fn variant_matches(
+        &self,
+        variant: &[NodeRef<Node>],
+        actual: &[NodeRef<Node>],
+        start: usize,
+        levels: HeadingLevels,
+    ) -> bool {
+        if self.options.ignore_sibling_order {
+            return variant.iter().all(|node| {
+                actual
+                    .iter()
+                    .any(|other| self.identical(*node, *other, levels))
+            });
+        }
+        actual
+            .get(start..start + variant.len())
+            .is_some_and(|actual| {
+                variant
+                    .iter()
+                    .zip(actual)
+                    .all(|(node, other)| self.identical(*node, *other, levels))
+            })
+    }
+}
+
+/// `node` as an element listing alternatives
+fn alternatives(node: NodeRef<Node>) -> Option<ElementRef> {
+    ElementRef::wrap(node).filter(|element| element.value().name() == ALTERNATIVES_TAG)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    const EXPECTED: &str = "<p>\
+        <html-compare-alt>\
+          <html-compare-variant>Hello</html-compare-variant>\
+          <html-compare-variant>Bonjour <b>!</b></html-compare-variant>\
+        </html-compare-alt>\
+        <a href='/'>home</a>\
+        <html-compare-alt>\
+          <html-compare-variant></html-compare-variant>\
+          <html-compare-variant><span class='beta'>beta</span></html-compare-variant>\
+        </html-compare-alt>\
+    </p>";
+
+    #[test]
+    fn test_alternatives() {
+        let comparer = HtmlComparer::new();
+        for actual in [
+            "<p>Hello<a href='/'>home</a></p>",
+            "<p>Bonjour <b>!</b><a href='/'>home</a></p>",
+            "<p>Hello<a href='/'>home</a><span class='beta'>beta</span></p>",
+        ] {
+            comparer.verify(EXPECTED, actual).unwrap();
+        }
+        let err = comparer
+            .verify(EXPECTED, "<p>Hallo<a href='/'>home</a></p>")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Expected: 'Hello', Actual: 'Hallo'"));
+        assert!(!comparer
+            .is_equal(
+                EXPECTED,
+                "<p>Hello<a href='/'>home</a><span>alpha</span></p>"
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn test_alternatives_in_diff_and_unordered() {
+        let comparer = HtmlComparer::new();
+        assert!(comparer
+            .diff(EXPECTED, "<p>Bonjour <b>!</b><a href='/'>home</a></p>")
+            .is_empty());
+        assert!(!comparer
+            .diff(EXPECTED, "<p>Bonjour <b>?</b><a href='/'>home</a></p>")
+            .is_empty());
+
+        let unordered = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_sibling_order: true,
+            ..Default::default()
+        });
+        unordered
+            .verify(
+                "<ul><li>a</li><html-compare-alt><html-compare-variant><li>b</li></html-compare-variant><html-compare-variant><li>c</li></html-compare-variant></html-compare-alt></ul>",
+                "<ul><li>c</li><li>a</li></ul>",
+            )
+            .unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+//! Files written for post-mortems of failed assertions.
+//!
+//! A panic message in a CI log shows the first mismatch, but investigating
+//! a failure usually needs both documents and every difference. With
+//! [`HtmlCompareOptions::dump_on_failure`](crate::HtmlCompareOptions::dump_on_failure)
+//! set to a directory, [`assert_html_eq!`](crate::assert_html_eq) and
+//! [`assert_template_renders!`](crate::assert_template_renders) write three
+//! files there when they fail, named after the running test:
+//!
+//! - `<test>.expected.html` and `<test>.actual.html`, both documents in
+//!   their compared form: normalized as the options say and formatted one
+//!   element per line
+//! - `<test>.diff.json`, every [`Difference`] with its path, category and
+//!   message
+//!
+//! The test name is the name of the current thread, which the standard test
+//! harness sets to the test's path, e.g. `pages::tests::test_home`.
+//! Pointing CI artifact collection at the directory keeps them around.
+
+use std::io;
+use std::path::PathBuf;
+
+use serde_json::{Map, Value};
+
+use crate::{format_html, FormatOptions, HtmlComparer, HtmlDiff};
+
+/// Name of the artifacts written outside of a named test thread
+const FALLBACK_NAME: &str = "html-compare";
+
+impl HtmlComparer {
+    /// Write the compared forms of `expected` and `actual` and their
+    /// differences to the `dump_on_failure` directory, returning the files
+    /// written, none if the option is unset
+    pub fn dump_failure(&self, expected: &str, actual: &str) -> io::Result<Vec<PathBuf>> {
+        let Some(directory) = &self.options.dump_on_failure else {
+            return Ok(Vec::new());
+        };
+        std::fs::create_dir_all(directory)?;
+        let name = artifact_name(std::thread::current().name());
+        let files = [
+            ("expected.html", self.canonical_html(expected)),
+            ("actual.html", self.canonical_html(actual)),
+            (
+                "diff.json",
+                serde_json::to_string_pretty(&diff_to_json(&self.diff(expected, actual)))
+                    .map_err(io::Error::other)?,
+            ),
+        ];
+        let mut written = Vec::with_capacity(files.len());
+        for (suffix, contents) in files {
+            let path = directory.join(format!("{}.{}", name, suffix));
+            std::fs::write(&path, contents)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /// `html` as compared, one element per line
+    fn canonical_html(&self, html: &str) -> String {
+        let document = self.parse(html);
+        let root = self
+            .compared_root(&document, "")
+            .unwrap_or_else(|_| document.root_element());
+        let format = FormatOptions {
+            attribute_order: self.options.attribute_order.clone(),
+            ..Default::default()
+        };
+        format_html(&self.serialize(*root), &format)
+    }
+}
+
+/// The differences of `diff` as JSON
+fn diff_to_json(diff: &HtmlDiff) -> Value {
+    let differences: Vec<Value> = diff
+        .iter()
+        .map(|difference| {
+            let mut object = Map::new();
+            object.insert("path".to_string(), Value::from(difference.path()));
+            object.insert(
+                "category".to_string(),
+                Value::from(difference.category().to_string()),
+            );
+            object.insert("message".to_string(), Value::from(difference.to_string()));
+            Value::Object(object)
+        })
+        .collect();
+    let mut object = Map::new();
+    object.insert("differences".to_string(), Value::Array(differences));
+    object.insert("summary".to_string(), Value::from(diff.summary()));
+    Value::Object(object)
+}
+
+/// A file name for the artifacts of the test named `test`
+fn artifact_name(test: Option<&str>) -> String {
+    match test {
+        Some(test) if test != "main" => test
+            .replace("::", ".")
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+                _ => '_',
+            })
+            .collect(),
+        _ => FALLBACK_NAME.to_string(),
+    }
+}
+
+/// The panic message line about the artifacts written by `dumped`
+#[doc(hidden)]
+pub fn __note(dumped: io::Result<Vec<PathBuf>>) -> String {
+    match dumped {
+        Ok(files) if files.is_empty() => String::new(),
+        Ok(files) => {
+            let files: Vec<_> = files
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect();
+            format!("\n\nartifacts written to: {}", files.join(", "))
+        }
+        Err(err) => format!("\n\nfailed to write artifacts: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    #[test]
+    fn test_dump_failure() {
+        let directory =
+            std::env::temp_dir().join(format!("html-compare-artifacts-{}", std::process::id()));
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            dump_on_failure: Some(directory.clone()),
+            ..Default::default()
+        });
+        let files = comparer
+            .dump_failure("<p class='a'>Hi</p>", "<p>Bye</p>")
+            .unwrap();
+        let name = "artifacts.tests.test_dump_failure";
+        assert_eq!(
+            files,
+            [
+                directory.join(format!("{}.expected.html", name)),
+                directory.join(format!("{}.actual.html", name)),
+                directory.join(format!("{}.diff.json", name)),
+            ]
+        );
+        let expected = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(expected.contains("<p class=\"a\">Hi</p>"), "{}", expected);
+        let diff: Value =
+            serde_json::from_str(&std::fs::read_to_string(&files[2]).unwrap()).unwrap();
+        assert_eq!(diff["differences"].as_array().unwrap().len(), 2);
+        assert_eq!(diff["differences"][0]["category"], "attribute");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(HtmlComparer::new()
+            .dump_failure("<p>", "")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_artifact_name() {
+        assert_eq!(
+            artifact_name(Some("pages::tests::test_home")),
+            "pages.tests.test_home"
+        );
+        assert_eq!(artifact_name(Some("case #1 / 2")), "case__1___2");
+        assert_eq!(artifact_name(Some("main")), FALLBACK_NAME);
+        assert_eq!(artifact_name(None), FALLBACK_NAME);
+    }
+}
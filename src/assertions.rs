@@ -0,0 +1,201 @@
+//! Soft assertions that report every failing comparison of a test at once.
+//!
+//! [`assert_html_eq!`](crate::assert_html_eq) panics at the first failing
+//! case of a table-driven test, hiding whether the others pass.
+//! [`HtmlAssertions`] records failures instead and panics once, listing all
+//! of them, when [`HtmlAssertions::finish`] is called:
+//!
+//! ```ignore
+//! let mut assertions = HtmlAssertions::new();
+//! for (input, expected) in CASES {
+//!     assertions.check_eq(expected, render(input), &HtmlCompareOptions::default());
+//! }
+//! assertions.finish();
+//! ```
+//!
+//! A collector dropped with unreported failures panics as well, so a
+//! forgotten `finish` doesn't let a test pass.
+
+use std::fmt;
+use std::panic::Location;
+
+use crate::markup::RenderToHtml;
+use crate::{HtmlCompareOptions, HtmlComparer};
+
+/// One failed check of an [`HtmlAssertions`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Position of the check among all checks, starting at 1
+    pub check: usize,
+    /// Source location of the check
+    pub location: String,
+    /// Why the check failed
+    pub message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "check #{} at {}: {}",
+            self.check, self.location, self.message
+        )
+    }
+}
+
+/// Collects the failures of HTML comparisons to report them together
+#[derive(Debug, Default)]
+pub struct HtmlAssertions {
+    checks: usize,
+    failures: Vec<Failure>,
+}
+
+impl HtmlAssertions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a failure unless `left` and `right` are equivalent under
+    /// `options`, returning whether they are
+    #[track_caller]
+    pub fn check_eq(
+        &mut self,
+        left: impl RenderToHtml,
+        right: impl RenderToHtml,
+        options: &HtmlCompareOptions,
+    ) -> bool {
+        let (left, right) = (left.render_to_html(), right.render_to_html());
+        let comparer = HtmlComparer::with_options(options.clone());
+        let message = comparer.verify(&left, &right).err().map(|err| {
+            let message = comparer
+                .failure_report(&left, &right)
+                .unwrap_or_else(|| err.to_string());
+            message + &crate::artifacts::__note(comparer.dump_failure(&left, &right))
+        });
+        self.record(message)
+    }
+
+    /// Record a failure if `left` and `right` are equivalent under
+    /// `options`, returning whether they differ
+    #[track_caller]
+    pub fn check_ne(
+        &mut self,
+        left: impl RenderToHtml,
+        right: impl RenderToHtml,
+        options: &HtmlCompareOptions,
+    ) -> bool {
+        let (left, right) = (left.render_to_html(), right.render_to_html());
+        let comparer = HtmlComparer::with_options(options.clone());
+        let message = comparer.verify(&left, &right).is_ok().then(|| {
+            format!(
+                "HTML strings were equal but expected to be different: {}",
+                left
+            )
+        });
+        self.record(message)
+    }
+
+    /// The failures recorded so far
+    pub fn failures(&self) -> &[Failure] {
+        &self.failures
+    }
+
+    /// Number of checks made so far
+    pub fn checks(&self) -> usize {
+        self.checks
+    }
+
+    /// Panic if any check failed, listing every failure
+    #[track_caller]
+    pub fn finish(mut self) {
+        let failures = std::mem::take(&mut self.failures);
+        if !failures.is_empty() {
+            panic!("{}", report(self.checks, &failures));
+        }
+    }
+
+    #[track_caller]
+    fn record(&mut self, message: Option<String>) -> bool {
+        self.checks += 1;
+        let Some(message) = message else {
+            return true;
+        };
+        self.failures.push(Failure {
+            check: self.checks,
+            location: Location::caller().to_string(),
+            message,
+        });
+        false
+    }
+}
+
+impl Drop for HtmlAssertions {
+    fn drop(&mut self) {
+        if !self.failures.is_empty() && !std::thread::panicking() {
+            panic!(
+                "{}\n\n(HtmlAssertions dropped without calling finish())",
+                report(self.checks, &self.failures)
+            );
+        }
+    }
+}
+
+fn report(checks: usize, failures: &[Failure]) -> String {
+    let mut report = format!(
+        "\n{} of {} HTML comparisons failed:\n",
+        failures.len(),
+        checks
+    );
+    for failure in failures {
+        report.push_str(&format!("\n{}\n", failure));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collects_failures() {
+        let options = HtmlCompareOptions::default();
+        let mut assertions = HtmlAssertions::new();
+        assert!(assertions.check_eq("<p>a</p>", "<p>a</p>", &options));
+        assert!(!assertions.check_eq("<p>a</p>", "<p>b</p>", &options));
+        assert!(!assertions.check_ne("<p>c</p>", "<p> c </p>", &options));
+        assert_eq!(assertions.checks(), 3);
+        let failures = assertions.failures().to_vec();
+        assert_eq!(
+            failures
+                .iter()
+                .map(|failure| failure.check)
+                .collect::<Vec<_>>(),
+            [2, 3]
+        );
+        assert!(failures[0].location.starts_with("src/assertions.rs:"));
+        assert!(failures[0].message.contains("Expected: 'a', Actual: 'b'"));
+
+        let panic = std::panic::catch_unwind(move || assertions.finish()).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(
+            message.contains("2 of 3 HTML comparisons failed"),
+            "{}",
+            message
+        );
+        assert!(message.contains(&failures[1].to_string()), "{}", message);
+    }
+
+    #[test]
+    fn test_finish_without_failures() {
+        let mut assertions = HtmlAssertions::new();
+        assertions.check_eq("<p>a</p>", "<p>a</p>", &HtmlCompareOptions::default());
+        assertions.finish();
+    }
+
+    #[test]
+    #[should_panic(expected = "dropped without calling finish()")]
+    fn test_drop_reports_failures() {
+        let mut assertions = HtmlAssertions::new();
+        assertions.check_eq("<p>a</p>", "<p>b</p>", &HtmlCompareOptions::default());
+    }
+}
@@ -0,0 +1,244 @@
+//! Existence checks for assets referenced by the actual document.
+//!
+//! Static-site tests often compare rendered pages against fixtures while the
+//! images, stylesheets and pages they point to are produced by the same
+//! build. Setting [`HtmlCompareOptions::asset_resolver`](crate::HtmlCompareOptions)
+//! makes the comparison also report `src`/`href` targets that do not exist.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use scraper::Html;
+
+use crate::{Difference, HtmlComparer};
+
+/// Decides whether a referenced URL exists
+#[derive(Clone)]
+pub enum AssetResolver {
+    /// Resolve local references against a directory, e.g. a static site's
+    /// output folder
+    ///
+    /// Root-relative and relative URLs are both looked up from the directory,
+    /// and URLs of directories must contain an `index.html`. URLs with a
+    /// scheme (`https:`, `mailto:`, `data:`...), scheme-relative URLs and
+    /// fragment-only links are not checked.
+    Directory(PathBuf),
+    /// Ask a callback about every reference, as written in the document
+    Callback(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+impl AssetResolver {
+    /// Resolve references against the files in `root`
+    pub fn directory(root: impl Into<PathBuf>) -> Self {
+        AssetResolver::Directory(root.into())
+    }
+
+    /// Resolve references with `exists`
+    pub fn callback(exists: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        AssetResolver::Callback(Arc::new(exists))
+    }
+
+    /// Whether the reference `url` points to an existing asset
+    pub fn exists(&self, url: &str) -> bool {
+        match self {
+            AssetResolver::Callback(exists) => exists(url),
+            AssetResolver::Directory(root) => {
+                if url.is_empty()
+                    || url.starts_with('#')
+                    || url.starts_with("//")
+                    || has_scheme(url)
+                {
+                    return true;
+                }
+                let path = url.split(['?', '#']).next().unwrap_or_default();
+                let path = percent_decode(path);
+                let file = root.join(path.trim_start_matches('/'));
+                if path.ends_with('/') || file.is_dir() {
+                    file.join("index.html").is_file()
+                } else {
+                    file.is_file()
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for AssetResolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetResolver::Directory(root) => f.debug_tuple("Directory").field(root).finish(),
+            AssetResolver::Callback(_) => f.write_str("Callback(..)"),
+        }
+    }
+}
+
+/// Attributes holding a single URL, by element
+const URL_ATTRIBUTES: &[(&str, &str)] = &[
+    ("a", "href"),
+    ("area", "href"),
+    ("link", "href"),
+    ("audio", "src"),
+    ("embed", "src"),
+    ("iframe", "src"),
+    ("img", "src"),
+    ("input", "src"),
+    ("script", "src"),
+    ("source", "src"),
+    ("track", "src"),
+    ("video", "src"),
+    ("video", "poster"),
+    ("object", "data"),
+];
+
+impl HtmlComparer {
+    /// Report every reference in `document` that the configured
+    /// [`AssetResolver`] cannot find
+    pub(crate) fn broken_references(&self, document: &Html) -> Vec<Difference> {
+        let Some(resolver) = &self.options.asset_resolver else {
+            return Vec::new();
+        };
+
+        let mut differences = Vec::new();
+        self.walk_elements(document.root_element(), "/html", &mut |element, path| {
+            let el = element.value();
+            let mut check = |attribute: &str, target: &str| {
+                let target = target.trim();
+                if !resolver.exists(target) {
+                    differences.push(Difference::BrokenReference {
+                        path: path.to_string(),
+                        attribute: attribute.to_string(),
+                        target: target.to_string(),
+                    });
+                }
+            };
+
+            for (_, attribute) in URL_ATTRIBUTES.iter().filter(|(tag, _)| *tag == el.name()) {
+                if let Some(target) = el.attr(attribute) {
+                    check(attribute, target);
+                }
+            }
+            if matches!(el.name(), "img" | "source") {
+                if let Some(srcset) = el.attr("srcset") {
+                    for candidate in srcset.split(',') {
+                        if let Some(url) = candidate.split_whitespace().next() {
+                            check("srcset", url);
+                        }
+                    }
+                }
+            }
+        });
+        differences
+    }
+}
+
+fn has_scheme(url: &str) -> bool {
+    match url.find(':') {
+        Some(index) if index > 0 => url[..index]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')),
+        _ => false,
+    }
+}
+
+pub(crate) fn percent_decode(path: &str) -> String {
+    String::from_utf8_lossy(&percent_decode_bytes(path)).into_owned()
+}
+
+/// The bytes `text` stands for, with `%` followed by two hex digits decoded
+/// and anything else, including other `%`s, left as it is
+pub(crate) fn percent_decode_bytes(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareError, HtmlCompareOptions};
+
+    #[test]
+    fn test_directory_resolver() {
+        let root = std::env::temp_dir().join(format!("html-compare-assets-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("img")).unwrap();
+        std::fs::create_dir_all(root.join("blog")).unwrap();
+        std::fs::write(root.join("img/logo image.png"), b"").unwrap();
+        std::fs::write(root.join("blog/index.html"), b"").unwrap();
+
+        let resolver = AssetResolver::directory(&root);
+        assert!(resolver.exists("/img/logo%20image.png?v=3"));
+        assert!(resolver.exists("img/logo image.png"));
+        assert!(resolver.exists("/blog/"));
+        assert!(resolver.exists("/blog"));
+        assert!(resolver.exists("https://example.com/missing.png"));
+        assert!(resolver.exists("#top"));
+        assert!(!resolver.exists("/img/missing.png"));
+        assert!(!resolver.exists("/about/"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_broken_references_are_reported() {
+        let options = HtmlCompareOptions {
+            asset_resolver: Some(AssetResolver::callback(|url| !url.contains("missing"))),
+            ..Default::default()
+        };
+        let html = r#"<img src="/ok.png" srcset="/ok-2x.png 2x, /missing-3x.png 3x">
+            <p><a href="/missing.html">Gone</a></p>"#;
+
+        let diff = HtmlComparer::with_options(options.clone()).diff(html, html);
+        assert_eq!(
+            diff.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                Difference::BrokenReference {
+                    path: "/html/body/img".to_string(),
+                    attribute: "srcset".to_string(),
+                    target: "/missing-3x.png".to_string(),
+                },
+                Difference::BrokenReference {
+                    path: "/html/body/p/a".to_string(),
+                    attribute: "href".to_string(),
+                    target: "/missing.html".to_string(),
+                },
+            ]
+        );
+
+        let result = HtmlComparer::with_options(options).verify(html, html);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            r#"Broken reference: /html/body/img: srcset="/missing-3x.png""#
+        );
+        assert!(matches!(
+            HtmlComparer::with_options(HtmlCompareOptions {
+                asset_resolver: Some(AssetResolver::callback(|_| false)),
+                ..Default::default()
+            })
+            .verify("<p>Same</p>", "<p>Same</p>"),
+            Ok(())
+        ));
+        assert!(matches!(
+            HtmlComparer::new().verify("<img src='x.png'>", "<img src='y.png'>"),
+            Err(HtmlCompareError::AttributeMismatch { .. })
+        ));
+    }
+}
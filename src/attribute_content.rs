@@ -0,0 +1,228 @@
+//! Attributes whose values are documents in another language.
+//!
+//! `data-config` often carries JSON, `srcdoc` a whole HTML document and
+//! `style` a CSS declaration list. Comparing those values as strings fails on
+//! key order, indentation or a trailing `;`.
+//! [`HtmlCompareOptions::attribute_content`](crate::HtmlCompareOptions) maps
+//! attribute names to an [`AttributeContent`], and the value is parsed and
+//! brought into a canonical form before it is compared. Values that fail to
+//! parse are compared as written.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::HtmlComparer;
+
+/// Normalizer of an [`AttributeContent::Custom`] type
+pub type ContentNormalizer = dyn Fn(&str) -> String + Send + Sync;
+
+/// The language of an attribute value
+#[derive(Clone)]
+pub enum AttributeContent {
+    /// JSON, compared as values so key order and formatting are ignored
+    Json,
+    /// An HTML document, compared with the same options as the page itself
+    Html,
+    /// A CSS declaration list, ignoring whitespace, empty declarations and
+    /// the case of property names
+    Css,
+    /// A type registered with [`AttributeContent::custom`]
+    Custom {
+        name: String,
+        normalize: Arc<ContentNormalizer>,
+    },
+}
+
+impl AttributeContent {
+    /// A type named `name` whose values compare equal when `normalize` maps
+    /// them to the same string
+    pub fn custom(
+        name: impl Into<String>,
+        normalize: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        AttributeContent::Custom {
+            name: name.into(),
+            normalize: Arc::new(normalize),
+        }
+    }
+
+    /// Look up a built-in type by name (`"json"`, `"html"` or `"css"`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(AttributeContent::Json),
+            "html" => Some(AttributeContent::Html),
+            "css" => Some(AttributeContent::Css),
+            _ => None,
+        }
+    }
+
+    /// The name of the type
+    pub fn name(&self) -> &str {
+        match self {
+            AttributeContent::Json => "json",
+            AttributeContent::Html => "html",
+            AttributeContent::Css => "css",
+            AttributeContent::Custom { name, .. } => name,
+        }
+    }
+
+    /// Bring `value` into the canonical form compared by `comparer`
+    pub(crate) fn normalize(&self, comparer: &HtmlComparer, value: &str) -> String {
+        match self {
+            AttributeContent::Json => match serde_json::from_str::<serde_json::Value>(value) {
+                Ok(json) => json.to_string(),
+                Err(_) => value.to_string(),
+            },
+            AttributeContent::Html => {
+                let document = comparer.parse(value);
+                let root = document.root_element();
+                let top_heading = comparer.top_heading_level(*root);
+                crate::json::element_to_json(comparer, &comparer.options, root, top_heading)
+                    .to_string()
+            }
+            AttributeContent::Css => normalize_declarations(value),
+            AttributeContent::Custom { normalize, .. } => normalize(value),
+        }
+    }
+}
+
+impl fmt::Debug for AttributeContent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AttributeContent::Custom { name, .. } => write!(f, "Custom({:?})", name),
+            content => f.write_str(content.name()),
+        }
+    }
+}
+
+/// Canonical form of a CSS declaration list such as a `style` attribute
+fn normalize_declarations(value: &str) -> String {
+    split_outside_nesting(value, ';')
+        .into_iter()
+        .filter_map(|declaration| {
+            let declaration = declaration.trim();
+            if declaration.is_empty() {
+                return None;
+            }
+            let Some((property, value)) = declaration.split_once(':') else {
+                return Some(collapse_whitespace(declaration));
+            };
+            Some(format!(
+                "{}:{}",
+                property.trim().to_ascii_lowercase(),
+                collapse_whitespace(value)
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Split on `delimiter` where it is not inside quotes or parentheses, so
+/// `url("a;b")` stays one piece
+fn split_outside_nesting(value: &str, delimiter: char) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut start = 0;
+    for (index, c) in value.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth = depth.saturating_sub(1),
+            (None, c) if c == delimiter && depth == 0 => {
+                pieces.push(&value[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    pieces.push(&value[start..]);
+    pieces
+}
+
+fn collapse_whitespace(value: &str) -> String {
+    value.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    fn comparer(attribute: &str, content: AttributeContent) -> HtmlComparer {
+        let mut options = HtmlCompareOptions::default();
+        options
+            .attribute_content
+            .insert(attribute.to_string(), content);
+        HtmlComparer::with_options(options)
+    }
+
+    #[test]
+    fn test_json_and_css_attributes() {
+        let json = comparer("data-config", AttributeContent::Json);
+        json.verify(
+            r#"<div data-config='{"a": 1, "b": [true, null]}'></div>"#,
+            r#"<div data-config='{"b":[true,null],"a":1}'></div>"#,
+        )
+        .unwrap();
+        assert!(!json
+            .is_equal(
+                r#"<div data-config='{"a": 1}'></div>"#,
+                r#"<div data-config='{"a": 2}'></div>"#,
+            )
+            .unwrap());
+
+        let css = comparer("style", AttributeContent::Css);
+        css.verify(
+            r#"<p style="COLOR: red;  margin : 0 auto;"></p>"#,
+            r#"<p style="color:red;margin:0   auto"></p>"#,
+        )
+        .unwrap();
+        assert!(!css
+            .is_equal(
+                r#"<p style="color: red"></p>"#,
+                r#"<p style="color: blue"></p>"#,
+            )
+            .unwrap());
+        assert_eq!(
+            normalize_declarations(r#"background: url("a;b.png") ; ;"#),
+            r#"background:url("a;b.png")"#
+        );
+    }
+
+    #[test]
+    fn test_html_attributes() {
+        let html = comparer("srcdoc", AttributeContent::Html);
+        html.verify(
+            r#"<iframe srcdoc="<p class='a'>Hi</p>"></iframe>"#,
+            r#"<iframe srcdoc="<html><body><p   class=a>Hi</p></body></html>"></iframe>"#,
+        )
+        .unwrap();
+        assert!(!html
+            .is_equal(
+                r#"<iframe srcdoc="<p>Hi</p>"></iframe>"#,
+                r#"<iframe srcdoc="<p>Bye</p>"></iframe>"#,
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn test_custom_content() {
+        let numbers = AttributeContent::custom("number", |value| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_or(value.to_string(), |n| n.to_string())
+        });
+        assert_eq!(format!("{:?}", numbers), r#"Custom("number")"#);
+        let comparer = comparer("data-price", numbers);
+        comparer
+            .verify(
+                r#"<span data-price="1.50"></span>"#,
+                r#"<span data-price=" 1.5"></span>"#,
+            )
+            .unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+//! Attribute names that only a parser oddity can produce.
+//!
+//! The HTML parser accepts almost anything as an attribute name, so a
+//! template that wasn't rendered, `<div {{class}}>`, or a stray quote,
+//! `<a href="x""title="y">`, turns into attributes named `{{class}}` or
+//! `"title`, and the comparison then reports attribute sets that differ in
+//! ways that are hard to trace back. With
+//! [`HtmlCompareOptions::strict_attribute_names`](crate::HtmlCompareOptions::strict_attribute_names)
+//! such names are reported up front: [`HtmlComparer::verify`] fails with
+//! [`HtmlCompareError::InvalidAttributeName`] before comparing, and
+//! [`HtmlComparer::diff`] lists them as
+//! [`Warning::InvalidAttributeName`](crate::diff::Warning::InvalidAttributeName),
+//! for both documents.
+//!
+//! A name is flagged when it is empty or holds whitespace, control
+//! characters, quotes, `<`, `>`, `/`, `=`, a backtick or the `{`, `}` and
+//! `%` of template delimiters.
+//!
+//! [`HtmlCompareError::InvalidAttributeName`]: crate::HtmlCompareError::InvalidAttributeName
+
+use scraper::Html;
+
+use crate::diff::Warning;
+use crate::{HtmlCompareError, HtmlComparer};
+
+/// Whether `name` can't be the name of an attribute written on purpose
+pub(crate) fn is_invalid_attribute_name(name: &str) -> bool {
+    name.is_empty()
+        || name.chars().any(|c| {
+            c.is_whitespace()
+                || c.is_control()
+                || matches!(
+                    c,
+                    '"' | '\'' | '<' | '>' | '/' | '=' | '`' | '{' | '}' | '%'
+                )
+        })
+}
+
+impl HtmlComparer {
+    /// The invalid attribute names of `document` along with the paths of
+    /// their elements
+    fn invalid_attribute_names(&self, document: &Html) -> Vec<(String, String)> {
+        let mut names = Vec::new();
+        self.walk_elements(document.root_element(), "/html", &mut |element, path| {
+            let mut invalid: Vec<_> = element
+                .value()
+                .attrs()
+                .map(|(name, _)| name)
+                .filter(|name| is_invalid_attribute_name(name))
+                .collect();
+            invalid.sort_unstable();
+            names.extend(
+                invalid
+                    .into_iter()
+                    .map(|name| (path.to_string(), name.to_string())),
+            );
+        });
+        names
+    }
+
+    /// Fail with the first invalid attribute name of `expected`, then of
+    /// `actual`
+    pub(crate) fn check_attribute_names(
+        &self,
+        expected: &Html,
+        actual: &Html,
+    ) -> Result<(), HtmlCompareError> {
+        if !self.options.strict_attribute_names {
+            return Ok(());
+        }
+        for (document, html) in [("expected", expected), ("actual", actual)] {
+            if let Some((path, name)) = self.invalid_attribute_names(html).into_iter().next() {
+                return Err(HtmlCompareError::InvalidAttributeName {
+                    document,
+                    path,
+                    name,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The invalid attribute names of both documents as warnings
+    pub(crate) fn attribute_name_warnings(&self, expected: &Html, actual: &Html) -> Vec<Warning> {
+        if !self.options.strict_attribute_names {
+            return Vec::new();
+        }
+        [("expected", expected), ("actual", actual)]
+            .into_iter()
+            .flat_map(|(document, html)| {
+                self.invalid_attribute_names(html)
+                    .into_iter()
+                    .map(move |(path, name)| Warning::InvalidAttributeName {
+                        document,
+                        path,
+                        name,
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    #[test]
+    fn test_is_invalid_attribute_name() {
+        for name in [
+            "class",
+            "data-id",
+            "aria-label",
+            "xlink:href",
+            "@click",
+            ":class",
+        ] {
+            assert!(!is_invalid_attribute_name(name), "{}", name);
+        }
+        for name in ["{{class}}", "\"title", "a=b", "{%", "%}", "`x`", ""] {
+            assert!(is_invalid_attribute_name(name), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_strict_attribute_names() {
+        let expected = r#"<div class="card"><p>Hi</p></div>"#;
+        let actual = r#"<div class="card" {{extra}}><p>Hi</p></div>"#;
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            strict_attribute_names: true,
+            ..Default::default()
+        });
+        comparer.verify(expected, expected).unwrap();
+        match comparer.verify(expected, actual) {
+            Err(HtmlCompareError::InvalidAttributeName {
+                document,
+                path,
+                name,
+            }) => {
+                assert_eq!((document, path.as_str()), ("actual", "/html/body/div"));
+                assert_eq!(name, "{{extra}}");
+            }
+            other => panic!("expected an invalid attribute name, got {:?}", other),
+        }
+        assert!(matches!(
+            comparer.verify(r#"<a href="x""title="y">A</a>"#, "<a>A</a>"),
+            Err(HtmlCompareError::InvalidAttributeName {
+                document: "expected",
+                ..
+            })
+        ));
+        assert!(matches!(
+            HtmlComparer::new().verify(expected, actual),
+            Err(HtmlCompareError::AttributeMismatch { .. })
+        ));
+
+        let diff = comparer.diff(expected, actual);
+        assert_eq!(
+            diff.warnings()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [r#"/html/body/div: invalid attribute name "{{extra}}" in the actual document"#]
+        );
+    }
+}
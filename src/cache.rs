@@ -0,0 +1,429 @@
+//! Caching of comparison results across tests.
+//!
+//! Parameterized test suites often compare the same fixtures under the same
+//! options many times. A [`ComparisonCache`] set as
+//! [`HtmlCompareOptions::cache`](crate::HtmlCompareOptions) remembers which
+//! comparisons succeeded, keyed by a hash of the expected document, the
+//! actual document and the options, so repeating one is free. Only
+//! successes are cached: a failing comparison is run again to report its
+//! differences. The cache lives in memory, optionally backed by a file so it
+//! survives between test runs.
+//!
+//! Options are hashed through their `Debug` output, with the contents of
+//! sets and maps sorted so that the same options always hash the same.
+//! Closures don't show in that output, so comparisons that run code of the
+//! caller's are never cached: those with an
+//! [`AssetResolver`](crate::AssetResolver), matchers, a custom parser,
+//! custom attribute content or lint rules, an `on_node_compared` hook or an
+//! error formatter. The version of this crate is hashed along with the
+//! options, so upgrading it doesn't answer from results of the old one.
+//! Keys are 64-bit hashes from the standard library's hasher; a toolchain
+//! upgrade may change them, which only costs misses.
+//!
+//! A backing file holds at most [`MAX_FILE_ENTRIES`] keys: past that it is
+//! started over, which drops the keys of stale fixtures and old versions.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::{AttributeContent, HtmlCompareOptions, HtmlComparer, LintRule};
+
+/// Hashes of the expected document, the actual document and the options
+type CacheKey = (u64, u64, u64);
+
+/// Number of keys a backing file holds before it is started over
+pub const MAX_FILE_ENTRIES: usize = 100_000;
+
+/// A shared set of comparisons known to succeed
+///
+/// Clones share the same entries.
+#[derive(Clone, Default)]
+pub struct ComparisonCache {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: Mutex<HashSet<CacheKey>>,
+    file: Option<PathBuf>,
+    /// Keys in the backing file, which may differ from those in memory
+    file_entries: AtomicUsize,
+    /// Keys the backing file holds before it is started over
+    file_capacity: usize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl ComparisonCache {
+    /// An empty cache kept in memory
+    pub fn in_memory() -> Self {
+        ComparisonCache::default()
+    }
+
+    /// A cache loaded from `path` that appends new entries to it, creating
+    /// the file when it doesn't exist and starting it over once it holds
+    /// [`MAX_FILE_ENTRIES`] keys
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        ComparisonCache::open_with_capacity(path.as_ref(), MAX_FILE_ENTRIES)
+    }
+
+    fn open_with_capacity(path: &Path, file_capacity: usize) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let entries = contents.lines().filter_map(parse_key).collect();
+        Ok(ComparisonCache {
+            inner: Arc::new(Inner {
+                entries: Mutex::new(entries),
+                file: Some(path.to_path_buf()),
+                file_entries: AtomicUsize::new(contents.lines().count()),
+                file_capacity,
+                ..Default::default()
+            }),
+        })
+    }
+
+    /// The in-memory cache shared by the whole process, e.g. by every test
+    /// of a test binary
+    pub fn global() -> &'static ComparisonCache {
+        static GLOBAL: OnceLock<ComparisonCache> = OnceLock::new();
+        GLOBAL.get_or_init(ComparisonCache::in_memory)
+    }
+
+    /// Number of comparisons answered from the cache
+    pub fn hits(&self) -> usize {
+        self.inner.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of cacheable comparisons that had to be run
+    pub fn misses(&self) -> usize {
+        self.inner.misses.load(Ordering::Relaxed)
+    }
+
+    /// Number of cached comparisons
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every cached comparison, including those in the backing file
+    pub fn clear(&self) -> io::Result<()> {
+        self.entries().clear();
+        self.inner.file_entries.store(0, Ordering::Relaxed);
+        match &self.inner.file {
+            Some(path) => fs::write(path, ""),
+            None => Ok(()),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashSet<CacheKey>> {
+        // A panic while holding the lock can't leave the set inconsistent
+        self.inner
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn contains(&self, key: CacheKey) -> bool {
+        let found = self.entries().contains(&key);
+        let counter = match found {
+            true => &self.inner.hits,
+            false => &self.inner.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    fn insert(&self, key: CacheKey) {
+        if !self.entries().insert(key) {
+            return;
+        }
+        if let Some(path) = &self.inner.file {
+            // A full file is started over rather than grown forever
+            let full =
+                self.inner.file_entries.fetch_add(1, Ordering::Relaxed) >= self.inner.file_capacity;
+            if full {
+                self.inner.file_entries.store(1, Ordering::Relaxed);
+            }
+            // The cache is an optimization; failing to persist an entry
+            // only costs a miss next time
+            let _ = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(!full)
+                .truncate(full)
+                .open(path)
+                .and_then(|mut file| {
+                    writeln!(file, "{:016x}-{:016x}-{:016x}", key.0, key.1, key.2)
+                });
+        }
+    }
+}
+
+impl fmt::Debug for ComparisonCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Constant, since the Debug output of the options is their cache key
+        f.write_str("ComparisonCache")
+    }
+}
+
+fn parse_key(line: &str) -> Option<CacheKey> {
+    let mut parts = line
+        .trim()
+        .split('-')
+        .map(|part| u64::from_str_radix(part, 16));
+    let key = (
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+    parts.next().is_none().then_some(key)
+}
+
+fn hash(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Whether `options` run code of the caller's that their `Debug` output
+/// doesn't describe
+fn runs_callbacks(options: &HtmlCompareOptions) -> bool {
+    options.asset_resolver.is_some()
+        || options.on_node_compared.is_some()
+        || options.error_formatter.is_some()
+        || !options.matchers.is_empty()
+        || !options.parser.is_builtin()
+        || options
+            .attribute_content
+            .values()
+            .any(|content| matches!(content, AttributeContent::Custom { .. }))
+        || options
+            .lint_rules
+            .iter()
+            .any(|rule| matches!(rule, LintRule::Custom { .. }))
+}
+
+/// The `Debug` output of `options` with the contents of their sets and maps
+/// in sorted order, which doesn't depend on the hasher's random state
+fn canonical(options: &HtmlCompareOptions) -> String {
+    let mut options = options.clone();
+    let ignored_attributes: BTreeSet<_> = options.ignored_attributes.drain().collect();
+    let empty_attribute_equals_missing: BTreeSet<_> =
+        options.empty_attribute_equals_missing.drain().collect();
+    let tokenized_attributes: BTreeMap<_, _> = options.tokenized_attributes.drain().collect();
+    let attribute_content: BTreeMap<_, _> = options.attribute_content.drain().collect();
+    let ignored_attributes_for: Vec<(_, BTreeSet<_>)> = options
+        .ignored_attributes_for
+        .drain(..)
+        .map(|(selector, names)| (selector, names.into_iter().collect()))
+        .collect();
+    let ignored_query_params: BTreeSet<_> = options.ignored_query_params.drain().collect();
+    format!(
+        "{:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+        options,
+        ignored_attributes,
+        empty_attribute_equals_missing,
+        tokenized_attributes,
+        attribute_content,
+        ignored_attributes_for,
+        ignored_query_params
+    )
+}
+
+/// The key of comparing `expected` with `actual` under `options`, if the
+/// comparison can be cached
+fn cache_key(options: &HtmlCompareOptions, expected: &str, actual: &str) -> Option<CacheKey> {
+    if runs_callbacks(options) {
+        return None;
+    }
+    Some((
+        hash(expected),
+        hash(actual),
+        options_hash(env!("CARGO_PKG_VERSION"), options),
+    ))
+}
+
+/// Hash of `options` as understood by version `version` of this crate
+fn options_hash(version: &str, options: &HtmlCompareOptions) -> u64 {
+    hash((version, canonical(options)))
+}
+
+impl HtmlComparer {
+    /// Compare two HTML strings, answering from and recording into the
+    /// configured cache
+    pub(crate) fn verify_cached(
+        &self,
+        cache: &ComparisonCache,
+        expected: &str,
+        actual: &str,
+    ) -> Result<(), crate::HtmlCompareError> {
+        let Some(key) = cache_key(&self.options, expected, actual) else {
+            return self.verify_uncached(expected, actual);
+        };
+        if cache.contains(key) {
+            return Ok(());
+        }
+        let result = self.verify_uncached(expected, actual);
+        if result.is_ok() {
+            cache.insert(key);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorFormatter, Matcher, Parser};
+
+    fn comparer(cache: &ComparisonCache) -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            cache: Some(cache.clone()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_in_memory_cache() {
+        let cache = ComparisonCache::in_memory();
+        let comparer = comparer(&cache);
+        for _ in 0..3 {
+            comparer.verify("<p>Hi</p>", "<p>\n  Hi\n</p>").unwrap();
+        }
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (2, 1, 1));
+
+        // Failures aren't cached, and other options get their own entries
+        assert!(comparer.verify("<p>Hi</p>", "<p>Bye</p>").is_err());
+        assert!(comparer.verify("<p>Hi</p>", "<p>Bye</p>").is_err());
+        let ignoring_text = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_text: true,
+            cache: Some(cache.clone()),
+            ..Default::default()
+        });
+        ignoring_text.verify("<p>Hi</p>", "<p>Bye</p>").unwrap();
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_file_backed_cache() {
+        let path =
+            std::env::temp_dir().join(format!("html-compare-cache-{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let cache = ComparisonCache::open(&path).unwrap();
+        comparer(&cache).verify("<b>x</b>", "<b>x</b>").unwrap();
+        assert_eq!(cache.misses(), 1);
+
+        let reopened = ComparisonCache::open(&path).unwrap();
+        comparer(&reopened).verify("<b>x</b>", "<b>x</b>").unwrap();
+        assert_eq!((reopened.hits(), reopened.misses()), (1, 0));
+
+        reopened.clear().unwrap();
+        assert!(ComparisonCache::open(&path).unwrap().is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_file_capacity() {
+        let path = std::env::temp_dir().join(format!(
+            "html-compare-cache-capacity-{}.txt",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let cache = ComparisonCache::open_with_capacity(&path, 3).unwrap();
+        for i in 0..5 {
+            let html = format!("<p>{}</p>", i);
+            comparer(&cache).verify(&html, &html).unwrap();
+        }
+        // Every entry stays in memory, but the file was started over at the
+        // fourth one
+        assert_eq!(cache.len(), 5);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        let reopened = ComparisonCache::open_with_capacity(&path, 3).unwrap();
+        assert_eq!(reopened.len(), 2);
+        comparer(&reopened).verify("<p>4</p>", "<p>4</p>").unwrap();
+        comparer(&reopened).verify("<p>0</p>", "<p>0</p>").unwrap();
+        assert_eq!((reopened.hits(), reopened.misses()), (1, 1));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_cache_key() {
+        // Sets built in different orders, with different random states
+        let names: Vec<String> = (0..32).map(|i| format!("data-{}", i)).collect();
+        let options = |reversed: bool| {
+            let mut names = names.clone();
+            if reversed {
+                names.reverse();
+            }
+            HtmlCompareOptions {
+                ignored_attributes: names.iter().cloned().collect(),
+                ignored_query_params: names.iter().cloned().collect(),
+                ignored_attributes_for: vec![("p".to_string(), names.iter().cloned().collect())],
+                ..Default::default()
+            }
+        };
+        assert_eq!(
+            cache_key(&options(false), "<p>", "<p>"),
+            cache_key(&options(true), "<p>", "<p>")
+        );
+        assert_ne!(
+            cache_key(&options(false), "<p>", "<p>"),
+            cache_key(&Default::default(), "<p>", "<p>")
+        );
+
+        // Results of one version of the crate aren't reused by another
+        let defaults = HtmlCompareOptions::default();
+        assert_ne!(
+            options_hash("0.1.0", &defaults),
+            options_hash("0.2.0", &defaults)
+        );
+
+        // Comparisons running the caller's code aren't cached
+        for options in [
+            HtmlCompareOptions {
+                matchers: vec![("id".to_string(), Matcher::new("id", |_| true))],
+                ..Default::default()
+            },
+            HtmlCompareOptions {
+                parser: Parser::new("html5ever", |html: &str| {
+                    scraper::Html::parse_document(html)
+                }),
+                ..Default::default()
+            },
+            HtmlCompareOptions {
+                attribute_content: [(
+                    "data-x".to_string(),
+                    AttributeContent::custom("x", str::to_string),
+                )]
+                .into_iter()
+                .collect(),
+                ..Default::default()
+            },
+            HtmlCompareOptions {
+                error_formatter: Some(ErrorFormatter::new(|_| String::new())),
+                ..Default::default()
+            },
+        ] {
+            assert_eq!(cache_key(&options, "<p>", "<p>"), None);
+        }
+        let fragment = HtmlCompareOptions {
+            parser: Parser::from_name("fragment").unwrap(),
+            ..Default::default()
+        };
+        assert!(cache_key(&fragment, "<p>", "<p>").is_some());
+    }
+}
@@ -0,0 +1,282 @@
+//! Placeholders that capture values from the actual document.
+//!
+//! Generated ids, tokens and timestamps can't be written into an expected
+//! document, but it still matters that they are used consistently. With
+//! [`HtmlCompareOptions::capture_placeholders`](crate::HtmlCompareOptions),
+//! `{{name}}` in an expected attribute value or text matches any non-empty
+//! run of characters, and the value it matched is recorded under `name`:
+//!
+//! ```html
+//! <p id="{{order_id}}">Order</p>
+//! <a href="/orders/{{order_id}}">Details</a>
+//! ```
+//!
+//! A name matches the same value everywhere it appears, so the link above
+//! must point to the order's id, whatever that was.
+//! [`HtmlComparer::capture`] returns the recorded values. Placeholders are
+//! matched the same way by [`HtmlComparer::verify`], [`HtmlComparer::is_equal`]
+//! and the exhaustive [`HtmlComparer::diff`], which reports a value that
+//! differs from the one captured before as a mismatch of that value.
+
+use std::fmt;
+
+use ego_tree::NodeRef;
+use scraper::Node;
+
+use crate::matchers;
+use crate::{HtmlCompareError, HtmlComparer};
+
+/// The values captured by the placeholders of an expected document
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Captures {
+    /// In the order they were first captured
+    values: Vec<(String, String)>,
+}
+
+impl Captures {
+    /// The value captured under `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(captured, _)| captured == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Names and values, in the order they were first captured
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// A point to [`Self::rollback`] to
+    pub(crate) fn mark(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Forget what was captured since `mark`, after a failed attempt to
+    /// match unordered siblings
+    pub(crate) fn rollback(&mut self, mark: usize) {
+        self.values.truncate(mark);
+    }
+
+    /// Match `actual` against the `pattern` from the expected document and
+    /// record its captures, failing if a name already holds another value
+    fn record(&mut self, pattern: &str, actual: &str) -> Result<bool, HtmlCompareError> {
+        let Some(captured) = match_pattern(&parse_pattern(pattern), actual) else {
+            return Ok(false);
+        };
+        for (name, value) in captured {
+            match self.get(name) {
+                Some(previous) if previous != value => {
+                    return Err(HtmlCompareError::CaptureMismatch {
+                        path: String::new(),
+                        name: name.to_string(),
+                        expected: previous.to_string(),
+                        actual: value.to_string(),
+                    })
+                }
+                Some(_) => {}
+                None => self.values.push((name.to_string(), value.to_string())),
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl fmt::Display for Captures {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in self.iter() {
+            writeln!(f, "{} = {:?}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl HtmlComparer {
+    /// Whether the `expected` value of an attribute or text matches the
+    /// `actual` one, recording the values of placeholders in `expected`
+    ///
+    /// `node` is the expected element the attribute `attribute` belongs to,
+    /// or the expected text node, which decides the [`Matcher`](crate::Matcher) to use, if
+    /// any.
+    pub(crate) fn values_match(
+        &self,
+        node: NodeRef<Node>,
+        attribute: Option<&str>,
+        expected: &str,
+        actual: &str,
+        captures: &mut Captures,
+    ) -> Result<bool, HtmlCompareError> {
+        if expected == actual {
+            return Ok(true);
+        }
+        if attribute.is_none() && self.texts_equal(expected, actual) {
+            return Ok(true);
+        }
+        if let Ok(matchers) = &self.matchers {
+            if let Some(matcher) = matchers::matcher_for(matchers, node, attribute) {
+                return Ok(matcher.matches(actual));
+            }
+        }
+        if !self.options.capture_placeholders || !expected.contains("{{") {
+            return Ok(false);
+        }
+        captures.record(expected, actual)
+    }
+}
+
+enum Segment<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+fn parse_pattern(pattern: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + length].trim();
+        if start > 0 {
+            segments.push(Segment::Literal(&rest[..start]));
+        }
+        segments.push(Segment::Placeholder(name));
+        rest = &rest[start + 2 + length + 2..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest));
+    }
+    segments
+}
+
+/// The values the placeholders of `segments` take to spell `value`, trying
+/// the shortest values first
+fn match_pattern<'a, 'v>(
+    segments: &[Segment<'a>],
+    value: &'v str,
+) -> Option<Vec<(&'a str, &'v str)>> {
+    match segments.split_first() {
+        None => value.is_empty().then(Vec::new),
+        Some((Segment::Literal(literal), rest)) => {
+            match_pattern(rest, value.strip_prefix(literal)?)
+        }
+        Some((Segment::Placeholder(name), rest)) => value
+            .char_indices()
+            .skip(1)
+            .map(|(end, _)| end)
+            .chain([value.len()])
+            .filter(|&end| end > 0)
+            .find_map(|end| {
+                let mut captured = match_pattern(rest, &value[end..])?;
+                captured.insert(0, (*name, &value[..end]));
+                Some(captured)
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    fn comparer() -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            capture_placeholders: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_match_pattern() {
+        let matched = |pattern, value| match_pattern(&parse_pattern(pattern), value);
+        assert_eq!(matched("{{id}}", "42"), Some(vec![("id", "42")]));
+        assert_eq!(
+            matched("/orders/{{id}}/items/{{item}}", "/orders/7/items/a-b"),
+            Some(vec![("id", "7"), ("item", "a-b")])
+        );
+        assert_eq!(matched("order-{{ id }}", "order-"), None);
+        assert_eq!(
+            matched("{{a}}-{{b}}", "x-y-z"),
+            Some(vec![("a", "x"), ("b", "y-z")])
+        );
+        assert_eq!(matched("plain", "plain"), Some(vec![]));
+    }
+
+    #[test]
+    fn test_capture() {
+        let expected = r#"<div id="{{order}}"><a href="/orders/{{order}}">Order {{order}}</a><time>{{date}}</time></div>"#;
+        let captures = comparer()
+            .capture(
+                expected,
+                r#"<div id="A17"><a href="/orders/A17">Order A17</a><time>Today</time></div>"#,
+            )
+            .unwrap();
+        assert_eq!(captures.get("order"), Some("A17"));
+        assert_eq!(captures.get("date"), Some("Today"));
+        assert_eq!(captures.len(), 2);
+
+        let err = comparer()
+            .verify(
+                expected,
+                r#"<div id="A17"><a href="/orders/B2">Order A17</a><time>Today</time></div>"#,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Node mismatch: Placeholder {{order}} captured 'A17' before, Actual: 'B2'"
+        );
+        assert_eq!(err.path(), Some("/html/body/div/a"));
+
+        let exhaustive = HtmlComparer::with_options(HtmlCompareOptions {
+            capture_placeholders: true,
+            exhaustive: true,
+            ..Default::default()
+        });
+        let captures = exhaustive
+            .capture(
+                expected,
+                r#"<div id="A17"><a href="/orders/A17">Order A17</a><time>Today</time></div>"#,
+            )
+            .unwrap();
+        assert_eq!(captures.get("order"), Some("A17"));
+        let diff = comparer().diff(
+            expected,
+            r#"<div id="A17"><a href="/orders/B2">Order A17</a><time>Today</time></div>"#,
+        );
+        assert_eq!(
+            diff.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                r#"/html/body/div/a: attribute href expected "/orders/{{order}}", found "/orders/B2""#
+            ]
+        );
+        assert!(!HtmlComparer::new()
+            .is_equal(expected, r#"<div id="A17"></div>"#)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_capture_unordered() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            capture_placeholders: true,
+            ignore_sibling_order: true,
+            ..Default::default()
+        });
+        let captures = comparer
+            .capture(
+                r#"<ul><li id="{{first}}">1</li><li data-ref="{{first}}">2</li></ul>"#,
+                r#"<ul><li data-ref="x">2</li><li id="x">1</li></ul>"#,
+            )
+            .unwrap();
+        assert_eq!(captures.get("first"), Some("x"));
+    }
+}
@@ -0,0 +1,233 @@
+//! Cross-checking the classes used in a document against a stylesheet.
+//!
+//! When templates are refactored, a typo in a `class` attribute silently
+//! unstyles an element. Setting
+//! [`HtmlCompareOptions::known_classes`](crate::HtmlCompareOptions) makes
+//! [`HtmlComparer::diff`] warn about classes the stylesheet doesn't define
+//! and, optionally, about defined classes the document never uses.
+
+use std::collections::BTreeSet;
+
+use scraper::Html;
+
+use crate::diff::Warning;
+use crate::HtmlComparer;
+
+/// The set of classes a stylesheet defines
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassList {
+    /// Known class names, without the leading `.`
+    pub classes: BTreeSet<String>,
+    /// Also warn about known classes that the document never uses
+    pub report_unused: bool,
+}
+
+impl ClassList {
+    /// Collect the classes used in the selectors of a CSS stylesheet
+    ///
+    /// This is a lightweight scan rather than a full CSS parser: it looks for
+    /// `.name` in rule selectors, skipping comments, strings, declarations and
+    /// at-rule preludes such as `@media (min-width: 1.5em)`.
+    pub fn from_css(css: &str) -> Self {
+        let css = strip_comments_and_strings(css);
+        let mut classes = BTreeSet::new();
+
+        let mut prelude = String::new();
+        for c in css.chars() {
+            match c {
+                '{' => {
+                    if !prelude.trim_start().starts_with('@') {
+                        collect_selector_classes(&prelude, &mut classes);
+                    }
+                    prelude.clear();
+                }
+                // Text before `}` or `;` is a declaration or statement at-rule
+                '}' | ';' => prelude.clear(),
+                _ => prelude.push(c),
+            }
+        }
+
+        ClassList {
+            classes,
+            report_unused: false,
+        }
+    }
+
+    /// Whether `class` is known
+    pub fn contains(&self, class: &str) -> bool {
+        self.classes.contains(class)
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for ClassList {
+    fn from_iter<I: IntoIterator<Item = S>>(classes: I) -> Self {
+        ClassList {
+            classes: classes.into_iter().map(Into::into).collect(),
+            report_unused: false,
+        }
+    }
+}
+
+impl HtmlComparer {
+    /// Warn about classes of `document` missing from the configured
+    /// [`ClassList`], and about unused known classes if requested
+    pub(crate) fn class_warnings(&self, document: &Html) -> Vec<Warning> {
+        let Some(known) = &self.options.known_classes else {
+            return Vec::new();
+        };
+
+        let mut warnings = Vec::new();
+        let mut used = BTreeSet::new();
+        self.walk_elements(document.root_element(), "/html", &mut |element, path| {
+            for class in element.value().classes() {
+                if !known.contains(class) {
+                    warnings.push(Warning::UnknownClass {
+                        path: path.to_string(),
+                        class: class.to_string(),
+                    });
+                }
+                used.insert(class.to_string());
+            }
+        });
+
+        if known.report_unused {
+            warnings.extend(
+                known
+                    .classes
+                    .difference(&used)
+                    .map(|class| Warning::UnusedClass {
+                        class: class.clone(),
+                    }),
+            );
+        }
+        warnings
+    }
+}
+
+fn collect_selector_classes(selector: &str, classes: &mut BTreeSet<String>) {
+    let mut chars = selector.chars().peekable();
+    let mut previous = ' ';
+    while let Some(c) = chars.next() {
+        // A `.` after a digit belongs to a number, not a class
+        if c == '.' && !previous.is_ascii_digit() {
+            let mut name = String::new();
+            while let Some(&next) = chars.peek() {
+                if next == '\\' {
+                    chars.next();
+                    if let Some(escaped) = chars.next() {
+                        name.push(escaped);
+                    }
+                } else if next.is_alphanumeric() || next == '-' || next == '_' || !next.is_ascii() {
+                    name.push(next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if name.starts_with(|c: char| !c.is_ascii_digit()) {
+                classes.insert(name);
+            }
+            previous = '_';
+            continue;
+        }
+        previous = c;
+    }
+}
+
+fn strip_comments_and_strings(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '"' | '\'' => {
+                let quote = c;
+                while let Some(c) = chars.next() {
+                    if c == '\\' {
+                        chars.next();
+                    } else if c == quote {
+                        break;
+                    }
+                }
+                stripped.push_str("\"\"");
+            }
+            _ => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    #[test]
+    fn test_class_list_from_css() {
+        let css = r#"
+            /* .commented { color: red } */
+            .btn, .btn-primary:hover > span.icon { margin: .5em 0.25rem; }
+            @media (min-width: 40.5em) { .grid .col-6 { width: 50% } }
+            @import url("theme.css");
+            a[href$=".pdf"]::after { content: ".not-a-class"; background: url(bg.png) }
+            .sm\:hidden { display: none }
+        "#;
+        let classes: Vec<_> = ClassList::from_css(css).classes.into_iter().collect();
+        assert_eq!(
+            classes,
+            ["btn", "btn-primary", "col-6", "grid", "icon", "sm:hidden"]
+        );
+    }
+
+    #[test]
+    fn test_class_warnings() {
+        let known = ClassList::from_css(".card { } .title { } .footer { }");
+        let html = r#"<div class="card"><h2 class="titel">Hi</h2></div>"#;
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            known_classes: Some(known.clone()),
+            ..Default::default()
+        });
+        let diff = comparer.diff(html, html);
+        assert!(diff.is_empty());
+        assert_eq!(
+            diff.warnings(),
+            [Warning::UnknownClass {
+                path: "/html/body/div/h2".to_string(),
+                class: "titel".to_string(),
+            }]
+        );
+        comparer.verify(html, html).unwrap();
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            known_classes: Some(ClassList {
+                report_unused: true,
+                ..known
+            }),
+            ..Default::default()
+        });
+        let warnings: Vec<_> = comparer
+            .diff(html, html)
+            .warnings()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                "/html/body/div/h2: unknown class \"titel\"",
+                "unused class \"footer\"",
+                "unused class \"title\"",
+            ]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Whitespace-exact comparison of code blocks.
+//!
+//! Whitespace in code is significant, but the usual culprits of a failing
+//! comparison, a trailing space or a tab where spaces were expected, are
+//! invisible in an error message. With
+//! [`HtmlCompareOptions::exact_code_whitespace`](crate::HtmlCompareOptions)
+//! the text inside `<pre>` is compared exactly, whatever the whitespace
+//! options, and a code block mismatch is reported with the first differing
+//! line of each side, spaces shown as `·`, tabs as `→` and line ends as `¶`.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+/// Whether `node` is inside a `<pre>` element
+pub(crate) fn in_code_block(node: NodeRef<Node>) -> bool {
+    node.ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|element| element.value().name() == "pre")
+}
+
+/// `text` with spaces, tabs and line ends made visible
+pub(crate) fn visible_whitespace(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            ' ' => '·',
+            '\t' => '→',
+            '\n' => '¶',
+            c => c,
+        })
+        .collect()
+}
+
+/// A description of where the code `actual` first differs from `expected`,
+/// with a caret under the differing character
+pub(crate) fn describe_difference(expected: &str, actual: &str) -> String {
+    let offset = expected
+        .char_indices()
+        .zip(actual.chars())
+        .find(|((_, expected), actual)| expected != actual)
+        .map(|((offset, _), _)| offset)
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+    let line_start = expected[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line = expected[..offset].matches('\n').count() + 1;
+    let column = expected[line_start..offset].chars().count() + 1;
+
+    let line_of = |text: &str| -> String {
+        let rest = text.get(line_start..).unwrap_or_default();
+        let end = rest.find('\n').map_or(rest.len(), |index| index + 1);
+        visible_whitespace(&rest[..end])
+    };
+    format!(
+        "first difference at line {}, column {}\n  expected: {}\n  actual:   {}\n            {}^",
+        line,
+        column,
+        line_of(expected),
+        line_of(actual),
+        " ".repeat(column - 1)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_describe_difference() {
+        assert_eq!(
+            describe_difference("fn main() {\n    run();\n}", "fn main() {\n\trun();\n}"),
+            "first difference at line 2, column 1\n  expected: ····run();¶\n  actual:   →run();¶\n            ^"
+        );
+        assert_eq!(
+            describe_difference("let x = 1;", "let x = 1; "),
+            "first difference at line 1, column 11\n  expected: let·x·=·1;\n  actual:   let·x·=·1;·\n                      ^"
+        );
+    }
+
+    #[test]
+    fn test_exact_code_whitespace() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            exact_code_whitespace: true,
+            ..Default::default()
+        });
+        let expected = "<pre><code>if x {\n    y();\n}</code></pre>";
+        let trailing = "<pre><code>if x {  \n    y();\n}</code></pre>";
+        HtmlComparer::new()
+            .verify(expected, &expected.replace("if x", "  if x"))
+            .unwrap();
+
+        let err = comparer.verify(expected, trailing).unwrap_err().to_string();
+        assert!(
+            err.contains("expected: if·x·{¶\n  actual:   if·x·{··¶"),
+            "{}",
+            err
+        );
+        assert!(comparer
+            .verify(expected, &expected.replace("if x", "  if x"))
+            .is_err());
+        comparer.verify("<p> text </p>", "<p>text</p>").unwrap();
+    }
+}
@@ -0,0 +1,149 @@
+//! Locale-aware comparison of text.
+//!
+//! Multilingual content goes through editors, translation tools and
+//! normalizers that don't agree on accents and case: `Crème brûlée` comes
+//! back as `Creme brulee`, `Straße` as `STRASSE`. With the `collation`
+//! feature, [`HtmlCompareOptions::text_collation`](crate::HtmlCompareOptions::text_collation)
+//! takes a [`Collation`], and text nodes compare equal when ICU's collator
+//! for its locale sorts them as equal at its [`CollationStrength`]:
+//!
+//! ```ignore
+//! options.text_collation = Some(Collation::new("fr", CollationStrength::Primary)?);
+//! ```
+//!
+//! Collation only loosens the comparison of text nodes after the whitespace
+//! options have applied; attributes and comments are compared as before,
+//! and text that differs is still reported as written.
+
+use std::fmt;
+use std::sync::Arc;
+
+use icu_collator::{Collator, CollatorOptions, Strength};
+use icu_locid::Locale;
+
+use crate::HtmlCompareError;
+
+/// Which differences a [`Collation`] tells apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollationStrength {
+    /// Base letters only, ignoring accents and case: `é` equals `E`
+    Primary,
+    /// Base letters and accents, ignoring case: `é` equals `É` but not `e`
+    Secondary,
+    /// Base letters, accents and case, ignoring differences such as the
+    /// width of East Asian characters
+    Tertiary,
+}
+
+/// A collator for a locale, deciding which texts compare equal
+#[derive(Clone)]
+pub struct Collation {
+    locale: String,
+    strength: CollationStrength,
+    collator: Arc<Collator>,
+}
+
+impl Collation {
+    /// The collation of the BCP 47 `locale`, e.g. `"de"` or `"sv-SE"`, at
+    /// `strength`
+    ///
+    /// Locales without collation data of their own fall back to the root
+    /// collation; a tag that doesn't parse is
+    /// [`HtmlCompareError::InvalidOptions`].
+    pub fn new(locale: &str, strength: CollationStrength) -> Result<Self, HtmlCompareError> {
+        let invalid = |err: &dyn fmt::Display| {
+            HtmlCompareError::InvalidOptions(format!("collation locale {:?}: {}", locale, err))
+        };
+        let parsed: Locale = locale.parse().map_err(|err| invalid(&err))?;
+        let mut options = CollatorOptions::new();
+        options.strength = Some(match strength {
+            CollationStrength::Primary => Strength::Primary,
+            CollationStrength::Secondary => Strength::Secondary,
+            CollationStrength::Tertiary => Strength::Tertiary,
+        });
+        let collator =
+            Collator::try_new(&(&parsed).into(), options).map_err(|err| invalid(&err))?;
+        Ok(Collation {
+            locale: locale.to_string(),
+            strength,
+            collator: Arc::new(collator),
+        })
+    }
+
+    /// The locale the collation was created for
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// The strength the collation was created with
+    pub fn strength(&self) -> CollationStrength {
+        self.strength
+    }
+
+    /// Whether `a` and `b` are equal under this collation
+    pub fn equal(&self, a: &str, b: &str) -> bool {
+        self.collator.compare(a, b).is_eq()
+    }
+}
+
+impl fmt::Debug for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Collation")
+            .field("locale", &self.locale)
+            .field("strength", &self.strength)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_collation_strength() {
+        let primary = Collation::new("fr", CollationStrength::Primary).unwrap();
+        assert!(primary.equal("Crème brûlée", "CREME BRULEE"));
+        assert!(!primary.equal("Crème brûlée", "Creme brule"));
+        assert!(primary.equal("Straße", "STRASSE"));
+
+        let secondary = Collation::new("de", CollationStrength::Secondary).unwrap();
+        assert!(secondary.equal("Éclair", "éclair"));
+        assert!(!secondary.equal("Éclair", "eclair"));
+
+        let tertiary = Collation::new("en", CollationStrength::Tertiary).unwrap();
+        assert!(!tertiary.equal("Éclair", "éclair"));
+        assert!(matches!(
+            Collation::new("not a locale!", CollationStrength::Primary),
+            Err(HtmlCompareError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_text_collation() {
+        let expected = r#"<p title="Crème">Crème brûlée</p><!-- Crème -->"#;
+        let actual = r#"<p title="Crème">Creme Brulee</p><!-- Crème -->"#;
+        assert!(!HtmlComparer::new().is_equal(expected, actual).unwrap());
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            text_collation: Some(Collation::new("fr", CollationStrength::Primary).unwrap()),
+            ignore_comments: false,
+            ..Default::default()
+        });
+        comparer.verify(expected, actual).unwrap();
+        assert!(comparer.diff(expected, actual).is_empty());
+        // Attributes and comments are compared as written
+        assert!(!comparer
+            .is_equal(
+                expected,
+                &actual.replace("title=\"Crème\"", "title=\"Creme\"")
+            )
+            .unwrap());
+        assert!(!comparer
+            .is_equal(expected, &actual.replace("<!-- Crème", "<!-- Creme"))
+            .unwrap());
+        assert!(!comparer
+            .is_equal(expected, &actual.replace("Brulee", "Bruler"))
+            .unwrap());
+    }
+}
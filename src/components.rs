@@ -0,0 +1,222 @@
+//! Grouping differences by the component they occur in.
+//!
+//! Server-rendered pages are assembled from components, and a flat list of
+//! node paths hides which of them changed. Registering the elements that
+//! start a component with
+//! [`HtmlCompareOptions::component_boundaries`](crate::HtmlCompareOptions),
+//! e.g. `("[data-component]", "data-component")`, lets
+//! [`HtmlDiff::by_component`] group the differences so that a large diff
+//! reads as "3 in ProductCard, 1 in Footer".
+
+use scraper::{ElementRef, Selector};
+
+use crate::{Difference, HtmlComparer, HtmlDiff};
+
+/// The differences inside one instance of a component
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentDiff {
+    /// The component's name, taken from its name attribute or else its tag
+    pub name: String,
+    /// Path of the element that starts the component
+    pub path: String,
+    pub differences: Vec<Difference>,
+}
+
+/// A `component_boundaries` entry with its selector parsed
+#[derive(Debug)]
+pub(crate) struct ComponentRule {
+    selector: Selector,
+    name_attribute: String,
+}
+
+/// Parse the selectors of `component_boundaries` entries, or describe the
+/// first invalid one
+pub(crate) fn compile_component_rules(
+    boundaries: &[(String, String)],
+) -> Result<Vec<ComponentRule>, String> {
+    boundaries
+        .iter()
+        .map(|(selector, name_attribute)| {
+            Ok(ComponentRule {
+                selector: Selector::parse(selector)
+                    .map_err(|err| format!("{}: {}", selector, err))?,
+                name_attribute: name_attribute.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Whether the difference at `path` lies within the element at `component`
+fn contains(component: &str, path: &str) -> bool {
+    path.strip_prefix(component)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+impl HtmlComparer {
+    /// The paths and names of the component instances in the document of
+    /// `root`, in document order
+    pub(crate) fn component_instances(&self, root: ElementRef) -> Vec<(String, String)> {
+        let Ok(rules) = &self.component_rules else {
+            return Vec::new();
+        };
+        let mut instances = Vec::new();
+        if rules.is_empty() {
+            return instances;
+        }
+        self.walk_elements(root, "/html", &mut |element, path| {
+            if let Some(rule) = rules.iter().find(|rule| rule.selector.matches(&element)) {
+                let name = element
+                    .attr(&rule.name_attribute)
+                    .unwrap_or_else(|| element.value().name());
+                instances.push((path.to_string(), name.to_string()));
+            }
+        });
+        instances
+    }
+}
+
+impl HtmlDiff {
+    /// The differences grouped by the innermost component instance they
+    /// occur in, in document order; instances without differences are left
+    /// out (see [`HtmlDiff::outside_components`] for the rest)
+    pub fn by_component(&self) -> Vec<ComponentDiff> {
+        let mut groups: Vec<ComponentDiff> = Vec::new();
+        for difference in self.iter() {
+            let Some((path, name)) = self.component_of(difference) else {
+                continue;
+            };
+            match groups.iter_mut().find(|group| group.path == *path) {
+                Some(group) => group.differences.push(difference.clone()),
+                None => groups.push(ComponentDiff {
+                    name: name.clone(),
+                    path: path.clone(),
+                    differences: vec![difference.clone()],
+                }),
+            }
+        }
+        let order = |group: &ComponentDiff| {
+            self.components
+                .iter()
+                .position(|(path, _)| *path == group.path)
+        };
+        groups.sort_by_key(order);
+        groups
+    }
+
+    /// The differences that are not inside any component
+    pub fn outside_components(&self) -> Vec<&Difference> {
+        self.iter()
+            .filter(|difference| self.component_of(difference).is_none())
+            .collect()
+    }
+
+    /// Difference counts per component name, e.g. `3 in ProductCard, 1 in
+    /// Footer`, or `None` without component boundaries
+    pub(crate) fn component_summary(&self) -> Option<String> {
+        if self.components.is_empty() || self.is_empty() {
+            return None;
+        }
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for group in self.by_component() {
+            match counts.iter_mut().find(|(name, _)| *name == group.name) {
+                Some((_, count)) => *count += group.differences.len(),
+                None => counts.push((group.name, group.differences.len())),
+            }
+        }
+        let outside = self.outside_components().len();
+        if outside > 0 {
+            counts.push(("no component".to_string(), outside));
+        }
+        let counts: Vec<_> = counts
+            .into_iter()
+            .map(|(name, count)| format!("{} in {}", count, name))
+            .collect();
+        Some(counts.join(", "))
+    }
+
+    /// The innermost component instance containing `difference`
+    fn component_of(&self, difference: &Difference) -> Option<&(String, String)> {
+        self.components
+            .iter()
+            .filter(|(path, _)| contains(path, difference.path()))
+            .max_by_key(|(path, _)| path.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareError, HtmlCompareOptions};
+
+    fn comparer() -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            component_boundaries: vec![(
+                "[data-component]".to_string(),
+                "data-component".to_string(),
+            )],
+            exhaustive: true,
+            ..Default::default()
+        })
+    }
+
+    const EXPECTED: &str = r#"
+        <main>
+            <div data-component="ProductCard"><h2>Lamp</h2><p class="price">10</p></div>
+            <div data-component="ProductCard"><h2>Desk</h2><p class="price">99</p></div>
+        </main>
+        <h1>Shop</h1>
+        <footer data-component="Footer"><a href="/about">About</a></footer>"#;
+
+    #[test]
+    fn test_differences_by_component() {
+        let actual = EXPECTED
+            .replace("Lamp", "Lamp!")
+            .replace(">10<", ">12<")
+            .replace("Desk", "Chair")
+            .replace("/about", "/team")
+            .replace("Shop", "Store");
+        let diff = comparer().diff(EXPECTED, &actual);
+
+        let groups: Vec<_> = diff
+            .by_component()
+            .into_iter()
+            .map(|group| (group.name, group.path, group.differences.len()))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (
+                    "ProductCard".to_string(),
+                    "/html/body/main/div[1]".to_string(),
+                    2
+                ),
+                (
+                    "ProductCard".to_string(),
+                    "/html/body/main/div[2]".to_string(),
+                    1
+                ),
+                ("Footer".to_string(), "/html/body/footer".to_string(), 1),
+            ]
+        );
+        assert_eq!(diff.outside_components().len(), 1);
+        assert!(diff
+            .summary()
+            .contains("\n  by component: 3 in ProductCard, 1 in Footer, 1 in no component"));
+    }
+
+    #[test]
+    fn test_invalid_component_selector() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            component_boundaries: vec![("div[".to_string(), "id".to_string())],
+            ..Default::default()
+        });
+        assert!(matches!(
+            comparer.verify("<p></p>", "<p></p>"),
+            Err(HtmlCompareError::InvalidSelector(_))
+        ));
+        assert!(HtmlComparer::new()
+            .diff(EXPECTED, &EXPECTED.replace("Lamp", "Bulb"))
+            .by_component()
+            .is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+//! Attributes of pages served with a Content-Security-Policy.
+//!
+//! A CSP-protected page carries a fresh `nonce` on every inline script and
+//! style, and subresource `integrity` hashes that change with each asset
+//! build. [`HtmlCompareOptions::ignore_nonces`](crate::HtmlCompareOptions)
+//! leaves nonces out of the comparison, and
+//! [`HtmlCompareOptions::integrity`](crate::HtmlCompareOptions) decides how
+//! much of an `integrity` value is compared.
+
+use crate::HtmlCompareOptions;
+
+/// Comparison of `integrity` attribute values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntegrityMode {
+    /// Compare values exactly
+    #[default]
+    Exact,
+    /// Leave `integrity` attributes out of the comparison
+    Ignore,
+    /// Require well-formed values on both sides but don't compare the
+    /// hashes; a malformed value is compared as written and so differs
+    Format,
+}
+
+/// Hash algorithms allowed by Subresource Integrity, with the length of
+/// their base64 digest
+const INTEGRITY_ALGORITHMS: &[(&str, usize)] = &[("sha256", 44), ("sha384", 64), ("sha512", 88)];
+
+/// Whether the attribute `name` is left out of the comparison
+pub(crate) fn is_ignored(options: &HtmlCompareOptions, name: &str) -> bool {
+    match name {
+        "nonce" => options.ignore_nonces,
+        "integrity" => options.integrity == IntegrityMode::Ignore,
+        _ => false,
+    }
+}
+
+/// Whether the value of the attribute `name` only has to be well-formed,
+/// and `value` is
+pub(crate) fn is_valid_format_only(options: &HtmlCompareOptions, name: &str, value: &str) -> bool {
+    name == "integrity" && options.integrity == IntegrityMode::Format && is_valid_integrity(value)
+}
+
+/// Whether `value` is a list of well-formed integrity metadata, e.g.
+/// `sha384-oqVuAfXRKap7fdgcCY5uykM6+R9GqQ8K/uxy9rx7HNQlGYl1kPzQho1wx4JwY8wC`
+pub(crate) fn is_valid_integrity(value: &str) -> bool {
+    let mut hashes = value.split_ascii_whitespace().peekable();
+    hashes.peek().is_some()
+        && hashes.all(|hash| {
+            // Options after `?` are reserved and ignored by browsers
+            let hash = hash.split('?').next().unwrap_or_default();
+            let Some((algorithm, digest)) = hash.split_once('-') else {
+                return false;
+            };
+            INTEGRITY_ALGORITHMS.iter().any(|(name, length)| {
+                algorithm.eq_ignore_ascii_case(name) && digest.len() == *length
+            }) && is_base64(digest)
+        })
+}
+
+fn is_base64(digest: &str) -> bool {
+    let data = digest.trim_end_matches('=');
+    digest.len() - data.len() <= 2
+        && data
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'-' | b'_'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{presets, HtmlComparer};
+
+    const SHA384: &str = "sha384-oqVuAfXRKap7fdgcCY5uykM6+R9GqQ8K/uxy9rx7HNQlGYl1kPzQho1wx4JwY8wC";
+    const SHA256: &str = "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=";
+
+    #[test]
+    fn test_csp_preset() {
+        let comparer = HtmlComparer::with_options(presets::csp());
+        let expected = format!(
+            r#"<script nonce="r4nd0m">init()</script><script src="/app.js" integrity="{}"></script>"#,
+            SHA384
+        );
+        let actual = format!(
+            r#"<script>init()</script><script src="/app.js" integrity="{}"></script>"#,
+            SHA256
+        );
+        comparer.verify(&expected, &actual).unwrap();
+        assert!(HtmlComparer::new().verify(&expected, &actual).is_err());
+
+        let malformed = actual.replace(SHA256, "sha1-abc");
+        assert!(comparer.verify(&expected, &malformed).is_err());
+        let missing = actual.replace(&format!(r#" integrity="{}""#, SHA256), "");
+        assert!(comparer.verify(&expected, &missing).is_err());
+    }
+
+    #[test]
+    fn test_integrity_format() {
+        assert!(is_valid_integrity(SHA384));
+        assert!(is_valid_integrity(&format!("{} {}?v=1", SHA256, SHA384)));
+        assert!(!is_valid_integrity(""));
+        assert!(!is_valid_integrity("sha384-tooshort"));
+        assert!(!is_valid_integrity(&SHA256.replace("sha256", "md5")));
+        assert!(!is_valid_integrity(&SHA256.replace('+', "!")));
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            integrity: IntegrityMode::Ignore,
+            ..Default::default()
+        });
+        comparer
+            .verify(
+                r#"<link rel="stylesheet" href="/a.css" integrity="sha1-x">"#,
+                r#"<link rel="stylesheet" href="/a.css">"#,
+            )
+            .unwrap();
+    }
+}
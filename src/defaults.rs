@@ -0,0 +1,147 @@
+//! Per-thread default comparison options.
+//!
+//! The test harness runs every test on its own thread, so defaults set in a
+//! test (or in a setup function it calls) only affect that test. For a
+//! narrower scope, [`HtmlCompareOptions::enter`] overrides the defaults until
+//! the returned guard is dropped.
+
+use std::cell::RefCell;
+
+use crate::HtmlCompareOptions;
+
+thread_local! {
+    static DEFAULT_OPTIONS: RefCell<Option<HtmlCompareOptions>> = const { RefCell::new(None) };
+}
+
+/// Set the options used by the assertion macros when none are passed
+///
+/// The override applies to the current thread until it is replaced or
+/// [`reset_default_options`] is called.
+///
+/// # Examples
+/// ```ignore
+/// use html_compare_rs::{assert_html_eq, presets, set_default_options};
+///
+/// set_default_options(presets::relaxed());
+/// assert_html_eq!("<ul><li>A</li><li>B</li></ul>", "<ul><li>B</li><li>A</li></ul>");
+/// ```
+pub fn set_default_options(options: HtmlCompareOptions) {
+    DEFAULT_OPTIONS.with(|defaults| *defaults.borrow_mut() = Some(options));
+}
+
+/// Remove the override installed by [`set_default_options`]
+pub fn reset_default_options() {
+    DEFAULT_OPTIONS.with(|defaults| *defaults.borrow_mut() = None);
+}
+
+/// The options the assertion macros use when none are passed
+///
+/// This is the current thread's override if one is set, and
+/// [`HtmlCompareOptions::default`] otherwise.
+pub fn default_options() -> HtmlCompareOptions {
+    DEFAULT_OPTIONS.with(|defaults| defaults.borrow().clone().unwrap_or_default())
+}
+
+impl HtmlCompareOptions {
+    /// Use these options as the current thread's defaults until the returned
+    /// guard is dropped
+    ///
+    /// Dropping the guard restores whatever defaults were in effect before,
+    /// so guards can be nested.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// use html_compare_rs::{assert_html_eq, presets, HtmlCompareOptions};
+    ///
+    /// {
+    ///     let _guard = HtmlCompareOptions::enter(presets::relaxed());
+    ///     assert_html_eq!("<p class='a'>Hi</p>", "<p class='b'>Hi</p>");
+    /// }
+    /// // Back to the previous defaults here
+    /// ```
+    pub fn enter(options: HtmlCompareOptions) -> DefaultOptionsGuard {
+        let previous = DEFAULT_OPTIONS.with(|defaults| defaults.replace(Some(options)));
+        DefaultOptionsGuard { previous }
+    }
+}
+
+/// Restores the previous default options when dropped
+///
+/// Returned by [`HtmlCompareOptions::enter`].
+#[must_use = "the options are only in effect while the guard is alive"]
+#[derive(Debug)]
+pub struct DefaultOptionsGuard {
+    previous: Option<HtmlCompareOptions>,
+}
+
+impl Drop for DefaultOptionsGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        DEFAULT_OPTIONS.with(|defaults| *defaults.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_options_override() {
+        assert!(!default_options().ignore_sibling_order);
+        assert_html_ne!("<p>A</p><p>B</p>", "<p>B</p><p>A</p>");
+
+        set_default_options(HtmlCompareOptions {
+            ignore_sibling_order: true,
+            ..Default::default()
+        });
+        assert!(default_options().ignore_sibling_order);
+        assert_html_eq!("<p>A</p><p>B</p>", "<p>B</p><p>A</p>");
+
+        // Explicit options still take precedence
+        assert_html_ne!(
+            "<p>A</p><p>B</p>",
+            "<p>B</p><p>A</p>",
+            HtmlCompareOptions::default()
+        );
+
+        reset_default_options();
+        assert!(!default_options().ignore_sibling_order);
+    }
+
+    #[test]
+    fn test_default_options_are_per_thread() {
+        set_default_options(crate::presets::relaxed());
+        let other_thread = std::thread::spawn(|| default_options().ignore_attributes)
+            .join()
+            .unwrap();
+        assert!(!other_thread);
+        assert!(default_options().ignore_attributes);
+    }
+
+    #[test]
+    fn test_enter_guard_restores_defaults() {
+        set_default_options(HtmlCompareOptions {
+            ignore_text: true,
+            ..Default::default()
+        });
+
+        {
+            let _outer = HtmlCompareOptions::enter(HtmlCompareOptions {
+                ignore_sibling_order: true,
+                ..Default::default()
+            });
+            assert_html_eq!("<p>A</p><b>B</b>", "<b>B</b><p>A</p>");
+            assert!(!default_options().ignore_text);
+
+            {
+                let _inner = HtmlCompareOptions::enter(crate::presets::relaxed());
+                assert!(default_options().ignore_attributes);
+            }
+            assert!(!default_options().ignore_attributes);
+            assert!(default_options().ignore_sibling_order);
+        }
+
+        assert!(default_options().ignore_text);
+        assert!(!default_options().ignore_sibling_order);
+    }
+}
@@ -0,0 +1,204 @@
+//! A tiny local HTTP server for browsing an [`HtmlDiff`].
+//!
+//! Differences are grouped into a collapsible tree following their node paths,
+//! with the expected and actual values side by side. Meant for triaging large
+//! page regressions by hand, not for serving anything in production.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+
+use crate::{Difference, HtmlDiff};
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em}\
+details{margin-left:1.2em}summary{cursor:pointer;font-family:monospace}\
+table{border-collapse:collapse;margin:.3em 0 .3em 1.2em}\
+td,th{border:1px solid #ccc;padding:.2em .6em;font-family:monospace;vertical-align:top;white-space:pre-wrap}\
+th{background:#f4f4f4;text-align:left}.expected{background:#fdd}.actual{background:#dfd}\
+.kind{color:#666}";
+
+impl HtmlDiff {
+    /// Serve the diff as a browsable page on `addr` until the process exits
+    ///
+    /// Every request gets the same page, so any URL on the server works.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let diff = HtmlComparer::new().diff(&expected, &actual);
+    /// diff.serve("127.0.0.1:8080")?;
+    /// ```
+    pub fn serve(&self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        eprintln!("Serving HTML diff on http://{}", listener.local_addr()?);
+
+        let page = self.to_html_report();
+        for stream in listener.incoming() {
+            let mut stream = stream?;
+            // The request itself doesn't matter; read it so clients don't see
+            // a reset before the response
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                page.len(),
+                page
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Render the diff as a standalone HTML page
+    pub fn to_html_report(&self) -> String {
+        let mut tree = PathTree::default();
+        for difference in self.iter() {
+            tree.insert(difference);
+        }
+
+        let mut body = String::new();
+        if self.is_empty() {
+            body.push_str("<p>No differences.</p>");
+        }
+        tree.render(&mut body);
+        if !self.warnings().is_empty() {
+            body.push_str("<h2>Warnings</h2><ul>");
+            for warning in self.warnings() {
+                body.push_str(&format!("<li>{}</li>", escape(&warning.to_string())));
+            }
+            body.push_str("</ul>");
+        }
+
+        format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>HTML diff</title>\
+             <style>{}</style></head><body><h1>{}</h1>{}</body></html>",
+            STYLE,
+            escape(self.summary().lines().next().unwrap_or_default()),
+            body
+        )
+    }
+}
+
+/// Differences grouped by the segments of their paths, in document order
+#[derive(Default)]
+struct PathTree<'a> {
+    children: Vec<(&'a str, PathTree<'a>)>,
+    differences: Vec<&'a Difference>,
+}
+
+impl<'a> PathTree<'a> {
+    fn insert(&mut self, difference: &'a Difference) {
+        let mut node = self;
+        for segment in difference.path().split('/').filter(|s| !s.is_empty()) {
+            let index = match node.children.iter().position(|(name, _)| *name == segment) {
+                Some(index) => index,
+                None => {
+                    node.children.push((segment, PathTree::default()));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index].1;
+        }
+        node.differences.push(difference);
+    }
+
+    fn count(&self) -> usize {
+        self.differences.len() + self.children.iter().map(|(_, c)| c.count()).sum::<usize>()
+    }
+
+    fn render(&self, out: &mut String) {
+        if !self.differences.is_empty() {
+            out.push_str("<table><tr><th></th><th>expected</th><th>actual</th></tr>");
+            for difference in &self.differences {
+                let (kind, expected, actual) = sides(difference);
+                out.push_str(&format!(
+                    "<tr><td class=\"kind\">{}</td><td class=\"expected\">{}</td><td class=\"actual\">{}</td></tr>",
+                    escape(&kind),
+                    escape(&expected),
+                    escape(&actual)
+                ));
+            }
+            out.push_str("</table>");
+        }
+        for (segment, child) in &self.children {
+            out.push_str(&format!(
+                "<details open><summary>{} <span class=\"kind\">({})</span></summary>",
+                escape(segment),
+                child.count()
+            ));
+            child.render(out);
+            out.push_str("</details>");
+        }
+    }
+}
+
+/// A short label plus the expected and actual side of a difference
+fn sides(difference: &Difference) -> (String, String, String) {
+    let absent = String::new;
+    match difference {
+        Difference::TagMismatch {
+            expected, actual, ..
+        } => ("tag".to_string(), expected.clone(), actual.clone()),
+        Difference::TagRenamed {
+            expected, actual, ..
+        } => ("renamed".to_string(), expected.clone(), actual.clone()),
+        Difference::AttributeMismatch {
+            name,
+            expected,
+            actual,
+            ..
+        } => (
+            format!("@{}", name),
+            expected.clone().unwrap_or_else(absent),
+            actual.clone().unwrap_or_else(absent),
+        ),
+        Difference::TextMismatch {
+            expected, actual, ..
+        } => ("text".to_string(), expected.clone(), actual.clone()),
+        Difference::MissingNode { expected, .. } => {
+            ("missing".to_string(), expected.clone(), absent())
+        }
+        Difference::ExtraNode { actual, .. } => ("extra".to_string(), absent(), actual.clone()),
+        Difference::BrokenReference {
+            attribute, target, ..
+        } => (format!("broken @{}", attribute), absent(), target.clone()),
+        Difference::Security { issue, .. } => ("security".to_string(), absent(), issue.to_string()),
+        Difference::ElementDiffers { differences, .. } => (
+            "element".to_string(),
+            absent(),
+            format!("{} differences", differences),
+        ),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HtmlComparer;
+
+    #[test]
+    fn test_html_report() {
+        let diff = HtmlComparer::new().diff(
+            "<ul><li class='a'>One</li><li>Two</li></ul>",
+            "<ul><li class='b'>One</li><li>2</li><li>Three</li></ul>",
+        );
+        let report = diff.to_html_report();
+
+        assert!(report.contains("<h1>3 differences: 1 structural, 1 attribute, 1 text</h1>"));
+        assert!(report.contains("<summary>ul <span class=\"kind\">(3)</span></summary>"));
+        assert!(report.contains("<summary>li[1] <span class=\"kind\">(1)</span></summary>"));
+        assert!(report.contains(
+            "<td class=\"kind\">@class</td><td class=\"expected\">a</td><td class=\"actual\">b</td>"
+        ));
+        assert!(report.contains(
+            "<td class=\"kind\">extra</td><td class=\"expected\"></td><td class=\"actual\">&lt;li&gt;</td>"
+        ));
+
+        let empty = HtmlComparer::new().diff("<p>Same</p>", "<p>Same</p>");
+        assert!(empty.to_html_report().contains("No differences."));
+    }
+}
@@ -0,0 +1,1681 @@
+//! Exhaustive comparison that reports every difference between two documents.
+//!
+//! [`HtmlComparer::verify`] stops at the first mismatch. [`HtmlComparer::diff`]
+//! keeps going and returns an [`HtmlDiff`] listing each [`Difference`] with the
+//! path of the node it was found at, e.g. `/html/body/ul/li[2]`. Declarative
+//! shadow roots appear as a `#shadow-root` step below their host.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Html, Node};
+
+use crate::format::attribute_rank;
+use crate::lint::Lint;
+use crate::path_glob;
+use crate::security::SecurityIssue;
+use crate::{
+    Captures, DocumentStats, Edit, HeadingLevels, HtmlCompareOptions, HtmlComparer, ShadowDomMode,
+};
+
+/// Number of differences listed by [`HtmlDiff::summary`]
+pub const SUMMARY_LIMIT: usize = 5;
+
+/// Broad kind of a difference, ordered from most to least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DifferenceCategory {
+    /// A security-relevant construct was introduced (see
+    /// [`HtmlCompareOptions::security_checks`](crate::HtmlCompareOptions))
+    Security,
+    /// Elements differ in tag or are missing/unexpected
+    Structural,
+    /// An attribute differs
+    Attribute,
+    /// Text or comment content differs
+    Text,
+    /// A referenced asset does not exist
+    Reference,
+}
+
+impl fmt::Display for DifferenceCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DifferenceCategory::Security => "security",
+            DifferenceCategory::Structural => "structural",
+            DifferenceCategory::Attribute => "attribute",
+            DifferenceCategory::Text => "text",
+            DifferenceCategory::Reference => "reference",
+        })
+    }
+}
+
+/// A single difference between the expected and actual document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// Nodes at the same position have different tags (or node types, shown
+    /// as `#text` and `#comment`)
+    TagMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+    /// An element has a different tag but the same attributes and contents
+    TagRenamed {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+    /// An attribute is missing, unexpected or has a different value
+    AttributeMismatch {
+        path: String,
+        name: String,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+    /// Text or comment content differs
+    TextMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+    /// A node of the expected document has no counterpart
+    MissingNode { path: String, expected: String },
+    /// A node of the actual document has no counterpart
+    ExtraNode { path: String, actual: String },
+    /// A `src`/`href`-like attribute of the actual document points to an
+    /// asset that does not exist (see [`AssetResolver`](crate::AssetResolver))
+    BrokenReference {
+        path: String,
+        attribute: String,
+        target: String,
+    },
+    /// The actual document introduces a script, event handler or other
+    /// security-relevant construct
+    Security { path: String, issue: SecurityIssue },
+    /// The attributes or text of an element differ in `differences` places,
+    /// reported instead of them at
+    /// [`DiffGranularity::Element`](crate::DiffGranularity::Element)
+    ElementDiffers { path: String, differences: usize },
+}
+
+impl Difference {
+    /// Path of the node the difference was found at
+    ///
+    /// Paths point into the expected document, except for
+    /// [`Difference::ExtraNode`], [`Difference::BrokenReference`] and
+    /// [`Difference::Security`] which point into the actual one.
+    pub fn path(&self) -> &str {
+        match self {
+            Difference::TagMismatch { path, .. }
+            | Difference::TagRenamed { path, .. }
+            | Difference::AttributeMismatch { path, .. }
+            | Difference::TextMismatch { path, .. }
+            | Difference::MissingNode { path, .. }
+            | Difference::ExtraNode { path, .. }
+            | Difference::BrokenReference { path, .. }
+            | Difference::Security { path, .. }
+            | Difference::ElementDiffers { path, .. } => path,
+        }
+    }
+
+    /// Broad kind of the difference
+    pub fn category(&self) -> DifferenceCategory {
+        match self {
+            Difference::AttributeMismatch { .. } => DifferenceCategory::Attribute,
+            Difference::TextMismatch { .. } => DifferenceCategory::Text,
+            Difference::BrokenReference { .. } => DifferenceCategory::Reference,
+            Difference::Security { .. } => DifferenceCategory::Security,
+            Difference::TagMismatch { .. }
+            | Difference::TagRenamed { .. }
+            | Difference::MissingNode { .. }
+            | Difference::ExtraNode { .. }
+            | Difference::ElementDiffers { .. } => DifferenceCategory::Structural,
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difference::TagMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(f, "{}: expected <{}>, found <{}>", path, expected, actual),
+            Difference::TagRenamed {
+                path,
+                expected,
+                actual,
+            } => write!(f, "{}: <{}> renamed to <{}>", path, expected, actual),
+            Difference::AttributeMismatch {
+                path,
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{}: attribute {} expected {}, found {}",
+                path,
+                name,
+                describe_value(expected),
+                describe_value(actual)
+            ),
+            Difference::TextMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(f, "{}: expected '{}', found '{}'", path, expected, actual),
+            Difference::MissingNode { path, expected } => {
+                write!(f, "{}: missing {}", path, expected)
+            }
+            Difference::ExtraNode { path, actual } => write!(f, "{}: unexpected {}", path, actual),
+            Difference::BrokenReference {
+                path,
+                attribute,
+                target,
+            } => write!(f, "{}: broken reference {}={:?}", path, attribute, target),
+            Difference::Security { path, issue } => write!(f, "{}: security: {}", path, issue),
+            Difference::ElementDiffers { path, differences } => write!(
+                f,
+                "{}: element differs ({} difference{})",
+                path,
+                differences,
+                if *differences == 1 { "" } else { "s" }
+            ),
+        }
+    }
+}
+
+/// A finding that doesn't make the documents differ but is worth a look
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// A class of the actual document is not defined by the stylesheet (see
+    /// [`ClassList`](crate::classes::ClassList))
+    UnknownClass { path: String, class: String },
+    /// A class defined by the stylesheet is not used by the actual document
+    UnusedClass { class: String },
+    /// A [`LintRule`](crate::lint::LintRule) flagged the actual document
+    Lint(Lint),
+    /// An attribute name of either document that only a parser oddity can
+    /// produce (see
+    /// [`HtmlCompareOptions::strict_attribute_names`](crate::HtmlCompareOptions::strict_attribute_names))
+    InvalidAttributeName {
+        document: &'static str,
+        path: String,
+        name: String,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::UnknownClass { path, class } => {
+                write!(f, "{}: unknown class {:?}", path, class)
+            }
+            Warning::UnusedClass { class } => write!(f, "unused class {:?}", class),
+            Warning::Lint(lint) => write!(f, "{}", lint),
+            Warning::InvalidAttributeName {
+                document,
+                path,
+                name,
+            } => write!(
+                f,
+                "{}: invalid attribute name {:?} in the {} document",
+                path, name, document
+            ),
+        }
+    }
+}
+
+fn describe_value(value: &Option<String>) -> String {
+    match value {
+        Some(value) => format!("{:?}", value),
+        None => "nothing".to_string(),
+    }
+}
+
+/// All differences found between two documents, in document order
+///
+/// Two diffs are equal when they hold the same findings about the same
+/// documents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlDiff {
+    differences: Vec<Difference>,
+    warnings: Vec<Warning>,
+    /// The edit script and node map, worked out when first asked for
+    derived: Box<Derived>,
+    /// Paths and names of the component instances in the expected document
+    pub(crate) components: Vec<(String, String)>,
+    /// Statistics of the expected and the actual document
+    pub(crate) stats: Box<(DocumentStats, DocumentStats)>,
+}
+
+impl HtmlDiff {
+    /// Whether the documents are equivalent
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Number of differences
+    pub fn len(&self) -> usize {
+        self.differences.len()
+    }
+
+    /// Iterate over the differences in document order
+    pub fn iter(&self) -> std::slice::Iter<'_, Difference> {
+        self.differences.iter()
+    }
+
+    /// Findings that don't count as differences, such as unknown classes
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// The edits that turn the expected document into the actual one (see
+    /// [`crate::edit`])
+    ///
+    /// The script is worked out from the documents the first time it is
+    /// asked for, parsing them again.
+    pub fn edit_script(&self) -> &[Edit] {
+        self.derived.edits.get_or_init(|| {
+            self.derived
+                .with_roots(|comparer, expected, actual, _| comparer.edit_script(expected, actual))
+                .unwrap_or_default()
+        })
+    }
+
+    /// The differences for which `keep` returns true
+    ///
+    /// Like [`Self::ignore_paths`] and [`Self::only_kind`], this narrows a
+    /// diff after the fact, e.g. to drop a region known to be noisy before
+    /// deciding whether a test passes. Warnings, the edit script and the
+    /// document statistics are kept as they are.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let diff = HtmlComparer::new()
+    ///     .diff(expected, actual)
+    ///     .filter(|difference| !difference.to_string().contains("data-build"));
+    /// assert!(diff.is_empty(), "{}", diff);
+    /// ```
+    pub fn filter(mut self, keep: impl FnMut(&Difference) -> bool) -> HtmlDiff {
+        self.differences.retain(keep);
+        self
+    }
+
+    /// The differences whose paths match none of `globs`, e.g.
+    /// `/html/body/footer/**` (see [`Self::filter`] and [`Self::at`] for
+    /// the glob syntax)
+    pub fn ignore_paths<I>(self, globs: I) -> HtmlDiff
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let globs: Vec<I::Item> = globs.into_iter().collect();
+        self.filter(|difference| {
+            !globs
+                .iter()
+                .any(|glob| path_glob::matches(glob.as_ref(), difference.path()))
+        })
+    }
+
+    /// The differences whose paths match `glob`, in document order
+    ///
+    /// In a glob, `*` matches within a step, `**` matches any number of
+    /// steps and `//` stands for `/**/`; a step without an index, like
+    /// `li`, matches it with any index. Paths are the same from run to run
+    /// for the same documents and options, so they can be asserted on.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let diff = HtmlComparer::new().diff(expected, actual);
+    /// // Only the footer may change
+    /// assert_eq!(diff.at("//footer//**").count(), diff.len());
+    /// assert!(diff.ignore_paths(["//footer//**"]).is_empty());
+    /// ```
+    pub fn at<'a>(&'a self, glob: &'a str) -> impl Iterator<Item = &'a Difference> + 'a {
+        self.iter()
+            .filter(move |difference| path_glob::matches(glob, difference.path()))
+    }
+
+    /// The differences in one of `categories` (see [`Self::filter`])
+    pub fn only_kind(self, categories: impl IntoIterator<Item = DifferenceCategory>) -> HtmlDiff {
+        let categories: Vec<_> = categories.into_iter().collect();
+        self.filter(|difference| categories.contains(&difference.category()))
+    }
+
+    /// The differences and warnings of `diffs` combined into one diff, e.g.
+    /// those of every page of a site
+    ///
+    /// The statistics of the documents are added up and the component
+    /// instances kept, so the summary still reports sizes and components.
+    /// The edit script and node map are left empty, since those of
+    /// different documents don't combine. See
+    /// [`DiffSummary`](crate::DiffSummary) to count differences across many
+    /// diffs without keeping them.
+    pub fn merge(diffs: impl IntoIterator<Item = HtmlDiff>) -> HtmlDiff {
+        let mut merged = HtmlDiff::default();
+        for diff in diffs {
+            merged.differences.extend(diff.differences);
+            merged.warnings.extend(diff.warnings);
+            for component in diff.components {
+                if !merged.components.contains(&component) {
+                    merged.components.push(component);
+                }
+            }
+            merged.stats.0.add(&diff.stats.0);
+            merged.stats.1.add(&diff.stats.1);
+        }
+        merged
+    }
+
+    /// `self` with its edit script and node map worked out from `expected`
+    /// and `actual` under `options` when asked for
+    pub(crate) fn derived_from(
+        mut self,
+        expected: &str,
+        actual: &str,
+        options: &HtmlCompareOptions,
+    ) -> HtmlDiff {
+        self.derived = Box::new(Derived {
+            source: Some(Arc::new(Source {
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+                options: options.clone(),
+            })),
+            ..Default::default()
+        });
+        self
+    }
+
+    /// The actual document the diff was made against, if it was made from
+    /// source
+    pub(crate) fn actual_source(&self) -> Option<&str> {
+        self.derived
+            .source
+            .as_ref()
+            .map(|source| source.actual.as_str())
+    }
+
+    /// The expected and actual paths of the nodes that were compared with
+    /// each other, in document order of the expected nodes
+    ///
+    /// Nodes without a counterpart are left out, as are the insides of code
+    /// blocks compared by their text. Like [`Self::edit_script`], the map is
+    /// worked out the first time it is asked for.
+    pub fn node_map(&self) -> &[(String, String)] {
+        self.derived.node_map.get_or_init(|| {
+            self.derived
+                .with_roots(|comparer, expected, actual, found| match found {
+                    true => comparer.node_map(expected, actual),
+                    false => Vec::new(),
+                })
+                .unwrap_or_default()
+        })
+    }
+
+    /// The path of the actual node compared with the expected node at
+    /// `expected_path`, if it had a counterpart
+    pub fn actual_path(&self, expected_path: &str) -> Option<&str> {
+        self.node_map()
+            .iter()
+            .find(|(expected, _)| expected == expected_path)
+            .map(|(_, actual)| actual.as_str())
+    }
+
+    /// Number of differences in `category`
+    pub fn count(&self, category: DifferenceCategory) -> usize {
+        self.iter()
+            .filter(|difference| difference.category() == category)
+            .count()
+    }
+
+    /// A bounded report: the number of differences per category followed by
+    /// the [`SUMMARY_LIMIT`] most severe ones
+    ///
+    /// ```text
+    /// 17 differences: 3 structural, 10 attribute, 4 text
+    ///   /html/body/main: missing <section>
+    ///   ...
+    ///   ... and 12 more; call `HtmlComparer::diff()` for the full report
+    /// ```
+    pub fn summary(&self) -> String {
+        let counts: Vec<_> = [
+            DifferenceCategory::Security,
+            DifferenceCategory::Structural,
+            DifferenceCategory::Attribute,
+            DifferenceCategory::Text,
+            DifferenceCategory::Reference,
+        ]
+        .into_iter()
+        .map(|category| (category, self.count(category)))
+        .filter(|(_, count)| *count > 0)
+        .map(|(category, count)| format!("{} {}", count, category))
+        .collect();
+
+        let mut summary = format!(
+            "{} difference{}: {}",
+            self.len(),
+            if self.len() == 1 { "" } else { "s" },
+            counts.join(", ")
+        );
+        if let Some(components) = self.component_summary() {
+            summary.push_str(&format!("\n  by component: {}", components));
+        }
+        if let Some(sizes) = self.size_summary() {
+            summary.push_str(&format!("\n  size: {}", sizes));
+        }
+
+        let mut by_severity: Vec<_> = self.iter().collect();
+        by_severity.sort_by_key(|difference| difference.category());
+        for difference in by_severity.iter().take(SUMMARY_LIMIT) {
+            summary.push_str(&format!("\n  {}", difference));
+        }
+        if self.len() > SUMMARY_LIMIT {
+            summary.push_str(&format!(
+                "\n  ... and {} more; call `HtmlComparer::diff()` for the full report",
+                self.len() - SUMMARY_LIMIT
+            ));
+        }
+        if !self.warnings.is_empty() {
+            summary.push_str(&format!(
+                "\n  plus {} warning{}",
+                self.warnings.len(),
+                if self.warnings.len() == 1 { "" } else { "s" }
+            ));
+        }
+        summary
+    }
+}
+
+/// The documents a diff was made of and what is worked out from them only
+/// when asked for
+#[derive(Debug, Clone, Default)]
+struct Derived {
+    source: Option<Arc<Source>>,
+    edits: OnceLock<Vec<Edit>>,
+    /// Expected and actual paths of the nodes compared with each other
+    node_map: OnceLock<Vec<(String, String)>>,
+}
+
+#[derive(Debug)]
+struct Source {
+    expected: String,
+    actual: String,
+    options: HtmlCompareOptions,
+}
+
+impl Derived {
+    /// Parse the documents again and call `derive` with the roots compared,
+    /// and whether those were found, or the root elements of the documents
+    fn with_roots<T>(
+        &self,
+        derive: impl FnOnce(&HtmlComparer, ElementRef, ElementRef, bool) -> T,
+    ) -> Option<T> {
+        let source = self.source.as_ref()?;
+        let comparer = HtmlComparer::with_options(source.options.clone());
+        let (expected, actual) = (
+            comparer.parse(&source.expected),
+            comparer.parse(&source.actual),
+        );
+        Some(
+            match (
+                comparer.compared_root(&expected, "expected"),
+                comparer.compared_root(&actual, "actual"),
+            ) {
+                (Ok(expected_root), Ok(actual_root)) => {
+                    derive(&comparer, expected_root, actual_root, true)
+                }
+                _ => derive(
+                    &comparer,
+                    expected.root_element(),
+                    actual.root_element(),
+                    false,
+                ),
+            },
+        )
+    }
+}
+
+impl PartialEq for Derived {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.source, &other.source) {
+            (Some(source), Some(other)) => {
+                (&source.expected, &source.actual) == (&other.expected, &other.actual)
+            }
+            (source, other) => source.is_none() && other.is_none(),
+        }
+    }
+}
+
+impl Eq for Derived {}
+
+impl fmt::Display for HtmlDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for difference in self.iter() {
+            writeln!(f, "{}", difference)?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoIterator for HtmlDiff {
+    type Item = Difference;
+    type IntoIter = std::vec::IntoIter<Difference>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.differences.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a HtmlDiff {
+    type Item = &'a Difference;
+    type IntoIter = std::slice::Iter<'a, Difference>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.differences.iter()
+    }
+}
+
+impl HtmlComparer {
+    /// Compare two HTML strings and collect every difference instead of
+    /// stopping at the first one
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let diff = HtmlComparer::new().diff("<p class='a'>Hi</p>", "<p class='b'>Bye</p>");
+    /// assert_eq!(diff.len(), 2);
+    /// ```
+    pub fn diff(&self, expected: &str, actual: &str) -> HtmlDiff {
+        let expected_doc = self.parse(expected);
+        let actual_doc = self.parse(actual);
+        let diff = match (
+            self.compared_root(&expected_doc, "expected"),
+            self.compared_root(&actual_doc, "actual"),
+        ) {
+            (Ok(expected_root), Ok(actual_root)) => self.diff_parsed(
+                &expected_doc,
+                &actual_doc,
+                expected_root,
+                actual_root,
+                &mut Captures::default(),
+            ),
+            (Err(err), _) | (_, Err(err)) => {
+                let (expected_root, actual_root) =
+                    (expected_doc.root_element(), actual_doc.root_element());
+                let mut diff = self.diff_parsed(
+                    &expected_doc,
+                    &actual_doc,
+                    expected_root,
+                    actual_root,
+                    &mut Captures::default(),
+                );
+                diff.differences = vec![Difference::MissingNode {
+                    path: "/html".to_string(),
+                    expected: err.to_string(),
+                }];
+                diff
+            }
+        };
+        diff.derived_from(expected, actual, &self.options)
+    }
+
+    /// The first `limit` differences [`Self::diff`] would find, and whether
+    /// there are more
+    ///
+    /// The walk stops as soon as it found more than `limit`, and nothing
+    /// else of a diff is worked out: no warnings, statistics or components.
+    pub(crate) fn differences_up_to(
+        &self,
+        expected: &str,
+        actual: &str,
+        limit: usize,
+    ) -> (Vec<Difference>, bool) {
+        let expected_doc = self.parse(expected);
+        let actual_doc = self.parse(actual);
+        let mut differences = match (
+            self.compared_root(&expected_doc, "expected"),
+            self.compared_root(&actual_doc, "actual"),
+        ) {
+            (Ok(expected_root), Ok(actual_root)) => {
+                let mut differences = Vec::new();
+                self.diff_trees(
+                    *expected_root,
+                    *actual_root,
+                    self.node_path(*expected_root),
+                    &mut differences,
+                    &mut Captures::default(),
+                    limit.saturating_add(1),
+                );
+                self.at_granularity(differences)
+            }
+            (Err(err), _) | (_, Err(err)) => vec![Difference::MissingNode {
+                path: "/html".to_string(),
+                expected: err.to_string(),
+            }],
+        };
+        if differences.len() <= limit {
+            differences.extend(self.broken_references(&actual_doc));
+            differences.extend(self.security_differences(&expected_doc, &actual_doc));
+        }
+        let more = differences.len() > limit;
+        differences.truncate(limit);
+        (differences, more)
+    }
+
+    /// [`Self::diff`] of parsed documents, comparing `expected_root` with
+    /// `actual_root` and recording the values of placeholders in `captures`
+    pub(crate) fn diff_parsed(
+        &self,
+        expected_doc: &Html,
+        actual_doc: &Html,
+        expected_root: ElementRef,
+        actual_root: ElementRef,
+        captures: &mut Captures,
+    ) -> HtmlDiff {
+        let mut differences = Vec::new();
+        self.diff_trees(
+            *expected_root,
+            *actual_root,
+            self.node_path(*expected_root),
+            &mut differences,
+            captures,
+            usize::MAX,
+        );
+        let mut differences = self.at_granularity(differences);
+        differences.extend(self.broken_references(actual_doc));
+        differences.extend(self.security_differences(expected_doc, actual_doc));
+        let mut warnings = self.attribute_name_warnings(expected_doc, actual_doc);
+        warnings.extend(self.class_warnings(actual_doc));
+        warnings.extend(
+            self.lint_document(actual_doc, &self.options.lint_rules)
+                .into_iter()
+                .map(Warning::Lint),
+        );
+        HtmlDiff {
+            differences,
+            warnings,
+            derived: Box::default(),
+            components: self.component_instances(expected_root),
+            stats: Box::new((
+                self.document_stats(expected_root),
+                self.document_stats(actual_root),
+            )),
+        }
+    }
+
+    /// Visit `element` and its descendants in document order along with
+    /// their paths, following the same node filtering as the comparison
+    pub(crate) fn walk_elements<'a>(
+        &self,
+        element: ElementRef<'a>,
+        path: &str,
+        visit: &mut impl FnMut(ElementRef<'a>, &str),
+    ) {
+        // An explicit stack, so that deep documents can't overflow the
+        // call stack; entries are pushed in reverse to pop in order
+        let mut stack = vec![(element, path.to_string())];
+        while let Some((element, path)) = stack.pop() {
+            visit(element, &path);
+
+            let shadow_root = match self.options.shadow_dom {
+                ShadowDomMode::Separate => self.shadow_root(element),
+                _ => None,
+            };
+            let shadow_root = shadow_root.map(|root| (root, format!("{}/#shadow-root", path)));
+            if self.options.light_dom_first {
+                stack.extend(shadow_root.clone());
+            }
+            let children = self.child_nodes(element);
+            for (child, child_path) in children.iter().zip(child_paths(&path, &children)).rev() {
+                if let Some(child) = ElementRef::wrap(*child) {
+                    stack.push((child, child_path));
+                }
+            }
+            if !self.options.light_dom_first {
+                stack.extend(shadow_root);
+            }
+        }
+    }
+
+    /// The path of `node` as [`Self::diff`] would report it
+    pub(crate) fn node_path(&self, node: NodeRef<Node>) -> String {
+        let mut lineage: Vec<_> = match self.options.shadow_dom {
+            ShadowDomMode::Composed => {
+                std::iter::successors(self.composed_parent(node), |ancestor| {
+                    self.composed_parent(*ancestor)
+                })
+                .collect()
+            }
+            _ => node
+                .ancestors()
+                .filter(|ancestor| ancestor.value().is_element())
+                .collect(),
+        };
+        lineage.reverse();
+        lineage.push(node);
+
+        let mut path = "/html".to_string();
+        let Some(mut parent) = ElementRef::wrap(lineage[0]) else {
+            return path;
+        };
+        for &child in &lineage[1..] {
+            let children = self.child_nodes(parent);
+            if let Some(index) = children.iter().position(|other| other.id() == child.id()) {
+                path = child_paths(&path, &children).swap_remove(index);
+            } else if self
+                .shadow_root(parent)
+                .is_some_and(|root| root.id() == child.id())
+            {
+                path.push_str("/#shadow-root");
+            }
+            // Otherwise the child is left out of the comparison in favor of
+            // its contents, like the only paragraph of a list item
+            if let Some(element) = ElementRef::wrap(child) {
+                parent = element;
+            }
+        }
+        path
+    }
+
+    /// Collect the differences between `expected` and `actual`, found at
+    /// `path`, and between their subtrees, stopping once there are `limit`
+    ///
+    /// Values are matched like [`Self::verify`] does, so placeholders record
+    /// what they match in `captures` and matchers apply.
+    ///
+    /// The trees are walked with an explicit stack of [`DiffTask`]s rather
+    /// than by recursion, so arbitrarily deep documents can't overflow the
+    /// stack. A walk cut short by `limit` doesn't report the elements still
+    /// open to [`HtmlCompareOptions::on_node_compared`](crate::HtmlCompareOptions).
+    pub(crate) fn diff_trees<'a>(
+        &self,
+        expected: NodeRef<'a, Node>,
+        actual: NodeRef<'a, Node>,
+        path: String,
+        differences: &mut Vec<Difference>,
+        captures: &mut Captures,
+        limit: usize,
+    ) {
+        let mut stack = DiffStack {
+            tasks: vec![DiffTask::Nodes(expected, actual, path)],
+            levels: self.heading_levels(expected, actual),
+        };
+        loop {
+            if differences.len() >= limit {
+                return close_renamed(&stack.tasks, differences);
+            }
+            let Some(task) = stack.tasks.pop() else {
+                return;
+            };
+            match task {
+                DiffTask::Nodes(expected, actual, path) => {
+                    self.diff_nodes(expected, actual, path, differences, captures, &mut stack)
+                }
+                DiffTask::Report(difference) => differences.push(difference),
+                DiffTask::Close {
+                    path,
+                    found,
+                    started,
+                    renamed,
+                } => {
+                    if let Some((expected, actual)) = renamed {
+                        // A subtree that differs only in its root tag was
+                        // renamed; otherwise the tags are reported alone
+                        let contents_differ = differences.len() > found;
+                        differences.truncate(found);
+                        differences.push(match contents_differ {
+                            false => Difference::TagRenamed {
+                                path: path.clone(),
+                                expected,
+                                actual,
+                            },
+                            true => Difference::TagMismatch {
+                                path: path.clone(),
+                                expected,
+                                actual,
+                            },
+                        });
+                    }
+                    if let (Some(hook), Some(started)) = (&self.options.on_node_compared, started) {
+                        hook.call(&path, started.elapsed(), differences.len() == found);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Diff two nodes, pushing what is left to diff of their subtrees
+    fn diff_nodes<'a>(
+        &self,
+        expected: NodeRef<'a, Node>,
+        actual: NodeRef<'a, Node>,
+        path: String,
+        differences: &mut Vec<Difference>,
+        captures: &mut Captures,
+        stack: &mut DiffStack<'a>,
+    ) {
+        match (expected.value(), actual.value()) {
+            (Node::Text(_), Node::Text(_)) => {
+                let expected_str = self.node_text(expected);
+                let actual_str = self.node_text(actual);
+                // A placeholder capturing another value than before is a
+                // mismatch like any other
+                if !self.options.ignore_text
+                    && !self
+                        .values_match(expected, None, &expected_str, &actual_str, captures)
+                        .unwrap_or(false)
+                {
+                    differences.push(Difference::TextMismatch {
+                        path,
+                        expected: expected_str.to_string(),
+                        actual: actual_str.to_string(),
+                    });
+                }
+            }
+            (Node::Comment(expected_comment), Node::Comment(actual_comment)) => {
+                if !self.options.ignore_comments && expected_comment.trim() != actual_comment.trim()
+                {
+                    differences.push(Difference::TextMismatch {
+                        path,
+                        expected: expected_comment.trim().to_string(),
+                        actual: actual_comment.trim().to_string(),
+                    });
+                }
+            }
+            _ => match (ElementRef::wrap(expected), ElementRef::wrap(actual)) {
+                (Some(expected_el), Some(actual_el)) => {
+                    self.diff_elements(expected_el, actual_el, path, differences, captures, stack)
+                }
+                _ => differences.push(Difference::TagMismatch {
+                    path,
+                    expected: node_key(expected),
+                    actual: node_key(actual),
+                }),
+            },
+        }
+    }
+
+    /// Diff what two elements hold themselves and push the diffs of their
+    /// shadow roots and children, in document order
+    fn diff_elements<'a>(
+        &self,
+        expected: ElementRef<'a>,
+        actual: ElementRef<'a>,
+        path: String,
+        differences: &mut Vec<Difference>,
+        captures: &mut Captures,
+        stack: &mut DiffStack<'a>,
+    ) {
+        let levels = stack.levels;
+        let renamed = (!self.tags_match(
+            self.element_name(expected, levels.expected),
+            self.element_name(actual, levels.actual),
+        ))
+        .then(|| {
+            (
+                expected.value().name().to_string(),
+                actual.value().name().to_string(),
+            )
+        });
+        let started = self
+            .options
+            .on_node_compared
+            .as_ref()
+            .map(|_| Instant::now());
+        if renamed.is_some() || started.is_some() {
+            stack.tasks.push(DiffTask::Close {
+                path: path.clone(),
+                found: differences.len(),
+                started,
+                renamed,
+            });
+        }
+
+        if !self.options.ignore_attributes {
+            self.diff_attributes(expected, actual, &path, differences, captures);
+        }
+
+        if self.options.ignore_style_contents && expected.value().name() == "style" {
+            return;
+        }
+
+        if self.is_highlighted_code(expected) {
+            let (expected_code, actual_code) = (self.code_text(expected), self.code_text(actual));
+            if !self.options.ignore_text && expected_code != actual_code {
+                differences.push(Difference::TextMismatch {
+                    path: format!("{}/text()", path),
+                    expected: expected_code,
+                    actual: actual_code,
+                });
+            }
+            return;
+        }
+
+        // Tasks are pushed in reverse to pop in document order
+        if self.options.light_dom_first {
+            self.push_shadow_roots(expected, actual, &path, stack);
+        }
+        let (expected_children, actual_children) = self.compared_children(expected, actual, levels);
+        let expected_paths = child_paths(&path, &expected_children);
+        let actual_paths = child_paths(&path, &actual_children);
+        let alignments = self.align_children(&expected_children, &actual_children, levels);
+        stack.tasks.extend(
+            alignments
+                .into_iter()
+                .rev()
+                .map(|alignment| match alignment {
+                    Alignment::Pair(i, j) => DiffTask::Nodes(
+                        expected_children[i],
+                        actual_children[j],
+                        expected_paths[i].clone(),
+                    ),
+                    Alignment::Missing(i) => DiffTask::Report(Difference::MissingNode {
+                        path: expected_paths[i].clone(),
+                        expected: describe_node(expected_children[i]),
+                    }),
+                    Alignment::Extra(j) => DiffTask::Report(Difference::ExtraNode {
+                        path: actual_paths[j].clone(),
+                        actual: describe_node(actual_children[j]),
+                    }),
+                }),
+        );
+        if !self.options.light_dom_first {
+            self.push_shadow_roots(expected, actual, &path, stack);
+        }
+    }
+
+    /// Push the diff of the declarative shadow roots of two elements in
+    /// [`ShadowDomMode::Separate`]
+    fn push_shadow_roots<'a>(
+        &self,
+        expected: ElementRef<'a>,
+        actual: ElementRef<'a>,
+        path: &str,
+        stack: &mut DiffStack<'a>,
+    ) {
+        if self.options.shadow_dom != ShadowDomMode::Separate {
+            return;
+        }
+        let shadow_path = format!("{}/#shadow-root", path);
+        stack.tasks.push(
+            match (self.shadow_root(expected), self.shadow_root(actual)) {
+                (Some(expected_root), Some(actual_root)) => {
+                    DiffTask::Nodes(*expected_root, *actual_root, shadow_path)
+                }
+                (Some(_), None) => DiffTask::Report(Difference::MissingNode {
+                    path: shadow_path,
+                    expected: "shadow root".to_string(),
+                }),
+                (None, Some(_)) => DiffTask::Report(Difference::ExtraNode {
+                    path: shadow_path,
+                    actual: "shadow root".to_string(),
+                }),
+                (None, None) => return,
+            },
+        );
+    }
+
+    fn diff_attributes(
+        &self,
+        expected: ElementRef,
+        actual: ElementRef,
+        path: &str,
+        differences: &mut Vec<Difference>,
+        captures: &mut Captures,
+    ) {
+        let expected_attrs: BTreeMap<_, _> =
+            self.compared_attributes(expected).into_iter().collect();
+        let actual_attrs: BTreeMap<_, _> = self.compared_attributes(actual).into_iter().collect();
+        let names: BTreeSet<&str> = expected_attrs
+            .keys()
+            .chain(actual_attrs.keys())
+            .copied()
+            .collect();
+        let mut names: Vec<&str> = names.into_iter().collect();
+        // A stable sort keeps the attributes of equal rank in name order
+        names.sort_by_key(|name| attribute_rank(&self.options.attribute_order, name));
+
+        for name in names {
+            let expected_value = expected_attrs.get(name);
+            let actual_value = actual_attrs.get(name);
+            let matches = match (expected_value, actual_value) {
+                (Some(expected_value), Some(actual_value)) => self
+                    .values_match(
+                        *expected,
+                        Some(name),
+                        expected_value,
+                        actual_value,
+                        captures,
+                    )
+                    .unwrap_or(false),
+                (expected_value, actual_value) => expected_value == actual_value,
+            };
+            if !matches {
+                differences.push(Difference::AttributeMismatch {
+                    path: path.to_string(),
+                    name: name.to_string(),
+                    expected: expected_value.map(|value| value.to_string()),
+                    actual: actual_value.map(|value| value.to_string()),
+                });
+            }
+        }
+    }
+
+    /// Pair up the children of two matched elements, in documents whose
+    /// topmost headings are at `levels`
+    pub(crate) fn align_children(
+        &self,
+        expected: &[NodeRef<Node>],
+        actual: &[NodeRef<Node>],
+        levels: HeadingLevels,
+    ) -> Vec<Alignment> {
+        if self.options.ignore_sibling_order {
+            self.align_unordered(expected, actual, levels)
+        } else {
+            let (expected_keys, actual_keys) = self.alignment_keys(expected, actual, levels);
+            align_ordered(&expected_keys, &actual_keys)
+        }
+    }
+
+    /// Match children regardless of order, preferring identical subtrees and
+    /// falling back to the first unmatched node of the same kind
+    ///
+    /// A node that is the only one of its kind on both sides is paired up
+    /// without checking that it is identical, which would walk its subtree
+    /// once more for every level above it. Pairs are listed in the order of
+    /// the expected children, followed by the extra actual ones.
+    fn align_unordered(
+        &self,
+        expected: &[NodeRef<Node>],
+        actual: &[NodeRef<Node>],
+        levels: HeadingLevels,
+    ) -> Vec<Alignment> {
+        let (expected_keys, actual_keys) = self.alignment_keys(expected, actual, levels);
+        let count = |keys: &[String], key: &String| keys.iter().filter(|k| *k == key).count();
+        let mut partners = vec![None; expected.len()];
+        let mut matched = vec![false; actual.len()];
+
+        for (i, expected_child) in expected.iter().enumerate() {
+            let key = &expected_keys[i];
+            if count(&expected_keys, key) == 1 && count(&actual_keys, key) == 1 {
+                continue;
+            }
+            partners[i] = (0..actual.len()).find(|&j| {
+                !matched[j]
+                    && actual_keys[j] == *key
+                    && self.identical(*expected_child, actual[j], levels)
+            });
+            if let Some(j) = partners[i] {
+                matched[j] = true;
+            }
+        }
+
+        for i in 0..expected.len() {
+            if partners[i].is_some() {
+                continue;
+            }
+            partners[i] =
+                (0..actual.len()).find(|&j| !matched[j] && actual_keys[j] == expected_keys[i]);
+            if let Some(j) = partners[i] {
+                matched[j] = true;
+            }
+        }
+
+        let mut alignments: Vec<_> = partners
+            .into_iter()
+            .enumerate()
+            .map(|(i, partner)| match partner {
+                Some(j) => Alignment::Pair(i, j),
+                None => Alignment::Missing(i),
+            })
+            .collect();
+        alignments.extend(
+            (0..actual.len())
+                .filter(|&j| !matched[j])
+                .map(Alignment::Extra),
+        );
+        alignments
+    }
+
+    /// Whether two nodes compare equal, including their subtrees, in
+    /// documents whose topmost headings are at `levels`
+    pub(crate) fn identical(
+        &self,
+        expected: NodeRef<Node>,
+        actual: NodeRef<Node>,
+        levels: HeadingLevels,
+    ) -> bool {
+        if self.alignment_key(expected, levels.expected)
+            != self.alignment_key(actual, levels.actual)
+        {
+            return false;
+        }
+        // Elements go through the walk of `verify`, whose explicit stack
+        // also covers the unordered matching of children; a diff walk would
+        // have to call back into this function for each level
+        if let (Some(expected), Some(actual)) =
+            (ElementRef::wrap(expected), ElementRef::wrap(actual))
+        {
+            return self
+                .compare_trees(expected, actual, &mut Captures::default(), levels)
+                .is_ok();
+        }
+        // Other nodes have no subtree, so diffing them pushes no tasks
+        let mut scratch = Vec::new();
+        self.diff_nodes(
+            expected,
+            actual,
+            String::new(),
+            &mut scratch,
+            &mut Captures::default(),
+            &mut DiffStack {
+                tasks: Vec::new(),
+                levels,
+            },
+        );
+        scratch.is_empty()
+    }
+
+    /// What children are aligned by: the tag of an element, in a document
+    /// whose topmost heading is at level `top_heading` and taking
+    /// [`HtmlCompareOptions::equivalent_tags`](crate::HtmlCompareOptions)
+    /// into account, or the node type
+    pub(crate) fn alignment_key(&self, node: NodeRef<Node>, top_heading: usize) -> String {
+        match ElementRef::wrap(node) {
+            Some(element) => self
+                .canonical_tag(self.element_name(element, top_heading))
+                .to_string(),
+            None => node_key(node),
+        }
+    }
+
+    /// The [`Self::alignment_key`]s of the expected and actual children
+    pub(crate) fn alignment_keys(
+        &self,
+        expected: &[NodeRef<Node>],
+        actual: &[NodeRef<Node>],
+        levels: HeadingLevels,
+    ) -> (Vec<String>, Vec<String>) {
+        let keys = |nodes: &[NodeRef<Node>], top_heading| {
+            nodes
+                .iter()
+                .map(|node| self.alignment_key(*node, top_heading))
+                .collect()
+        };
+        (keys(expected, levels.expected), keys(actual, levels.actual))
+    }
+}
+
+/// The explicit stack [`HtmlComparer::diff_trees`] walks the trees with,
+/// along with the heading levels of their documents
+struct DiffStack<'a> {
+    tasks: Vec<DiffTask<'a>>,
+    levels: HeadingLevels,
+}
+
+/// One entry of the explicit stack [`HtmlComparer::diff_trees`] walks the
+/// trees with
+pub(crate) enum DiffTask<'a> {
+    /// Diff two nodes found at a path, and their subtrees
+    Nodes(NodeRef<'a, Node>, NodeRef<'a, Node>, String),
+    /// Report a difference found ahead of its turn in document order
+    Report(Difference),
+    /// Finish the diff of two elements once their subtrees are done
+    Close {
+        path: String,
+        /// Number of differences found before the elements
+        found: usize,
+        /// When the elements were opened, to report to
+        /// [`HtmlCompareOptions::on_node_compared`](crate::HtmlCompareOptions)
+        started: Option<Instant>,
+        /// The expected and actual tag of elements whose tags differ
+        renamed: Option<(String, String)>,
+    },
+}
+
+/// Replace the differences found inside the outermost renamed element still
+/// open on `stack` by the mismatch of its tags, for a walk cut short
+fn close_renamed(stack: &[DiffTask], differences: &mut Vec<Difference>) {
+    let renamed = stack.iter().find_map(|task| match task {
+        DiffTask::Close {
+            path,
+            found,
+            renamed: Some((expected, actual)),
+            ..
+        } => Some((path, *found, expected, actual)),
+        _ => None,
+    });
+    if let Some((path, found, expected, actual)) = renamed {
+        differences.truncate(found);
+        differences.push(Difference::TagMismatch {
+            path: path.clone(),
+            expected: expected.clone(),
+            actual: actual.clone(),
+        });
+    }
+}
+
+/// How a child of one element corresponds to the children of the other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Alignment {
+    /// `expected[i]` corresponds to `actual[j]`
+    Pair(usize, usize),
+    /// `expected[i]` has no counterpart
+    Missing(usize),
+    /// `actual[j]` has no counterpart
+    Extra(usize),
+}
+
+/// Align children by their tag (or node type) with a longest common
+/// subsequence, pairing up the unaligned nodes between two anchors
+/// positionally so that a changed tag is reported as one mismatch rather
+/// than a missing and an extra node
+pub(crate) fn align_ordered(expected_keys: &[String], actual_keys: &[String]) -> Vec<Alignment> {
+    let (expected_len, actual_len) = (expected_keys.len(), actual_keys.len());
+    let mut anchors = longest_common_subsequence(expected_keys, actual_keys);
+    anchors.push((expected_len, actual_len));
+
+    let mut alignments = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (anchor_i, anchor_j) in anchors {
+        while i < anchor_i && j < anchor_j {
+            alignments.push(Alignment::Pair(i, j));
+            i += 1;
+            j += 1;
+        }
+        alignments.extend((i..anchor_i).map(Alignment::Missing));
+        alignments.extend((j..anchor_j).map(Alignment::Extra));
+        if anchor_i < expected_len {
+            alignments.push(Alignment::Pair(anchor_i, anchor_j));
+        }
+        i = anchor_i + 1;
+        j = anchor_j + 1;
+    }
+    alignments
+}
+
+/// The tag of an element, or `#text`/`#comment` for other nodes
+pub(crate) fn node_key(node: NodeRef<Node>) -> String {
+    match node.value() {
+        Node::Element(element) => element.name().to_string(),
+        Node::Text(_) => "#text".to_string(),
+        Node::Comment(_) => "#comment".to_string(),
+        other => format!("#{}", crate::node_type_name(other).to_ascii_lowercase()),
+    }
+}
+
+fn describe_node(node: NodeRef<Node>) -> String {
+    match node.value() {
+        Node::Element(element) => format!("<{}>", element.name()),
+        Node::Text(text) => format!("text '{}'", text.trim()),
+        Node::Comment(comment) => format!("comment '{}'", comment.trim()),
+        other => crate::node_type_name(other).to_string(),
+    }
+}
+
+/// XPath-like paths for each child, indexing only repeated names:
+/// `/html/body/p`, `/html/body/li[2]`, `/html/body/text()`
+pub(crate) fn child_paths(parent: &str, children: &[NodeRef<Node>]) -> Vec<String> {
+    let names: Vec<String> = children
+        .iter()
+        .map(|child| match child.value() {
+            Node::Text(_) => "text()".to_string(),
+            Node::Comment(_) => "comment()".to_string(),
+            _ => node_key(*child),
+        })
+        .collect();
+
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let total = names.iter().filter(|other| *other == name).count();
+            if total == 1 {
+                format!("{}/{}", parent, name)
+            } else {
+                let position = names[..i].iter().filter(|other| *other == name).count() + 1;
+                format!("{}/{}[{}]", parent, name, position)
+            }
+        })
+        .collect()
+}
+
+/// Largest table of lengths [`longest_common_subsequence`] fills in; longer
+/// sequences are split up so that memory stays linear in their length
+const LCS_TABLE_CELLS: usize = 1 << 20;
+
+/// Index pairs of a longest common subsequence of `a` and `b`
+pub(crate) fn longest_common_subsequence<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    if (a.len() + 1).saturating_mul(b.len() + 1) > LCS_TABLE_CELLS {
+        let mut pairs = Vec::new();
+        split_common_subsequence(a, b, (0, 0), &mut pairs);
+        return pairs;
+    }
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Hirschberg's algorithm: find where a longest common subsequence of `a`
+/// and `b` crosses the middle of `a` from two rows of lengths, and solve
+/// both halves, pushing the pairs offset by `offset` in order
+///
+/// Halving `a` keeps the recursion as deep as the logarithm of its length.
+fn split_common_subsequence<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    offset: (usize, usize),
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+    if a.len() == 1 {
+        if let Some(j) = b.iter().position(|item| *item == a[0]) {
+            pairs.push((offset.0, offset.1 + j));
+        }
+        return;
+    }
+    let middle = a.len() / 2;
+    let before = prefix_lengths(a[..middle].iter(), b.iter());
+    let mut after = prefix_lengths(a[middle..].iter().rev(), b.iter().rev());
+    after.reverse();
+    let mut split = 0;
+    for j in 1..=b.len() {
+        if before[j] + after[j] > before[split] + after[split] {
+            split = j;
+        }
+    }
+    split_common_subsequence(&a[..middle], &b[..split], offset, pairs);
+    split_common_subsequence(
+        &a[middle..],
+        &b[split..],
+        (offset.0 + middle, offset.1 + split),
+        pairs,
+    );
+}
+
+/// The lengths of the longest common subsequences of `a` and each prefix of
+/// `b`, keeping a single row of the table
+fn prefix_lengths<'a, T: PartialEq + 'a>(
+    a: impl Iterator<Item = &'a T>,
+    b: impl Iterator<Item = &'a T> + Clone,
+) -> Vec<usize> {
+    let mut row = vec![0; b.clone().count() + 1];
+    for item in a {
+        let mut diagonal = 0;
+        for (j, other) in b.clone().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = match item == other {
+                true => diagonal + 1,
+                false => above.max(row[j]),
+            };
+            diagonal = above;
+        }
+    }
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareError, HtmlCompareOptions};
+
+    #[test]
+    fn test_split_common_subsequence() {
+        let a: Vec<u32> = (0..300).map(|i| (i * 7 + i / 5) % 11).collect();
+        let b: Vec<u32> = (0..250).map(|i| (i * 5 + i / 3) % 11).collect();
+        let table = longest_common_subsequence(&a, &b);
+        let mut split = Vec::new();
+        split_common_subsequence(&a, &b, (0, 0), &mut split);
+        assert_eq!(split.len(), table.len());
+        assert!(split.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+        assert!(split.iter().all(|&(i, j)| a[i] == b[j]));
+    }
+
+    #[test]
+    fn test_filter_diff() {
+        let diff = HtmlComparer::new().diff(
+            r#"<main><p class="a">One</p></main><footer><p>Built 1</p><p>v1</p></footer>"#,
+            r#"<main><p class="b">Two</p></main><footer><p>Built 2</p><p>v2</p></footer>"#,
+        );
+        assert_eq!(diff.len(), 4);
+
+        let outside_footer = diff.clone().ignore_paths(["/html/body/footer/**"]);
+        assert_eq!(
+            outside_footer
+                .iter()
+                .map(Difference::path)
+                .collect::<Vec<_>>(),
+            ["/html/body/main/p", "/html/body/main/p/text()"]
+        );
+        let text = outside_footer.clone().only_kind([DifferenceCategory::Text]);
+        assert_eq!(text.len(), 1);
+        assert_eq!(text.count(DifferenceCategory::Attribute), 0);
+        assert!(outside_footer
+            .clone()
+            .only_kind([DifferenceCategory::Security])
+            .is_empty());
+
+        assert_eq!(diff.at("//footer//**").count(), 2);
+        assert_eq!(
+            diff.at("/html/body/*/p[2]/text()")
+                .map(Difference::path)
+                .collect::<Vec<_>>(),
+            ["/html/body/footer/p[2]/text()"]
+        );
+        assert_eq!(diff.at("//text()").count(), 3);
+        assert_eq!(diff.at("/html/body/nav/**").count(), 0);
+
+        let first_paragraphs = diff.filter(|difference| !difference.path().contains("p[2]"));
+        assert_eq!(first_paragraphs.len(), 3);
+    }
+
+    #[test]
+    fn test_diff_collects_all_differences() {
+        let diff = HtmlComparer::new().diff(
+            r#"<ul class="nav"><li>One</li><li>Two</li><li>Three</li></ul><p>End</p>"#,
+            r#"<ul class="menu"><li>One</li><li>2</li></ul><div>Fin</div>"#,
+        );
+
+        assert_eq!(
+            diff.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                Difference::AttributeMismatch {
+                    path: "/html/body/ul".to_string(),
+                    name: "class".to_string(),
+                    expected: Some("nav".to_string()),
+                    actual: Some("menu".to_string()),
+                },
+                Difference::TextMismatch {
+                    path: "/html/body/ul/li[2]/text()".to_string(),
+                    expected: "Two".to_string(),
+                    actual: "2".to_string(),
+                },
+                Difference::MissingNode {
+                    path: "/html/body/ul/li[3]".to_string(),
+                    expected: "<li>".to_string(),
+                },
+                Difference::TagMismatch {
+                    path: "/html/body/p".to_string(),
+                    expected: "p".to_string(),
+                    actual: "div".to_string(),
+                },
+            ]
+        );
+        assert_eq!(diff.count(DifferenceCategory::Structural), 2);
+        assert!(HtmlComparer::new()
+            .diff("<p>Same</p>", "<p>\n  Same\n</p>")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_diff_aligns_inserted_nodes() {
+        let diff = HtmlComparer::new().diff(
+            "<div><h1>Title</h1><p>Body</p></div>",
+            "<div><h1>Title</h1><aside>Ad</aside><p>Body</p></div>",
+        );
+        assert_eq!(
+            diff.iter().collect::<Vec<_>>(),
+            [&Difference::ExtraNode {
+                path: "/html/body/div/aside".to_string(),
+                actual: "<aside>".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_unordered() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_sibling_order: true,
+            ..Default::default()
+        });
+        assert!(comparer
+            .diff("<p>A</p><p>B</p>", "<p>B</p><p>A</p>")
+            .is_empty());
+
+        let diff = comparer.diff("<p>A</p><p>B</p><hr>", "<p>C</p><p>A</p>");
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff.count(DifferenceCategory::Text), 1);
+        assert_eq!(diff.count(DifferenceCategory::Structural), 1);
+    }
+
+    #[test]
+    fn test_exhaustive_summary() {
+        let expected: String = (1..=8)
+            .map(|i| format!("<p class='c{}'>Text {}</p>", i, i))
+            .collect();
+        let actual: String = (1..=8)
+            .map(|i| format!("<p class='x{}'>Text {}</p>", i, i))
+            .collect::<String>()
+            + "<hr>";
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            exhaustive: true,
+            ..Default::default()
+        });
+        let err = comparer.verify(&expected, &actual).unwrap_err();
+        assert!(matches!(err, HtmlCompareError::Differences(ref diff) if diff.len() == 9));
+
+        let message = err.to_string();
+        let lines: Vec<_> = message.lines().collect();
+        assert_eq!(lines[0], "9 differences: 1 structural, 8 attribute");
+        assert_eq!(lines[1], "  /html/body/hr: unexpected <hr>");
+        assert_eq!(
+            lines[2],
+            r#"  /html/body/p[1]: attribute class expected "c1", found "x1""#
+        );
+        assert_eq!(lines.len(), SUMMARY_LIMIT + 2);
+        assert_eq!(
+            lines[SUMMARY_LIMIT + 1],
+            "  ... and 4 more; call `HtmlComparer::diff()` for the full report"
+        );
+
+        comparer.verify("<p>Same</p>", "<p>Same</p>").unwrap();
+    }
+
+    #[test]
+    fn test_diff_shadow_root() {
+        let diff = HtmlComparer::new().diff(
+            r#"<x-card><template shadowrootmode="open"><b>Hi</b></template>Light</x-card>"#,
+            r#"<x-card>Light<template shadowrootmode="closed"><i>Hi</i></template></x-card>"#,
+        );
+        let paths: Vec<_> = diff.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            paths,
+            [
+                r#"/html/body/x-card/#shadow-root: attribute shadowrootmode expected "open", found "closed""#,
+                "/html/body/x-card/#shadow-root/b: <b> renamed to <i>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_attribute_order() {
+        let diff = HtmlComparer::new().diff(
+            "<input alt='a' class='a' id='a' name='a'>",
+            "<input alt='b' class='b' id='b' name='b'>",
+        );
+        let names: Vec<_> = diff
+            .iter()
+            .map(|difference| match difference {
+                Difference::AttributeMismatch { name, .. } => name.as_str(),
+                _ => "",
+            })
+            .collect();
+        assert_eq!(names, ["id", "class", "name", "alt"]);
+    }
+
+    #[test]
+    fn test_diff_tag_renamed() {
+        let expected = r#"<div class="card"><p>Hi</p></div><b>Bold</b><i>It</i>"#;
+        let actual =
+            r#"<section class="card"><p>Hi</p></section><strong>Bold</strong><em>It!</em>"#;
+
+        let diff = HtmlComparer::new().diff(expected, actual);
+        let lines: Vec<_> = diff.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "/html/body/div: <div> renamed to <section>",
+                "/html/body/b: <b> renamed to <strong>",
+                "/html/body/i: expected <i>, found <em>",
+            ]
+        );
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            equivalent_tags: vec![
+                ("b".to_string(), "strong".to_string()),
+                ("i".to_string(), "em".to_string()),
+            ],
+            ..Default::default()
+        });
+        let lines: Vec<_> = comparer
+            .diff(expected, actual)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "/html/body/div: <div> renamed to <section>",
+                "/html/body/i/text(): expected 'It', found 'It!'",
+            ]
+        );
+        comparer
+            .verify(
+                "<p><b>a</b> <em>b</em></p>",
+                "<p><strong>a</strong> <i>b</i></p>",
+            )
+            .unwrap();
+    }
+}
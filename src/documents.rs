@@ -0,0 +1,216 @@
+//! Comparison of sequences of documents.
+//!
+//! Paginated listings, infinite-scroll fragments and HTML streams render as
+//! several documents. [`HtmlComparer::diff_documents`] compares two such
+//! sequences pairwise and keeps the differences of each position, so a
+//! failure says which page broke instead of ending a hand-rolled loop at the
+//! first mismatch. [`lines`] and [`from_jsonl`] split the usual serialized
+//! forms into documents.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{HtmlCompareError, HtmlComparer, HtmlDiff};
+
+/// The differences between two sequences of documents
+///
+/// Documents are indexed from 0; reports number them from 1.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentsDiff {
+    documents: Vec<(usize, HtmlDiff)>,
+    expected_count: usize,
+    actual_count: usize,
+}
+
+impl DocumentsDiff {
+    /// Whether the sequences have the same length and all documents are
+    /// equivalent
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty() && self.expected_count == self.actual_count
+    }
+
+    /// Number of expected and actual documents
+    pub fn counts(&self) -> (usize, usize) {
+        (self.expected_count, self.actual_count)
+    }
+
+    /// The differences of the document at `index`, if it has any
+    pub fn get(&self, index: usize) -> Option<&HtmlDiff> {
+        self.documents
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, diff)| diff)
+    }
+
+    /// Iterate over the indices and differences of the documents that differ
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &HtmlDiff)> {
+        self.documents.iter().map(|(index, diff)| (*index, diff))
+    }
+
+    /// A bounded report: which documents differ, each with its
+    /// [`HtmlDiff::summary`]
+    pub fn summary(&self) -> String {
+        let compared = self.expected_count.min(self.actual_count);
+        let mut summary = format!(
+            "{} of {} document{} differ",
+            self.documents.len(),
+            compared,
+            if compared == 1 { "" } else { "s" }
+        );
+        if self.expected_count != self.actual_count {
+            summary.push_str(&format!(
+                "; expected {} documents, found {}",
+                self.expected_count, self.actual_count
+            ));
+        }
+        for (index, diff) in &self.documents {
+            summary.push_str(&format!("\ndocument {}: ", index + 1));
+            summary.push_str(&diff.summary().replace('\n', "\n  "));
+        }
+        summary
+    }
+}
+
+impl fmt::Display for DocumentsDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.expected_count != self.actual_count {
+            writeln!(
+                f,
+                "expected {} documents, found {}",
+                self.expected_count, self.actual_count
+            )?;
+        }
+        for (index, diff) in &self.documents {
+            writeln!(f, "document {}:", index + 1)?;
+            for line in diff.to_string().lines() {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split an HTML stream with one document per line, skipping blank lines
+pub fn lines(stream: &str) -> impl Iterator<Item = &str> {
+    stream.lines().filter(|line| !line.trim().is_empty())
+}
+
+/// Parse JSON Lines where each line is a JSON string holding a document or
+/// an object with the document under `"html"`
+pub fn from_jsonl(stream: &str) -> Result<Vec<String>, HtmlCompareError> {
+    stream
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let invalid = |reason: &str| {
+                HtmlCompareError::InvalidDocumentStream(format!("line {}: {}", index + 1, reason))
+            };
+            match serde_json::from_str(line) {
+                Ok(Value::String(html)) => Ok(html),
+                Ok(Value::Object(mut object)) => match object.remove("html") {
+                    Some(Value::String(html)) => Ok(html),
+                    _ => Err(invalid("expected an \"html\" string")),
+                },
+                Ok(_) => Err(invalid("expected a string or an object")),
+                Err(err) => Err(invalid(&err.to_string())),
+            }
+        })
+        .collect()
+}
+
+impl HtmlComparer {
+    /// Compare two sequences of documents pairwise, collecting the
+    /// differences of every position
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let pages = ["<p>1</p>", "<p>2</p>"];
+    /// let diff = HtmlComparer::new().diff_documents(pages, ["<p>1</p>", "<p>3</p>"]);
+    /// assert!(diff.get(1).is_some());
+    /// ```
+    pub fn diff_documents<E, A>(&self, expected: E, actual: A) -> DocumentsDiff
+    where
+        E: IntoIterator,
+        E::Item: AsRef<str>,
+        A: IntoIterator,
+        A::Item: AsRef<str>,
+    {
+        let expected: Vec<E::Item> = expected.into_iter().collect();
+        let actual: Vec<A::Item> = actual.into_iter().collect();
+        let documents = expected
+            .iter()
+            .zip(&actual)
+            .map(|(expected, actual)| self.diff(expected.as_ref(), actual.as_ref()))
+            .enumerate()
+            .filter(|(_, diff)| !diff.is_empty())
+            .collect();
+        DocumentsDiff {
+            documents,
+            expected_count: expected.len(),
+            actual_count: actual.len(),
+        }
+    }
+
+    /// Compare two sequences of documents pairwise, failing with
+    /// [`HtmlCompareError::DocumentDifferences`] if any pair differs or the
+    /// lengths don't match
+    pub fn compare_documents<E, A>(&self, expected: E, actual: A) -> Result<bool, HtmlCompareError>
+    where
+        E: IntoIterator,
+        E::Item: AsRef<str>,
+        A: IntoIterator,
+        A::Item: AsRef<str>,
+    {
+        let diff = self.diff_documents(expected, actual);
+        match diff.is_empty() {
+            true => Ok(true),
+            false => Err(HtmlCompareError::DocumentDifferences(diff)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_documents() {
+        let expected = "<ul><li>a</li></ul>\n<ul><li>b</li></ul>\n<ul><li>c</li></ul>\n";
+        let actual = "<ul><li>a</li></ul>\n\n<ul><li>b</li></ul>\n<ul><li>C</li></ul>";
+
+        let diff = HtmlComparer::new().diff_documents(lines(expected), lines(actual));
+        assert_eq!(diff.counts(), (3, 3));
+        assert_eq!(diff.iter().map(|(index, _)| index).collect::<Vec<_>>(), [2]);
+        assert!(diff.get(2).is_some() && diff.get(1).is_none());
+        assert_eq!(
+            diff.summary(),
+            "1 of 3 documents differ\ndocument 3: 1 difference: 1 text\n    /html/body/ul/li/text(): expected 'c', found 'C'"
+        );
+
+        let shorter = HtmlComparer::new().diff_documents(lines(expected), ["<ul><li>a</li></ul>"]);
+        assert!(!shorter.is_empty());
+        assert_eq!(shorter.to_string(), "expected 3 documents, found 1\n");
+        assert!(matches!(
+            HtmlComparer::new().compare_documents(lines(expected), ["<ul><li>a</li></ul>"]),
+            Err(HtmlCompareError::DocumentDifferences(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_jsonl() {
+        let stream = r#""<p>one</p>"
+{"page": 2, "html": "<p>two</p>"}
+"#;
+        assert_eq!(from_jsonl(stream).unwrap(), ["<p>one</p>", "<p>two</p>"]);
+        assert!(HtmlComparer::new()
+            .compare_documents(from_jsonl(stream).unwrap(), ["<p>one</p>", "<p>two</p>"])
+            .unwrap());
+
+        assert_eq!(
+            from_jsonl("\"<p>ok</p>\"\n\n42").unwrap_err().to_string(),
+            "Invalid document stream: line 3: expected a string or an object"
+        );
+    }
+}
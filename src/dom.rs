@@ -0,0 +1,437 @@
+//! Comparison of documents held in a DOM other than scraper's.
+//!
+//! Embedders with their own parser or tree (tl, kuchiki, a templating
+//! engine's output) can implement [`DomNode`] for their node type and reuse
+//! the comparison through [`diff_trees`] and [`compare_trees`]. This core
+//! covers the options that only need the tree itself: whitespace, text,
+//! comments, attributes (ignored, by prefix, tokenized and
+//! `empty_attribute_equals_missing`), style contents, sibling order and
+//! `equivalent_tags`. Options that rely on selectors or on scraper's parsing
+//! (sorting rules, shadow roots, MathML, optional tags, ...) are only
+//! applied by [`HtmlComparer`](crate::HtmlComparer). scraper's own nodes
+//! implement [`DomNode`] too.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+use ego_tree::NodeRef;
+use scraper::Node;
+
+use crate::diff::{align_ordered, Alignment};
+use crate::{tokens, Difference, HtmlCompareOptions, HtmlComparer};
+
+/// What a [`DomNode`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DomKind<'a> {
+    /// An element with its lowercase tag name
+    Element(&'a str),
+    Text(&'a str),
+    Comment(&'a str),
+    /// Doctypes, processing instructions and other nodes, which are not
+    /// compared
+    Other,
+}
+
+/// A node of a user-supplied document tree
+pub trait DomNode: Sized {
+    fn kind(&self) -> DomKind<'_>;
+
+    /// The attributes of an element, in any order
+    fn attributes(&self) -> Vec<(&str, &str)>;
+
+    /// The children in document order
+    fn children(&self) -> Vec<Self>;
+}
+
+impl DomNode for NodeRef<'_, Node> {
+    fn kind(&self) -> DomKind<'_> {
+        match self.value() {
+            Node::Element(element) => DomKind::Element(element.name()),
+            Node::Text(text) => DomKind::Text(text),
+            Node::Comment(comment) => DomKind::Comment(comment),
+            _ => DomKind::Other,
+        }
+    }
+
+    fn attributes(&self) -> Vec<(&str, &str)> {
+        match self.value() {
+            Node::Element(element) => element.attrs().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn children(&self) -> Vec<Self> {
+        NodeRef::children(self).collect()
+    }
+}
+
+/// Every difference between two trees rooted at the given nodes, with paths
+/// starting at `/<root tag>`
+pub fn diff_trees<N: DomNode>(
+    expected: &N,
+    actual: &N,
+    options: &HtmlCompareOptions,
+) -> Vec<Difference> {
+    let comparer = TreeComparer {
+        comparer: HtmlComparer::with_options(options.clone()),
+    };
+    let path = format!("/{}", child_name(expected));
+    let mut differences = Vec::new();
+    comparer.diff_nodes(expected, actual, &path, &mut differences);
+    differences
+}
+
+/// The first difference between two trees rooted at the given nodes
+pub fn compare_trees<N: DomNode>(
+    expected: &N,
+    actual: &N,
+    options: &HtmlCompareOptions,
+) -> Result<(), Difference> {
+    match diff_trees(expected, actual, options).into_iter().next() {
+        Some(difference) => Err(difference),
+        None => Ok(()),
+    }
+}
+
+struct TreeComparer {
+    comparer: HtmlComparer,
+}
+
+impl TreeComparer {
+    fn diff_nodes<N: DomNode>(
+        &self,
+        expected: &N,
+        actual: &N,
+        path: &str,
+        differences: &mut Vec<Difference>,
+    ) {
+        match (expected.kind(), actual.kind()) {
+            (DomKind::Element(expected_name), DomKind::Element(actual_name)) => {
+                if !self.comparer.tags_match(expected_name, actual_name) {
+                    differences.push(Difference::TagMismatch {
+                        path: path.to_string(),
+                        expected: expected_name.to_string(),
+                        actual: actual_name.to_string(),
+                    });
+                    return;
+                }
+                if !self.comparer.options.ignore_attributes {
+                    self.diff_attributes(expected, actual, path, differences);
+                }
+                if self.comparer.options.ignore_style_contents && expected_name == "style" {
+                    return;
+                }
+                let expected_children = self.children(expected);
+                let actual_children = self.children(actual);
+                self.diff_children(&expected_children, &actual_children, path, differences);
+            }
+            (DomKind::Text(expected_text), DomKind::Text(actual_text))
+            | (DomKind::Comment(expected_text), DomKind::Comment(actual_text)) => {
+                let (expected_text, actual_text) =
+                    (self.text(expected_text), self.text(actual_text));
+                if expected_text != actual_text {
+                    differences.push(Difference::TextMismatch {
+                        path: path.to_string(),
+                        expected: expected_text.to_string(),
+                        actual: actual_text.to_string(),
+                    });
+                }
+            }
+            _ => differences.push(Difference::TagMismatch {
+                path: path.to_string(),
+                expected: self.key(expected),
+                actual: self.key(actual),
+            }),
+        }
+    }
+
+    fn diff_attributes<N: DomNode>(
+        &self,
+        expected: &N,
+        actual: &N,
+        path: &str,
+        differences: &mut Vec<Difference>,
+    ) {
+        let expected_attrs = self.attributes(expected);
+        let actual_attrs = self.attributes(actual);
+        let names: BTreeSet<&str> = expected_attrs
+            .keys()
+            .chain(actual_attrs.keys())
+            .copied()
+            .collect();
+        for name in names {
+            let (expected_value, actual_value) = (expected_attrs.get(name), actual_attrs.get(name));
+            if expected_value != actual_value {
+                differences.push(Difference::AttributeMismatch {
+                    path: path.to_string(),
+                    name: name.to_string(),
+                    expected: expected_value.map(|value| value.to_string()),
+                    actual: actual_value.map(|value| value.to_string()),
+                });
+            }
+        }
+    }
+
+    fn diff_children<N: DomNode>(
+        &self,
+        expected: &[N],
+        actual: &[N],
+        path: &str,
+        differences: &mut Vec<Difference>,
+    ) {
+        let expected_paths = self.child_paths(path, expected);
+        let actual_paths = self.child_paths(path, actual);
+        for alignment in self.align(expected, actual) {
+            match alignment {
+                Alignment::Pair(i, j) => {
+                    self.diff_nodes(&expected[i], &actual[j], &expected_paths[i], differences)
+                }
+                Alignment::Missing(i) => differences.push(Difference::MissingNode {
+                    path: expected_paths[i].clone(),
+                    expected: self.describe(&expected[i]),
+                }),
+                Alignment::Extra(j) => differences.push(Difference::ExtraNode {
+                    path: actual_paths[j].clone(),
+                    actual: self.describe(&actual[j]),
+                }),
+            }
+        }
+    }
+
+    /// Pair up children like [`HtmlComparer::diff`](crate::HtmlComparer::diff)
+    /// does: by a longest common subsequence of their tags, or without
+    /// regard to order preferring identical subtrees
+    fn align<N: DomNode>(&self, expected: &[N], actual: &[N]) -> Vec<Alignment> {
+        let expected_keys: Vec<_> = expected.iter().map(|n| self.key(n)).collect();
+        let actual_keys: Vec<_> = actual.iter().map(|n| self.key(n)).collect();
+        if !self.comparer.options.ignore_sibling_order {
+            return align_ordered(&expected_keys, &actual_keys);
+        }
+
+        let mut alignments = Vec::new();
+        let mut matched = vec![false; actual.len()];
+        let mut unmatched_expected = Vec::new();
+        for (i, expected_child) in expected.iter().enumerate() {
+            let identical = (0..actual.len()).find(|&j| {
+                !matched[j] && expected_keys[i] == actual_keys[j] && {
+                    let mut scratch = Vec::new();
+                    self.diff_nodes(expected_child, &actual[j], "", &mut scratch);
+                    scratch.is_empty()
+                }
+            });
+            match identical {
+                Some(j) => {
+                    matched[j] = true;
+                    alignments.push(Alignment::Pair(i, j));
+                }
+                None => unmatched_expected.push(i),
+            }
+        }
+        for i in unmatched_expected {
+            match (0..actual.len()).find(|&j| !matched[j] && actual_keys[j] == expected_keys[i]) {
+                Some(j) => {
+                    matched[j] = true;
+                    alignments.push(Alignment::Pair(i, j));
+                }
+                None => alignments.push(Alignment::Missing(i)),
+            }
+        }
+        alignments.extend(
+            (0..actual.len())
+                .filter(|&j| !matched[j])
+                .map(Alignment::Extra),
+        );
+        alignments
+    }
+
+    /// The children of `node` that take part in the comparison
+    fn children<N: DomNode>(&self, node: &N) -> Vec<N> {
+        node.children()
+            .into_iter()
+            .filter(|child| match child.kind() {
+                DomKind::Element(_) => true,
+                DomKind::Text(text) => {
+                    !self.comparer.options.ignore_text
+                        && (!self.comparer.options.ignore_whitespace || !text.trim().is_empty())
+                }
+                DomKind::Comment(_) => !self.comparer.options.ignore_comments,
+                DomKind::Other => false,
+            })
+            .collect()
+    }
+
+    /// The compared attributes of `node` with their normalized values
+    fn attributes<'n, N: DomNode>(&self, node: &'n N) -> BTreeMap<&'n str, Cow<'n, str>> {
+        node.attributes()
+            .into_iter()
+            .filter(|(name, _)| !self.comparer.options.ignored_attributes.contains(*name))
+            .filter(|(name, _)| {
+                !self
+                    .comparer
+                    .options
+                    .ignored_attribute_prefixes
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix.as_str()))
+            })
+            .map(|(name, value)| {
+                let value = tokens::normalize_attribute(
+                    &self.comparer.options.tokenized_attributes,
+                    name,
+                    value,
+                );
+                (name, value)
+            })
+            .filter(|(name, value)| {
+                !(value.is_empty()
+                    && self
+                        .comparer
+                        .options
+                        .empty_attribute_equals_missing
+                        .contains(*name))
+            })
+            .collect()
+    }
+
+    fn text<'t>(&self, text: &'t str) -> &'t str {
+        match self.comparer.options.ignore_whitespace {
+            true => text.trim(),
+            false => text,
+        }
+    }
+
+    /// What children are aligned by: the canonical tag or the node type
+    fn key<N: DomNode>(&self, node: &N) -> String {
+        match node.kind() {
+            DomKind::Element(name) => self.comparer.canonical_tag(name).to_string(),
+            DomKind::Text(_) => "#text".to_string(),
+            DomKind::Comment(_) => "#comment".to_string(),
+            DomKind::Other => "#other".to_string(),
+        }
+    }
+
+    fn child_paths<N: DomNode>(&self, parent: &str, children: &[N]) -> Vec<String> {
+        let names: Vec<String> = children.iter().map(child_name).collect();
+        names
+            .iter()
+            .enumerate()
+            .map(
+                |(i, name)| match names.iter().filter(|other| *other == name).count() {
+                    1 => format!("{}/{}", parent, name),
+                    _ => {
+                        let position = names[..i].iter().filter(|other| *other == name).count() + 1;
+                        format!("{}/{}[{}]", parent, name, position)
+                    }
+                },
+            )
+            .collect()
+    }
+
+    fn describe<N: DomNode>(&self, node: &N) -> String {
+        match node.kind() {
+            DomKind::Element(name) => format!("<{}>", name),
+            DomKind::Text(text) => format!("text '{}'", text.trim()),
+            DomKind::Comment(comment) => format!("comment '{}'", comment.trim()),
+            DomKind::Other => "node".to_string(),
+        }
+    }
+}
+
+/// The step a node adds to a path
+fn child_name<N: DomNode>(node: &N) -> String {
+    match node.kind() {
+        DomKind::Element(name) => name.to_string(),
+        DomKind::Text(_) => "text()".to_string(),
+        DomKind::Comment(_) => "comment()".to_string(),
+        DomKind::Other => "node()".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    /// A minimal DOM of an embedder
+    enum TestNode {
+        Element(
+            &'static str,
+            Vec<(&'static str, &'static str)>,
+            Vec<TestNode>,
+        ),
+        Text(&'static str),
+    }
+
+    impl DomNode for &TestNode {
+        fn kind(&self) -> DomKind<'_> {
+            match self {
+                TestNode::Element(name, _, _) => DomKind::Element(name),
+                TestNode::Text(text) => DomKind::Text(text),
+            }
+        }
+
+        fn attributes(&self) -> Vec<(&str, &str)> {
+            match self {
+                TestNode::Element(_, attributes, _) => attributes.clone(),
+                TestNode::Text(_) => Vec::new(),
+            }
+        }
+
+        fn children(&self) -> Vec<Self> {
+            match self {
+                TestNode::Element(_, _, children) => children.iter().collect(),
+                TestNode::Text(_) => Vec::new(),
+            }
+        }
+    }
+
+    fn list(class: &'static str, items: &[&'static str]) -> TestNode {
+        let items = items
+            .iter()
+            .map(|item| TestNode::Element("li", Vec::new(), vec![TestNode::Text(item)]))
+            .collect();
+        TestNode::Element("ul", vec![("class", class)], items)
+    }
+
+    #[test]
+    fn test_custom_dom() {
+        let options = HtmlCompareOptions::default();
+        let expected = list("nav", &["Home", " About "]);
+        assert_eq!(
+            compare_trees(&&expected, &&list("nav", &["Home", "About"]), &options),
+            Ok(())
+        );
+
+        let differences: Vec<_> = diff_trees(&&expected, &&list("menu", &["Home"]), &options)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            differences,
+            [
+                "/ul: attribute class expected \"nav\", found \"menu\"",
+                "/ul/li[2]: missing <li>",
+            ]
+        );
+
+        let unordered = HtmlCompareOptions {
+            ignore_sibling_order: true,
+            ignored_attributes: ["class".to_string()].into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            compare_trees(&&expected, &&list("menu", &["About", "Home"]), &unordered),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_scraper_nodes_match_html_comparer() {
+        let expected = Html::parse_document("<ul><li>a</li><li class='x'>b</li></ul><p>c</p>");
+        let actual = Html::parse_document("<ul><li>a</li><li class='y'>b</li></ul><p>C</p>");
+        let options = HtmlCompareOptions::default();
+        let differences = diff_trees(&*expected.root_element(), &*actual.root_element(), &options);
+        let html = HtmlComparer::new().diff(&expected.html(), &actual.html());
+        assert_eq!(differences, html.iter().cloned().collect::<Vec<_>>());
+        assert_eq!(differences.len(), 2);
+    }
+}
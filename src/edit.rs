@@ -0,0 +1,473 @@
+//! Edit scripts that turn the expected document into the actual one.
+//!
+//! Besides describing differences, an [`HtmlDiff`](crate::HtmlDiff) gives
+//! the tree edits that reconcile them from
+//! [`HtmlDiff::edit_script`](crate::HtmlDiff::edit_script), worked out when
+//! first asked for. The script follows the same top-down alignment as the
+//! diff: children are matched by a longest common subsequence of their tags,
+//! unmatched nodes are deleted or inserted, elements paired up with a
+//! different tag are relabeled, and a deleted subtree that is inserted
+//! unchanged elsewhere becomes a move.
+//!
+//! This is a heuristic, not a tree edit distance: the script takes about as
+//! long to work out as the diff, where the minimum script of an algorithm
+//! like Zhang–Shasha takes time cubic in the size of the documents, but it
+//! isn't always the shortest. Children with the same tag are paired in
+//! order, so removing the first of `<p>A</p><p>B</p>` sets the text of the
+//! first paragraph to `B` and deletes the second, and a node that moved to
+//! another parent and changed on the way is deleted and inserted.
+//!
+//! Paths are those of the expected document, as in
+//! [`Difference`](crate::Difference). The `index` of an insert or move is the
+//! node's position among its parent's compared children once the whole script
+//! has been applied, so inserts and moves can be applied in script order after
+//! removing deleted and moved nodes.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::diff::{child_paths, Alignment};
+use crate::patch::RAW_TEXT_ELEMENTS;
+use crate::{HeadingLevels, HtmlComparer, ShadowDomMode};
+
+/// One operation of an edit script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// Insert `html` as child number `index` of the element at `parent`; a
+    /// missing shadow root is inserted at index 0 of its host
+    Insert {
+        parent: String,
+        index: usize,
+        html: String,
+    },
+    /// Delete the node at `path` along with its subtree
+    Delete { path: String },
+    /// Move the node at `path` to child number `index` of the element at
+    /// `parent`
+    Move {
+        path: String,
+        parent: String,
+        index: usize,
+    },
+    /// Rename the element at `path`, keeping its attributes and children
+    Relabel {
+        path: String,
+        from: String,
+        to: String,
+    },
+    /// Set the attribute `name` of the element at `path`, or remove it when
+    /// `value` is `None`
+    SetAttribute {
+        path: String,
+        name: String,
+        value: Option<String>,
+    },
+    /// Replace the content of the text or comment node at `path`
+    SetText { path: String, text: String },
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Edit::Insert {
+                parent,
+                index,
+                html,
+            } => write!(f, "insert {} into {} at {}", html, parent, index),
+            Edit::Delete { path } => write!(f, "delete {}", path),
+            Edit::Move {
+                path,
+                parent,
+                index,
+            } => write!(f, "move {} into {} at {}", path, parent, index),
+            Edit::Relabel { path, from, to } => {
+                write!(f, "relabel {} from <{}> to <{}>", path, from, to)
+            }
+            Edit::SetAttribute {
+                path,
+                name,
+                value: Some(value),
+            } => write!(f, "set {}@{} to {:?}", path, name, value),
+            Edit::SetAttribute {
+                path,
+                name,
+                value: None,
+            } => write!(f, "remove {}@{}", path, name),
+            Edit::SetText { path, text } => write!(f, "set {} to {:?}", path, text),
+        }
+    }
+}
+
+/// An edit script under construction, remembering the nodes behind deletes
+/// and inserts so that matching pairs can be turned into moves
+#[derive(Default)]
+struct Script<'a> {
+    edits: Vec<Edit>,
+    deleted: Vec<(usize, NodeRef<'a, Node>)>,
+    inserted: Vec<(usize, NodeRef<'a, Node>)>,
+}
+
+impl<'a> Script<'a> {
+    fn delete(&mut self, path: String, node: NodeRef<'a, Node>) {
+        self.deleted.push((self.edits.len(), node));
+        self.edits.push(Edit::Delete { path });
+    }
+
+    fn insert(&mut self, parent: &str, index: usize, node: NodeRef<'a, Node>) {
+        self.inserted.push((self.edits.len(), node));
+        self.edits.push(Edit::Insert {
+            parent: parent.to_string(),
+            index,
+            html: node_html(node),
+        });
+    }
+}
+
+/// One entry of the explicit stack [`HtmlComparer::edit_trees`] walks the
+/// trees with
+enum EditTask<'a> {
+    /// Edit two nodes paired up as child number `index` of `parent`
+    Nodes {
+        expected: NodeRef<'a, Node>,
+        actual: NodeRef<'a, Node>,
+        path: String,
+        parent: String,
+        index: usize,
+    },
+    Delete(String, NodeRef<'a, Node>),
+    Insert(String, usize, NodeRef<'a, Node>),
+}
+
+impl HtmlComparer {
+    /// The edits turning `expected` into `actual`
+    pub(crate) fn edit_script(&self, expected: ElementRef, actual: ElementRef) -> Vec<Edit> {
+        let mut script = Script::default();
+        let levels = self.heading_levels(*expected, *actual);
+        self.edit_trees(expected, actual, &mut script, levels);
+
+        // Pair each inserted subtree with an identical deleted one, only
+        // comparing nodes of the same kind
+        let mut deleted_by_key: HashMap<String, Vec<(usize, NodeRef<Node>)>> = HashMap::new();
+        for (delete, deleted) in &script.deleted {
+            deleted_by_key
+                .entry(self.alignment_key(*deleted, levels.expected))
+                .or_default()
+                .push((*delete, *deleted));
+        }
+        let mut moved = vec![false; script.edits.len()];
+        for (insert, inserted) in &script.inserted {
+            let Some(candidates) =
+                deleted_by_key.get(&self.alignment_key(*inserted, levels.actual))
+            else {
+                continue;
+            };
+            let source = candidates.iter().find(|(delete, deleted)| {
+                !moved[*delete] && self.identical(*deleted, *inserted, levels)
+            });
+            if let Some((delete, _)) = source {
+                moved[*delete] = true;
+                let Edit::Delete { path } = &script.edits[*delete] else {
+                    unreachable!("deleted nodes are recorded with their delete");
+                };
+                let Edit::Insert { parent, index, .. } = &script.edits[*insert] else {
+                    unreachable!("inserted nodes are recorded with their insert");
+                };
+                script.edits[*insert] = Edit::Move {
+                    path: path.clone(),
+                    parent: parent.clone(),
+                    index: *index,
+                };
+            }
+        }
+
+        script
+            .edits
+            .into_iter()
+            .zip(moved)
+            .filter(|(_, moved)| !moved)
+            .map(|(edit, _)| edit)
+            .collect()
+    }
+
+    /// The edits turning `expected` into `actual` before moves are paired
+    /// up, walking the trees with an explicit stack like
+    /// [`Self::diff_trees`]
+    fn edit_trees<'a>(
+        &self,
+        expected: ElementRef<'a>,
+        actual: ElementRef<'a>,
+        script: &mut Script<'a>,
+        levels: HeadingLevels,
+    ) {
+        let mut stack = vec![EditTask::Nodes {
+            expected: *expected,
+            actual: *actual,
+            path: "/html".to_string(),
+            parent: String::new(),
+            index: 0,
+        }];
+        while let Some(task) = stack.pop() {
+            match task {
+                EditTask::Nodes {
+                    expected,
+                    actual,
+                    path,
+                    parent,
+                    index,
+                } => match (expected.value(), actual.value()) {
+                    (Node::Text(_), Node::Text(actual_text)) => {
+                        if !self.options.ignore_text
+                            && self.node_text(expected) != self.node_text(actual)
+                        {
+                            script.edits.push(Edit::SetText {
+                                path,
+                                text: actual_text.to_string(),
+                            });
+                        }
+                    }
+                    (Node::Comment(expected_comment), Node::Comment(actual_comment)) => {
+                        if expected_comment.trim() != actual_comment.trim() {
+                            script.edits.push(Edit::SetText {
+                                path,
+                                text: actual_comment.to_string(),
+                            });
+                        }
+                    }
+                    _ => match (ElementRef::wrap(expected), ElementRef::wrap(actual)) {
+                        (Some(expected_el), Some(actual_el)) => self.edit_elements(
+                            expected_el,
+                            actual_el,
+                            path,
+                            script,
+                            &mut stack,
+                            levels,
+                        ),
+                        // A node of another kind can only be replaced
+                        _ => {
+                            script.delete(path, expected);
+                            script.insert(&parent, index, actual);
+                        }
+                    },
+                },
+                EditTask::Delete(path, node) => script.delete(path, node),
+                EditTask::Insert(parent, index, node) => script.insert(&parent, index, node),
+            }
+        }
+    }
+
+    /// Record the edits of two elements themselves and push those of their
+    /// shadow roots and children, in document order
+    fn edit_elements<'a>(
+        &self,
+        expected: ElementRef<'a>,
+        actual: ElementRef<'a>,
+        path: String,
+        script: &mut Script<'a>,
+        stack: &mut Vec<EditTask<'a>>,
+        levels: HeadingLevels,
+    ) {
+        if !self.tags_match(
+            self.element_name(expected, levels.expected),
+            self.element_name(actual, levels.actual),
+        ) {
+            script.edits.push(Edit::Relabel {
+                path: path.clone(),
+                from: expected.value().name().to_string(),
+                to: actual.value().name().to_string(),
+            });
+        }
+
+        if !self.options.ignore_attributes {
+            let expected_attrs: BTreeMap<_, _> =
+                self.compared_attributes(expected).into_iter().collect();
+            let actual_attrs: BTreeMap<_, _> =
+                self.compared_attributes(actual).into_iter().collect();
+            let names: BTreeSet<&str> = expected_attrs
+                .keys()
+                .chain(actual_attrs.keys())
+                .copied()
+                .collect();
+            for name in names {
+                let value = actual_attrs.get(name);
+                if expected_attrs.get(name) != value {
+                    script.edits.push(Edit::SetAttribute {
+                        path: path.clone(),
+                        name: name.to_string(),
+                        value: value.map(|value| value.to_string()),
+                    });
+                }
+            }
+        }
+
+        if self.options.ignore_style_contents && expected.value().name() == "style" {
+            return;
+        }
+
+        // Highlighted code with the same text needs no edits; otherwise the
+        // markup is patched like any other
+        if self.is_highlighted_code(expected) && self.code_text(expected) == self.code_text(actual)
+        {
+            return;
+        }
+
+        let (expected_children, actual_children) = self.compared_children(expected, actual, levels);
+        let expected_paths = child_paths(&path, &expected_children);
+        let (expected_keys, actual_keys) =
+            self.alignment_keys(&expected_children, &actual_children, levels);
+
+        let mut tasks = Vec::new();
+        if self.options.shadow_dom == ShadowDomMode::Separate {
+            let shadow_path = format!("{}/#shadow-root", path);
+            match (self.shadow_root(expected), self.shadow_root(actual)) {
+                (Some(expected_root), Some(actual_root)) => tasks.push(EditTask::Nodes {
+                    expected: *expected_root,
+                    actual: *actual_root,
+                    path: shadow_path,
+                    parent: path.clone(),
+                    index: 0,
+                }),
+                (Some(expected_root), None) => {
+                    tasks.push(EditTask::Delete(shadow_path, *expected_root))
+                }
+                (None, Some(actual_root)) => {
+                    tasks.push(EditTask::Insert(path.clone(), 0, *actual_root))
+                }
+                (None, None) => {}
+            }
+        }
+
+        for alignment in self.align_children(&expected_children, &actual_children, levels) {
+            match alignment {
+                Alignment::Pair(i, j) => {
+                    let (expected_child, actual_child) = (expected_children[i], actual_children[j]);
+                    // Nodes of different kinds are paired up positionally;
+                    // if either also appears unchanged elsewhere, it moved
+                    // rather than changed its tag
+                    let moved = expected_keys[i] != actual_keys[j]
+                        && (actual_children.iter().zip(&actual_keys).any(|(n, key)| {
+                            *key == expected_keys[i] && self.identical(expected_child, *n, levels)
+                        }) || expected_children
+                            .iter()
+                            .zip(&expected_keys)
+                            .any(|(n, key)| {
+                                *key == actual_keys[j] && self.identical(*n, actual_child, levels)
+                            }));
+                    if moved {
+                        tasks.push(EditTask::Delete(expected_paths[i].clone(), expected_child));
+                        tasks.push(EditTask::Insert(path.clone(), j, actual_child));
+                    } else {
+                        tasks.push(EditTask::Nodes {
+                            expected: expected_child,
+                            actual: actual_child,
+                            path: expected_paths[i].clone(),
+                            parent: path.clone(),
+                            index: j,
+                        });
+                    }
+                }
+                Alignment::Missing(i) => tasks.push(EditTask::Delete(
+                    expected_paths[i].clone(),
+                    expected_children[i],
+                )),
+                Alignment::Extra(j) => {
+                    tasks.push(EditTask::Insert(path.clone(), j, actual_children[j]))
+                }
+            }
+        }
+        stack.extend(tasks.into_iter().rev());
+    }
+}
+
+/// Serialize a node of the actual document for an insert
+fn node_html(node: NodeRef<Node>) -> String {
+    match node.value() {
+        // The text of a script or style isn't escaped
+        Node::Text(text)
+            if node
+                .parent()
+                .and_then(ElementRef::wrap)
+                .is_some_and(|parent| RAW_TEXT_ELEMENTS.contains(&parent.value().name())) =>
+        {
+            text.to_string()
+        }
+        Node::Text(text) => text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+        Node::Comment(comment) => format!("<!--{}-->", &**comment),
+        _ => ElementRef::wrap(node)
+            .map(|element| element.html())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    #[test]
+    fn test_edit_script() {
+        let diff = HtmlComparer::new().diff(
+            r#"<div class="a"><h1>Title</h1><p>One</p><p>Two</p><span>x</span></div>"#,
+            r#"<section class="b"><p>Two!</p><h1>Title</h1><em>new</em><span>x</span></section>"#,
+        );
+        let edits: Vec<_> = diff.edit_script().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            edits,
+            [
+                "relabel /html/body/div from <div> to <section>",
+                "set /html/body/div@class to \"b\"",
+                "set /html/body/div/p[1]/text() to \"Two!\"",
+                "delete /html/body/div/p[2]",
+                "move /html/body/div/h1 into /html/body/div at 1",
+                "insert <em>new</em> into /html/body/div at 2",
+            ]
+        );
+
+        // The diff of an exhaustive comparison works out the same script
+        let exhaustive = HtmlComparer::with_options(HtmlCompareOptions {
+            exhaustive: true,
+            ..Default::default()
+        });
+        match exhaustive.verify("<p>One</p><p>Two</p>", "<p>Two</p>") {
+            Err(crate::HtmlCompareError::Differences(diff)) => assert_eq!(
+                diff.edit_script()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+                [
+                    "set /html/body/p[1]/text() to \"Two\"",
+                    "delete /html/body/p[2]"
+                ]
+            ),
+            other => panic!("expected differences, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_edit_script_is_empty_for_equal_documents() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_sibling_order: true,
+            ..Default::default()
+        });
+        let diff = comparer.diff(
+            "<ul><li>a</li><li>b</li></ul>",
+            "<ul><li>b</li><li>a</li></ul>",
+        );
+        assert!(diff.edit_script().is_empty());
+
+        let diff = comparer.diff("<ul><li>a</li></ul>", "<ul><li>b</li><li>a</li></ul>");
+        assert_eq!(
+            diff.edit_script(),
+            [Edit::Insert {
+                parent: "/html/body/ul".to_string(),
+                index: 0,
+                html: "<li>b</li>".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Inputs that are not valid UTF-8.
+//!
+//! Documents read from legacy systems come as bytes that may not decode.
+//! [`HtmlComparer::verify_bytes`] and [`HtmlComparer::is_equal_bytes`]
+//! accept such bytes and handle invalid sequences according to
+//! [`HtmlCompareOptions::invalid_utf8`](crate::HtmlCompareOptions::invalid_utf8):
+//!
+//! - [`InvalidUtf8::Replace`], the default, replaces each invalid sequence
+//!   with U+FFFD, as `String::from_utf8_lossy` does, so any two invalid
+//!   sequences compare equal.
+//! - [`InvalidUtf8::Error`] fails with [`HtmlCompareError::InvalidUtf8`].
+//! - [`InvalidUtf8::CompareBytes`] keeps the invalid bytes, so the nodes
+//!   they are in compare equal only if the bytes are the same. Each byte is
+//!   represented by a character of the last private use plane, U+10FF00 plus
+//!   its value, which is what mismatches show.
+
+use std::borrow::Cow;
+
+use crate::{HtmlCompareError, HtmlComparer};
+
+/// The character standing for the invalid byte 0 in
+/// [`InvalidUtf8::CompareBytes`]
+const FIRST_BYTE_CHAR: u32 = 0x10FF00;
+
+/// What to do with invalid UTF-8 in byte inputs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidUtf8 {
+    /// Fail the comparison
+    Error,
+    /// Replace invalid sequences with U+FFFD
+    #[default]
+    Replace,
+    /// Compare the nodes containing invalid sequences byte for byte
+    CompareBytes,
+}
+
+impl InvalidUtf8 {
+    /// Look up a policy by its name: `error`, `replace` or `compare_bytes`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "error" => Some(InvalidUtf8::Error),
+            "replace" => Some(InvalidUtf8::Replace),
+            "compare_bytes" => Some(InvalidUtf8::CompareBytes),
+            _ => None,
+        }
+    }
+
+    /// `bytes` as text; `document` names the document in the error
+    pub(crate) fn decode<'a>(
+        self,
+        bytes: &'a [u8],
+        document: &'static str,
+    ) -> Result<Cow<'a, str>, HtmlCompareError> {
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return Ok(Cow::Borrowed(text));
+        }
+        let mut text = String::with_capacity(bytes.len());
+        self.decode_into(bytes, document, &mut text)?;
+        Ok(Cow::Owned(text))
+    }
+
+    /// Append `bytes` as text to `text`, as [`Self::decode`] does
+    pub(crate) fn decode_into(
+        self,
+        bytes: &[u8],
+        document: &'static str,
+        text: &mut String,
+    ) -> Result<(), HtmlCompareError> {
+        if let (InvalidUtf8::Error, Err(invalid)) = (self, std::str::from_utf8(bytes)) {
+            return Err(HtmlCompareError::InvalidUtf8 {
+                document,
+                offset: invalid.valid_up_to(),
+            });
+        }
+        text.reserve(bytes.len());
+        for chunk in bytes.utf8_chunks() {
+            text.push_str(chunk.valid());
+            if chunk.invalid().is_empty() {
+                continue;
+            }
+            match self {
+                // One replacement per invalid sequence, as
+                // `String::from_utf8_lossy` does
+                InvalidUtf8::Error | InvalidUtf8::Replace => text.push(char::REPLACEMENT_CHARACTER),
+                InvalidUtf8::CompareBytes => text.extend(
+                    chunk
+                        .invalid()
+                        .iter()
+                        .filter_map(|&byte| char::from_u32(FIRST_BYTE_CHAR + u32::from(byte))),
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl HtmlComparer {
+    /// [`Self::verify`] for documents given as bytes, decoded as UTF-8
+    /// according to [`HtmlCompareOptions::invalid_utf8`](crate::HtmlCompareOptions::invalid_utf8)
+    pub fn verify_bytes(&self, expected: &[u8], actual: &[u8]) -> Result<(), HtmlCompareError> {
+        let policy = self.options.invalid_utf8;
+        self.verify(
+            &policy.decode(expected, "expected")?,
+            &policy.decode(actual, "actual")?,
+        )
+    }
+
+    /// [`Self::is_equal`] for documents given as bytes (see
+    /// [`Self::verify_bytes`])
+    pub fn is_equal_bytes(&self, expected: &[u8], actual: &[u8]) -> Result<bool, HtmlCompareError> {
+        match self.verify_bytes(expected, actual) {
+            Ok(()) => Ok(true),
+            Err(err) if err.is_mismatch() => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    fn comparer(invalid_utf8: InvalidUtf8) -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            invalid_utf8,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_invalid_utf8_policies() {
+        let (latin1, other) = (b"<p>caf\xe9</p>".as_slice(), b"<p>caf\xe8</p>".as_slice());
+
+        assert!(comparer(InvalidUtf8::Replace)
+            .is_equal_bytes(latin1, other)
+            .unwrap());
+        assert!(comparer(InvalidUtf8::Replace)
+            .is_equal_bytes(latin1, "<p>caf\u{fffd}</p>".as_bytes())
+            .unwrap());
+
+        let err = comparer(InvalidUtf8::Error)
+            .is_equal_bytes(b"<p>ok</p>", latin1)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid UTF-8 at byte 6 of the actual document"
+        );
+        assert!(!err.is_mismatch());
+
+        let bytes = comparer(InvalidUtf8::CompareBytes);
+        assert!(bytes.is_equal_bytes(latin1, latin1).unwrap());
+        assert!(!bytes.is_equal_bytes(latin1, other).unwrap());
+        bytes
+            .verify_bytes(b"<p>caf\xc3\xa9</p>", "<p>café</p>".as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            InvalidUtf8::CompareBytes
+                .decode(b"a\xffb", "actual")
+                .unwrap(),
+            "a\u{10ffff}b"
+        );
+        assert!(matches!(
+            InvalidUtf8::Replace.decode(b"valid", "actual").unwrap(),
+            Cow::Borrowed("valid")
+        ));
+        assert_eq!(
+            InvalidUtf8::from_name("compare_bytes"),
+            Some(InvalidUtf8::CompareBytes)
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Decoding of character references left in attribute values.
+//!
+//! The parser decodes the references of the markup itself, so
+//! `title='it&#39;s'` already compares equal to `title="it's"`. Values
+//! escaped once more than they should be, as by a template engine escaping
+//! an already escaped string, keep references after parsing:
+//! `data-x="a&amp;amp;b"` holds `a&amp;b`. With
+//! [`HtmlCompareOptions::decode_attribute_entities`](crate::HtmlCompareOptions::decode_attribute_entities)
+//! those are decoded as well before values are compared. Numeric references
+//! and the named references of the characters that need escaping are
+//! decoded; other text starting with `&` is kept as is.
+
+use std::borrow::Cow;
+
+/// Named references decoded, with the characters they stand for
+const NAMED: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+];
+
+/// Longest reference decoded, without `&` and `;`
+const MAX_REFERENCE_LEN: usize = 10;
+
+/// `value` with its character references decoded, repeatedly until none is
+/// left
+pub(crate) fn decode(value: Cow<'_, str>) -> Cow<'_, str> {
+    let mut value = value;
+    while let Some(decoded) = decode_once(&value) {
+        value = Cow::Owned(decoded);
+    }
+    value
+}
+
+/// `value` with one level of references decoded, if it has any
+fn decode_once(value: &str) -> Option<String> {
+    if !value.contains('&') {
+        return None;
+    }
+    let mut decoded = String::with_capacity(value.len());
+    let mut changed = false;
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match reference(rest) {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+                changed = true;
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    changed.then_some(decoded)
+}
+
+/// The character of the reference `text` starts with and its length
+fn reference(text: &str) -> Option<(char, usize)> {
+    let end = text
+        .char_indices()
+        .take(MAX_REFERENCE_LEN + 2)
+        .find(|(_, c)| *c == ';')?
+        .0;
+    let name = &text[1..end];
+    let c = match name.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code).filter(|c| *c != '\0')?
+        }
+        None => NAMED.iter().find(|(named, _)| *named == name)?.1,
+    };
+    Some((c, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let decode = |value: &str| decode(Cow::Borrowed(value)).into_owned();
+        assert_eq!(decode("it&#39;s"), "it's");
+        assert_eq!(decode("a&amp;amp;b"), "a&b");
+        assert_eq!(
+            decode("&lt;b&gt; &#x2014; &quot;q&quot;"),
+            "<b> \u{2014} \"q\""
+        );
+        assert_eq!(decode("AT&T &copy; &#xZZ; & &;"), "AT&T &copy; &#xZZ; & &;");
+        assert!(matches!(
+            super::decode(Cow::Borrowed("plain")),
+            Cow::Borrowed("plain")
+        ));
+    }
+}
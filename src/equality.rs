@@ -0,0 +1,124 @@
+//! The comparison's equality of single nodes, for reuse by other tools.
+//!
+//! Custom matchers, deduplication and similar utilities need to know
+//! whether two nodes are equal exactly as the comparison would decide,
+//! without comparing whole documents. [`text_equal`], [`attributes_equal`]
+//! and [`element_shallow_equal`] apply the same normalization as
+//! [`HtmlComparer::compare`] to one piece of a node each. The free functions
+//! set up a comparer per call; in a loop, call the methods of the same names
+//! on one [`HtmlComparer`] instead.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::{HtmlCompareOptions, HtmlComparer};
+
+/// Whether two text or comment nodes are equal under `options`
+///
+/// Nodes of different kinds, and nodes that are neither text nor comment,
+/// are never equal. Ignored text and comments are always equal.
+pub fn text_equal(a: NodeRef<Node>, b: NodeRef<Node>, options: &HtmlCompareOptions) -> bool {
+    HtmlComparer::with_options(options.clone()).text_equal(a, b)
+}
+
+/// Whether the compared attributes of two elements are equal under
+/// `options`
+pub fn attributes_equal(a: ElementRef, b: ElementRef, options: &HtmlCompareOptions) -> bool {
+    HtmlComparer::with_options(options.clone()).attributes_equal(a, b)
+}
+
+/// Whether two elements have the same tag and attributes under `options`,
+/// regardless of their children
+pub fn element_shallow_equal(a: ElementRef, b: ElementRef, options: &HtmlCompareOptions) -> bool {
+    HtmlComparer::with_options(options.clone()).element_shallow_equal(a, b)
+}
+
+impl HtmlComparer {
+    /// [`text_equal`] under the options of this comparer
+    pub fn text_equal(&self, a: NodeRef<Node>, b: NodeRef<Node>) -> bool {
+        match (a.value(), b.value()) {
+            (Node::Text(_), Node::Text(_)) if self.options.ignore_text => true,
+            (Node::Comment(_), Node::Comment(_)) if self.options.ignore_comments => true,
+            (Node::Text(_), Node::Text(_)) => {
+                self.texts_equal(&self.node_text(a), &self.node_text(b))
+            }
+            (Node::Comment(_), Node::Comment(_)) => self.node_text(a) == self.node_text(b),
+            _ => false,
+        }
+    }
+
+    /// [`attributes_equal`] under the options of this comparer
+    pub fn attributes_equal(&self, a: ElementRef, b: ElementRef) -> bool {
+        if self.options.ignore_attributes {
+            return true;
+        }
+        let mut a = self.compared_attributes(a);
+        let mut b = self.compared_attributes(b);
+        a.sort_unstable();
+        b.sort_unstable();
+        a == b
+    }
+
+    /// [`element_shallow_equal`] under the options of this comparer
+    pub fn element_shallow_equal(&self, a: ElementRef, b: ElementRef) -> bool {
+        self.tags_match(
+            self.element_name(a, self.top_heading_level(*a)),
+            self.element_name(b, self.top_heading_level(*b)),
+        ) && self.attributes_equal(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::{Html, Selector};
+
+    fn elements(html: &str) -> Html {
+        Html::parse_fragment(html)
+    }
+
+    #[test]
+    fn test_element_equality() {
+        let document = elements(
+            "<b class='a b' id=x>One</b><strong class='a b' id=y>Two</strong><b class=a>Three</b>",
+        );
+        let selector = Selector::parse("b, strong").unwrap();
+        let found: Vec<_> = document.select(&selector).collect();
+        let options = HtmlCompareOptions {
+            ignored_attributes: ["id".to_string()].into(),
+            treat_presentational_tags_as_equivalent: true,
+            ..Default::default()
+        };
+        assert!(attributes_equal(found[0], found[1], &options));
+        assert!(element_shallow_equal(found[0], found[1], &options));
+        assert!(!attributes_equal(found[0], found[2], &options));
+        assert!(!element_shallow_equal(
+            found[0],
+            found[1],
+            &HtmlCompareOptions::default()
+        ));
+    }
+
+    #[test]
+    fn test_text_equal() {
+        let document = elements("<p> Hello </p><p>Hello</p><p>Bye</p><!--Hello-->");
+        let texts: Vec<_> = document
+            .tree
+            .root()
+            .descendants()
+            .filter(|node| node.value().is_text() || node.value().is_comment())
+            .collect();
+        let comparer = HtmlComparer::new();
+        assert!(comparer.text_equal(texts[0], texts[1]));
+        assert!(!comparer.text_equal(texts[0], texts[2]));
+        assert!(!comparer.text_equal(texts[1], texts[3]));
+        assert!(text_equal(
+            texts[0],
+            texts[2],
+            &HtmlCompareOptions {
+                ignore_text: true,
+                ..Default::default()
+            }
+        ));
+    }
+}
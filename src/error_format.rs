@@ -0,0 +1,90 @@
+//! Custom rendering of assertion failures.
+//!
+//! [`assert_html_eq!`](crate::assert_html_eq) and
+//! [`assert_template_renders!`](crate::assert_template_renders) panic with
+//! the first mismatch and both documents. Teams with their own tooling can
+//! replace that message, e.g. with a link to a diff viewer the documents
+//! were uploaded to, by setting
+//! [`HtmlCompareOptions::error_formatter`](crate::HtmlCompareOptions::error_formatter),
+//! or [`HtmlComparer::set_error_formatter`] on a comparer. The formatter
+//! receives the [`HtmlDiff`] of the two documents and returns the panic
+//! message. The macros see it when it is set in the options they are given,
+//! or in the thread's defaults (see
+//! [`HtmlCompareOptions::enter`](crate::HtmlCompareOptions::enter)).
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{HtmlComparer, HtmlDiff};
+
+type FormatFn = dyn Fn(&HtmlDiff) -> String + Send + Sync;
+
+/// Renders the differences of a failed assertion into its panic message
+#[derive(Clone)]
+pub struct ErrorFormatter(Arc<FormatFn>);
+
+impl ErrorFormatter {
+    pub fn new(format: impl Fn(&HtmlDiff) -> String + Send + Sync + 'static) -> Self {
+        ErrorFormatter(Arc::new(format))
+    }
+
+    pub fn format(&self, diff: &HtmlDiff) -> String {
+        (self.0)(diff)
+    }
+}
+
+impl fmt::Debug for ErrorFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ErrorFormatter(..)")
+    }
+}
+
+impl HtmlComparer {
+    /// Render failed assertions with `format` instead of the built-in message
+    pub fn set_error_formatter(
+        &mut self,
+        format: impl Fn(&HtmlDiff) -> String + Send + Sync + 'static,
+    ) {
+        self.options.error_formatter = Some(ErrorFormatter::new(format));
+    }
+
+    /// The error formatter's rendering of the differences between `expected`
+    /// and `actual`, if one is set
+    pub fn failure_report(&self, expected: &str, actual: &str) -> Option<String> {
+        let formatter = self.options.error_formatter.as_ref()?;
+        Some(formatter.format(&self.diff(expected, actual)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    #[test]
+    fn test_failure_report() {
+        let mut comparer = HtmlComparer::new();
+        assert_eq!(comparer.failure_report("<p>a</p>", "<p>b</p>"), None);
+        comparer.set_error_formatter(|diff| {
+            format!("{} differences, see https://diff.example", diff.len())
+        });
+        assert_eq!(
+            comparer
+                .failure_report("<p class='x'>a</p>", "<p>b</p>")
+                .as_deref(),
+            Some("2 differences, see https://diff.example")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "custom: /html/body/p/text(): expected 'a', found 'b'")]
+    fn test_macro_uses_formatter() {
+        let options = HtmlCompareOptions {
+            error_formatter: Some(ErrorFormatter::new(|diff| {
+                format!("custom: {}", diff.iter().next().unwrap())
+            })),
+            ..Default::default()
+        };
+        crate::assert_html_eq!("<p>a</p>", "<p>b</p>", options);
+    }
+}
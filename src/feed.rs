@@ -0,0 +1,383 @@
+//! Semantic comparison of RSS and Atom feeds.
+//!
+//! Feeds are XML, but many of their element names (`link`, `image`, `source`)
+//! have special meaning to an HTML parser. Before parsing, every tag is moved
+//! into a private `x-` namespace and CDATA sections are escaped, so the HTML
+//! parser builds a plain tree of custom elements that mirrors the XML.
+//!
+//! The feed is then flattened into fields keyed by their path (`author/name`,
+//! `link@href`), and entries are matched by `guid`/`id` rather than position.
+//!
+//! Field names are lowercased by the parser, so all names used in
+//! [`FeedCompareOptions`] must be lowercase.
+
+use std::collections::{BTreeMap, HashSet};
+
+use scraper::{ElementRef, Html, Node};
+
+use crate::HtmlCompareError;
+
+const TAG_PREFIX: &str = "x-";
+
+/// Fields flattened from an element subtree, keyed by path
+pub type FeedFields = BTreeMap<String, Vec<String>>;
+
+/// Configuration for feed comparison
+#[derive(Debug, Clone)]
+pub struct FeedCompareOptions {
+    /// Field names (last path segment, e.g. `updated`) or full paths to ignore
+    pub ignored_fields: HashSet<String>,
+    /// Match entries by their identity instead of their position
+    pub ignore_entry_order: bool,
+}
+
+impl Default for FeedCompareOptions {
+    fn default() -> Self {
+        Self {
+            ignored_fields: ["lastbuilddate", "updated"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            ignore_entry_order: true,
+        }
+    }
+}
+
+/// A parsed RSS or Atom feed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Feed {
+    /// Channel-level (RSS) or feed-level (Atom) fields
+    pub fields: FeedFields,
+    /// The `<item>` or `<entry>` elements, in document order
+    pub entries: Vec<FeedEntry>,
+}
+
+/// A single `<item>` or `<entry>`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedEntry {
+    /// Identity used to match entries: `guid`, `id`, `link`, or `title`
+    pub key: String,
+    /// Fields of the entry, keyed by path relative to the entry
+    pub fields: FeedFields,
+}
+
+/// Parse an RSS or Atom document
+pub fn parse_feed(xml: &str) -> Feed {
+    let document = Html::parse_fragment(&prepare(xml));
+    let root = document.root_element();
+
+    let mut feed = Feed::default();
+    for element in root.descendants().filter_map(ElementRef::wrap) {
+        match local_name(element) {
+            "channel" | "feed" => collect_fields(element, "", &mut feed.fields),
+            "item" | "entry" => {
+                let mut fields = FeedFields::new();
+                collect_fields(element, "", &mut fields);
+                feed.entries.push(FeedEntry {
+                    key: entry_key(&fields),
+                    fields,
+                });
+            }
+            _ => {}
+        }
+    }
+    feed
+}
+
+/// Compare two feeds by their content rather than their markup
+pub fn compare_feeds(
+    expected: &str,
+    actual: &str,
+    options: &FeedCompareOptions,
+) -> Result<(), HtmlCompareError> {
+    let expected = parse_feed(expected);
+    let actual = parse_feed(actual);
+
+    compare_fields("Feed", &expected.fields, &actual.fields, options)?;
+
+    if options.ignore_entry_order {
+        let mut unmatched: Vec<_> = actual.entries.iter().collect();
+        for entry in &expected.entries {
+            let Some(index) = unmatched.iter().position(|e| e.key == entry.key) else {
+                return Err(HtmlCompareError::FeedMismatch(format!(
+                    "Missing entry: {}",
+                    entry.key
+                )));
+            };
+            let candidate = unmatched.remove(index);
+            compare_entry(entry, candidate, options)?;
+        }
+        if let Some(extra) = unmatched.first() {
+            return Err(HtmlCompareError::FeedMismatch(format!(
+                "Unexpected entry: {}",
+                extra.key
+            )));
+        }
+    } else {
+        if expected.entries.len() != actual.entries.len() {
+            return Err(HtmlCompareError::FeedMismatch(format!(
+                "Entry count mismatch. Expected: {}, Actual: {}",
+                expected.entries.len(),
+                actual.entries.len()
+            )));
+        }
+        for (expected_entry, actual_entry) in expected.entries.iter().zip(&actual.entries) {
+            compare_entry(expected_entry, actual_entry, options)?;
+        }
+    }
+    Ok(())
+}
+
+fn compare_entry(
+    expected: &FeedEntry,
+    actual: &FeedEntry,
+    options: &FeedCompareOptions,
+) -> Result<(), HtmlCompareError> {
+    compare_fields(
+        &format!("Entry {}", expected.key),
+        &expected.fields,
+        &actual.fields,
+        options,
+    )
+}
+
+fn compare_fields(
+    context: &str,
+    expected: &FeedFields,
+    actual: &FeedFields,
+    options: &FeedCompareOptions,
+) -> Result<(), HtmlCompareError> {
+    let is_ignored = |path: &str| {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let name = name.split('@').next().unwrap_or(name);
+        options.ignored_fields.contains(path) || options.ignored_fields.contains(name)
+    };
+    let empty = Vec::new();
+
+    let paths = expected.keys().chain(actual.keys());
+    for path in paths.filter(|path| !is_ignored(path)) {
+        let expected_values = expected.get(path).unwrap_or(&empty);
+        let actual_values = actual.get(path).unwrap_or(&empty);
+        if expected_values != actual_values {
+            return Err(HtmlCompareError::FeedMismatch(format!(
+                "{} field {} mismatch. Expected: {:?}, Actual: {:?}",
+                context, path, expected_values, actual_values
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Flatten the text and attributes below `element` into `fields`, skipping
+/// nested entries
+fn collect_fields(element: ElementRef, prefix: &str, fields: &mut FeedFields) {
+    for child in element.children() {
+        let Some(child) = ElementRef::wrap(child) else {
+            continue;
+        };
+        let name = local_name(child);
+        if name == "item" || name == "entry" {
+            continue;
+        }
+        let path = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+
+        for (attr, value) in child.value().attrs() {
+            insert_sorted(fields, format!("{}@{}", path, attr), value.trim());
+        }
+        let text = child
+            .children()
+            .filter_map(|node| match node.value() {
+                Node::Text(text) => Some(&**text),
+                _ => None,
+            })
+            .collect::<String>();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !text.is_empty() {
+            insert_sorted(fields, path.clone(), &text);
+        }
+        collect_fields(child, &path, fields);
+    }
+}
+
+fn insert_sorted(fields: &mut FeedFields, path: String, value: &str) {
+    let values = fields.entry(path).or_default();
+    let index = values.partition_point(|existing| existing.as_str() < value);
+    values.insert(index, value.to_string());
+}
+
+fn entry_key(fields: &FeedFields) -> String {
+    ["guid", "id", "link", "link@href", "title"]
+        .iter()
+        .find_map(|path| fields.get(*path).and_then(|values| values.first()))
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn local_name<'a>(element: ElementRef<'a>) -> &'a str {
+    let name = element.value().name();
+    name.strip_prefix(TAG_PREFIX).unwrap_or(name)
+}
+
+/// Rewrite feed XML so the HTML parser treats every element as a custom element
+fn prepare(xml: &str) -> String {
+    let mut output = String::with_capacity(xml.len() + xml.len() / 4);
+    let mut rest = xml;
+
+    while let Some(index) = rest.find('<') {
+        output.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            output.push_str(
+                &cdata[..end]
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;"),
+            );
+            rest = cdata.get(end + 3..).unwrap_or_default();
+            continue;
+        }
+
+        let (slash, name_start) = match rest.as_bytes().get(1) {
+            Some(b'/') => ("/", 2),
+            _ => ("", 1),
+        };
+        let starts_name = rest[name_start..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_');
+        if !starts_name {
+            // Comments, processing instructions and doctypes pass through untouched
+            output.push('<');
+            rest = &rest[1..];
+            continue;
+        }
+
+        let tag_end = rest.find('>').map_or(rest.len(), |end| end + 1);
+        let tag = &rest[name_start..tag_end];
+        output.push('<');
+        output.push_str(slash);
+        output.push_str(TAG_PREFIX);
+        match tag.strip_suffix("/>") {
+            Some(body) => {
+                // Expand self-closing elements, which HTML would leave open
+                let name_len = body.find(|c: char| c.is_whitespace()).unwrap_or(body.len());
+                output.push_str(body);
+                output.push_str("></");
+                output.push_str(TAG_PREFIX);
+                output.push_str(&body[..name_len]);
+                output.push('>');
+            }
+            None => output.push_str(tag),
+        }
+        rest = &rest[tag_end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Example</title>
+    <link>https://example.com/</link>
+    <lastBuildDate>Mon, 06 Sep 2010 00:01:00 +0000</lastBuildDate>
+    <item>
+      <title>First</title>
+      <link>https://example.com/1</link>
+      <guid isPermaLink="false">1</guid>
+      <description><![CDATA[<p>Hello & welcome</p>]]></description>
+      <enclosure url="https://example.com/1.mp3" length="1" type="audio/mpeg"/>
+    </item>
+    <item>
+      <title>Second</title>
+      <guid isPermaLink="false">2</guid>
+    </item>
+  </channel>
+</rss>"#;
+
+    #[test]
+    fn test_parse_rss() {
+        let feed = parse_feed(RSS);
+        assert_eq!(feed.fields["title"], ["Example"]);
+        assert_eq!(feed.fields["link"], ["https://example.com/"]);
+        assert_eq!(feed.entries.len(), 2);
+
+        let first = &feed.entries[0];
+        assert_eq!(first.key, "1");
+        assert_eq!(first.fields["link"], ["https://example.com/1"]);
+        assert_eq!(first.fields["description"], ["<p>Hello & welcome</p>"]);
+        assert_eq!(first.fields["enclosure@url"], ["https://example.com/1.mp3"]);
+    }
+
+    #[test]
+    fn test_compare_rss() {
+        let reordered = r#"<rss version="2.0"><channel>
+            <lastBuildDate>Tue, 07 Sep 2010 00:01:00 +0000</lastBuildDate>
+            <title>Example</title>
+            <link>https://example.com/</link>
+            <item><guid isPermaLink="false">2</guid><title>Second</title></item>
+            <item>
+              <guid isPermaLink="false">1</guid>
+              <title>First</title>
+              <link>https://example.com/1</link>
+              <description>&lt;p&gt;Hello &amp; welcome&lt;/p&gt;</description>
+              <enclosure url="https://example.com/1.mp3" length="1" type="audio/mpeg"></enclosure>
+            </item>
+        </channel></rss>"#;
+        let options = FeedCompareOptions::default();
+        assert!(compare_feeds(RSS, reordered, &options).is_ok());
+
+        let in_order = FeedCompareOptions {
+            ignore_entry_order: false,
+            ..Default::default()
+        };
+        assert!(compare_feeds(RSS, reordered, &in_order).is_err());
+
+        let changed = RSS.replace("<title>Second</title>", "<title>Changed</title>");
+        assert_eq!(
+            compare_feeds(RSS, &changed, &options)
+                .unwrap_err()
+                .to_string(),
+            r#"Feed mismatch: Entry 2 field title mismatch. Expected: ["Second"], Actual: ["Changed"]"#
+        );
+    }
+
+    #[test]
+    fn test_compare_atom() {
+        let expected = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+            <title>Example</title>
+            <updated>2003-12-13T18:30:02Z</updated>
+            <entry>
+                <id>urn:uuid:1</id>
+                <title>Entry</title>
+                <link href="https://example.com/1"/>
+                <updated>2003-12-13T18:30:02Z</updated>
+                <author><name>Ada</name></author>
+            </entry>
+        </feed>"#;
+        let actual = r#"<feed xmlns="http://www.w3.org/2005/Atom"><updated>2024-01-01T00:00:00Z</updated><title>Example</title><entry><updated>2024-01-01T00:00:00Z</updated><link href="https://example.com/1"></link><author><name>Ada</name></author><title>Entry</title><id>urn:uuid:1</id></entry></feed>"#;
+        let options = FeedCompareOptions::default();
+        assert!(compare_feeds(expected, actual, &options).is_ok());
+
+        let feed = parse_feed(expected);
+        assert_eq!(feed.entries[0].key, "urn:uuid:1");
+        assert_eq!(feed.entries[0].fields["author/name"], ["Ada"]);
+
+        let other_author = actual.replace("Ada", "Grace");
+        assert!(compare_feeds(expected, &other_author, &options).is_err());
+        assert!(matches!(
+            compare_feeds(expected, "<feed></feed>", &options),
+            Err(HtmlCompareError::FeedMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,451 @@
+//! Pretty-printing of HTML documents.
+//!
+//! [`format_html`] re-serializes a document with consistent indentation,
+//! quoting and attribute layout. Line breaks and indentation are only added
+//! between block-level elements, where the browser ignores whitespace, and
+//! inline content is written as it was, so the output compares equal to the
+//! input under the default options as well as under
+//! [`PrettyProfile::default`](crate::PrettyProfile).
+//!
+//! Attributes are written in a stable order that puts the ones a reader
+//! looks for first, `id`, `class` and `name` by default (see
+//! [`FormatOptions::attribute_order`]), and the rest in order of their
+//! names. `<html>`, `<head>` and
+//! `<body>` tags are only written when the input has them; otherwise the
+//! parser adds them back in the same place.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Html, Node};
+
+use crate::pretty;
+
+/// Quotes around attribute values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// `class="a"`
+    #[default]
+    Double,
+    /// `class='a'`
+    Single,
+}
+
+/// How void elements such as `<br>` end
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SelfClosingStyle {
+    /// `<br>`
+    #[default]
+    Void,
+    /// `<br/>`
+    Slash,
+    /// `<br />`
+    SpacedSlash,
+}
+
+/// When the attributes of a start tag are put on lines of their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeWrap {
+    /// Keep start tags on one line
+    Never,
+    /// Wrap every start tag with more than one attribute
+    Always,
+    /// Wrap start tags that would run past this column
+    Width(usize),
+}
+
+/// Layout of the output of [`format_html`]
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Spaces per level of indentation
+    pub indent_width: usize,
+    /// When to put attributes on lines of their own
+    pub wrap_attributes: AttributeWrap,
+    /// Quotes around attribute values; attributes with empty values are
+    /// written without one, e.g. `required`
+    pub quote_style: QuoteStyle,
+    /// How void elements end
+    pub self_closing: SelfClosingStyle,
+    /// Attributes written before all others, in this order; the others
+    /// follow in order of their names
+    pub attribute_order: Vec<String>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 2,
+            wrap_attributes: AttributeWrap::Width(80),
+            quote_style: QuoteStyle::Double,
+            self_closing: SelfClosingStyle::Void,
+            attribute_order: default_attribute_order(),
+        }
+    }
+}
+
+/// Attributes written before all others by default, in this order
+pub const DEFAULT_ATTRIBUTE_ORDER: &[&str] = &["id", "class", "name"];
+
+/// [`DEFAULT_ATTRIBUTE_ORDER`] as owned names
+pub(crate) fn default_attribute_order() -> Vec<String> {
+    DEFAULT_ATTRIBUTE_ORDER
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Sort `attributes` by their position in `order`, then by name and value
+pub(crate) fn sort_attributes<V: Ord>(attributes: &mut [(&str, V)], order: &[String]) {
+    attributes.sort_by(|a, b| {
+        attribute_rank(order, a.0)
+            .cmp(&attribute_rank(order, b.0))
+            .then_with(|| a.cmp(b))
+    });
+}
+
+/// Where the attribute `name` goes in `order`: its position, or after all
+/// listed attributes
+pub(crate) fn attribute_rank(order: &[String], name: &str) -> usize {
+    order
+        .iter()
+        .position(|first| first == name)
+        .unwrap_or(order.len())
+}
+
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose text is written without escaping
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "xmp", "iframe", "noembed", "noframes"];
+
+/// Elements whose content is written exactly as it was
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "textarea", "listing"];
+
+/// Format `html` as an indented document
+///
+/// # Examples
+/// ```ignore
+/// use html_compare::{format_html, FormatOptions};
+///
+/// let formatted = format_html("<ul><li>One</li><li>Two</li></ul>", &FormatOptions::default());
+/// assert_eq!(formatted, "<ul>\n  <li>One</li>\n  <li>Two</li>\n</ul>\n");
+/// ```
+pub fn format_html(html: &str, options: &FormatOptions) -> String {
+    let document = Html::parse_document(html);
+    let source = html.to_ascii_lowercase();
+    let mut formatter = Formatter {
+        options,
+        explicit: ["html", "head", "body"].map(|name| has_start_tag(&source, name)),
+        output: String::new(),
+    };
+    formatter.children(document.tree.root(), 0);
+    let mut formatted = formatter.output.trim_start_matches('\n').to_string();
+    formatted.push('\n');
+    formatted
+}
+
+/// Whether `source` contains a `<name` start tag
+fn has_start_tag(source: &str, name: &str) -> bool {
+    let open = format!("<{}", name);
+    source.match_indices(&open).any(|(start, _)| {
+        !source[start + open.len()..].starts_with(|c: char| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+struct Formatter<'o> {
+    options: &'o FormatOptions,
+    /// Whether the `<html>`, `<head>` and `<body>` tags appear in the input
+    explicit: [bool; 3],
+    output: String,
+}
+
+impl Formatter<'_> {
+    /// Whether `node` is an `<html>`, `<head>` or `<body>` element that the
+    /// parser inserted on its own
+    fn implied(&self, node: NodeRef<Node>) -> bool {
+        match node.value() {
+            Node::Element(element) => match element.name() {
+                "html" => !self.explicit[0],
+                "head" => !self.explicit[1],
+                "body" => !self.explicit[2],
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn newline(&mut self, depth: usize) {
+        self.output.push('\n');
+        self.output
+            .push_str(&" ".repeat(depth * self.options.indent_width));
+    }
+
+    /// Write the children of `parent` at indentation `depth`, one per line if
+    /// they are all blocks and inline otherwise; returns whether they were
+    /// put on lines of their own
+    fn children(&mut self, parent: NodeRef<Node>, depth: usize) -> bool {
+        let children: Vec<NodeRef<Node>> = match parent.children().next() {
+            // <template> contents live in a document fragment
+            Some(fragment) if matches!(fragment.value(), Node::Fragment) => {
+                fragment.children().collect()
+            }
+            _ => parent.children().collect(),
+        };
+        let top_level = matches!(parent.value(), Node::Document) || self.implied(parent);
+        let blocks = children.iter().any(|child| child.value().is_element())
+            && children.iter().all(|child| match child.value() {
+                Node::Text(text) => text.trim().is_empty(),
+                Node::Element(_) => pretty::is_block(Some(*child)) || self.implied(*child),
+                _ => true,
+            });
+
+        if top_level || blocks {
+            if !blocks && !children.is_empty() {
+                self.newline(depth);
+            }
+            for child in children {
+                match child.value() {
+                    Node::Text(text) if blocks && text.trim().is_empty() => {}
+                    _ if self.implied(child) => {
+                        self.children(child, depth);
+                    }
+                    _ => {
+                        if blocks {
+                            self.newline(depth);
+                        }
+                        self.node(child, depth);
+                    }
+                }
+            }
+        } else {
+            for child in children {
+                self.node(child, depth);
+            }
+        }
+        blocks
+    }
+
+    fn node(&mut self, node: NodeRef<Node>, depth: usize) {
+        match node.value() {
+            Node::Doctype(doctype) => {
+                self.output.push_str("<!DOCTYPE ");
+                self.output.push_str(doctype.name());
+                if !doctype.public_id().is_empty() {
+                    self.output
+                        .push_str(&format!(" PUBLIC \"{}\"", doctype.public_id()));
+                }
+                if !doctype.system_id().is_empty() {
+                    self.output
+                        .push_str(&format!(" \"{}\"", doctype.system_id()));
+                }
+                self.output.push('>');
+            }
+            Node::Comment(comment) => {
+                self.output.push_str("<!--");
+                self.output.push_str(comment);
+                self.output.push_str("-->");
+            }
+            Node::Text(text) => {
+                let raw = node
+                    .parent()
+                    .and_then(ElementRef::wrap)
+                    .is_some_and(|parent| RAW_TEXT_ELEMENTS.contains(&parent.value().name()));
+                if raw {
+                    self.output.push_str(text);
+                } else {
+                    self.output.push_str(&escape_text(text));
+                }
+            }
+            Node::Element(_) => {
+                if let Some(element) = ElementRef::wrap(node) {
+                    self.element(element, depth);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn element(&mut self, element: ElementRef, depth: usize) {
+        let name = element.value().name();
+        self.start_tag(element, depth);
+        if VOID_ELEMENTS.contains(&name) {
+            return;
+        }
+
+        if PREFORMATTED_ELEMENTS.contains(&name) || RAW_TEXT_ELEMENTS.contains(&name) {
+            // The parser drops a newline right after <pre>, so one that
+            // belongs to the content needs another in front of it
+            let leading_newline = matches!(
+                element.first_child().map(|child| child.value()),
+                Some(Node::Text(text)) if text.starts_with('\n')
+            );
+            if leading_newline && PREFORMATTED_ELEMENTS.contains(&name) {
+                self.output.push('\n');
+            }
+            for child in element.children() {
+                self.node(child, depth);
+            }
+        } else if self.children(*element, depth + 1) {
+            self.newline(depth);
+        }
+
+        self.output.push_str("</");
+        self.output.push_str(name);
+        self.output.push('>');
+    }
+
+    fn start_tag(&mut self, element: ElementRef, depth: usize) {
+        let name = element.value().name();
+        let mut attributes: Vec<(&str, &str)> = element.value().attrs().collect();
+        sort_attributes(&mut attributes, &self.options.attribute_order);
+        let attributes: Vec<String> = attributes
+            .into_iter()
+            .map(|(name, value)| self.attribute(name, value))
+            .collect();
+        let end = match (VOID_ELEMENTS.contains(&name), self.options.self_closing) {
+            (false, _) | (true, SelfClosingStyle::Void) => ">",
+            (true, SelfClosingStyle::Slash) => "/>",
+            (true, SelfClosingStyle::SpacedSlash) => " />",
+        };
+
+        let one_line = format!(
+            "<{}{}{}",
+            name,
+            attributes
+                .iter()
+                .map(|attribute| format!(" {}", attribute))
+                .collect::<String>(),
+            end
+        );
+        let wrap = match self.options.wrap_attributes {
+            AttributeWrap::Never => false,
+            AttributeWrap::Always => attributes.len() > 1,
+            AttributeWrap::Width(width) => {
+                !attributes.is_empty() && depth * self.options.indent_width + one_line.len() > width
+            }
+        };
+        if !wrap {
+            self.output.push_str(&one_line);
+            return;
+        }
+
+        self.output.push('<');
+        self.output.push_str(name);
+        for attribute in &attributes {
+            self.newline(depth + 1);
+            self.output.push_str(attribute);
+        }
+        self.newline(depth);
+        self.output.push_str(end.trim_start());
+    }
+
+    fn attribute(&self, name: &str, value: &str) -> String {
+        if value.is_empty() {
+            return name.to_string();
+        }
+        let (quote, escaped_quote) = match self.options.quote_style {
+            QuoteStyle::Double => ('"', "&quot;"),
+            QuoteStyle::Single => ('\'', "&#39;"),
+        };
+        let value = value.replace('&', "&amp;").replace(quote, escaped_quote);
+        format!("{}={}{}{}", name, quote, value, quote)
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\u{a0}', "&nbsp;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlComparer, PrettyProfile};
+
+    const PAGE: &str = r#"<!DOCTYPE html><html lang="en"><head><title>Hi &amp; bye</title><meta charset="utf-8"></head><body><div class="card"><h1>Title</h1><p>Some <b>bold</b> text</p><hr><ul><li>One</li><li>Two <a href="/?a=1&amp;b=2">link</a></li></ul></div><pre>  code
+</pre></body></html>"#;
+
+    #[test]
+    fn test_format_html() {
+        assert_eq!(
+            format_html(PAGE, &FormatOptions::default()),
+            r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <title>Hi &amp; bye</title>
+    <meta charset="utf-8">
+  </head>
+  <body>
+    <div class="card">
+      <h1>Title</h1>
+      <p>Some <b>bold</b> text</p>
+      <hr>
+      <ul>
+        <li>One</li>
+        <li>Two <a href="/?a=1&amp;b=2">link</a></li>
+      </ul>
+    </div>
+    <pre>  code
+</pre>
+  </body>
+</html>
+"#
+        );
+
+        // Tags the parser would add are left out
+        assert_eq!(
+            format_html("<p>a</p><p>b<br>c</p>", &FormatOptions::default()),
+            "<p>a</p>\n<p>b<br>c</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_format_options() {
+        let options = FormatOptions {
+            indent_width: 4,
+            wrap_attributes: AttributeWrap::Always,
+            quote_style: QuoteStyle::Single,
+            self_closing: SelfClosingStyle::SpacedSlash,
+            attribute_order: Vec::new(),
+        };
+        assert_eq!(
+            format_html(
+                r#"<input type="text" name="q" value="it's" required>"#,
+                &options
+            ),
+            "<input\n    name='q'\n    required\n    type='text'\n    value='it&#39;s'\n/>\n"
+        );
+
+        let options = FormatOptions {
+            wrap_attributes: AttributeWrap::Width(25),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_html(
+                r#"<p id="short">x</p><p id="a" class="much longer">y</p>"#,
+                &options
+            ),
+            "<p id=\"short\">x</p>\n<p\n  id=\"a\"\n  class=\"much longer\"\n>y</p>\n"
+        );
+
+        let options = FormatOptions {
+            attribute_order: vec!["type".to_string(), "name".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            format_html(r#"<input value="x" name="q" type="text" id="i">"#, &options),
+            "<input type=\"text\" name=\"q\" id=\"i\" value=\"x\">\n"
+        );
+    }
+
+    #[test]
+    fn test_format_round_trips() {
+        let formatted = format_html(PAGE, &FormatOptions::default());
+        HtmlComparer::new().verify(PAGE, &formatted).unwrap();
+        HtmlComparer::with_options(PrettyProfile::default().options())
+            .verify(PAGE, &formatted)
+            .unwrap();
+    }
+}
@@ -0,0 +1,326 @@
+//! Comparison of the data a `<form>` would submit.
+//!
+//! Backend tests usually care about which name/value pairs reach the server,
+//! not about the layout wrapping the controls. [`serialize_form`] builds the
+//! entry list a browser would submit for a form without a submitter (e.g. when
+//! submitted by pressing enter in a form without buttons), and
+//! [`compare_forms`] checks that two documents produce the same list.
+
+use scraper::{ElementRef, Html, Selector};
+
+use crate::HtmlCompareError;
+
+/// The name/value pairs of a form submission, in tree order
+pub type FormData = Vec<(String, String)>;
+
+/// Serialize the first `<form>` matching `selector`
+///
+/// Controls are included following the HTML form submission rules: disabled
+/// controls (including those inside a disabled `<fieldset>`), unnamed
+/// controls, buttons, unchecked checkboxes and radios are skipped; controls
+/// outside the form that reference it through their `form` attribute are
+/// included. File inputs contribute an empty value and textarea line breaks
+/// are normalized to `\n`.
+pub fn serialize_form(html: &str, selector: &str) -> Result<FormData, HtmlCompareError> {
+    let selector = Selector::parse(selector)
+        .map_err(|err| HtmlCompareError::InvalidSelector(format!("{}: {}", selector, err)))?;
+    let document = Html::parse_document(html);
+
+    let form = document
+        .select(&selector)
+        .find(|element| element.value().name() == "form")
+        .ok_or_else(|| {
+            HtmlCompareError::FormMismatch("No <form> matches the selector".to_string())
+        })?;
+    let form_id = form.value().id();
+
+    let mut entries = Vec::new();
+    for element in document
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+    {
+        if !matches!(element.value().name(), "input" | "select" | "textarea")
+            || !is_owned_by(element, form, form_id)
+            || is_disabled(element)
+            || element
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|ancestor| ancestor.value().name() == "datalist")
+        {
+            continue;
+        }
+        let name = match element.value().attr("name") {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+        append_entries(element, name, &mut entries);
+    }
+
+    Ok(entries)
+}
+
+/// Compare the submissions of the forms matching `selector` in both documents
+pub fn compare_forms(expected: &str, actual: &str, selector: &str) -> Result<(), HtmlCompareError> {
+    let expected = serialize_form(expected, selector)?;
+    let actual = serialize_form(actual, selector)?;
+
+    for (i, (expected_entry, actual_entry)) in expected.iter().zip(&actual).enumerate() {
+        if expected_entry != actual_entry {
+            return Err(HtmlCompareError::FormMismatch(format!(
+                "Entry mismatch at position {}. Expected: {:?}, Actual: {:?}",
+                i, expected_entry, actual_entry
+            )));
+        }
+    }
+    if let Some(missing) = expected.get(actual.len()) {
+        return Err(HtmlCompareError::FormMismatch(format!(
+            "Missing entry at position {}: {:?}",
+            actual.len(),
+            missing
+        )));
+    }
+    if let Some(extra) = actual.get(expected.len()) {
+        return Err(HtmlCompareError::FormMismatch(format!(
+            "Unexpected entry at position {}: {:?}",
+            expected.len(),
+            extra
+        )));
+    }
+    Ok(())
+}
+
+fn append_entries(element: ElementRef, name: &str, entries: &mut FormData) {
+    let el = element.value();
+    let mut push = |value: &str| entries.push((name.to_string(), value.to_string()));
+
+    match el.name() {
+        "input" => {
+            let input_type = el.attr("type").unwrap_or("text").to_ascii_lowercase();
+            match input_type.as_str() {
+                "submit" | "image" | "reset" | "button" => {}
+                "checkbox" | "radio" => {
+                    if el.attr("checked").is_some() {
+                        push(el.attr("value").unwrap_or("on"));
+                    }
+                }
+                "file" => push(""),
+                "hidden" if name.eq_ignore_ascii_case("_charset_") => push("UTF-8"),
+                _ => push(el.attr("value").unwrap_or_default()),
+            }
+        }
+        "textarea" => {
+            let text = element.text().collect::<String>();
+            push(&text.replace("\r\n", "\n").replace('\r', "\n"));
+        }
+        "select" => {
+            for option in selected_options(element) {
+                push(&option_value(option));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The options of a `<select>` that are selected when the page loads
+fn selected_options(select: ElementRef) -> Vec<ElementRef> {
+    let option_selector = Selector::parse("option").unwrap();
+    let options: Vec<_> = select.select(&option_selector).collect();
+    let enabled = |option: &ElementRef| {
+        option.value().attr("disabled").is_none()
+            && !option
+                .parent()
+                .and_then(ElementRef::wrap)
+                .is_some_and(|parent| {
+                    parent.value().name() == "optgroup" && parent.value().attr("disabled").is_some()
+                })
+    };
+    let mut selected: Vec<_> = options
+        .iter()
+        .copied()
+        .filter(|option| option.value().attr("selected").is_some())
+        .collect();
+
+    if select.value().attr("multiple").is_some() {
+        return selected.into_iter().filter(enabled).collect();
+    }
+
+    // A single-select keeps only its last selected option and, when it is
+    // rendered as a drop-down, falls back to its first enabled option
+    if selected.len() > 1 {
+        selected.drain(..selected.len() - 1);
+    }
+    let display_size = select
+        .value()
+        .attr("size")
+        .and_then(|size| size.trim().parse::<u32>().ok())
+        .unwrap_or(1);
+    if selected.is_empty() && display_size <= 1 {
+        selected.extend(options.iter().copied().find(enabled));
+    }
+    selected.into_iter().filter(enabled).collect()
+}
+
+fn option_value(option: ElementRef) -> String {
+    match option.value().attr("value") {
+        Some(value) => value.to_string(),
+        None => option
+            .text()
+            .collect::<String>()
+            .split_ascii_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+fn is_owned_by(element: ElementRef, form: ElementRef, form_id: Option<&str>) -> bool {
+    match element.value().attr("form") {
+        Some(owner) => form_id == Some(owner),
+        None => element
+            .ancestors()
+            .any(|ancestor| ancestor.id() == form.id()),
+    }
+}
+
+fn is_disabled(element: ElementRef) -> bool {
+    if element.value().attr("disabled").is_some() {
+        return true;
+    }
+
+    // Controls inside a disabled fieldset are disabled, except for those in
+    // its first <legend>
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .filter(|ancestor| {
+            ancestor.value().name() == "fieldset" && ancestor.value().attr("disabled").is_some()
+        })
+        .any(|fieldset| {
+            let legend = fieldset
+                .children()
+                .filter_map(ElementRef::wrap)
+                .find(|child| child.value().name() == "legend");
+            !legend.is_some_and(|legend| {
+                element
+                    .ancestors()
+                    .any(|ancestor| ancestor.id() == legend.id())
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(entries: &[(&str, &str)]) -> FormData {
+        entries
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_serialize_form() {
+        let html = r#"
+            <form id="signup">
+                <input name="user" value="ada">
+                <input name="password" type="password">
+                <input name="disabled" value="x" disabled>
+                <input value="no name">
+                <input type="checkbox" name="terms" checked>
+                <input type="checkbox" name="newsletter" value="yes">
+                <input type="radio" name="plan" value="free">
+                <input type="radio" name="plan" value="pro" checked>
+                <input type="hidden" name="_charset_">
+                <input type="file" name="avatar">
+                <fieldset disabled>
+                    <legend><input name="in-legend" value="1"></legend>
+                    <input name="in-fieldset" value="2">
+                </fieldset>
+                <textarea name="bio">Hello</textarea>
+                <button name="action" value="save">Save</button>
+                <input type="submit" name="go" value="Go">
+            </form>
+            <input name="outside" value="ignored">
+            <input name="associated" value="kept" form="signup">
+        "#;
+
+        assert_eq!(
+            serialize_form(html, "#signup").unwrap(),
+            pairs(&[
+                ("user", "ada"),
+                ("password", ""),
+                ("terms", "on"),
+                ("plan", "pro"),
+                ("_charset_", "UTF-8"),
+                ("avatar", ""),
+                ("in-legend", "1"),
+                ("bio", "Hello"),
+                ("associated", "kept"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_serialize_select() {
+        let html = r#"<form>
+            <select name="default"><option disabled>A</option><option>  B  </option></select>
+            <select name="last"><option selected>A</option><option value="b" selected>B</option></select>
+            <select name="listbox" size="3"><option>A</option></select>
+            <select name="many" multiple>
+                <option value="1" selected>One</option>
+                <optgroup disabled><option value="2" selected>Two</option></optgroup>
+                <option value="3" selected>Three</option>
+            </select>
+        </form>"#;
+
+        assert_eq!(
+            serialize_form(html, "form").unwrap(),
+            pairs(&[
+                ("default", "B"),
+                ("last", "b"),
+                ("many", "1"),
+                ("many", "3")
+            ])
+        );
+    }
+
+    #[test]
+    fn test_compare_forms() {
+        assert!(compare_forms(
+            r#"<form class="search"><input name="q" value="rust"><input type="hidden" name="page" value="1"></form>"#,
+            r#"<form class="search"><div class="row"><label>Query <input value="rust" name="q"></label></div>
+               <p><input name="page" type="hidden" value="1"><button>Search</button></p></form>"#,
+            "form.search",
+        )
+        .is_ok());
+
+        assert_eq!(
+            compare_forms(
+                r#"<form><input name="q" value="rust"></form>"#,
+                r#"<form><input name="q" value="go"></form>"#,
+                "form",
+            )
+            .unwrap_err()
+            .to_string(),
+            r#"Form mismatch: Entry mismatch at position 0. Expected: ("q", "rust"), Actual: ("q", "go")"#
+        );
+
+        assert!(matches!(
+            compare_forms(
+                r#"<form><input name="q"></form>"#,
+                r#"<form><input name="q"><input name="extra"></form>"#,
+                "form",
+            ),
+            Err(HtmlCompareError::FormMismatch(_))
+        ));
+        assert!(matches!(
+            serialize_form("<p>No form</p>", "form"),
+            Err(HtmlCompareError::FormMismatch(_))
+        ));
+        assert!(matches!(
+            serialize_form("<form></form>", "form["),
+            Err(HtmlCompareError::InvalidSelector(_))
+        ));
+    }
+}
@@ -0,0 +1,154 @@
+//! Hydration markers of server-side rendering frameworks.
+//!
+//! SSR frameworks annotate their output so the client can hydrate it: React
+//! separates adjacent text with `<!-- -->` and wraps Suspense boundaries in
+//! `<!--$-->`, Vue and Svelte bracket fragments with `<!--[-->` and
+//! `<!--]-->`, Leptos anchors dynamic content with `<!--#-->`, and Qwik
+//! stores component state in `q:*` attributes. With
+//! [`HtmlCompareOptions::ignore_framework_markers`](crate::HtmlCompareOptions)
+//! the markers of one framework are stripped before parsing, so text split by
+//! a marker compares equal to the same text without it, and its bookkeeping
+//! attributes are left out of the comparison. Other comments are still
+//! compared.
+
+use std::borrow::Cow;
+
+/// A framework whose hydration markers are ignored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrameworkMarkers {
+    /// Compare markers like any other markup
+    #[default]
+    None,
+    React,
+    Vue,
+    Svelte,
+    Leptos,
+    Qwik,
+}
+
+impl FrameworkMarkers {
+    /// The framework called `name`, e.g. `"react"`
+    pub fn from_name(name: &str) -> Option<FrameworkMarkers> {
+        match name {
+            "none" => Some(FrameworkMarkers::None),
+            "react" => Some(FrameworkMarkers::React),
+            "vue" => Some(FrameworkMarkers::Vue),
+            "svelte" => Some(FrameworkMarkers::Svelte),
+            "leptos" => Some(FrameworkMarkers::Leptos),
+            "qwik" => Some(FrameworkMarkers::Qwik),
+            _ => None,
+        }
+    }
+
+    /// Whether a comment with the text `comment` is a marker of this
+    /// framework
+    pub fn is_marker_comment(self, comment: &str) -> bool {
+        match self {
+            FrameworkMarkers::None => false,
+            FrameworkMarkers::React => matches!(comment, " " | "$" | "/$" | "$?" | "$!"),
+            FrameworkMarkers::Vue => matches!(comment, "[" | "]" | ""),
+            FrameworkMarkers::Svelte => matches!(
+                comment,
+                "[" | "]" | "[!" | "" | " HTML_TAG_START " | " HTML_TAG_END "
+            ),
+            FrameworkMarkers::Leptos => {
+                matches!(comment, "#" | "/" | "")
+                    || comment.starts_with("hk=")
+                    || comment.starts_with("leptos-")
+            }
+            FrameworkMarkers::Qwik => {
+                matches!(comment, "qv" | "/qv" | "")
+                    || comment.starts_with("qv ")
+                    || comment.starts_with("t=")
+                    || comment.starts_with("qkssr-")
+            }
+        }
+    }
+
+    /// Whether the attribute `name` is bookkeeping of this framework
+    pub fn is_marker_attribute(self, name: &str) -> bool {
+        match self {
+            FrameworkMarkers::None => false,
+            FrameworkMarkers::React => matches!(name, "data-reactroot" | "data-reactid"),
+            FrameworkMarkers::Vue => {
+                name.starts_with("data-v-")
+                    || matches!(name, "data-server-rendered" | "data-allow-mismatch")
+            }
+            FrameworkMarkers::Svelte => name == "data-svelte-h",
+            FrameworkMarkers::Leptos => name == "data-hk",
+            FrameworkMarkers::Qwik => ["q:", "on:", "on-document:", "on-window:"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix)),
+        }
+    }
+
+    /// `html` without the marker comments of this framework
+    pub(crate) fn strip_comments(self, html: &str) -> Cow<'_, str> {
+        if self == FrameworkMarkers::None || !html.contains("<!--") {
+            return Cow::Borrowed(html);
+        }
+        let mut stripped = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(start) = rest.find("<!--") {
+            let Some(length) = rest[start + 4..].find("-->") else {
+                break;
+            };
+            let end = start + 4 + length + 3;
+            stripped.push_str(&rest[..start]);
+            if !self.is_marker_comment(&rest[start + 4..start + 4 + length]) {
+                stripped.push_str(&rest[start..end]);
+            }
+            rest = &rest[end..];
+        }
+        stripped.push_str(rest);
+        Cow::Owned(stripped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    fn comparer(markers: FrameworkMarkers) -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_framework_markers: markers,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_react_markers() {
+        let rendered = r#"<div data-reactroot=""><p>Hello, <!-- -->Alice<!-- -->!</p><!--$--><ul><li>1</li></ul><!--/$--><!-- keep --></div>"#;
+        let expected = "<div><p>Hello, Alice!</p><ul><li>1</li></ul><!-- keep --></div>";
+        comparer(FrameworkMarkers::React)
+            .verify(expected, rendered)
+            .unwrap();
+        assert!(HtmlComparer::new().verify(expected, rendered).is_err());
+        assert!(comparer(FrameworkMarkers::Vue)
+            .verify(expected, rendered)
+            .is_err());
+    }
+
+    #[test]
+    fn test_vue_svelte_and_qwik_markers() {
+        let expected = "<ul><li>One</li><li>Two</li></ul>";
+        let vue = r#"<ul data-v-7ba5bd90=""><!--[--><li data-v-7ba5bd90="">One</li><li data-v-7ba5bd90="">Two</li><!--]--><!----></ul>"#;
+        comparer(FrameworkMarkers::Vue)
+            .verify(expected, vue)
+            .unwrap();
+        let svelte =
+            r#"<ul><!--[--><li data-svelte-h="svelte-1x2y">One</li><li>Two</li><!--]--></ul>"#;
+        comparer(FrameworkMarkers::Svelte)
+            .verify(expected, svelte)
+            .unwrap();
+        let qwik = r#"<ul q:key="k1"><!--qv q:id=1 q:key=a--><li on:click="q-abc.js#s_1">One</li><!--/qv--><li>Two</li></ul>"#;
+        comparer(FrameworkMarkers::Qwik)
+            .verify(expected, qwik)
+            .unwrap();
+        assert_eq!(
+            FrameworkMarkers::from_name("leptos"),
+            Some(FrameworkMarkers::Leptos)
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! How deep the differences of [`HtmlComparer::diff`] go.
+//!
+//! A report on every attribute and text node is too much for a dashboard of
+//! hundreds of pages, and too little when a single character differs in a
+//! long paragraph. [`HtmlCompareOptions::diff_granularity`] picks the level:
+//!
+//! - [`DiffGranularity::Element`] reports one [`Difference::ElementDiffers`]
+//!   per element whose attributes or text differ, e.g. "this `<li>`
+//!   differs", along with missing, extra and renamed elements.
+//! - [`DiffGranularity::Node`], the default, reports each attribute and
+//!   text node.
+//! - [`DiffGranularity::Character`] splits text differences into the runs of
+//!   characters that differ, with the character offset of the run in the
+//!   expected text appended to the path: `/html/body/p/text():12`.
+//!
+//! [`HtmlCompareOptions::diff_granularity`]: crate::HtmlCompareOptions::diff_granularity
+
+use std::collections::HashMap;
+
+use crate::diff::longest_common_subsequence;
+use crate::{Difference, HtmlComparer};
+
+/// Texts longer than this, in characters, after removing their common
+/// prefix and suffix, are reported as one run at character granularity
+const MAX_CHARACTER_DIFF: usize = 2000;
+
+/// How deep the differences of [`HtmlComparer::diff`] go
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffGranularity {
+    /// One difference per element whose attributes or text differ
+    Element,
+    /// One difference per attribute and node
+    #[default]
+    Node,
+    /// Text differences split into runs of differing characters
+    Character,
+}
+
+impl DiffGranularity {
+    /// Look up a granularity by its name: `element`, `node` or `character`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "element" => Some(DiffGranularity::Element),
+            "node" => Some(DiffGranularity::Node),
+            "character" => Some(DiffGranularity::Character),
+            _ => None,
+        }
+    }
+}
+
+impl HtmlComparer {
+    /// `differences` at the granularity of the options
+    pub(crate) fn at_granularity(&self, differences: Vec<Difference>) -> Vec<Difference> {
+        match self.options.diff_granularity {
+            DiffGranularity::Element => by_element(differences),
+            DiffGranularity::Node => differences,
+            DiffGranularity::Character => differences
+                .into_iter()
+                .flat_map(|difference| match difference {
+                    Difference::TextMismatch {
+                        path,
+                        expected,
+                        actual,
+                    } => character_runs(&path, &expected, &actual),
+                    difference => vec![difference],
+                })
+                .collect(),
+        }
+    }
+}
+
+/// `differences` within an element merged into one
+fn by_element(differences: Vec<Difference>) -> Vec<Difference> {
+    let mut merged: Vec<Difference> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for difference in differences {
+        let Some(path) = element_path(&difference) else {
+            merged.push(difference);
+            continue;
+        };
+        match positions.get(path) {
+            Some(&position) => {
+                if let Difference::ElementDiffers { differences, .. } = &mut merged[position] {
+                    *differences += 1;
+                }
+            }
+            None => {
+                positions.insert(path.to_string(), merged.len());
+                merged.push(Difference::ElementDiffers {
+                    path: path.to_string(),
+                    differences: 1,
+                });
+            }
+        }
+    }
+    merged
+}
+
+/// The path of the element a difference within it belongs to, for
+/// differences that are not about whole elements
+fn element_path(difference: &Difference) -> Option<&str> {
+    match difference {
+        Difference::AttributeMismatch { path, .. } => Some(path),
+        Difference::TextMismatch { path, .. }
+        | Difference::MissingNode { path, .. }
+        | Difference::ExtraNode { path, .. } => within_parent(path),
+        _ => None,
+    }
+}
+
+/// The path of the element containing the text or comment at `path`
+fn within_parent(path: &str) -> Option<&str> {
+    let (parent, step) = path.rsplit_once('/')?;
+    (step.starts_with("text()") || step.starts_with("comment()")).then_some(parent)
+}
+
+/// The runs of characters that differ between the `expected` and `actual`
+/// text at `path`
+fn character_runs(path: &str, expected: &str, actual: &str) -> Vec<Difference> {
+    let expected: Vec<char> = expected.chars().collect();
+    let actual: Vec<char> = actual.chars().collect();
+    let prefix = expected
+        .iter()
+        .zip(&actual)
+        .take_while(|(e, a)| e == a)
+        .count();
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(actual[prefix..].iter().rev())
+        .take_while(|(e, a)| e == a)
+        .count();
+    let (expected_middle, actual_middle) = (
+        &expected[prefix..expected.len() - suffix],
+        &actual[prefix..actual.len() - suffix],
+    );
+
+    let pairs = if expected_middle.len().max(actual_middle.len()) > MAX_CHARACTER_DIFF {
+        Vec::new()
+    } else {
+        longest_common_subsequence(expected_middle, actual_middle)
+    };
+    // Every unpaired stretch between two pairs, or before the first or after
+    // the last, is a run
+    let mut runs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in pairs
+        .into_iter()
+        .chain([(expected_middle.len(), actual_middle.len())])
+    {
+        if next_i > i || next_j > j {
+            runs.push(Difference::TextMismatch {
+                path: format!("{}:{}", path, prefix + i),
+                expected: expected_middle[i..next_i].iter().collect(),
+                actual: actual_middle[j..next_j].iter().collect(),
+            });
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    fn comparer(diff_granularity: DiffGranularity) -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            diff_granularity,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_element_granularity() {
+        let diff = comparer(DiffGranularity::Element).diff(
+            "<ul><li class='a'>One</li><li>Two</li><li>Three</li></ul>",
+            "<ul><li class='b'>Uno</li><li>Two</li></ul>",
+        );
+        let differences: Vec<_> = diff.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            differences,
+            [
+                "/html/body/ul/li[1]: element differs (2 differences)",
+                "/html/body/ul/li[3]: missing <li>",
+            ]
+        );
+        assert_eq!(
+            comparer(DiffGranularity::Node)
+                .diff("<p class='a'>One</p>", "<p class='b'>Uno</p>")
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_character_granularity() {
+        let diff = comparer(DiffGranularity::Character)
+            .diff("<p>The quick brown fox</p>", "<p>The quick crown fix</p>");
+        let differences: Vec<_> = diff.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            differences,
+            [
+                "/html/body/p/text():10: expected 'b', found 'c'",
+                "/html/body/p/text():17: expected 'o', found 'i'",
+            ]
+        );
+        assert_eq!(
+            character_runs("p", "abc", "abxyc"),
+            [Difference::TextMismatch {
+                path: "p:2".to_string(),
+                expected: String::new(),
+                actual: "xy".to_string(),
+            }]
+        );
+        assert_eq!(
+            DiffGranularity::from_name("element"),
+            Some(DiffGranularity::Element)
+        );
+    }
+}
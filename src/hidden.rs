@@ -0,0 +1,70 @@
+//! Detection of elements the user doesn't see.
+//!
+//! With [`HtmlCompareOptions::ignore_hidden_elements`](crate::HtmlCompareOptions)
+//! elements hidden by markup are pruned before comparing, so implementations
+//! that hide content differently (or leave it out) compare equal when they
+//! render the same. Only markup is considered; stylesheets and scripts are
+//! not evaluated.
+
+use scraper::node::Element;
+
+/// Whether `element` is hidden by its `hidden` or `aria-hidden` attribute,
+/// is a hidden input, or has an inline `display: none` style
+pub(crate) fn is_hidden(element: &Element) -> bool {
+    element.attr("hidden").is_some()
+        || element
+            .attr("aria-hidden")
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"))
+        || (element.name() == "input"
+            && element
+                .attr("type")
+                .is_some_and(|value| value.trim().eq_ignore_ascii_case("hidden")))
+        || element.attr("style").is_some_and(has_display_none)
+}
+
+/// Whether the inline style `style` sets `display: none`
+fn has_display_none(style: &str) -> bool {
+    style.split(';').any(|declaration| {
+        let Some((property, value)) = declaration.split_once(':') else {
+            return false;
+        };
+        let value = value.trim();
+        let value = value
+            .strip_suffix("!important")
+            .map_or(value, str::trim_end);
+        property.trim().eq_ignore_ascii_case("display") && value.eq_ignore_ascii_case("none")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_ignore_hidden_elements() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_hidden_elements: true,
+            ..Default::default()
+        });
+        let expected = r#"<form><input type="hidden" name="csrf" value="abc">
+            <label>Name <input name="name"></label>
+            <span class="sr-only" style="color: red; DISPLAY : none !important">Skip</span>
+            <svg aria-hidden="true"><path d="M0 0"/></svg></form>"#;
+        let actual = r#"<form><label>Name <input name="name"></label>
+            <p hidden>Loading...</p></form>"#;
+        comparer.verify(expected, actual).unwrap();
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+
+        let visible = actual.replace("<p hidden>", r#"<p aria-hidden="false">"#);
+        assert!(comparer.verify(expected, &visible).is_err());
+    }
+
+    #[test]
+    fn test_display_none() {
+        assert!(has_display_none("display:none"));
+        assert!(has_display_none("margin: 0; display: None !important;"));
+        assert!(!has_display_none("display: block"));
+        assert!(!has_display_none("--display: none-ish"));
+    }
+}
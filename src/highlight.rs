@@ -0,0 +1,74 @@
+//! Comparison of syntax-highlighted code blocks.
+//!
+//! Syntax highlighters wrap code in `<span class="token keyword">` and
+//! similar elements whose nesting differs between highlighters, themes and
+//! versions. With
+//! [`HtmlCompareOptions::ignore_code_highlighting`](crate::HtmlCompareOptions)
+//! a `<pre>` block is compared by its attributes and its concatenated text
+//! only, so highlighted and plain renderings of the same code compare equal.
+
+use scraper::ElementRef;
+
+use crate::HtmlComparer;
+
+impl HtmlComparer {
+    /// Whether `element` is a code block compared by its text only
+    pub(crate) fn is_highlighted_code(&self, element: ElementRef) -> bool {
+        self.options.ignore_code_highlighting && element.value().name() == "pre"
+    }
+
+    /// The text of the code block `pre` as compared
+    pub(crate) fn code_text(&self, pre: ElementRef) -> String {
+        let text = self.text_outside_ignored(pre);
+        let mut code = text.as_str();
+        if self
+            .options
+            .markdown
+            .as_ref()
+            .is_some_and(|profile| profile.code_trailing_newline)
+        {
+            code = code.strip_suffix('\n').unwrap_or(code);
+        }
+        match self.options.ignore_whitespace && !self.options.exact_code_whitespace {
+            true => code.trim().to_string(),
+            false => code.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Difference, HtmlCompareOptions, HtmlComparer};
+
+    const PLAIN: &str = r#"<pre><code class="language-rust">fn main() {}</code></pre>"#;
+    const HIGHLIGHTED: &str = r#"<pre><code class="language-rust"><span class="token keyword">fn</span> <span class="token function">main</span><span class="token punctuation">(</span><span class="token punctuation">)</span> <span class="token punctuation">{</span><span class="token punctuation">}</span></code></pre>"#;
+
+    fn comparer() -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_code_highlighting: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_highlighted_code_equals_plain() {
+        comparer().verify(PLAIN, HIGHLIGHTED).unwrap();
+        assert!(comparer().diff(PLAIN, HIGHLIGHTED).is_empty());
+        assert!(HtmlComparer::new().verify(PLAIN, HIGHLIGHTED).is_err());
+    }
+
+    #[test]
+    fn test_code_text_differences() {
+        let changed = HIGHLIGHTED.replace(">main<", ">start<");
+        assert!(comparer().verify(PLAIN, &changed).is_err());
+        let diff = comparer().diff(PLAIN, &changed);
+        assert_eq!(
+            diff.iter().collect::<Vec<_>>(),
+            [&Difference::TextMismatch {
+                path: "/html/body/pre/text()".to_string(),
+                expected: "fn main() {}".to_string(),
+                actual: "fn start() {}".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,895 @@
+//! Comparison of pages fetched over HTTP.
+//!
+//! Smoke tests often compare a staging page with production, or a live page
+//! with a fixture. [`HtmlComparer::compare_urls`] and
+//! [`HtmlComparer::compare_url_to_file`] fetch the pages with a [`Fetcher`],
+//! decode them by their charset and compare the documents. The default
+//! [`HttpFetcher`] speaks plain HTTP/1.1 and follows redirects; tests can
+//! pass any closure returning a [`Response`] instead, and HTTPS needs a
+//! fetcher backed by an HTTP client.
+//!
+//! [`HtmlComparer::compare_responses`] compares responses a test already has,
+//! from any client type implementing [`HttpResponse`]: the status and media
+//! type must match, and the bodies are decompressed and decoded by their
+//! charset before the markup is compared. `gzip`, `deflate` and `br` bodies
+//! are decoded here, up to
+//! [`HtmlCompareOptions::max_decompressed_size`] bytes so that a
+//! compression bomb fails the comparison instead of exhausting memory, and
+//! the Latin-1 labels decode as windows-1252, as browsers do.
+//!
+//! Handler tests can assert on the rendered page in one line with
+//! [`ResponseHtmlExt::assert_html_eq`], available on every [`HttpResponse`].
+//! With the `axum` feature, axum-test's `TestResponse` is one; with the
+//! `actix` feature, so are actix-web's `HttpResponse` and `ServiceResponse`
+//! once their body is read into bytes:
+//!
+//! ```ignore
+//! let (_, response) = test::call_service(&app, request).await.into_parts();
+//! let (response, body) = response.into_parts();
+//! let response = response.set_body(actix_web::body::to_bytes(body).await?);
+//! response.assert_html_eq("<h1>Orders</h1>", HtmlCompareOptions::default());
+//! ```
+//!
+//! Other clients and harnesses plug in by implementing [`HttpResponse`] or
+//! by passing `(status, headers, body)`.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::time::Duration;
+
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+
+use crate::{HtmlCompareError, HtmlCompareOptions, HtmlComparer};
+
+/// Default of [`HtmlCompareOptions::max_decompressed_size`]: 64 MiB
+pub const MAX_DECOMPRESSED_SIZE: usize = 64 << 20;
+
+/// A fetched page
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    /// Header names and values in the order received
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, headers: Vec<(String, String)>, body: impl Into<Vec<u8>>) -> Self {
+        Response {
+            status,
+            headers,
+            body: body.into(),
+        }
+    }
+
+    /// The value of the first header called `name`, ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The charset of the body: from the `Content-Type` header, a byte order
+    /// mark or a `<meta charset>` near the start, defaulting to UTF-8
+    pub fn charset(&self) -> String {
+        charset(self.header("content-type"), &self.body)
+    }
+
+    /// The body, decompressed and decoded by its [`Response::charset`]
+    ///
+    /// Bodies decompressing to more than [`MAX_DECOMPRESSED_SIZE`] bytes are
+    /// rejected.
+    pub fn text(&self) -> Result<String, HtmlCompareError> {
+        text(self, MAX_DECOMPRESSED_SIZE)
+    }
+}
+
+/// A response of an HTTP client, as compared by
+/// [`HtmlComparer::compare_responses`]
+///
+/// Implemented for [`Response`], for `(status, headers, body)` tuples and,
+/// with the `axum` and `actix` features, for the test responses of those
+/// frameworks; implement it for the response type of your client or test
+/// harness.
+pub trait HttpResponse {
+    fn status(&self) -> u16;
+    /// The value of the header called `name`, ignoring case
+    fn header(&self, name: &str) -> Option<&str>;
+    /// The body as received, before decompression
+    fn body(&self) -> &[u8];
+}
+
+impl HttpResponse for Response {
+    fn status(&self) -> u16 {
+        self.status
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        Response::header(self, name)
+    }
+
+    fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>, B: AsRef<[u8]>> HttpResponse for (u16, Vec<(K, V)>, B) {
+    fn status(&self) -> u16 {
+        self.0
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.1
+            .iter()
+            .find(|(header, _)| header.as_ref().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_ref())
+    }
+
+    fn body(&self) -> &[u8] {
+        self.2.as_ref()
+    }
+}
+
+#[cfg(feature = "axum")]
+impl HttpResponse for axum_test::TestResponse {
+    fn status(&self) -> u16 {
+        self.status_code().as_u16()
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers().get(name)?.to_str().ok()
+    }
+
+    fn body(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+#[cfg(feature = "actix")]
+impl<B: AsRef<[u8]>> HttpResponse for actix_web::HttpResponse<B> {
+    fn status(&self) -> u16 {
+        actix_web::HttpResponse::status(self).as_u16()
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers().get(name)?.to_str().ok()
+    }
+
+    fn body(&self) -> &[u8] {
+        actix_web::HttpResponse::body(self).as_ref()
+    }
+}
+
+#[cfg(feature = "actix")]
+impl<B: AsRef<[u8]>> HttpResponse for actix_web::dev::ServiceResponse<B> {
+    fn status(&self) -> u16 {
+        HttpResponse::status(self.response())
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        HttpResponse::header(self.response(), name)
+    }
+
+    fn body(&self) -> &[u8] {
+        HttpResponse::body(self.response())
+    }
+}
+
+/// Assertions on the HTML body of a response
+pub trait ResponseHtmlExt: HttpResponse {
+    /// Assert that the decoded body is equivalent to `expected`, panicking
+    /// with every difference otherwise
+    ///
+    /// The status isn't checked; assert on it separately.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// use html_compare_rs::http::ResponseHtmlExt;
+    ///
+    /// let response = app.oneshot(Request::get("/").body(Body::empty())?).await?;
+    /// let (parts, body) = response.into_parts();
+    /// let body = axum::body::to_bytes(body, usize::MAX).await?;
+    /// let headers: Vec<_> = parts
+    ///     .headers
+    ///     .iter()
+    ///     .map(|(name, value)| (name.as_str(), value.to_str().unwrap_or_default()))
+    ///     .collect();
+    /// (parts.status.as_u16(), headers, body).assert_html_eq("<h1>Home</h1>", presets::relaxed());
+    /// ```
+    fn assert_html_eq(&self, expected: &str, options: HtmlCompareOptions) {
+        let actual =
+            text(self, options.max_decompressed_size).unwrap_or_else(|err| panic!("{}", err));
+        let comparer = HtmlComparer::with_options(options);
+        if let Err(err) = comparer.verify(expected, &actual) {
+            panic!(
+                "\n\
+                HTML comparison failed:\n\
+                {}\n\n\
+                differences:\n\
+                {}\n\
+                expected HTML:\n\
+                {}\n\n\
+                response HTML (status {}):\n\
+                {}\
+            ",
+                err,
+                comparer.diff(expected, &actual),
+                expected,
+                self.status(),
+                actual
+            );
+        }
+    }
+}
+
+impl<R: HttpResponse + ?Sized> ResponseHtmlExt for R {}
+
+/// The characters of the bytes 0x80..=0x9F in windows-1252; the five bytes
+/// it leaves undefined stand for the C1 controls, as in ISO-8859-1
+const WINDOWS_1252_C1: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// The labels the Encoding Standard maps to windows-1252, including
+/// ISO-8859-1 and US-ASCII
+const WINDOWS_1252_LABELS: &[&str] = &[
+    "ansi_x3.4-1968",
+    "ascii",
+    "cp1252",
+    "cp819",
+    "csisolatin1",
+    "ibm819",
+    "iso-8859-1",
+    "iso-ir-100",
+    "iso8859-1",
+    "iso88591",
+    "iso_8859-1",
+    "iso_8859-1:1987",
+    "l1",
+    "latin1",
+    "us-ascii",
+    "windows-1252",
+    "x-cp1252",
+];
+
+/// The charset of `body` served with `content_type`
+fn charset(content_type: Option<&str>, body: &[u8]) -> String {
+    if let Some(charset) = content_type.and_then(charset_parameter) {
+        return charset;
+    }
+    if body.starts_with(b"\xEF\xBB\xBF") {
+        return "utf-8".to_string();
+    }
+    let head = String::from_utf8_lossy(&body[..body.len().min(1024)]).to_lowercase();
+    head.find("<meta")
+        .and_then(|meta| charset_parameter(&head[meta..]))
+        .unwrap_or_else(|| "utf-8".to_string())
+}
+
+/// The body of `response`, decompressed by its `Content-Encoding` to at most
+/// `limit` bytes and decoded by its charset
+///
+/// UTF-8 and windows-1252, under any of its labels, are supported; invalid
+/// UTF-8 sequences are replaced rather than rejected.
+fn text<R: HttpResponse + ?Sized>(response: &R, limit: usize) -> Result<String, HtmlCompareError> {
+    let body = decompress(response.header("content-encoding"), response.body(), limit)?;
+    match charset(response.header("content-type"), &body).as_str() {
+        "utf-8" | "utf8" => {
+            let body = body.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&body);
+            Ok(String::from_utf8_lossy(body).into_owned())
+        }
+        charset if WINDOWS_1252_LABELS.contains(&charset) => Ok(decode_windows_1252(&body)),
+        charset => Err(HtmlCompareError::InvalidResponse(format!(
+            "unsupported charset {:?}",
+            charset
+        ))),
+    }
+}
+
+/// `bytes` decoded as windows-1252
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => WINDOWS_1252_C1[(b - 0x80) as usize],
+            _ => b as char,
+        })
+        .collect()
+}
+
+/// Whether `data` starts with a zlib header rather than being the raw
+/// DEFLATE stream some servers send for `Content-Encoding: deflate`
+fn is_zlib(data: &[u8]) -> bool {
+    match data {
+        [cmf, flg, ..] => {
+            cmf & 0x0f == 8
+                && (*cmf as u16 * 256 + *flg as u16).is_multiple_of(31)
+                && flg & 0x20 == 0
+        }
+        _ => false,
+    }
+}
+
+/// `body` with the content codings of `encoding` undone, last applied first,
+/// failing once a coding decodes to more than `limit` bytes
+fn decompress(
+    encoding: Option<&str>,
+    body: &[u8],
+    limit: usize,
+) -> Result<Vec<u8>, HtmlCompareError> {
+    let mut body = body.to_vec();
+    for coding in encoding.unwrap_or_default().rsplit(',') {
+        let coding = coding.trim().to_ascii_lowercase();
+        let data = body.as_slice();
+        let decoder: Box<dyn Read + '_> = match coding.as_str() {
+            "" | "identity" => continue,
+            "gzip" | "x-gzip" => Box::new(MultiGzDecoder::new(data)),
+            "deflate" if is_zlib(data) => Box::new(ZlibDecoder::new(data)),
+            "deflate" => Box::new(DeflateDecoder::new(data)),
+            "br" => Box::new(brotli_decompressor::Decompressor::new(data, 4096)),
+            _ => {
+                return Err(HtmlCompareError::InvalidResponse(format!(
+                    "unsupported content encoding {:?}; decode the body in the client",
+                    coding
+                )))
+            }
+        };
+        let mut decoded = Vec::new();
+        decoder
+            .take(limit as u64 + 1)
+            .read_to_end(&mut decoded)
+            .map_err(|_| HtmlCompareError::InvalidResponse(format!("malformed {} body", coding)))?;
+        if decoded.len() > limit {
+            return Err(HtmlCompareError::InvalidResponse(format!(
+                "{} body decompresses to more than {} bytes",
+                coding, limit
+            )));
+        }
+        body = decoded;
+    }
+    Ok(body)
+}
+
+/// The media type of a `Content-Type` value without its parameters
+fn media_type(content_type: Option<&str>) -> Option<String> {
+    let essence = content_type?.split(';').next()?.trim();
+    (!essence.is_empty()).then(|| essence.to_ascii_lowercase())
+}
+
+/// The `charset=` parameter of a header value or tag, lowercased
+fn charset_parameter(value: &str) -> Option<String> {
+    let start = value.to_ascii_lowercase().find("charset=")? + "charset=".len();
+    let charset: String = value[start..]
+        .trim_start_matches(['"', '\''])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+        .collect();
+    (!charset.is_empty()).then(|| charset.to_ascii_lowercase())
+}
+
+/// Fetches pages by URL
+pub trait Fetcher {
+    fn fetch(&self, url: &str) -> Result<Response, HtmlCompareError>;
+}
+
+impl<F: Fn(&str) -> Result<Response, HtmlCompareError>> Fetcher for F {
+    fn fetch(&self, url: &str) -> Result<Response, HtmlCompareError> {
+        self(url)
+    }
+}
+
+/// A minimal HTTP/1.1 client for `http://` URLs
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    /// Redirects followed before giving up
+    pub max_redirects: usize,
+    /// Timeout for connecting and for each read
+    pub timeout: Duration,
+}
+
+impl Default for HttpFetcher {
+    fn default() -> Self {
+        HttpFetcher {
+            max_redirects: 5,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, url: &str) -> Result<Response, HtmlCompareError> {
+        let mut url = url.to_string();
+        for _ in 0..=self.max_redirects {
+            let response = self.get(&url)?;
+            match (response.status, response.header("location")) {
+                (301 | 302 | 303 | 307 | 308, Some(location)) => url = resolve(&url, location),
+                _ => return Ok(response),
+            }
+        }
+        Err(HtmlCompareError::Fetch(format!(
+            "{}: more than {} redirects",
+            url, self.max_redirects
+        )))
+    }
+}
+
+impl HttpFetcher {
+    fn get(&self, url: &str) -> Result<Response, HtmlCompareError> {
+        let error = |reason: String| HtmlCompareError::Fetch(format!("{}: {}", url, reason));
+        let (host, path) = split_url(url).map_err(error)?;
+        let address = match host.contains(':') {
+            true => host.to_string(),
+            false => format!("{}:80", host),
+        };
+
+        let mut stream = TcpStream::connect(&address).map_err(|err| error(err.to_string()))?;
+        stream
+            .set_read_timeout(Some(self.timeout))
+            .map_err(|err| error(err.to_string()))?;
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: text/html\r\n\
+             Accept-Encoding: gzip, deflate, br\r\nConnection: close\r\n\r\n",
+            path, host
+        );
+        stream
+            .write_all(request.as_bytes())
+            .map_err(|err| error(err.to_string()))?;
+        let mut raw = Vec::new();
+        stream
+            .read_to_end(&mut raw)
+            .map_err(|err| error(err.to_string()))?;
+        parse_response(&raw).ok_or_else(|| error("malformed response".to_string()))
+    }
+}
+
+/// The `host[:port]` and path of an `http://` URL
+fn split_url(url: &str) -> Result<(&str, &str), String> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        return Err(match url.starts_with("https://") {
+            true => "HttpFetcher only speaks plain HTTP; use a Fetcher backed by an HTTP client"
+                .to_string(),
+            false => "not an http:// URL".to_string(),
+        });
+    };
+    let rest = rest.split('#').next().unwrap_or_default();
+    Ok(match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    })
+}
+
+/// The URL a redirect from `base` to `location` points to
+fn resolve(base: &str, location: &str) -> String {
+    if location.contains("://") {
+        return location.to_string();
+    }
+    if let Some(rest) = location.strip_prefix("//") {
+        return format!("http://{}", rest);
+    }
+    let Ok((host, path)) = split_url(base) else {
+        return location.to_string();
+    };
+    match location.starts_with('/') {
+        true => format!("http://{}{}", host, location),
+        false => {
+            let directory = &path[..path.rfind('/').map_or(0, |slash| slash + 1)];
+            format!("http://{}{}{}", host, directory, location)
+        }
+    }
+}
+
+/// Split a raw HTTP/1.1 response into status, headers and decoded body
+fn parse_response(raw: &[u8]) -> Option<Response> {
+    let end = raw.windows(4).position(|window| window == b"\r\n\r\n")?;
+    let head = std::str::from_utf8(&raw[..end]).ok()?;
+    let mut lines = head.split("\r\n");
+    let status = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let mut response = Response::new(status, headers, Vec::new());
+
+    let body = &raw[end + 4..];
+    response.body = match response.header("transfer-encoding") {
+        Some(encoding) if encoding.eq_ignore_ascii_case("chunked") => dechunk(body)?,
+        _ => match response.header("content-length").map(str::parse::<usize>) {
+            Some(Ok(length)) => body.get(..length)?.to_vec(),
+            _ => body.to_vec(),
+        },
+    };
+    Some(response)
+}
+
+/// Join the chunks of a chunked transfer-encoded body
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|window| window == b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line_end]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(decoded);
+        }
+        decoded.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+/// The decoded body of a successful response for `url`
+fn fetch_page(fetcher: &impl Fetcher, url: &str) -> Result<String, HtmlCompareError> {
+    let response = fetcher.fetch(url)?;
+    if !(200..300).contains(&response.status) {
+        return Err(HtmlCompareError::Fetch(format!(
+            "{}: status {}",
+            url, response.status
+        )));
+    }
+    response.text()
+}
+
+impl HtmlComparer {
+    /// Compare two HTTP responses: their status, their media type and their
+    /// decoded bodies as HTML
+    ///
+    /// Charsets are decoded on each side, so the same markup served as UTF-8
+    /// and as Latin-1, or gzipped and uncompressed, compares equal.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let expected = (200, vec![("Content-Type", "text/html")], "<p>Hi</p>");
+    /// comparer.compare_responses(&expected, &actual)?;
+    /// ```
+    pub fn compare_responses(
+        &self,
+        expected: &impl HttpResponse,
+        actual: &impl HttpResponse,
+    ) -> Result<(), HtmlCompareError> {
+        if expected.status() != actual.status() {
+            return Err(HtmlCompareError::ResponseMismatch(format!(
+                "expected status {}, actual {}",
+                expected.status(),
+                actual.status()
+            )));
+        }
+        let expected_type = media_type(expected.header("content-type"));
+        let actual_type = media_type(actual.header("content-type"));
+        if expected_type != actual_type {
+            return Err(HtmlCompareError::ResponseMismatch(format!(
+                "expected content type {:?}, actual {:?}",
+                expected_type, actual_type
+            )));
+        }
+        let limit = self.options.max_decompressed_size;
+        self.verify(&text(expected, limit)?, &text(actual, limit)?)
+    }
+
+    /// Fetch two pages over plain HTTP and compare them
+    ///
+    /// # Examples
+    /// ```ignore
+    /// HtmlComparer::new().compare_urls("http://prod.internal/", "http://staging.internal/")?;
+    /// ```
+    pub fn compare_urls(&self, expected: &str, actual: &str) -> Result<(), HtmlCompareError> {
+        self.compare_urls_with(&HttpFetcher::default(), expected, actual)
+    }
+
+    /// Fetch two pages with `fetcher` and compare them
+    pub fn compare_urls_with(
+        &self,
+        fetcher: &impl Fetcher,
+        expected: &str,
+        actual: &str,
+    ) -> Result<(), HtmlCompareError> {
+        let expected = fetch_page(fetcher, expected)?;
+        let actual = fetch_page(fetcher, actual)?;
+        self.verify(&expected, &actual)
+    }
+
+    /// Fetch the page at `url` over plain HTTP and compare it with the
+    /// expected document in the file at `path`
+    pub fn compare_url_to_file(
+        &self,
+        url: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<(), HtmlCompareError> {
+        self.compare_url_to_file_with(&HttpFetcher::default(), url, path)
+    }
+
+    /// Fetch the page at `url` with `fetcher` and compare it with the
+    /// expected document in the file at `path`
+    pub fn compare_url_to_file_with(
+        &self,
+        fetcher: &impl Fetcher,
+        url: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<(), HtmlCompareError> {
+        let path = path.as_ref();
+        let expected = fs::read_to_string(path)
+            .map_err(|err| HtmlCompareError::Fetch(format!("{}: {}", path.display(), err)))?;
+        let actual = fetch_page(fetcher, url)?;
+        self.verify(&expected, &actual)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_compare_urls_with_mock_fetcher() {
+        let fetcher = |url: &str| match url {
+            "http://prod/" => Ok(Response::new(200, Vec::new(), "<p>Hello</p>")),
+            "http://staging/" => Ok(Response::new(
+                200,
+                vec![("Content-Type".to_string(), "text/html".to_string())],
+                "<p>\n  Hello\n</p>",
+            )),
+            _ => Ok(Response::new(404, Vec::new(), "Not found")),
+        };
+        let comparer = HtmlComparer::new();
+        comparer
+            .compare_urls_with(&fetcher, "http://prod/", "http://staging/")
+            .unwrap();
+        assert_eq!(
+            comparer
+                .compare_urls_with(&fetcher, "http://prod/", "http://gone/")
+                .unwrap_err()
+                .to_string(),
+            "Fetch failed: http://gone/: status 404"
+        );
+
+        let fixture = std::env::temp_dir().join("html-compare-http-fixture.html");
+        fs::write(&fixture, "<p>Hello</p>").unwrap();
+        comparer
+            .compare_url_to_file_with(&fetcher, "http://staging/", &fixture)
+            .unwrap();
+        fs::remove_file(fixture).unwrap();
+    }
+
+    #[test]
+    fn test_compare_responses() {
+        let gzip: &[u8] = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xb3\x29\xb0\xf3\x48\xcd\xc9\xc9\xd7\x51\x48\xce\xcf\x2d\x28\x4a\x2d\x2e\x4e\x4d\x51\x28\xcf\x2f\xca\x49\x51\xb4\xd1\x2f\xb0\x03\x00\xe2\xbb\x79\x2d\x1f\x00\x00\x00";
+        let expected = (
+            200,
+            vec![("Content-Type", "text/html; charset=UTF-8")],
+            "<p>Hello, compressed world!</p>",
+        );
+        let actual = (
+            200,
+            vec![("content-type", "text/html"), ("Content-Encoding", "gzip")],
+            gzip,
+        );
+        let comparer = HtmlComparer::new();
+        comparer.compare_responses(&expected, &actual).unwrap();
+
+        let latin1 = Response::new(
+            200,
+            vec![(
+                "Content-Type".to_string(),
+                "text/html; charset=iso-8859-1".to_string(),
+            )],
+            b"<p>caf\xE9</p>".to_vec(),
+        );
+        let utf8 = (200, vec![("Content-Type", "text/html")], "<p>café</p>");
+        comparer.compare_responses(&utf8, &latin1).unwrap();
+
+        let json = (200, vec![("Content-Type", "application/json")], "{}");
+        assert_eq!(
+            comparer.compare_responses(&utf8, &json).unwrap_err().to_string(),
+            "Response mismatch: expected content type Some(\"text/html\"), actual Some(\"application/json\")"
+        );
+        let missing = (404, vec![("Content-Type", "text/html")], "<p>café</p>");
+        assert!(matches!(
+            comparer.compare_responses(&utf8, &missing),
+            Err(HtmlCompareError::ResponseMismatch(_))
+        ));
+        let brotli: &[u8] = b"\x1b\x1e\x00\xf8\x8d\x94\xa9\xe3\x5b\xf3\x8c\xb5\xc1\xc9\x10\x9f\xb7\x1e\x44\x91\x4d\x3a\xd1\x28\x20\x33\x8f\x4c\xe2\x7e\xea\x5e\x1e\x90\x04";
+        let brotli = (
+            200,
+            vec![("Content-Type", "text/html"), ("Content-Encoding", "br")],
+            brotli,
+        );
+        comparer.compare_responses(&expected, &brotli).unwrap();
+        let truncated = (200, brotli.1.clone(), &brotli.2[..10]);
+        assert!(matches!(
+            comparer.compare_responses(&expected, &truncated),
+            Err(HtmlCompareError::InvalidResponse(_))
+        ));
+        let zstd = (
+            200,
+            vec![("Content-Type", "text/html"), ("Content-Encoding", "zstd")],
+            "",
+        );
+        assert!(matches!(
+            comparer.compare_responses(&utf8, &zstd),
+            Err(HtmlCompareError::InvalidResponse(_))
+        ));
+        // The Latin-1 labels decode as windows-1252
+        let quoted = (
+            200,
+            vec![("Content-Type", "text/html")],
+            "<p>“€5” – café</p>",
+        );
+        for label in ["windows-1252", "iso-8859-1", "us-ascii", "latin1"] {
+            let legacy = Response::new(
+                200,
+                vec![(
+                    "Content-Type".to_string(),
+                    format!("text/html; charset={}", label),
+                )],
+                b"<p>\x93\x805\x94 \x96 caf\xE9</p>".to_vec(),
+            );
+            comparer.compare_responses(&quoted, &legacy).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_decompress() {
+        const HTML: &str = r#"<!DOCTYPE html><html><head><title>Compressed</title></head><body><ul><li class="item">One</li><li class="item">Two</li><li class="item">Three</li><li class="item">Four</li></ul><p>Repeated text, repeated text, repeated text.</p></body></html>"#;
+        let zlib = b"\x78\xda\x7d\x8f\xb1\x0e\xc2\x30\x0c\x44\x7f\x25\x74\x46\xf8\x07\x8c\x97\x02\x6b\x11\xea\xc2\x58\xc8\x49\x8d\x94\x90\x28\x71\x05\xfc\x3d\x85\xb0\xa1\xb2\x58\x3a\xbf\x3b\xd9\xc7\xab\x5d\xd7\xf6\xe7\xe3\xde\x8c\x1a\xbc\xf0\x77\x62\xb0\xc2\xea\xd4\x43\xda\x18\x52\x46\x29\xb0\x4c\x75\xc3\x54\xf9\x25\xda\xa7\xf0\x34\xfb\xbd\x33\x57\x3f\x94\xb2\x6d\x9c\x22\x34\xd2\xdd\xc0\xe4\xdd\x2f\xe8\xef\x71\x01\x8c\x19\x0b\x99\x43\x9c\x72\x25\xf4\xbe\x95\xe4\x84\x84\x41\x61\x8d\xe2\xa1\x6b\x93\xff\xc8\x0d\x53\x9a\x73\xf5\x53\xfa\x94\x7b\x01\x61\x52\x53\x2f";
+        let limit = MAX_DECOMPRESSED_SIZE;
+        assert_eq!(
+            decompress(Some("deflate"), zlib, limit).unwrap(),
+            HTML.as_bytes()
+        );
+        // A raw DEFLATE stored block: final, type 0, then LEN and NLEN
+        let stored = [&[0x01, 0x05, 0x00, 0xfa, 0xff][..], b"<br/>"].concat();
+        assert_eq!(
+            decompress(Some("deflate"), &stored, limit).unwrap(),
+            b"<br/>"
+        );
+        assert!(decompress(Some("gzip"), b"<p>plain</p>", limit).is_err());
+
+        // A compression bomb stops at the limit
+        let mut bomb = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        bomb.write_all(&vec![b' '; 1 << 20]).unwrap();
+        let bomb = bomb.finish().unwrap();
+        assert!(bomb.len() < 2048);
+        assert_eq!(
+            decompress(Some("gzip"), &bomb, 1 << 20).unwrap().len(),
+            1 << 20
+        );
+        assert_eq!(
+            decompress(Some("gzip"), &bomb, 4096)
+                .unwrap_err()
+                .to_string(),
+            "Invalid response: gzip body decompresses to more than 4096 bytes"
+        );
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            max_decompressed_size: 4096,
+            ..Default::default()
+        });
+        let expected = (200, vec![("Content-Type", "text/html")], "");
+        let actual = (
+            200,
+            vec![("Content-Type", "text/html"), ("Content-Encoding", "gzip")],
+            bomb,
+        );
+        assert!(matches!(
+            comparer.compare_responses(&expected, &actual),
+            Err(HtmlCompareError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_assert_html_eq_on_response() {
+        let response = (
+            200,
+            vec![("Content-Type", "text/html; charset=utf-8")],
+            "<main>\n  <h1>Home</h1>\n</main>",
+        );
+        response.assert_html_eq("<main><h1>Home</h1></main>", HtmlCompareOptions::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "/html/body/main/h1/text(): expected 'Home', found 'Oops'")]
+    fn test_assert_html_eq_on_response_failure() {
+        let response = (
+            500,
+            vec![("Content-Type", "text/html")],
+            "<main><h1>Oops</h1></main>",
+        );
+        response.assert_html_eq("<main><h1>Home</h1></main>", HtmlCompareOptions::default());
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn test_axum_test_response() {
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::get(|| async {
+                axum::response::Html("<main>\n  <h1>Home</h1>\n</main>")
+            }),
+        );
+        let server = axum_test::TestServer::new(app).unwrap();
+        let response = server.get("/").await;
+        assert_eq!(HttpResponse::status(&response), 200);
+        response.assert_html_eq("<main><h1>Home</h1></main>", HtmlCompareOptions::default());
+    }
+
+    #[cfg(feature = "actix")]
+    #[test]
+    fn test_actix_response() {
+        use actix_web::http::header::{HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+        use actix_web::http::StatusCode;
+
+        let gzip: &[u8] = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xb3\x29\xb0\xf3\x48\xcd\xc9\xc9\xd7\x51\x48\xce\xcf\x2d\x28\x4a\x2d\x2e\x4e\x4d\x51\x28\xcf\x2f\xca\x49\x51\xb4\xd1\x2f\xb0\x03\x00\xe2\xbb\x79\x2d\x1f\x00\x00\x00";
+        let mut response = actix_web::HttpResponse::with_body(StatusCode::OK, gzip.to_vec());
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        assert_eq!(
+            HttpResponse::header(&response, "Content-Type"),
+            Some("text/html")
+        );
+        response.assert_html_eq(
+            "<p>Hello, compressed world!</p>",
+            HtmlCompareOptions::default(),
+        );
+        HtmlComparer::new()
+            .compare_responses(
+                &(
+                    200,
+                    vec![("Content-Type", "text/html")],
+                    "<p>Hello, compressed world!</p>",
+                ),
+                &response,
+            )
+            .unwrap();
+
+        let request = actix_web::test::TestRequest::default().to_http_request();
+        let response = actix_web::dev::ServiceResponse::new(request, response);
+        assert_eq!(HttpResponse::status(&response), 200);
+        response.assert_html_eq(
+            "<p>Hello, compressed world!</p>",
+            HtmlCompareOptions::default(),
+        );
+    }
+
+    #[test]
+    fn test_http_fetcher_follows_redirects() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let responses: [&[u8]; 2] = [
+                b"HTTP/1.1 302 Found\r\nLocation: /page\r\nContent-Length: 0\r\n\r\n",
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=ISO-8859-1\r\n\
+                  Transfer-Encoding: chunked\r\n\r\n4\r\n<p>\xE9\r\n4\r\n</p>\r\n0\r\n\r\n",
+            ];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                stream.write_all(response).unwrap();
+            }
+        });
+
+        let response = HttpFetcher::default()
+            .fetch(&format!("http://{}/start", address))
+            .unwrap();
+        server.join().unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.charset(), "iso-8859-1");
+        assert_eq!(response.text().unwrap(), "<p>\u{e9}</p>");
+
+        assert!(HttpFetcher::default()
+            .fetch("https://example.com/")
+            .is_err());
+        assert_eq!(resolve("http://a/b/c", "d"), "http://a/b/d");
+        assert_eq!(resolve("http://a/b/c", "/d"), "http://a/d");
+    }
+}
@@ -0,0 +1,119 @@
+//! Leaving out or unwrapping selected elements.
+//!
+//! Generators and themes add markup that carries no content: copy buttons
+//! in code blocks, permalink anchors in headings, wrappers around
+//! highlighted code. Elements matching a selector of
+//! [`HtmlCompareOptions::ignore_elements`](crate::HtmlCompareOptions::ignore_elements)
+//! are left out of the comparison with everything inside them, and those
+//! matching a selector of
+//! [`HtmlCompareOptions::unwrap_elements`](crate::HtmlCompareOptions::unwrap_elements)
+//! are compared as their children, so `<div class="highlight"><pre>` matches
+//! a bare `<pre>`. Text that code blocks are compared by (see
+//! [`HtmlCompareOptions::ignore_code_highlighting`](crate::HtmlCompareOptions::ignore_code_highlighting))
+//! leaves out the ignored elements too.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::HtmlComparer;
+
+impl HtmlComparer {
+    /// Whether `node` is an element matching `ignore_elements`
+    pub(crate) fn is_ignored_element(&self, node: NodeRef<Node>) -> bool {
+        let Ok(selectors) = &self.ignored_element_selectors else {
+            return false;
+        };
+        !selectors.is_empty()
+            && ElementRef::wrap(node)
+                .is_some_and(|element| selectors.iter().any(|selector| selector.matches(&element)))
+    }
+
+    /// Whether `element` matches `unwrap_elements` and is compared as its
+    /// children
+    pub(crate) fn is_unwrapped_element(&self, element: ElementRef) -> bool {
+        self.unwrapped_element_selectors
+            .as_ref()
+            .is_ok_and(|selectors| selectors.iter().any(|selector| selector.matches(&element)))
+    }
+
+    /// The text inside `element`, leaving out that of ignored elements
+    pub(crate) fn text_outside_ignored(&self, element: ElementRef) -> String {
+        let mut text = String::new();
+        let mut stack: Vec<_> = element.children().collect();
+        stack.reverse();
+        while let Some(node) = stack.pop() {
+            match node.value() {
+                Node::Text(fragment) => text.push_str(fragment),
+                Node::Element(_) if !self.is_ignored_element(node) => {
+                    stack.extend(node.children().rev());
+                }
+                _ => {}
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HtmlCompareError, HtmlCompareOptions, HtmlComparer};
+
+    fn comparer(ignore: &[&str], unwrap: &[&str]) -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_elements: ignore.iter().map(|s| s.to_string()).collect(),
+            unwrap_elements: unwrap.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_ignore_elements() {
+        let expected = r#"<h2 id="usage">Usage</h2><p>Run it</p>"#;
+        let actual = r##"<h2 id="usage"><a class="anchor" href="#usage">#</a>Usage</h2><p>Run it</p><button class="copy">Copy</button>"##;
+        let comparer = comparer(&["a.anchor", ".copy"], &[]);
+        comparer.verify(expected, actual).unwrap();
+        assert!(comparer.diff(expected, actual).is_empty());
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+        assert!(!comparer
+            .is_equal(expected, &actual.replace("Run it", "Run"))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_unwrap_elements() {
+        let expected = r#"<pre><code>ls -l</code></pre>"#;
+        let actual = r#"<div class="highlight"><pre><code>ls -l</code></pre></div>"#;
+        let comparer = comparer(&[], &["div.highlight"]);
+        comparer.verify(expected, actual).unwrap();
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+        assert!(!comparer
+            .is_equal(expected, &actual.replace("highlight", "other"))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_ignored_elements_in_code() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_elements: vec![".ln".to_string()],
+            ignore_code_highlighting: true,
+            ..Default::default()
+        });
+        comparer
+            .verify(
+                "<pre><code>a\nb</code></pre>",
+                r#"<pre><code><span class="ln">1</span>a
+<span class="ln">2</span>b</code></pre>"#,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_invalid_selector() {
+        for comparer in [comparer(&["p["], &[]), comparer(&[], &["p["])] {
+            assert!(matches!(
+                comparer.verify("<p></p>", "<p></p>"),
+                Err(HtmlCompareError::InvalidSelector(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Ignoring the text of selected elements only.
+//!
+//! [`HtmlCompareOptions::ignore_text`](crate::HtmlCompareOptions) drops every
+//! text node. Pages usually hold just a few volatile bits, like a
+//! `.timestamp` or the `.username` of whoever is logged in, and
+//! [`HtmlCompareOptions::ignore_text_in`](crate::HtmlCompareOptions) drops
+//! only the text inside elements matching its selectors. Everything else,
+//! including the elements themselves and their attributes, is still
+//! compared.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node, Selector};
+
+/// Parse the selectors of `ignore_text_in` or a similar list, or describe
+/// the first invalid one
+pub(crate) fn compile_selectors(selectors: &[String]) -> Result<Vec<Selector>, String> {
+    selectors
+        .iter()
+        .map(|selector| Selector::parse(selector).map_err(|err| format!("{}: {}", selector, err)))
+        .collect()
+}
+
+/// Whether the text node `node` lies inside an element matching one of
+/// `selectors`
+pub(crate) fn is_ignored(selectors: &[Selector], node: NodeRef<Node>) -> bool {
+    !selectors.is_empty()
+        && node
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|element| selectors.iter().any(|selector| selector.matches(&element)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HtmlCompareError, HtmlCompareOptions, HtmlComparer};
+
+    fn comparer(selectors: &[&str]) -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_text_in: selectors.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_ignore_text_in_selectors() {
+        let expected = r#"<p>Posted by <span class="username">alice</span> <time class="timestamp">5 minutes ago</time></p>"#;
+        let actual = r#"<p>Posted by <span class="username">bob</span> <time class="timestamp">just now</time></p>"#;
+        let comparer = comparer(&[".username", ".timestamp"]);
+        comparer.verify(expected, actual).unwrap();
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+
+        // Other text, and the ignored elements themselves, still count
+        let other_text = actual.replace("Posted by", "Written by");
+        assert!(!comparer.is_equal(expected, &other_text).unwrap());
+        let other_tag = actual.replace("<span class=\"username\">bob</span>", "<em>bob</em>");
+        assert!(!comparer.is_equal(expected, &other_tag).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_selector() {
+        assert!(matches!(
+            comparer(&["p["]).verify("<p>a</p>", "<p>a</p>"),
+            Err(HtmlCompareError::InvalidSelector(_))
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+//! Inline event handlers compared as JavaScript.
+//!
+//! Legacy templates write `onclick="doThing( 1 )"` where a rewrite writes
+//! `onclick="doThing(1);"`. With
+//! [`HtmlCompareOptions::normalize_inline_js`](crate::HtmlCompareOptions::normalize_inline_js)
+//! the values of `on*` attributes are split into JavaScript tokens, so that
+//! whitespace, comments, the semicolon ending the value and repeated
+//! semicolons between statements don't take part in the comparison, while
+//! identifiers, operators and literals must match, strings in their
+//! original quotes. Semicolons still separate statements, so
+//! `return; confirm()` differs from `return confirm()`, and those inside
+//! parentheses, as in `for (;;)`, are kept as written. Values that don't
+//! tokenize, such as one with an unterminated string, are compared as
+//! written.
+
+/// Operators of more than one character, longest first
+const OPERATORS: &[&str] = &[
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "&&=", "||=", "??=", "=>", "==", "!=",
+    "<=", ">=", "&&", "||", "??", "?.", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+    "**", "<<", ">>",
+];
+
+/// Whether the attribute `name` holds an event handler
+pub(crate) fn is_event_handler(name: &str) -> bool {
+    name.len() > 2 && name.starts_with("on")
+}
+
+/// `value` as its tokens separated by single spaces, without comments,
+/// repeated statement separators and the semicolons ending the value, or
+/// `None` if it doesn't tokenize
+pub(crate) fn normalize(value: &str) -> Option<String> {
+    let tokens = tokenize(value)?;
+    let mut depth = 0usize;
+    let mut kept: Vec<&str> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        match token {
+            "(" => depth += 1,
+            ")" => depth = depth.saturating_sub(1),
+            ";" if depth == 0 && kept.last() == Some(&";") => continue,
+            _ => {}
+        }
+        kept.push(token);
+    }
+    while depth == 0 && kept.last() == Some(&";") {
+        kept.pop();
+    }
+    Some(kept.join(" "))
+}
+
+/// The tokens of the script `source`, without whitespace and comments
+fn tokenize(source: &str) -> Option<Vec<&str>> {
+    let mut tokens: Vec<&str> = Vec::new();
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if let Some(comment) = rest.strip_prefix("//") {
+            rest = comment.find(['\n', '\r']).map_or("", |end| &comment[end..]);
+            continue;
+        }
+        if let Some(comment) = rest.strip_prefix("/*") {
+            rest = &comment[comment.find("*/")? + 2..];
+            continue;
+        }
+        let len = match c {
+            '"' | '\'' | '`' => literal_len(rest, c, false)?,
+            '/' if starts_regex(tokens.last().copied()) => {
+                let len = literal_len(rest, '/', true)?;
+                len + rest[len..]
+                    .find(|c: char| !c.is_alphanumeric())
+                    .unwrap_or(rest.len() - len)
+            }
+            // Numbers, with their fraction and exponent
+            _ if c.is_ascii_digit()
+                || c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                rest.find(|c: char| !is_word_char(c) && c != '.')
+                    .unwrap_or(rest.len())
+            }
+            _ if is_word_char(c) => rest.find(|c| !is_word_char(c)).unwrap_or(rest.len()),
+            _ => OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(*operator))
+                .map_or(c.len_utf8(), |operator| operator.len()),
+        };
+        tokens.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    Some(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Whether a `/` following `previous` starts a regular expression rather
+/// than a division
+fn starts_regex(previous: Option<&str>) -> bool {
+    match previous {
+        None => true,
+        Some(token) => {
+            !token.starts_with(|c: char| is_word_char(c) || matches!(c, '"' | '\'' | '`' | '.'))
+                && !matches!(token, ")" | "]" | "}")
+        }
+    }
+}
+
+/// The length of the literal at the start of `source`, from its opening
+/// `quote` to the matching unescaped one, or `None` if it isn't closed;
+/// in a regular expression, a `quote` inside `[...]` doesn't close it
+fn literal_len(source: &str, quote: char, regex: bool) -> Option<usize> {
+    let mut escaped = false;
+    let mut in_class = false;
+    for (index, c) in source.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' if regex => in_class = true,
+            ']' if regex => in_class = false,
+            '\n' | '\r' if quote != '`' => return None,
+            _ if c == quote && !in_class => return Some(index + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("doThing( 1 )").unwrap(), "doThing ( 1 )");
+        assert_eq!(normalize("doThing(1);").unwrap(), "doThing ( 1 )");
+        assert_eq!(
+            normalize("a.b  =  'x y' ; /* note */ c(.5, 1.5e3)// done").unwrap(),
+            "a . b = 'x y' ; c ( .5 , 1.5e3 )"
+        );
+        assert_eq!(normalize("i++ + ++j").unwrap(), "i ++ + ++ j");
+        assert_eq!(
+            normalize("for (;;) { x = /a b;/g.test(s) / 2 }").unwrap(),
+            "for ( ; ; ) { x = /a b;/g . test ( s ) / 2 }"
+        );
+        assert_eq!(normalize("alert(`a ${b}`)").unwrap(), "alert ( `a ${b}` )");
+        assert_eq!(normalize("a();; ;b();;").unwrap(), "a ( ) ; b ( )");
+        assert_eq!(normalize("alert('unclosed)"), None);
+        assert!(is_event_handler("onclick") && !is_event_handler("on"));
+    }
+
+    #[test]
+    fn test_normalize_inline_js() {
+        let legacy = r#"<button onclick="doThing( 1 );  return false" title="a  b">Go</button>"#;
+        let current = r#"<button onclick="doThing(1);return false;" title="a  b">Go</button>"#;
+        assert!(!HtmlComparer::new().is_equal(legacy, current).unwrap());
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            normalize_inline_js: true,
+            ..Default::default()
+        });
+        comparer.verify(legacy, current).unwrap();
+        for changed in [
+            current.replace("(1)", "(2)"),
+            current.replace("(1)", "(\"1\")"),
+            current.replace("a  b", "a b"),
+            current.replace("onclick", "onmouseover"),
+        ] {
+            assert!(!comparer.is_equal(legacy, &changed).unwrap(), "{}", changed);
+        }
+        // Semicolons between statements still separate them
+        for (expected, actual) in [
+            ("return; confirm()", "return confirm()"),
+            ("f;(g)", "f(g)"),
+            ("a;-b", "a-b"),
+        ] {
+            let [expected, actual] = [expected, actual]
+                .map(|handler| format!("<button onclick=\"{}\">Go</button>", handler));
+            assert!(
+                !comparer.is_equal(&expected, &actual).unwrap(),
+                "{}",
+                actual
+            );
+        }
+        comparer
+            .verify(legacy, &current.replace(';', ";;"))
+            .unwrap();
+    }
+}
@@ -0,0 +1,699 @@
+//! Normalized JSON export of the DOM.
+//!
+//! [`to_json_tree`] applies the same normalization as [`HtmlComparer`] and
+//! emits the result as a [`serde_json::Value`], so documents can be fed to
+//! jq, JSON snapshot tools or asserted on piecewise.
+//!
+//! Elements become `{"tag": ..., "attributes": {...}, "children": [...]}`
+//! objects, text nodes become strings and comments become
+//! `{"comment": ...}` objects. Declarative shadow roots are exported under a
+//! `"shadow_root"` key of their host unless they are flattened.
+//!
+//! [`HtmlCompareOptions::from_json`] goes the other way for configuration,
+//! building options from a JSON object received at runtime.
+
+use std::collections::{HashMap, HashSet};
+
+use scraper::{ElementRef, Node};
+use serde_json::{Map, Value};
+
+use crate::tokens::Delimiter;
+use crate::{
+    AssetResolver, AttributeContent, DiffGranularity, FrameworkMarkers, HtmlCompareError,
+    HtmlCompareOptions, HtmlComparer, IntegrityMode, InvalidUtf8, LintRule, MarkdownProfile,
+    Parser, ShadowDomMode, SortKey, TokenRule,
+};
+
+/// Convert an HTML document to a normalized JSON tree rooted at `<html>`
+///
+/// Nodes and attributes excluded by `options` are left out, text is trimmed
+/// when `ignore_whitespace` is set, attributes are sorted by name and, with
+/// `ignore_sibling_order`, children are sorted into a canonical order so that
+/// documents which compare equal produce identical trees.
+///
+/// # Examples
+/// ```ignore
+/// use html_compare_rs::{json::to_json_tree, HtmlCompareOptions};
+///
+/// let tree = to_json_tree("<p class='x'>Hi</p>", &HtmlCompareOptions::default());
+/// assert_eq!(tree["children"][1]["children"][0]["tag"], "p");
+/// ```
+pub fn to_json_tree(html: &str, options: &HtmlCompareOptions) -> Value {
+    let comparer = HtmlComparer::with_options(options.clone());
+    let document = comparer.parse(html);
+    let root = document.root_element();
+    element_to_json(&comparer, options, root, comparer.top_heading_level(*root))
+}
+
+/// The tree of `element`, in a document whose topmost heading is at level
+/// `top_heading`
+pub(crate) fn element_to_json(
+    comparer: &HtmlComparer,
+    options: &HtmlCompareOptions,
+    element: ElementRef,
+    top_heading: usize,
+) -> Value {
+    let mut object = Map::new();
+    object.insert(
+        "tag".to_string(),
+        Value::from(comparer.element_name(element, top_heading)),
+    );
+
+    if !options.ignore_attributes {
+        let attributes: Map<String, Value> = comparer
+            .compared_attributes(element)
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), Value::from(value.into_owned())))
+            .collect();
+        object.insert("attributes".to_string(), Value::Object(attributes));
+    }
+
+    if options.shadow_dom == ShadowDomMode::Separate {
+        if let Some(shadow_root) = comparer.shadow_root(element) {
+            object.insert(
+                "shadow_root".to_string(),
+                element_to_json(comparer, options, shadow_root, top_heading),
+            );
+        }
+    }
+
+    let mut children = Vec::new();
+    if !(options.ignore_style_contents && element.value().name() == "style") {
+        for child in comparer.child_nodes(element) {
+            let value = match child.value() {
+                Node::Text(_) => Value::from(comparer.node_text(child).into_owned()),
+                Node::Comment(comment) => {
+                    let mut object = Map::new();
+                    object.insert("comment".to_string(), Value::from(comment.trim()));
+                    Value::Object(object)
+                }
+                Node::Element(_) => match ElementRef::wrap(child) {
+                    Some(child) => element_to_json(comparer, options, child, top_heading),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            children.push(value);
+        }
+    }
+    if options.ignore_sibling_order {
+        children.sort_by_cached_key(|child| child.to_string());
+    }
+    object.insert("children".to_string(), Value::Array(children));
+
+    Value::Object(object)
+}
+
+impl HtmlCompareOptions {
+    /// Build options from a JSON object
+    ///
+    /// Keys are the field names of [`HtmlCompareOptions`]; omitted keys keep
+    /// their default value. `asset_resolver` takes the path of a directory and
+    /// `known_classes` an array of class names. `sort_children` takes
+    /// `[selector, key]` pairs where the key is `"text"`, `{"attribute": name}`
+    /// or `{"selector": selector}`, `ignored_attributes_for` takes
+    /// `[selector, [attribute, ...]]` pairs and `equivalent_tags` takes
+    /// `[tag, tag]` pairs. `attribute_content` maps attribute names to
+    /// `"json"`, `"html"` or `"css"`. `lint_rules` takes the names of built-in rules, e.g.
+    /// `"img-missing-alt"`. Unknown keys and values of the wrong type are
+    /// rejected so that typos in options sent by a test harness fail loudly
+    /// instead of silently falling back to defaults.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let options = HtmlCompareOptions::from_json(
+    ///     r#"{"ignore_sibling_order": true, "ignored_attributes": ["id"]}"#,
+    /// )?;
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, HtmlCompareError> {
+        let value: Value = serde_json::from_str(json)
+            .map_err(|err| HtmlCompareError::InvalidOptions(err.to_string()))?;
+        let Value::Object(fields) = value else {
+            return Err(HtmlCompareError::InvalidOptions(
+                "expected a JSON object".to_string(),
+            ));
+        };
+
+        let mut options = Self::default();
+        for (key, value) in fields {
+            let flag = match key.as_str() {
+                "ignore_whitespace" => &mut options.ignore_whitespace,
+                "ignore_attributes" => &mut options.ignore_attributes,
+                "ignore_text" => &mut options.ignore_text,
+                "ignore_comments" => &mut options.ignore_comments,
+                "ignore_sibling_order" => &mut options.ignore_sibling_order,
+                "ignore_style_contents" => &mut options.ignore_style_contents,
+                "exhaustive" => &mut options.exhaustive,
+                "mathml" => &mut options.mathml,
+                "normalize_head_order" => &mut options.normalize_head_order,
+                "relative_heading_levels" => &mut options.relative_heading_levels,
+                "compare_media_queries" => &mut options.compare_media_queries,
+                "collapse_whitespace" => &mut options.collapse_whitespace,
+                "normalize_boolean_attributes" => &mut options.normalize_boolean_attributes,
+                "normalize_optional_tags" => &mut options.normalize_optional_tags,
+                "ignore_block_whitespace" => &mut options.ignore_block_whitespace,
+                "turbo_streams" => &mut options.turbo_streams,
+                "security_checks" => &mut options.security_checks,
+                "ignore_nonces" => &mut options.ignore_nonces,
+                "ignore_hidden_elements" => &mut options.ignore_hidden_elements,
+                "unordered_picture_sources" => &mut options.unordered_picture_sources,
+                "unordered_media_sources" => &mut options.unordered_media_sources,
+                "normalize_list_tightness" => &mut options.normalize_list_tightness,
+                "ignore_code_highlighting" => &mut options.ignore_code_highlighting,
+                "exact_code_whitespace" => &mut options.exact_code_whitespace,
+                "tolerate_prolog_junk" => &mut options.tolerate_prolog_junk,
+                "capture_placeholders" => &mut options.capture_placeholders,
+                "light_dom_first" => &mut options.light_dom_first,
+                "flatten_slots" => &mut options.flatten_slots,
+                "decode_attribute_entities" => &mut options.decode_attribute_entities,
+                "ignore_attribute_wrapping" => &mut options.ignore_attribute_wrapping,
+                "ignore_void_trailing_slash" => &mut options.ignore_void_trailing_slash,
+                "strict_attribute_names" => &mut options.strict_attribute_names,
+                "normalize_inline_js" => &mut options.normalize_inline_js,
+                "unordered_query_params" => &mut options.unordered_query_params,
+                "treat_presentational_tags_as_equivalent" => {
+                    &mut options.treat_presentational_tags_as_equivalent
+                }
+                "ignored_attributes" => {
+                    options.ignored_attributes = string_set(&key, value)?;
+                    continue;
+                }
+                "ignored_attribute_prefixes" => {
+                    options.ignored_attribute_prefixes =
+                        string_set::<HashSet<_>>(&key, value)?.into_iter().collect();
+                    continue;
+                }
+                "attribute_order" => {
+                    options.attribute_order = string_set(&key, value)?;
+                    continue;
+                }
+                "ignored_query_params" => {
+                    options.ignored_query_params = string_set(&key, value)?;
+                    continue;
+                }
+                "empty_attribute_equals_missing" => {
+                    options.empty_attribute_equals_missing = string_set(&key, value)?;
+                    continue;
+                }
+                "ignore_text_in" => {
+                    options.ignore_text_in = string_set(&key, value)?;
+                    continue;
+                }
+                "ignore_elements" => {
+                    options.ignore_elements = string_set(&key, value)?;
+                    continue;
+                }
+                "unwrap_elements" => {
+                    options.unwrap_elements = string_set(&key, value)?;
+                    continue;
+                }
+                "asset_resolver" => {
+                    let Value::String(root) = value else {
+                        return Err(HtmlCompareError::InvalidOptions(format!(
+                            "`{}` must be a directory path",
+                            key
+                        )));
+                    };
+                    options.asset_resolver = Some(AssetResolver::directory(root));
+                    continue;
+                }
+                "dump_on_failure" => {
+                    let Value::String(directory) = value else {
+                        return Err(HtmlCompareError::InvalidOptions(format!(
+                            "`{}` must be a directory path",
+                            key
+                        )));
+                    };
+                    options.dump_on_failure = Some(directory.into());
+                    continue;
+                }
+                "shadow_dom" => {
+                    options.shadow_dom = match value.as_str() {
+                        Some("separate") => ShadowDomMode::Separate,
+                        Some("flatten") => ShadowDomMode::Flatten,
+                        Some("composed") => ShadowDomMode::Composed,
+                        _ => {
+                            return Err(HtmlCompareError::InvalidOptions(format!(
+                                "`{}` must be \"separate\", \"flatten\" or \"composed\"",
+                                key
+                            )))
+                        }
+                    };
+                    continue;
+                }
+                "parser" => {
+                    options.parser =
+                        value.as_str().and_then(Parser::from_name).ok_or_else(|| {
+                            HtmlCompareError::InvalidOptions(format!(
+                                "`{}` must be \"html5ever\" or \"fragment\"",
+                                key
+                            ))
+                        })?;
+                    continue;
+                }
+                "integrity" => {
+                    options.integrity = match value.as_str() {
+                        Some("exact") => IntegrityMode::Exact,
+                        Some("ignore") => IntegrityMode::Ignore,
+                        Some("format") => IntegrityMode::Format,
+                        _ => {
+                            return Err(HtmlCompareError::InvalidOptions(format!(
+                                "`{}` must be \"exact\", \"ignore\" or \"format\"",
+                                key
+                            )))
+                        }
+                    };
+                    continue;
+                }
+                "ignore_framework_markers" => {
+                    options.ignore_framework_markers = value
+                        .as_str()
+                        .and_then(FrameworkMarkers::from_name)
+                        .ok_or_else(|| {
+                            HtmlCompareError::InvalidOptions(format!(
+                                "`{}` must be \"none\", \"react\", \"vue\", \"svelte\", \"leptos\" or \"qwik\"",
+                                key
+                            ))
+                        })?;
+                    continue;
+                }
+                "markdown" => {
+                    options.markdown = match value.as_str() {
+                        Some("commonmark") => Some(MarkdownProfile::commonmark()),
+                        Some("gfm") => Some(MarkdownProfile::gfm()),
+                        _ if value.is_null() => None,
+                        _ => {
+                            return Err(HtmlCompareError::InvalidOptions(format!(
+                                "`{}` must be \"commonmark\", \"gfm\" or null",
+                                key
+                            )))
+                        }
+                    };
+                    continue;
+                }
+                "known_classes" => {
+                    options.known_classes =
+                        Some(string_set::<HashSet<_>>(&key, value)?.into_iter().collect());
+                    continue;
+                }
+                "equivalent_tags" => {
+                    options.equivalent_tags = string_pairs(&key, value, "[tag, tag]")?;
+                    continue;
+                }
+                "lint_rules" => {
+                    options.lint_rules = lint_rules(&key, value)?;
+                    continue;
+                }
+                "component_boundaries" => {
+                    options.component_boundaries =
+                        string_pairs(&key, value, "[selector, attribute]")?;
+                    continue;
+                }
+                "sort_children" => {
+                    options.sort_children = sort_rules(&key, value)?;
+                    continue;
+                }
+                "ignored_attributes_for" => {
+                    options.ignored_attributes_for = scoped_attributes(&key, value)?;
+                    continue;
+                }
+                "root_selector" => {
+                    let selector = value.as_str().ok_or_else(|| {
+                        HtmlCompareError::InvalidOptions(format!("`{}` must be a string", key))
+                    })?;
+                    options.root_selector = Some(selector.to_string());
+                    continue;
+                }
+                "diff_granularity" => {
+                    options.diff_granularity = value
+                        .as_str()
+                        .and_then(DiffGranularity::from_name)
+                        .ok_or_else(|| {
+                            HtmlCompareError::InvalidOptions(format!(
+                                "`{}` must be \"element\", \"node\" or \"character\"",
+                                key
+                            ))
+                        })?;
+                    continue;
+                }
+                "invalid_utf8" => {
+                    options.invalid_utf8 = value
+                        .as_str()
+                        .and_then(InvalidUtf8::from_name)
+                        .ok_or_else(|| {
+                            HtmlCompareError::InvalidOptions(format!(
+                                "`{}` must be \"error\", \"replace\" or \"compare_bytes\"",
+                                key
+                            ))
+                        })?;
+                    continue;
+                }
+                "tokenized_attributes" => {
+                    options.tokenized_attributes = token_rules(&key, value)?;
+                    continue;
+                }
+                "attribute_content" => {
+                    options.attribute_content = content_types(&key, value)?;
+                    continue;
+                }
+                _ => {
+                    return Err(HtmlCompareError::InvalidOptions(format!(
+                        "unknown field `{}`",
+                        key
+                    )))
+                }
+            };
+            *flag = value.as_bool().ok_or_else(|| {
+                HtmlCompareError::InvalidOptions(format!("`{}` must be a boolean", key))
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+fn string_set<C: FromIterator<String>>(key: &str, value: Value) -> Result<C, HtmlCompareError> {
+    let invalid =
+        || HtmlCompareError::InvalidOptions(format!("`{}` must be an array of strings", key));
+    let Value::Array(items) = value else {
+        return Err(invalid());
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::String(text) => Ok(text),
+            _ => Err(invalid()),
+        })
+        .collect()
+}
+
+fn lint_rules(key: &str, value: Value) -> Result<Vec<LintRule>, HtmlCompareError> {
+    let invalid = || {
+        HtmlCompareError::InvalidOptions(format!(
+            "`{}` must be an array of built-in lint rule names",
+            key
+        ))
+    };
+    let Value::Array(names) = value else {
+        return Err(invalid());
+    };
+    names
+        .into_iter()
+        .map(|name| match name {
+            Value::String(name) => LintRule::from_name(&name).ok_or_else(invalid),
+            _ => Err(invalid()),
+        })
+        .collect()
+}
+
+fn string_pairs(
+    key: &str,
+    value: Value,
+    shape: &str,
+) -> Result<Vec<(String, String)>, HtmlCompareError> {
+    let invalid = || {
+        HtmlCompareError::InvalidOptions(format!("`{}` must be an array of {} pairs", key, shape))
+    };
+    let Value::Array(pairs) = value else {
+        return Err(invalid());
+    };
+    pairs
+        .into_iter()
+        .map(|pair| match pair {
+            Value::Array(pair) => match <[Value; 2]>::try_from(pair) {
+                Ok([Value::String(first), Value::String(second)]) => Ok((first, second)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        })
+        .collect()
+}
+
+fn sort_rules(key: &str, value: Value) -> Result<Vec<(String, SortKey)>, HtmlCompareError> {
+    let invalid = || {
+        HtmlCompareError::InvalidOptions(format!(
+            "`{}` must be an array of [selector, key] pairs",
+            key
+        ))
+    };
+    let Value::Array(rules) = value else {
+        return Err(invalid());
+    };
+    rules
+        .into_iter()
+        .map(|rule| {
+            let Value::Array(pair) = rule else {
+                return Err(invalid());
+            };
+            let Ok([Value::String(parent), sort_key]) = <[Value; 2]>::try_from(pair) else {
+                return Err(invalid());
+            };
+            let sort_key = match sort_key {
+                Value::String(text) if text == "text" => SortKey::Text,
+                Value::Object(object) if object.len() == 1 => match object.into_iter().next() {
+                    Some((kind, Value::String(name))) if kind == "attribute" => {
+                        SortKey::Attribute(name)
+                    }
+                    Some((kind, Value::String(selector))) if kind == "selector" => {
+                        SortKey::Selector(selector)
+                    }
+                    _ => return Err(invalid()),
+                },
+                _ => return Err(invalid()),
+            };
+            Ok((parent, sort_key))
+        })
+        .collect()
+}
+
+fn scoped_attributes(
+    key: &str,
+    value: Value,
+) -> Result<Vec<(String, HashSet<String>)>, HtmlCompareError> {
+    let invalid = || {
+        HtmlCompareError::InvalidOptions(format!(
+            "`{}` must be an array of [selector, [attribute, ...]] pairs",
+            key
+        ))
+    };
+    let Value::Array(entries) = value else {
+        return Err(invalid());
+    };
+    entries
+        .into_iter()
+        .map(|entry| {
+            let Value::Array(pair) = entry else {
+                return Err(invalid());
+            };
+            let Ok([Value::String(selector), attributes]) = <[Value; 2]>::try_from(pair) else {
+                return Err(invalid());
+            };
+            let attributes = string_set(key, attributes).map_err(|_| invalid())?;
+            Ok((selector, attributes))
+        })
+        .collect()
+}
+
+fn content_types(
+    key: &str,
+    value: Value,
+) -> Result<HashMap<String, AttributeContent>, HtmlCompareError> {
+    let invalid = || {
+        HtmlCompareError::InvalidOptions(format!(
+            "`{}` must map attribute names to \"json\", \"html\" or \"css\"",
+            key
+        ))
+    };
+    let Value::Object(entries) = value else {
+        return Err(invalid());
+    };
+    entries
+        .into_iter()
+        .map(|(attribute, content)| {
+            let content = content
+                .as_str()
+                .and_then(AttributeContent::from_name)
+                .ok_or_else(invalid)?;
+            Ok((attribute, content))
+        })
+        .collect()
+}
+
+fn token_rules(key: &str, value: Value) -> Result<HashMap<String, TokenRule>, HtmlCompareError> {
+    let invalid = || {
+        HtmlCompareError::InvalidOptions(format!(
+            "`{}` must map attribute names to {{\"delimiter\", \"ordered\"}} objects",
+            key
+        ))
+    };
+    let Value::Object(rules) = value else {
+        return Err(invalid());
+    };
+    rules
+        .into_iter()
+        .map(|(name, rule)| {
+            let Value::Object(rule) = rule else {
+                return Err(invalid());
+            };
+            let delimiter = match rule.get("delimiter").and_then(Value::as_str) {
+                None | Some("whitespace") => Delimiter::Whitespace,
+                Some(text) => {
+                    let mut chars = text.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Delimiter::Char(c),
+                        _ => return Err(invalid()),
+                    }
+                }
+            };
+            let ordered = match rule.get("ordered") {
+                None => false,
+                Some(ordered) => ordered.as_bool().ok_or_else(invalid)?,
+            };
+            Ok((name, TokenRule { delimiter, ordered }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_json_tree() {
+        let tree = to_json_tree(
+            r#"<html><head></head><body>
+                <p id="a" class="x">  Hello <b>world</b></p>
+                <!-- note -->
+            </body></html>"#,
+            &HtmlCompareOptions::default(),
+        );
+
+        assert_eq!(
+            tree,
+            json!({
+                "tag": "html",
+                "attributes": {},
+                "children": [
+                    { "tag": "head", "attributes": {}, "children": [] },
+                    { "tag": "body", "attributes": {}, "children": [
+                        { "tag": "p", "attributes": { "class": "x", "id": "a" }, "children": [
+                            "Hello",
+                            { "tag": "b", "attributes": {}, "children": ["world"] }
+                        ]}
+                    ]}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_json_tree_respects_options() {
+        let options = HtmlCompareOptions {
+            ignore_comments: false,
+            ignore_sibling_order: true,
+            ignored_attributes: ["id".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
+
+        let body = |html: &str| to_json_tree(html, &options)["children"][1].clone();
+        assert_eq!(
+            body(r#"<p>x</p><ul id="one"><li>A</li><li>B</li></ul><!-- c -->"#),
+            body(r#"<ul id="two"><li>B</li><li>A</li></ul><!--c--><p>x</p>"#)
+        );
+        assert_eq!(
+            body("<p>x</p><!-- c -->")["children"][1],
+            json!({ "comment": "c" })
+        );
+
+        let relaxed = to_json_tree(r#"<div class="x">Hi</div>"#, &crate::presets::relaxed());
+        assert_eq!(
+            relaxed["children"][1]["children"][0],
+            json!({ "tag": "div", "children": ["Hi"] })
+        );
+    }
+
+    #[test]
+    fn test_options_from_json() {
+        let options = HtmlCompareOptions::from_json(
+            r#"{"ignore_sibling_order": true, "ignore_comments": false, "ignored_attributes": ["id", "class"]}"#,
+        )
+        .unwrap();
+        assert!(options.ignore_sibling_order);
+        assert!(!options.ignore_comments);
+        assert!(options.ignore_whitespace);
+        assert_eq!(
+            options.ignored_attributes,
+            HashSet::from(["id".to_string(), "class".to_string()])
+        );
+
+        let options = HtmlCompareOptions::from_json(
+            r#"{"sort_children": [["ul", {"attribute": "data-id"}], ["tbody", "text"]]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            options.sort_children,
+            [
+                ("ul".to_string(), SortKey::Attribute("data-id".to_string())),
+                ("tbody".to_string(), SortKey::Text),
+            ]
+        );
+
+        let options = HtmlCompareOptions::from_json(
+            r#"{"tokenized_attributes": {"rel": {}, "sizes": {"delimiter": ",", "ordered": true}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            options.tokenized_attributes["sizes"],
+            TokenRule::ordered(Delimiter::Char(','))
+        );
+        assert_eq!(
+            options.tokenized_attributes["rel"],
+            TokenRule::unordered(Delimiter::Whitespace)
+        );
+
+        let options = HtmlCompareOptions::from_json(
+            r#"{"ignored_attributes_for": [[".chart svg *", ["style"]]], "ignore_text_in": [".timestamp"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            options.ignored_attributes_for,
+            [(
+                ".chart svg *".to_string(),
+                HashSet::from(["style".to_string()])
+            )]
+        );
+        assert_eq!(options.ignore_text_in, [".timestamp"]);
+
+        let options =
+            HtmlCompareOptions::from_json(r#"{"attribute_content": {"data-config": "json"}}"#)
+                .unwrap();
+        assert_eq!(options.attribute_content["data-config"].name(), "json");
+
+        let default = HtmlCompareOptions::from_json("{}").unwrap();
+        assert!(!default.ignore_sibling_order);
+        assert!(default.ignored_attributes.is_empty());
+    }
+
+    #[test]
+    fn test_options_from_json_is_strict() {
+        let error = |json: &str| HtmlCompareOptions::from_json(json).unwrap_err().to_string();
+
+        assert_eq!(
+            error(r#"{"ignore_sibling_ordr": true}"#),
+            "Invalid options: unknown field `ignore_sibling_ordr`"
+        );
+        assert_eq!(
+            error(r#"{"ignore_text": "yes"}"#),
+            "Invalid options: `ignore_text` must be a boolean"
+        );
+        assert_eq!(
+            error(r#"{"ignored_attributes": ["id", 1]}"#),
+            "Invalid options: `ignored_attributes` must be an array of strings"
+        );
+        assert_eq!(error("[]"), "Invalid options: expected a JSON object");
+        assert!(matches!(
+            HtmlCompareOptions::from_json("{"),
+            Err(HtmlCompareError::InvalidOptions(_))
+        ));
+    }
+}
@@ -12,7 +12,7 @@
 //! let html2 = "<div>\n  <p>Hello</p>\n</div>";
 //!
 //! let comparer = HtmlComparer::new();
-//! assert!(comparer.compare(html1, html2).unwrap());
+//! comparer.verify(html1, html2).unwrap();
 //! ```
 //!
 //! For testing, you can use the provided assertion macros:
@@ -24,9 +24,30 @@
 //!     "<div>\n  <p>Hello</p>\n</div>"
 //! );
 //! ```
+//!
+//! # Robustness
+//!
+//! [`HtmlComparer::verify`] never panics, whatever its input: any string
+//! parses to some document, and the documents are walked with an explicit
+//! stack rather than by recursion, so deeply nested markup can't overflow
+//! the stack. The same goes for [`HtmlComparer::diff`] and for exhaustive
+//! comparison, including the edit script of the diff. The `fuzz` directory
+//! holds a `cargo fuzz` target that feeds arbitrary bytes to the comparer.
 
 /// Asserts that two HTML strings are equivalent according to the given comparison options.
 ///
+/// Either side may be any [`RenderToHtml`] value, such as `maud::Markup` with the `maud`
+/// feature, instead of a string.
+///
+/// Without explicit options, the current thread's defaults are used (see
+/// [`set_default_options`]). Options can be given as an [`HtmlCompareOptions`]
+/// expression or with a small shorthand on top of the defaults:
+///
+/// - `preset = "relaxed"` (first) starts from a named preset instead (see [`presets::from_name`])
+/// - `ignore_sibling_order` sets a boolean option, `ignore_text = false` sets it explicitly
+/// - `ignore_attributes("class", "id")` adds to `ignored_attributes`
+/// - `ignore_text_in(".timestamp")` adds to `ignore_text_in`
+///
 /// # Examples
 /// ```ignore
 /// use html_compare::assert_html_eq;
@@ -46,28 +67,50 @@
 ///         ..Default::default()
 ///     }
 /// );
+///
+/// // With a preset or inline flags
+/// assert_html_eq!("<p class='a'>Hi</p>", "<p class='b'>Hi</p>", preset = "relaxed");
+/// assert_html_eq!(
+///     "<ul id='a'><li>1</li><li>2</li></ul>",
+///     "<ul id='b'><li>2</li><li>1</li></ul>",
+///     ignore_sibling_order,
+///     ignore_attributes("id"),
+/// );
 /// ```
 #[macro_export]
 macro_rules! assert_html_eq {
     ($left:expr, $right:expr $(,)?) => {
-        $crate::assert_html_eq!($left, $right, $crate::HtmlCompareOptions::default())
+        $crate::assert_html_eq!($left, $right, $crate::default_options())
     };
-    ($left:expr, $right:expr, $options:expr $(,)?) => {{
-        match (&$left, &$right, &$options) {
+    ($left:expr, $right:expr, $($options:tt)+) => {{
+        match (&$left, &$right, &$crate::__html_compare_options!($($options)+)) {
             (left_val, right_val, options) => {
+                let left_html = $crate::markup::RenderToHtml::render_to_html(left_val);
+                let right_html = $crate::markup::RenderToHtml::render_to_html(right_val);
                 let comparer = $crate::HtmlComparer::with_options(options.clone());
-                if let Err(err) = comparer.compare(left_val, right_val) {
+                if let Err(err) = comparer.verify(&left_html, &right_html) {
+                    let artifacts =
+                        $crate::artifacts::__note(comparer.dump_failure(&left_html, &right_html));
+                    if let Some(report) = comparer.failure_report(&left_html, &right_html) {
+                        panic!("{}{}", report, artifacts);
+                    }
                     panic!(
                         "\n\
                         HTML comparison failed:\n\
                         {}\n\n\
-                        left HTML:\n\
+                        left HTML{}:\n\
                         {}\n\n\
-                        right HTML:\n\
+                        right HTML{}:\n\
                         {}\n\n\
-                        options: {:#?}\
+                        options: {:#?}{}\
                     ",
-                        err, left_val, right_val, options
+                        err,
+                        $crate::markup::__label(left_val),
+                        left_html,
+                        $crate::markup::__label(right_val),
+                        right_html,
+                        options,
+                        artifacts
                     );
                 }
             }
@@ -77,6 +120,9 @@ macro_rules! assert_html_eq {
 
 /// Asserts that two HTML strings are not equivalent according to the given comparison options.
 ///
+/// Without explicit options, the current thread's defaults are used (see
+/// [`set_default_options`]). Options accept the same shorthand as [`assert_html_eq!`].
+///
 /// # Examples
 /// ```ignore
 /// use html_compare::assert_html_ne;
@@ -89,21 +135,190 @@ macro_rules! assert_html_eq {
 #[macro_export]
 macro_rules! assert_html_ne {
     ($left:expr, $right:expr $(,)?) => {
-        $crate::assert_html_ne!($left, $right, $crate::HtmlCompareOptions::default())
+        $crate::assert_html_ne!($left, $right, $crate::default_options())
     };
-    ($left:expr, $right:expr, $options:expr $(,)?) => {{
-        match (&$left, &$right, &$options) {
+    ($left:expr, $right:expr, $($options:tt)+) => {{
+        match (&$left, &$right, &$crate::__html_compare_options!($($options)+)) {
             (left_val, right_val, options) => {
+                let left_html = $crate::markup::RenderToHtml::render_to_html(left_val);
+                let right_html = $crate::markup::RenderToHtml::render_to_html(right_val);
                 let comparer = $crate::HtmlComparer::with_options(options.clone());
-                if let Ok(_) = comparer.compare(left_val, right_val) {
+                if let Ok(_) = comparer.verify(&left_html, &right_html) {
                     panic!(
                         "\n\
                         HTML strings were equal but expected to be different:\n\n\
-                        HTML:\n\
+                        HTML{}:\n\
                         {}\n\n\
                         options: {:#?}\
                     ",
-                        left_val, options
+                        $crate::markup::__label(left_val),
+                        left_html,
+                        options
+                    );
+                }
+            }
+        }
+    }};
+}
+
+/// Builds [`HtmlCompareOptions`] from the options argument of the assertion macros.
+///
+/// Accepts either an options expression or the shorthand described on [`assert_html_eq!`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __html_compare_options {
+    (preset = $($dsl:tt)+) => { $crate::__html_compare_options!(@build preset = $($dsl)+) };
+    (ignore_whitespace $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_whitespace $($dsl)*) };
+    (ignore_attributes $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_attributes $($dsl)*) };
+    (ignore_text $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_text $($dsl)*) };
+    (ignore_comments $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_comments $($dsl)*) };
+    (ignore_sibling_order $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_sibling_order $($dsl)*) };
+    (ignore_style_contents $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_style_contents $($dsl)*) };
+    (exhaustive $($dsl:tt)*) => { $crate::__html_compare_options!(@build exhaustive $($dsl)*) };
+    (shadow_dom $($dsl:tt)*) => { $crate::__html_compare_options!(@build shadow_dom $($dsl)*) };
+    (mathml $($dsl:tt)*) => { $crate::__html_compare_options!(@build mathml $($dsl)*) };
+    (normalize_head_order $($dsl:tt)*) => { $crate::__html_compare_options!(@build normalize_head_order $($dsl)*) };
+    (relative_heading_levels $($dsl:tt)*) => { $crate::__html_compare_options!(@build relative_heading_levels $($dsl)*) };
+    (treat_presentational_tags_as_equivalent $($dsl:tt)*) => { $crate::__html_compare_options!(@build treat_presentational_tags_as_equivalent $($dsl)*) };
+    (compare_media_queries $($dsl:tt)*) => { $crate::__html_compare_options!(@build compare_media_queries $($dsl)*) };
+    (collapse_whitespace $($dsl:tt)*) => { $crate::__html_compare_options!(@build collapse_whitespace $($dsl)*) };
+    (normalize_boolean_attributes $($dsl:tt)*) => { $crate::__html_compare_options!(@build normalize_boolean_attributes $($dsl)*) };
+    (normalize_optional_tags $($dsl:tt)*) => { $crate::__html_compare_options!(@build normalize_optional_tags $($dsl)*) };
+    (ignore_block_whitespace $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_block_whitespace $($dsl)*) };
+    (turbo_streams $($dsl:tt)*) => { $crate::__html_compare_options!(@build turbo_streams $($dsl)*) };
+    (security_checks $($dsl:tt)*) => { $crate::__html_compare_options!(@build security_checks $($dsl)*) };
+    (ignore_nonces $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_nonces $($dsl)*) };
+    (integrity $($dsl:tt)*) => { $crate::__html_compare_options!(@build integrity $($dsl)*) };
+    (ignore_hidden_elements $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_hidden_elements $($dsl)*) };
+    (unordered_picture_sources $($dsl:tt)*) => { $crate::__html_compare_options!(@build unordered_picture_sources $($dsl)*) };
+    (unordered_media_sources $($dsl:tt)*) => { $crate::__html_compare_options!(@build unordered_media_sources $($dsl)*) };
+    (ignore_framework_markers $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_framework_markers $($dsl)*) };
+    (normalize_list_tightness $($dsl:tt)*) => { $crate::__html_compare_options!(@build normalize_list_tightness $($dsl)*) };
+    (ignore_code_highlighting $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_code_highlighting $($dsl)*) };
+    (exact_code_whitespace $($dsl:tt)*) => { $crate::__html_compare_options!(@build exact_code_whitespace $($dsl)*) };
+    (ignore_text_in $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_text_in $($dsl)*) };
+    (capture_placeholders $($dsl:tt)*) => { $crate::__html_compare_options!(@build capture_placeholders $($dsl)*) };
+    (tolerate_prolog_junk $($dsl:tt)*) => { $crate::__html_compare_options!(@build tolerate_prolog_junk $($dsl)*) };
+    (flatten_slots $($dsl:tt)*) => { $crate::__html_compare_options!(@build flatten_slots $($dsl)*) };
+    (decode_attribute_entities $($dsl:tt)*) => { $crate::__html_compare_options!(@build decode_attribute_entities $($dsl)*) };
+    (ignore_attribute_wrapping $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_attribute_wrapping $($dsl)*) };
+    (ignore_void_trailing_slash $($dsl:tt)*) => { $crate::__html_compare_options!(@build ignore_void_trailing_slash $($dsl)*) };
+    (strict_attribute_names $($dsl:tt)*) => { $crate::__html_compare_options!(@build strict_attribute_names $($dsl)*) };
+    (normalize_inline_js $($dsl:tt)*) => { $crate::__html_compare_options!(@build normalize_inline_js $($dsl)*) };
+    (unordered_query_params $($dsl:tt)*) => { $crate::__html_compare_options!(@build unordered_query_params $($dsl)*) };
+    (light_dom_first $($dsl:tt)*) => { $crate::__html_compare_options!(@build light_dom_first $($dsl)*) };
+    (diff_granularity $($dsl:tt)*) => { $crate::__html_compare_options!(@build diff_granularity $($dsl)*) };
+    (@build preset = $preset:expr $(, $($dsl:tt)*)?) => {{
+        #[allow(unused_mut)]
+        let mut options = $crate::presets::from_name($preset)
+            .unwrap_or_else(|| panic!("Unknown HTML comparison preset: {:?}", $preset));
+        $crate::__html_compare_options!(@apply options; $($($dsl)*)?);
+        options
+    }};
+    (@build $($dsl:tt)+) => {{
+        let mut options = $crate::default_options();
+        $crate::__html_compare_options!(@apply options; $($dsl)+);
+        options
+    }};
+    (@apply $options:ident; $(,)?) => {};
+    (@apply $options:ident; ignore_attributes($($attr:expr),* $(,)?) $(, $($rest:tt)*)?) => {
+        $($options.ignored_attributes.insert(::std::string::ToString::to_string($attr));)*
+        $crate::__html_compare_options!(@apply $options; $($($rest)*)?);
+    };
+    (@apply $options:ident; ignore_text_in($($selector:expr),* $(,)?) $(, $($rest:tt)*)?) => {
+        $($options.ignore_text_in.push(::std::string::ToString::to_string($selector));)*
+        $crate::__html_compare_options!(@apply $options; $($($rest)*)?);
+    };
+    (@apply $options:ident; $flag:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $options.$flag = $value;
+        $crate::__html_compare_options!(@apply $options; $($($rest)*)?);
+    };
+    (@apply $options:ident; $flag:ident $(, $($rest:tt)*)?) => {
+        $options.$flag = true;
+        $crate::__html_compare_options!(@apply $options; $($($rest)*)?);
+    };
+    ($options:expr $(,)?) => { $options };
+}
+
+/// Asserts that minified HTML is equivalent to the original it was produced from.
+///
+/// Uses [`presets::minified`], which tolerates what minifiers legitimately change: collapsed
+/// whitespace, omitted optional tags such as `</li>` and `</p>`, unquoted attribute values,
+/// shortened boolean attributes, reordered classes and removed comments.
+///
+/// # Examples
+/// ```ignore
+/// use html_compare::assert_minified_equivalent;
+///
+/// assert_minified_equivalent!(
+///     "<ul>\n  <li>One</li>\n  <li>Two</li>\n</ul>\n<input checked=\"checked\">",
+///     "<ul><li>One<li>Two</ul><input checked>"
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_minified_equivalent {
+    ($original:expr, $minified:expr $(,)?) => {{
+        match (&$original, &$minified) {
+            (original, minified) => {
+                let comparer = $crate::HtmlComparer::with_options($crate::presets::minified());
+                if let Err(err) = comparer.verify(original, minified) {
+                    panic!(
+                        "\n\
+                        Minified HTML is not equivalent to the original:\n\
+                        {}\n\n\
+                        original HTML:\n\
+                        {}\n\n\
+                        minified HTML:\n\
+                        {}\
+                    ",
+                        err, original, minified
+                    );
+                }
+            }
+        }
+    }};
+}
+
+/// Asserts that formatted HTML is equivalent to the original it was produced from.
+///
+/// Whitespace may be added or removed only where the [`PrettyProfile`] allows a formatter to
+/// change it; without a profile, only around block-level elements.
+///
+/// # Examples
+/// ```ignore
+/// use html_compare::{assert_prettified_equivalent, PrettyProfile};
+///
+/// assert_prettified_equivalent!(
+///     "<ul><li>One</li><li>Two</li></ul>",
+///     "<ul>\n  <li>One</li>\n  <li>Two</li>\n</ul>"
+/// );
+/// assert_prettified_equivalent!(
+///     "<p>Hello <b>world</b></p>",
+///     "<p>\n  Hello\n  <b>world</b>\n</p>",
+///     PrettyProfile { indent_inline: true, ..Default::default() }
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_prettified_equivalent {
+    ($original:expr, $pretty:expr $(,)?) => {
+        $crate::assert_prettified_equivalent!($original, $pretty, $crate::PrettyProfile::default())
+    };
+    ($original:expr, $pretty:expr, $profile:expr $(,)?) => {{
+        match (&$original, &$pretty, &$profile) {
+            (original, pretty, profile) => {
+                let comparer = $crate::HtmlComparer::with_options(profile.options());
+                if let Err(err) = comparer.verify(original, pretty) {
+                    panic!(
+                        "\n\
+                        Formatted HTML is not equivalent to the original:\n\
+                        {}\n\n\
+                        original HTML:\n\
+                        {}\n\n\
+                        formatted HTML:\n\
+                        {}\n\n\
+                        profile: {:?}\
+                    ",
+                        err, original, pretty, profile
                     );
                 }
             }
@@ -111,19 +326,495 @@ macro_rules! assert_html_ne {
     }};
 }
 
+/// Asserts that a document's `<meta>` tags carry the expected `property`/`name` to `content` pairs.
+///
+/// Only the names listed in the expectation are checked; other meta tags and the order of
+/// tags in `<head>` are ignored.
+///
+/// # Examples
+/// ```ignore
+/// use html_compare::assert_meta_eq;
+///
+/// assert_meta_eq!(
+///     r#"<head><meta property="og:title" content="Hello"><meta name="robots" content="all"></head>"#,
+///     [("og:title", "Hello")]
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_meta_eq {
+    ($html:expr, $expected:expr $(,)?) => {{
+        match &$html {
+            html => {
+                let meta = $crate::meta::extract_meta(html);
+                if let Err(err) = meta.check($expected) {
+                    panic!(
+                        "\n\
+                        Meta tag comparison failed:\n\
+                        {}\n\n\
+                        meta tags:\n\
+                        {}\n\
+                        HTML:\n\
+                        {}\
+                    ",
+                        err, meta, html
+                    );
+                }
+            }
+        }
+    }};
+}
+
+/// Asserts that a template renders to HTML equivalent to the expected HTML.
+///
+/// The template is anything implementing [`templates::RenderTemplate`]: a
+/// `(&tera::Tera, name)` pair with the `tera` feature, an `askama::Template`
+/// (with `()` as the context) with the `askama` feature, or a closure wrapped
+/// in [`templates::from_fn`]. Options accept the same shorthand as
+/// [`assert_html_eq!`]. A mismatch panics with a [`HtmlComparer::pretty_diff`]
+/// of the expected and rendered HTML.
+///
+/// # Examples
+/// ```ignore
+/// use html_compare::assert_template_renders;
+///
+/// let mut tera = tera::Tera::default();
+/// tera.add_raw_template("hello.html", "<p>Hello {{ name }}</p>")?;
+/// let mut context = tera::Context::new();
+/// context.insert("name", "world");
+/// assert_template_renders!((&tera, "hello.html"), context, "<p>Hello world</p>");
+///
+/// #[derive(askama::Template)]
+/// #[template(source = "<p class='{{ class }}'>Hi</p>", ext = "html")]
+/// struct Greeting<'a> { class: &'a str }
+/// assert_template_renders!(Greeting { class: "a" }, (), "<p>Hi</p>", ignore_attributes("class"));
+/// ```
+#[macro_export]
+macro_rules! assert_template_renders {
+    ($template:expr, $context:expr, $expected:expr $(,)?) => {
+        $crate::assert_template_renders!($template, $context, $expected, $crate::default_options())
+    };
+    ($template:expr, $context:expr, $expected:expr, $($options:tt)+) => {{
+        match (&$template, &$context, &$expected, &$crate::__html_compare_options!($($options)+)) {
+            (template, context, expected, options) => {
+                let rendered =
+                    match $crate::templates::RenderTemplate::render_template(template, context) {
+                        Ok(rendered) => rendered,
+                        Err(err) => panic!("\nTemplate failed to render:\n{}", err),
+                    };
+                let comparer = $crate::HtmlComparer::with_options(options.clone());
+                if let Err(err) = comparer.verify(expected, &rendered) {
+                    let artifacts = $crate::artifacts::__note(comparer.dump_failure(expected, &rendered));
+                    if let Some(report) = comparer.failure_report(expected, &rendered) {
+                        panic!("{}{}", report, artifacts);
+                    }
+                    panic!(
+                        "\n\
+                        Rendered template does not match:\n\
+                        {}\n\n\
+                        {}\n\n\
+                        rendered HTML:\n\
+                        {}\n\n\
+                        options: {:#?}{}\
+                    ",
+                        err,
+                        comparer.pretty_diff(expected, &rendered, 3),
+                        rendered,
+                        options,
+                        artifacts
+                    );
+                }
+            }
+        }
+    }};
+}
+
+pub mod aggregate;
+pub mod alternatives;
+pub mod artifacts;
+pub mod assertions;
+pub mod assets;
+pub mod attribute_content;
+mod attribute_names;
+pub mod cache;
+pub mod captures;
+pub mod classes;
+mod code;
+#[cfg(feature = "collation")]
+pub mod collation;
+pub mod components;
+pub mod csp;
+mod defaults;
+#[cfg(feature = "devserver")]
+mod devserver;
+pub mod diff;
+pub mod documents;
+pub mod dom;
+pub mod edit;
+pub mod encoding;
+mod entities;
+pub mod equality;
+pub mod error_format;
+pub mod feed;
+pub mod format;
+pub mod forms;
+pub mod frameworks;
+pub mod granularity;
+mod hidden;
+mod highlight;
+#[cfg(feature = "http")]
+pub mod http;
+mod ignored_elements;
+mod ignored_text;
+mod inline_js;
+pub mod json;
+pub mod links;
+pub mod lint;
+pub mod markdown;
+pub mod markup;
+pub mod matchers;
+mod mathml;
+pub mod mdbook;
+mod media;
+pub mod meta;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+mod minify;
+mod node_map;
+mod optional_tags;
+pub mod parser;
+mod patch;
+mod path_glob;
+pub mod pretty;
+pub mod profile;
+mod prolog;
+mod query_params;
+mod root;
+mod roundtrip;
+pub mod sanitization;
+mod scoped_attributes;
+pub mod security;
+mod slots;
+pub mod sorting;
+mod sources;
+pub mod ssg;
+pub mod stats;
+pub mod structured_data;
+pub mod templates;
+pub mod tokens;
+mod unified;
+pub mod validator;
+pub mod visit;
+mod void_elements;
+pub mod wasm;
+pub mod wire;
+mod wrapping;
+
+pub use aggregate::DiffSummary;
+pub use assertions::HtmlAssertions;
+pub use assets::AssetResolver;
+pub use attribute_content::AttributeContent;
+pub use cache::ComparisonCache;
+pub use captures::Captures;
+pub use classes::ClassList;
+#[cfg(feature = "collation")]
+pub use collation::{Collation, CollationStrength};
+pub use csp::IntegrityMode;
+pub use diff::{Difference, HtmlDiff};
+pub use documents::DocumentsDiff;
+pub use edit::Edit;
+pub use encoding::InvalidUtf8;
+pub use equality::{attributes_equal, element_shallow_equal, text_equal};
+pub use error_format::ErrorFormatter;
+pub use format::{format_html, FormatOptions};
+pub use frameworks::FrameworkMarkers;
+pub use granularity::DiffGranularity;
+pub use lint::LintRule;
+pub use markdown::MarkdownProfile;
+pub use markup::RenderToHtml;
+pub use matchers::Matcher;
+pub use parser::{HtmlParserBackend, Parser};
+pub use pretty::PrettyProfile;
+pub use profile::{NodeHook, Profiler};
+pub use sanitization::{explain_sanitization, SanitizationCategory, SanitizationReport};
+pub use sorting::SortKey;
+pub use stats::DocumentStats;
+pub use tokens::TokenRule;
+pub use validator::{HtmlValidator, Validation, ValidatorLimits};
+pub use visit::{walk, HtmlVisitor, VisitedElement};
+
+pub use defaults::{
+    default_options, reset_default_options, set_default_options, DefaultOptionsGuard,
+};
+
+use components::ComponentRule;
 use ego_tree::NodeRef;
-use scraper::{ElementRef, Html, Node};
-use std::collections::HashSet;
+use scoped_attributes::ScopedAttributes;
+use scraper::{ElementRef, Html, Node, Selector};
+use smallvec::SmallVec;
+use sorting::SortRule;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
 use thiserror::Error;
 
+/// Why a comparison failed
+///
+/// The mismatches found while walking the documents carry the path of the
+/// expected node, e.g. `/html/body/ul/li[2]`, and what was expected and
+/// found there.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum HtmlCompareError {
-    #[error("Node mismatch: {0}")]
-    NodeMismatch(String),
+    #[error("Node mismatch: Tag name mismatch. Expected: {expected}, Actual: {actual}")]
+    #[non_exhaustive]
+    TagMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+    /// The compared attributes of the two elements, as name and value
+    #[error("Node mismatch: Attributes mismatch. Expected: {expected:?}, Actual: {actual:?}")]
+    #[non_exhaustive]
+    AttributeMismatch {
+        path: String,
+        expected: BTreeSet<(String, String)>,
+        actual: BTreeSet<(String, String)>,
+    },
+    /// `position` is the index of the text node among the compared children
+    /// of its parent
+    #[error("Node mismatch: Text content mismatch at position {position}. Expected: '{expected}', Actual: '{actual}'")]
+    #[non_exhaustive]
+    TextMismatch {
+        path: String,
+        position: usize,
+        expected: String,
+        actual: String,
+    },
+    /// Text that differs inside `<pre>`; without a position, the whole text
+    /// of a highlighted code block
+    #[error(
+        "Node mismatch: Code block mismatch{}, {}",
+        .position.map(|position| format!(" at position {}", position)).unwrap_or_default(),
+        code::describe_difference(.expected, .actual)
+    )]
+    #[non_exhaustive]
+    CodeMismatch {
+        path: String,
+        position: Option<usize>,
+        expected: String,
+        actual: String,
+    },
+    #[error("Node mismatch: Comment content mismatch at position {position}. Expected: '{expected}', Actual: '{actual}'")]
+    #[non_exhaustive]
+    CommentMismatch {
+        path: String,
+        position: usize,
+        expected: String,
+        actual: String,
+    },
+    /// A placeholder that captured `expected` earlier and would capture
+    /// `actual` here
+    #[error("Node mismatch: Placeholder {{{{{name}}}}} captured '{expected}' before, Actual: '{actual}'")]
+    #[non_exhaustive]
+    CaptureMismatch {
+        path: String,
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Node mismatch: {kind}")]
+    #[non_exhaustive]
+    StructureMismatch {
+        path: String,
+        kind: StructureMismatchKind,
+    },
+    /// A void element written with a trailing slash in one document and
+    /// without in the other, with
+    /// [`HtmlCompareOptions::ignore_void_trailing_slash`] off
+    #[error("Node mismatch: Void element syntax mismatch. Expected: {expected}, Actual: {actual}")]
+    #[non_exhaustive]
+    VoidSyntaxMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+    /// An attribute name of either document that only a parser oddity can
+    /// produce, e.g. `{{class}}`, with
+    /// [`HtmlCompareOptions::strict_attribute_names`] on
+    #[error("Invalid attribute name {name:?} at {path} in the {document} document")]
+    InvalidAttributeName {
+        document: &'static str,
+        path: String,
+        name: String,
+    },
     #[error("Missing expected node: {expected} at position {position}")]
     MissingNode { expected: String, position: usize },
     #[error("Extra node found: {found} at position {position}")]
     ExtraNode { found: String, position: usize },
+    #[error("Structured data mismatch: {0}")]
+    StructuredDataMismatch(String),
+    #[error("Invalid JSON-LD: {0}")]
+    InvalidJsonLd(String),
+    #[error("Meta tag mismatch for {name}: expected {expected:?}, actual {actual:?}")]
+    MetaMismatch {
+        name: String,
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    #[error("Feed mismatch: {0}")]
+    FeedMismatch(String),
+    #[error("Link mismatch: {0}")]
+    LinkMismatch(String),
+    #[error("Form mismatch: {0}")]
+    FormMismatch(String),
+    #[error("Invalid selector: {0}")]
+    InvalidSelector(String),
+    #[error("Invalid options: {0}")]
+    InvalidOptions(String),
+    #[error("No element matches the root selector `{selector}` in the {document} document")]
+    RootNotFound {
+        selector: String,
+        document: &'static str,
+    },
+    #[error("{}", .0.summary())]
+    Differences(HtmlDiff),
+    #[error("Broken reference: {0}")]
+    BrokenReference(String),
+    #[error("Cannot apply edit: {0}")]
+    InvalidEdit(String),
+    #[error("{}", .0.summary())]
+    DocumentDifferences(DocumentsDiff),
+    #[error("Invalid document stream: {0}")]
+    InvalidDocumentStream(String),
+    #[error("Security risk: {0}")]
+    SecurityRisk(String),
+    #[error("Fetch failed: {0}")]
+    Fetch(String),
+    #[error("Response mismatch: {0}")]
+    ResponseMismatch(String),
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+    /// With [`InvalidUtf8::Error`], the first invalid byte of a document
+    /// given as bytes
+    #[error("Invalid UTF-8 at byte {offset} of the {document} document")]
+    InvalidUtf8 {
+        document: &'static str,
+        offset: usize,
+    },
+    #[error("Input of {size} bytes exceeds the limit of {limit} bytes")]
+    InputTooLarge { size: usize, limit: usize },
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+/// How the children of two elements fail to line up
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StructureMismatchKind {
+    ChildCount {
+        expected: usize,
+        actual: usize,
+    },
+    NodeType {
+        position: usize,
+        expected: &'static str,
+        actual: &'static str,
+    },
+    /// With [`HtmlCompareOptions::ignore_sibling_order`], no actual child
+    /// matches the expected one
+    Unmatched {
+        node: String,
+    },
+    ShadowRoot {
+        element: String,
+        expected: bool,
+        actual: bool,
+    },
+}
+
+impl std::fmt::Display for StructureMismatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StructureMismatchKind::ChildCount { expected, actual } => write!(
+                f,
+                "Child count mismatch. Expected: {}, Actual: {}",
+                expected, actual
+            ),
+            StructureMismatchKind::NodeType {
+                position,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Node type mismatch at position {}. Expected type: {:?}, Actual type: {:?}",
+                position, expected, actual
+            ),
+            StructureMismatchKind::Unmatched { node } => {
+                write!(f, "No matching node found for {}", node)
+            }
+            StructureMismatchKind::ShadowRoot {
+                element,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Shadow root mismatch on <{}>. Expected shadow root: {}, Actual shadow root: {}",
+                element, expected, actual
+            ),
+        }
+    }
+}
+
+impl HtmlCompareError {
+    /// Path of the expected node a mismatch was found at
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            HtmlCompareError::TagMismatch { path, .. }
+            | HtmlCompareError::AttributeMismatch { path, .. }
+            | HtmlCompareError::TextMismatch { path, .. }
+            | HtmlCompareError::CodeMismatch { path, .. }
+            | HtmlCompareError::CommentMismatch { path, .. }
+            | HtmlCompareError::CaptureMismatch { path, .. }
+            | HtmlCompareError::StructureMismatch { path, .. }
+            | HtmlCompareError::VoidSyntaxMismatch { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Whether the error reports a difference between the documents, rather
+    /// than a comparison that couldn't be made
+    pub fn is_mismatch(&self) -> bool {
+        !matches!(
+            self,
+            HtmlCompareError::InvalidJsonLd(_)
+                | HtmlCompareError::InvalidSelector(_)
+                | HtmlCompareError::InvalidOptions(_)
+                | HtmlCompareError::InvalidAttributeName { .. }
+                | HtmlCompareError::InvalidEdit(_)
+                | HtmlCompareError::InvalidDocumentStream(_)
+                | HtmlCompareError::Fetch(_)
+                | HtmlCompareError::InvalidResponse(_)
+                | HtmlCompareError::InvalidUtf8 { .. }
+                | HtmlCompareError::InputTooLarge { .. }
+                | HtmlCompareError::Internal(_)
+        )
+    }
+
+    /// The mismatch with its path set to `at`
+    pub(crate) fn at(mut self, at: String) -> Self {
+        if let HtmlCompareError::TagMismatch { path, .. }
+        | HtmlCompareError::AttributeMismatch { path, .. }
+        | HtmlCompareError::TextMismatch { path, .. }
+        | HtmlCompareError::CodeMismatch { path, .. }
+        | HtmlCompareError::CommentMismatch { path, .. }
+        | HtmlCompareError::CaptureMismatch { path, .. }
+        | HtmlCompareError::StructureMismatch { path, .. }
+        | HtmlCompareError::VoidSyntaxMismatch { path, .. } = &mut self
+        {
+            *path = at;
+        }
+        self
+    }
 }
 
 /// Configuration for HTML comparison
@@ -143,6 +834,223 @@ pub struct HtmlCompareOptions {
     pub ignore_sibling_order: bool,
     /// Ignore contents of <style> blocks
     pub ignore_style_contents: bool,
+    /// Collect all differences instead of stopping at the first one; `compare`
+    /// then fails with a summarized [`HtmlCompareError::Differences`]
+    pub exhaustive: bool,
+    /// Report `src`/`href` targets of the actual document that the resolver
+    /// cannot find
+    pub asset_resolver: Option<AssetResolver>,
+    /// Warn about classes of the actual document missing from this list (see
+    /// [`HtmlDiff::warnings`])
+    pub known_classes: Option<ClassList>,
+    /// How declarative shadow roots (`<template shadowrootmode>`) are compared
+    pub shadow_dom: ShadowDomMode,
+    /// Ignore incidental MathML structure inside `<math>`: namespace
+    /// prefixes, redundant `<mrow>` wrappers, invisible operators and
+    /// whitespace in token elements
+    pub mathml: bool,
+    /// Sort the children of `<head>` by element kind, `name`/`property`/`rel`
+    /// and URL before comparing, since their order is rarely meaningful
+    pub normalize_head_order: bool,
+    /// Sort the children of containers matching a selector by a key before
+    /// comparing them; the first matching entry applies. An invalid selector
+    /// makes `compare` fail with [`HtmlCompareError::InvalidSelector`]
+    pub sort_children: Vec<(String, SortKey)>,
+    /// Pairs of tags that compare as the same tag, e.g. `("b", "strong")`
+    pub equivalent_tags: Vec<(String, String)>,
+    /// Treat the presentational and semantic spellings in
+    /// [`PRESENTATIONAL_EQUIVALENTS`] as the same tag; add further pairs with
+    /// `equivalent_tags`
+    pub treat_presentational_tags_as_equivalent: bool,
+    /// Compare headings by their level below the document's topmost heading
+    /// rather than by `h1`...`h6`, so a component rendered from `<h2>` down
+    /// matches the same component rendered from `<h3>` down
+    pub relative_heading_levels: bool,
+    /// Attributes for which an empty value, e.g. `alt=""`, compares equal to
+    /// the attribute being absent
+    pub empty_attribute_equals_missing: HashSet<String>,
+    /// Attributes whose values are token lists, compared token by token (see
+    /// [`tokens::html_token_lists`] for the standard ones)
+    pub tokenized_attributes: HashMap<String, TokenRule>,
+    /// Attributes whose values are JSON, HTML, CSS or a custom language,
+    /// parsed and compared in a canonical form instead of as strings
+    pub attribute_content: HashMap<String, AttributeContent>,
+    /// Parse `media` and `sizes` attributes and compare their media
+    /// conditions, ignoring case, whitespace, the order of `and`-joined
+    /// features and the order of the queries in a `media` list
+    pub compare_media_queries: bool,
+    /// Collapse runs of whitespace inside text into a single space, except
+    /// within `<pre>` and `<textarea>`
+    pub collapse_whitespace: bool,
+    /// Compare boolean attributes such as `disabled` by presence only, so
+    /// `disabled="disabled"` matches `disabled`
+    pub normalize_boolean_attributes: bool,
+    /// Drop the whitespace that moves into or out of an element depending on
+    /// whether its optional end tag (`</li>`, `</p>`, ...) is written, so
+    /// omitted end tags also compare equal when whitespace is significant
+    pub normalize_optional_tags: bool,
+    /// Ignore whitespace where the browser does not render it: between
+    /// block-level elements and at the edges of their content, outside
+    /// `<pre>` and `<textarea>` (see [`PrettyProfile`])
+    pub ignore_block_whitespace: bool,
+    /// Ignore every attribute whose name starts with one of these prefixes,
+    /// e.g. `hx-` (see [`wire::HTMX_ATTRIBUTE_PREFIXES`])
+    pub ignored_attribute_prefixes: Vec<String>,
+    /// Compare `<turbo-stream>` elements by their action, target and
+    /// `<template>` content only
+    pub turbo_streams: bool,
+    /// Elements that start a component, as `(selector, attribute)` pairs
+    /// where the attribute names the component, e.g. `("[data-component]",
+    /// "data-component")`; differences are then grouped by component (see
+    /// [`HtmlDiff::by_component`])
+    pub component_boundaries: Vec<(String, String)>,
+    /// Checks run on the actual document, reported as warnings (see
+    /// [`lint`])
+    pub lint_rules: Vec<LintRule>,
+    /// Report scripts, event handlers, `javascript:` URLs, unsafe
+    /// `target=_blank` links and form actions that the actual document
+    /// introduces, regardless of the options that filter attributes
+    pub security_checks: bool,
+    /// Leave CSP `nonce` attributes out of the comparison, so that fresh
+    /// nonces and pages rendered without a policy compare equal
+    pub ignore_nonces: bool,
+    /// How `integrity` attribute values are compared (see [`IntegrityMode`])
+    pub integrity: IntegrityMode,
+    /// Leave out elements with a `hidden` attribute, `aria-hidden="true"`,
+    /// hidden inputs and elements styled `display: none` inline
+    pub ignore_hidden_elements: bool,
+    /// Match the `<source>` elements of a `<picture>` by media and type
+    /// instead of position, and compare their `srcset` candidates as sets
+    pub unordered_picture_sources: bool,
+    /// Match the `<source>` and `<track>` elements of `<video>` and
+    /// `<audio>` by type and `src`, and kind and language, instead of
+    /// position, and compare flags like `muted` as boolean attributes
+    pub unordered_media_sources: bool,
+    /// Called after each pair of elements compared by [`HtmlComparer::diff`]
+    /// (see [`profile::Profiler`])
+    pub on_node_compared: Option<NodeHook>,
+    /// The parser that builds the compared trees (see [`parser`])
+    pub parser: Parser,
+    /// Strip the hydration markers of an SSR framework before comparing (see
+    /// [`frameworks`])
+    pub ignore_framework_markers: FrameworkMarkers,
+    /// Tolerate the differences between Markdown renderers (see
+    /// [`markdown`])
+    pub markdown: Option<MarkdownProfile>,
+    /// Treat `<li><p>x</p></li>` as `<li>x</li>` when the paragraph is the
+    /// only child of the list item, as renderers disagree on loose lists
+    pub normalize_list_tightness: bool,
+    /// Compare `<pre>` code blocks by their text only, ignoring the markup
+    /// added by syntax highlighters
+    pub ignore_code_highlighting: bool,
+    /// Compare text inside `<pre>` exactly, whatever the whitespace options,
+    /// and report mismatches with visible whitespace
+    pub exact_code_whitespace: bool,
+    /// Remember successful comparisons (see [`cache`])
+    pub cache: Option<ComparisonCache>,
+    /// Ignore the text inside elements matching any of these selectors, e.g.
+    /// `.timestamp`, and compare all other text (see [`ignored_text`])
+    pub ignore_text_in: Vec<String>,
+    /// Attributes to ignore only on elements matching a selector, e.g.
+    /// `style` on `.chart svg *` (see [`scoped_attributes`])
+    pub ignored_attributes_for: Vec<(String, HashSet<String>)>,
+    /// Strip byte order marks, `<?xml ...?>` declarations, whitespace and
+    /// comments before the doctype, which export tools add (see [`prolog`])
+    pub tolerate_prolog_junk: bool,
+    /// Compare only the first element matching this selector in each
+    /// document, e.g. `main#content` (see [`root`])
+    pub root_selector: Option<String>,
+    /// Let `{{name}}` in expected attribute values and text match any value,
+    /// which must be the same everywhere the name appears (see [`captures`])
+    pub capture_placeholders: bool,
+    /// Check the text or an attribute of elements matching a selector with a
+    /// [`Matcher`] instead of comparing it (see [`matchers`])
+    pub matchers: Vec<(String, Matcher)>,
+    /// How deep the differences reported by [`HtmlComparer::diff`] go (see
+    /// [`granularity`])
+    pub diff_granularity: DiffGranularity,
+    /// Render the panic message of failed assertions (see [`error_format`])
+    pub error_formatter: Option<ErrorFormatter>,
+    /// Directory that failed assertions write both documents and their
+    /// differences to (see [`artifacts`])
+    pub dump_on_failure: Option<PathBuf>,
+    /// Compare the light children of a shadow host before its shadow tree in
+    /// [`ShadowDomMode::Separate`], and splice the shadow tree after them
+    /// rather than where the `<template>` appears in
+    /// [`ShadowDomMode::Flatten`]
+    pub light_dom_first: bool,
+    /// In [`ShadowDomMode::Composed`], replace `<slot>` elements by the
+    /// nodes they render and ignore `slot` attributes, to compare a
+    /// component against a flat expected tree (see [`slots`])
+    pub flatten_slots: bool,
+    /// Attributes listed first, in this order, in [`HtmlComparer::pretty_diff`],
+    /// [`HtmlComparer::diff`] and other output; the others follow in order of
+    /// their names. Comparison ignores attribute order regardless
+    pub attribute_order: Vec<String>,
+    /// What [`HtmlComparer::verify_bytes`] does with invalid UTF-8 (see
+    /// [`encoding`])
+    pub invalid_utf8: InvalidUtf8,
+    /// Decode character references left in attribute values after parsing,
+    /// so that values escaped twice compare equal to values escaped once
+    /// (see [`entities`])
+    pub decode_attribute_entities: bool,
+    /// Treat line breaks in the values of attributes such as `class` and
+    /// `style`, left by formatters wrapping long start tags, as single
+    /// spaces (see [`wrapping`])
+    pub ignore_attribute_wrapping: bool,
+    /// Let `<br>` compare equal to `<br/>`; when off, the start tags of void
+    /// elements must agree on the trailing slash (see [`void_elements`])
+    pub ignore_void_trailing_slash: bool,
+    /// Leave out elements matching any of these selectors, with everything
+    /// inside them, e.g. `.copy-button` (see [`ignored_elements`])
+    pub ignore_elements: Vec<String>,
+    /// Compare elements matching any of these selectors as their children,
+    /// e.g. `div.highlight` around code blocks (see [`ignored_elements`])
+    pub unwrap_elements: Vec<String>,
+    /// Fail on attribute names that only a parser oddity can produce, such
+    /// as leftover template syntax like `{{class}}`, in either document,
+    /// instead of reporting the attribute sets as different
+    pub strict_attribute_names: bool,
+    /// Compare the values of `on*` event handler attributes as JavaScript
+    /// tokens, ignoring whitespace, comments and statement-ending
+    /// semicolons (see [`inline_js`])
+    pub normalize_inline_js: bool,
+    /// Compare the query parameters of URL attributes such as `href` and
+    /// `src` decoded and in any order (see [`query_params`])
+    pub unordered_query_params: bool,
+    /// Query parameters left out of URL attributes, e.g. the `v` of cache
+    /// busters (see [`query_params`])
+    pub ignored_query_params: HashSet<String>,
+    /// Let text nodes compare equal when the collation of a locale sorts
+    /// them as equal, e.g. ignoring accents (see [`collation`])
+    #[cfg(feature = "collation")]
+    pub text_collation: Option<Collation>,
+    /// Largest body, in bytes, that a compressed response may decompress
+    /// to before it is rejected (see [`http`])
+    #[cfg(feature = "http")]
+    pub max_decompressed_size: usize,
+}
+
+/// Tags that editors and renderers use interchangeably, enabled by
+/// [`HtmlCompareOptions::treat_presentational_tags_as_equivalent`]
+pub const PRESENTATIONAL_EQUIVALENTS: &[(&str, &str)] =
+    &[("b", "strong"), ("i", "em"), ("s", "del")];
+
+/// Comparison of declarative shadow DOM
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShadowDomMode {
+    /// Compare an element's shadow tree and its light children separately,
+    /// so the position of the `<template>` among the light children doesn't
+    /// matter
+    #[default]
+    Separate,
+    /// Splice the shadow tree's contents into the light tree where the
+    /// `<template>` appears and compare the result as one tree
+    Flatten,
+    /// Compare the composed tree: a host's shadow tree in place of its light
+    /// children, with each light child inside the `<slot>` it is assigned to
+    /// (see [`slots`])
+    Composed,
 }
 
 impl Default for HtmlCompareOptions {
@@ -155,10 +1063,113 @@ impl Default for HtmlCompareOptions {
             ignore_comments: true,
             ignore_sibling_order: false,
             ignore_style_contents: false,
+            exhaustive: false,
+            asset_resolver: None,
+            known_classes: None,
+            shadow_dom: ShadowDomMode::Separate,
+            mathml: false,
+            normalize_head_order: false,
+            sort_children: Vec::new(),
+            equivalent_tags: Vec::new(),
+            treat_presentational_tags_as_equivalent: false,
+            relative_heading_levels: false,
+            empty_attribute_equals_missing: HashSet::new(),
+            tokenized_attributes: HashMap::new(),
+            compare_media_queries: false,
+            collapse_whitespace: false,
+            normalize_boolean_attributes: false,
+            normalize_optional_tags: false,
+            ignore_block_whitespace: false,
+            ignored_attribute_prefixes: Vec::new(),
+            turbo_streams: false,
+            component_boundaries: Vec::new(),
+            lint_rules: Vec::new(),
+            security_checks: false,
+            ignore_nonces: false,
+            integrity: IntegrityMode::Exact,
+            ignore_hidden_elements: false,
+            unordered_picture_sources: false,
+            unordered_media_sources: false,
+            on_node_compared: None,
+            parser: Parser::default(),
+            ignore_framework_markers: FrameworkMarkers::None,
+            markdown: None,
+            normalize_list_tightness: false,
+            ignore_code_highlighting: false,
+            exact_code_whitespace: false,
+            cache: None,
+            ignore_text_in: Vec::new(),
+            ignored_attributes_for: Vec::new(),
+            attribute_content: HashMap::new(),
+            tolerate_prolog_junk: false,
+            root_selector: None,
+            capture_placeholders: false,
+            matchers: Vec::new(),
+            diff_granularity: DiffGranularity::Node,
+            error_formatter: None,
+            dump_on_failure: None,
+            light_dom_first: false,
+            flatten_slots: false,
+            attribute_order: format::default_attribute_order(),
+            invalid_utf8: InvalidUtf8::Replace,
+            decode_attribute_entities: false,
+            ignore_attribute_wrapping: true,
+            ignore_void_trailing_slash: true,
+            strict_attribute_names: false,
+            normalize_inline_js: false,
+            unordered_query_params: false,
+            ignored_query_params: HashSet::new(),
+            #[cfg(feature = "collation")]
+            text_collation: None,
+            #[cfg(feature = "http")]
+            max_decompressed_size: http::MAX_DECOMPRESSED_SIZE,
+            ignore_elements: Vec::new(),
+            unwrap_elements: Vec::new(),
         }
     }
 }
 
+const HEADINGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// The level of a heading tag, counting from 0 for `h1`
+fn heading_level(name: &str) -> Option<usize> {
+    HEADINGS.iter().position(|heading| *heading == name)
+}
+
+/// The level of the topmost heading in the document of `node`
+fn top_heading_level(node: NodeRef<Node>) -> usize {
+    node.tree()
+        .root()
+        .descendants()
+        .filter_map(|node| match node.value() {
+            Node::Element(element) => heading_level(element.name()),
+            _ => None,
+        })
+        .min()
+        .unwrap_or(0)
+}
+
+/// Canonical sort key of a `<head>` child: element kind, then what it
+/// describes, then what it points to
+fn head_order_key(node: &NodeRef<Node>) -> (String, String, String) {
+    let Node::Element(element) = node.value() else {
+        return Default::default();
+    };
+    let describes = ["name", "property", "http-equiv", "charset", "rel"]
+        .iter()
+        .find_map(|attr| element.attr(attr))
+        .unwrap_or_default();
+    let points_to = ["href", "src", "content"]
+        .iter()
+        .find_map(|attr| element.attr(attr))
+        .unwrap_or_default();
+    (
+        element.name().to_string(),
+        describes.to_string(),
+        points_to.to_string(),
+    )
+}
+
 fn node_type_name(node: &Node) -> &'static str {
     match node {
         Node::Text(_) => "Text",
@@ -170,10 +1181,74 @@ fn node_type_name(node: &Node) -> &'static str {
         Node::Fragment => "Fragment",
     }
 }
+/// The compared children of an element, kept inline up to the number most
+/// elements have
+pub(crate) type ChildNodes<'a> = SmallVec<[NodeRef<'a, Node>; 8]>;
+
+/// The compared attributes of an element with their normalized values, kept
+/// inline like [`ChildNodes`]
+pub(crate) type Attributes<'a> = SmallVec<[(&'a str, Cow<'a, str>); 4]>;
+
+/// The level of the topmost heading in each of the documents compared, which
+/// [`HtmlCompareOptions::relative_heading_levels`] counts heading levels from
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct HeadingLevels {
+    pub(crate) expected: usize,
+    pub(crate) actual: usize,
+}
+
+/// The comparison of the children of two elements, as one entry of the
+/// explicit stack [`HtmlComparer::compare_element_refs`] walks the trees with
+struct Frame<'a> {
+    /// The expected element whose children are compared
+    element: ElementRef<'a>,
+    expected: ChildNodes<'a>,
+    actual: ChildNodes<'a>,
+    /// Declarative shadow roots still to compare, before the children or,
+    /// with `light_dom_first`, after them
+    shadow_roots: Option<(ElementRef<'a>, ElementRef<'a>)>,
+    /// Whether the child counts have been checked
+    started: bool,
+    /// What the pair of elements last handed out was
+    waiting: Option<Waiting>,
+    /// Index of the expected child being compared
+    position: usize,
+    /// With unordered siblings, index of the actual child being tried
+    candidate: usize,
+    /// With unordered siblings, which actual children are taken
+    matched: Vec<bool>,
+    /// The captures to roll back to when the actual child being tried turns
+    /// out not to match
+    mark: usize,
+}
+
+enum Waiting {
+    ShadowRoot,
+    Child,
+}
+
+/// What a [`Frame`] needs next
+enum Step<'a> {
+    Compare(ElementRef<'a>, ElementRef<'a>),
+    Done(Result<(), Mismatch<'a>>),
+}
+
+/// A difference found while walking the trees, with the expected node it
+/// is at; the error's path is filled in once the walk is over
+type Mismatch<'a> = Box<(NodeRef<'a, Node>, HtmlCompareError)>;
+
 /// Main struct for comparing HTML
 #[derive(Debug)]
 pub struct HtmlComparer {
     options: HtmlCompareOptions,
+    sort_rules: Result<Vec<SortRule>, String>,
+    component_rules: Result<Vec<ComponentRule>, String>,
+    ignored_text_selectors: Result<Vec<Selector>, String>,
+    ignored_element_selectors: Result<Vec<Selector>, String>,
+    unwrapped_element_selectors: Result<Vec<Selector>, String>,
+    scoped_attributes: Result<Vec<ScopedAttributes>, String>,
+    root_selector: Result<Option<Selector>, String>,
+    matchers: Result<Vec<(Selector, Matcher)>, String>,
 }
 
 impl Default for HtmlComparer {
@@ -191,245 +1266,901 @@ impl HtmlComparer {
     /// - Setting `ignore_whitespace: false` only affects element whitespace, not text content
     /// - Special elements like <pre> and attributes like xml:space are treated the same as regular elements
     pub fn new() -> Self {
-        Self {
-            options: HtmlCompareOptions::default(),
-        }
+        Self::with_options(HtmlCompareOptions::default())
     }
 
     /// Create a new HTML comparer with custom options
     pub fn with_options(options: HtmlCompareOptions) -> Self {
-        Self { options }
+        let sort_rules = sorting::compile_sort_rules(&options.sort_children);
+        let component_rules = components::compile_component_rules(&options.component_boundaries);
+        let ignored_text_selectors = ignored_text::compile_selectors(&options.ignore_text_in);
+        let ignored_element_selectors = ignored_text::compile_selectors(&options.ignore_elements);
+        let unwrapped_element_selectors = ignored_text::compile_selectors(&options.unwrap_elements);
+        let scoped_attributes =
+            scoped_attributes::compile_scoped_attributes(&options.ignored_attributes_for);
+        let root_selector = root::compile_root_selector(options.root_selector.as_deref());
+        let matchers = matchers::compile_matchers(&options.matchers);
+        Self {
+            options,
+            sort_rules,
+            component_rules,
+            ignored_text_selectors,
+            ignored_element_selectors,
+            unwrapped_element_selectors,
+            scoped_attributes,
+            root_selector,
+            matchers,
+        }
+    }
+
+    /// Parse `html` with the configured [`HtmlCompareOptions::parser`]
+    pub(crate) fn parse(&self, html: &str) -> Html {
+        let html = match self.options.tolerate_prolog_junk {
+            true => prolog::strip_prolog(html),
+            false => html,
+        };
+        let html = self.options.ignore_framework_markers.strip_comments(html);
+        self.options.parser.parse_document(&html)
     }
 
     /// Compare two HTML strings
+    ///
+    /// Never returns `Ok(false)`: a difference is an error. Use
+    /// [`Self::verify`], or [`Self::is_equal`] for a plain answer.
+    #[deprecated(note = "use `verify` to fail on differences, or `is_equal` to get a bool")]
     pub fn compare(&self, expected: &str, actual: &str) -> Result<bool, HtmlCompareError> {
-        let expected_doc = Html::parse_document(expected);
-        let actual_doc = Html::parse_document(actual);
+        self.verify(expected, actual).map(|()| true)
+    }
+
+    /// Check that two HTML strings are equivalent, returning the first
+    /// difference found as an error
+    ///
+    /// Never panics (see [Robustness](crate#robustness)).
+    pub fn verify(&self, expected: &str, actual: &str) -> Result<(), HtmlCompareError> {
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let result = match &self.options.cache {
+            Some(cache) => self.verify_cached(cache, expected, actual),
+            None => self.verify_uncached(expected, actual),
+        };
+        #[cfg(feature = "metrics")]
+        metrics::record_comparison(&result, started.elapsed());
+        result
+    }
+
+    /// Whether two HTML strings are equivalent
+    ///
+    /// Differences give `Ok(false)`; errors are left for comparisons that
+    /// can't be made at all, like an invalid selector in the options (see
+    /// [`HtmlCompareError::is_mismatch`]).
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let comparer = HtmlComparer::new();
+    /// assert!(comparer.is_equal("<p>Hi</p>", "<p> Hi </p>")?);
+    /// assert!(!comparer.is_equal("<p>Hi</p>", "<p>Bye</p>")?);
+    /// ```
+    pub fn is_equal(&self, expected: &str, actual: &str) -> Result<bool, HtmlCompareError> {
+        match self.verify(expected, actual) {
+            Ok(()) => Ok(true),
+            Err(err) if err.is_mismatch() => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Check that two HTML strings are equivalent, returning the values
+    /// captured by `{{name}}` placeholders in `expected` (see
+    /// [`HtmlCompareOptions::capture_placeholders`])
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let captures = comparer.capture(r#"<p id="{{id}}">Hi</p>"#, r#"<p id="p-81">Hi</p>"#)?;
+    /// assert_eq!(captures.get("id"), Some("p-81"));
+    /// ```
+    pub fn capture(&self, expected: &str, actual: &str) -> Result<Captures, HtmlCompareError> {
+        let mut captures = Captures::default();
+        self.verify_capturing(expected, actual, &mut captures)?;
+        Ok(captures)
+    }
+
+    pub(crate) fn verify_uncached(
+        &self,
+        expected: &str,
+        actual: &str,
+    ) -> Result<(), HtmlCompareError> {
+        self.verify_capturing(expected, actual, &mut Captures::default())
+    }
 
-        let expected_root = expected_doc.root_element();
-        let actual_root = actual_doc.root_element();
+    /// Fail with the first selector of the options that doesn't parse
+    pub(crate) fn check_selectors(&self) -> Result<(), HtmlCompareError> {
+        let errors = [
+            self.sort_rules.as_ref().err(),
+            self.component_rules.as_ref().err(),
+            self.ignored_text_selectors.as_ref().err(),
+            self.ignored_element_selectors.as_ref().err(),
+            self.unwrapped_element_selectors.as_ref().err(),
+            self.scoped_attributes.as_ref().err(),
+            self.root_selector.as_ref().err(),
+            self.matchers.as_ref().err(),
+        ];
+        match errors.into_iter().flatten().next() {
+            Some(message) => Err(HtmlCompareError::InvalidSelector(message.clone())),
+            None => Ok(()),
+        }
+    }
 
-        self.compare_element_refs(expected_root, actual_root)
-            .map(|_| true)
+    fn verify_capturing(
+        &self,
+        expected: &str,
+        actual: &str,
+        captures: &mut Captures,
+    ) -> Result<(), HtmlCompareError> {
+        self.check_selectors()?;
+
+        let expected_doc = self.parse(expected);
+        let actual_doc = self.parse(actual);
+        #[cfg(feature = "metrics")]
+        {
+            metrics::record_document("expected", expected_doc.tree.nodes().count());
+            metrics::record_document("actual", actual_doc.tree.nodes().count());
+        }
+
+        let expected_root = self.compared_root(&expected_doc, "expected")?;
+        let actual_root = self.compared_root(&actual_doc, "actual")?;
+        self.check_attribute_names(&expected_doc, &actual_doc)?;
+
+        if self.options.exhaustive {
+            let diff = self.diff_parsed(
+                &expected_doc,
+                &actual_doc,
+                expected_root,
+                actual_root,
+                captures,
+            );
+            return match diff.is_empty() {
+                true => self.check_void_syntax(expected, actual, &expected_doc),
+                false => Err(HtmlCompareError::Differences(diff.derived_from(
+                    expected,
+                    actual,
+                    &self.options,
+                ))),
+            };
+        }
+
+        self.compare_element_refs(expected_root, actual_root, captures)?;
+        self.check_void_syntax(expected, actual, &expected_doc)?;
+
+        if let Some(Difference::BrokenReference {
+            path,
+            attribute,
+            target,
+        }) = self.broken_references(&actual_doc).into_iter().next()
+        {
+            return Err(HtmlCompareError::BrokenReference(format!(
+                "{}: {}={:?}",
+                path, attribute, target
+            )));
+        }
+        if let Some(difference) = self
+            .security_differences(&expected_doc, &actual_doc)
+            .into_iter()
+            .next()
+        {
+            return Err(HtmlCompareError::SecurityRisk(difference.to_string()));
+        }
+        Ok(())
     }
 
-    /// Compare two ElementRefs
+    /// Compare two ElementRefs, recording the values of placeholders in
+    /// `captures`
+    ///
+    /// The trees are walked with an explicit stack of [`Frame`]s rather
+    /// than by recursion, so arbitrarily deep documents can't overflow the
+    /// stack.
     fn compare_element_refs(
         &self,
         expected: ElementRef,
         actual: ElementRef,
+        captures: &mut Captures,
     ) -> Result<(), HtmlCompareError> {
+        let levels = self.heading_levels(*expected, *actual);
+        self.compare_trees(expected, actual, captures, levels)
+            .map_err(|mismatch| {
+                let (node, err) = *mismatch;
+                err.at(self.node_path(node))
+            })
+    }
+
+    /// Walk two trees as [`Self::compare_element_refs`] does, failing with
+    /// the expected node where they differ
+    pub(crate) fn compare_trees<'a>(
+        &self,
+        expected: ElementRef<'a>,
+        actual: ElementRef<'a>,
+        captures: &mut Captures,
+        levels: HeadingLevels,
+    ) -> Result<(), Mismatch<'a>> {
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut next = Some((expected, actual));
+        // The outcome of the last pair compared, for the frame below it
+        let mut outcome = None;
+        loop {
+            if let Some((expected, actual)) = next.take() {
+                match self.open_frame(expected, actual, captures, levels) {
+                    Ok(Some(frame)) => stack.push(frame),
+                    Ok(None) => outcome = Some(Ok(())),
+                    Err(err) => outcome = Some(Err(err)),
+                }
+            }
+            let Some(frame) = stack.last_mut() else {
+                return outcome.unwrap_or(Ok(()));
+            };
+            match self.step_frame(frame, outcome.take(), captures) {
+                Step::Compare(expected, actual) => next = Some((expected, actual)),
+                Step::Done(result) => {
+                    stack.pop();
+                    outcome = Some(result);
+                }
+            }
+        }
+    }
+
+    /// Compare what two elements hold themselves, and set up the comparison
+    /// of their children if there is any to do
+    fn open_frame<'a>(
+        &self,
+        expected: ElementRef<'a>,
+        actual: ElementRef<'a>,
+        captures: &mut Captures,
+        levels: HeadingLevels,
+    ) -> Result<Option<Frame<'a>>, Mismatch<'a>> {
         // Compare tag names
-        if expected.value().name() != actual.value().name() {
-            return Err(HtmlCompareError::NodeMismatch(format!(
-                "Tag name mismatch. Expected: {}, Actual: {}",
-                expected.value().name(),
-                actual.value().name()
+        if !self.tags_match(
+            self.element_name(expected, levels.expected),
+            self.element_name(actual, levels.actual),
+        ) {
+            return Err(Box::new((
+                *expected,
+                HtmlCompareError::TagMismatch {
+                    path: String::new(),
+                    expected: expected.value().name().to_string(),
+                    actual: actual.value().name().to_string(),
+                },
             )));
         }
 
         // Compare attributes if not ignored
         if !self.options.ignore_attributes {
-            self.compare_attributes(expected, actual)?;
+            self.compare_attributes(expected, actual, captures)
+                .map_err(|err| Box::new((*expected, err)))?;
         }
 
         // Special handling for style tags if ignore_style_contents is true
         if self.options.ignore_style_contents && expected.value().name() == "style" {
             // When ignoring style contents, we only compare the tag existence
-            return Ok(());
+            return Ok(None);
         }
 
-        // Get child nodes
-        let expected_children: Vec<_> = expected
-            .children()
-            .filter(|n| self.should_include_node(n))
-            .collect();
-        let actual_children: Vec<_> = actual
-            .children()
-            .filter(|n| self.should_include_node(n))
-            .collect();
+        if self.is_highlighted_code(expected) {
+            let (expected_code, actual_code) = (self.code_text(expected), self.code_text(actual));
+            if !self.options.ignore_text && expected_code != actual_code {
+                return Err(Box::new((
+                    *expected,
+                    HtmlCompareError::CodeMismatch {
+                        path: String::new(),
+                        position: None,
+                        expected: expected_code,
+                        actual: actual_code,
+                    },
+                )));
+            }
+            return Ok(None);
+        }
 
-        if self.options.ignore_sibling_order {
-            self.compare_unordered_nodes(&expected_children, &actual_children)?;
-        } else {
-            self.compare_ordered_nodes(&expected_children, &actual_children)?;
+        // Compare declarative shadow roots on their own, regardless of where
+        // the <template> sits among the light children
+        let mut shadow_roots = None;
+        if self.options.shadow_dom == ShadowDomMode::Separate {
+            match (self.shadow_root(expected), self.shadow_root(actual)) {
+                (Some(expected_root), Some(actual_root)) => {
+                    shadow_roots = Some((expected_root, actual_root))
+                }
+                (None, None) => {}
+                (expected_root, actual_root) => {
+                    return Err(Box::new((
+                        *expected,
+                        HtmlCompareError::StructureMismatch {
+                            path: String::new(),
+                            kind: StructureMismatchKind::ShadowRoot {
+                                element: expected.value().name().to_string(),
+                                expected: expected_root.is_some(),
+                                actual: actual_root.is_some(),
+                            },
+                        },
+                    )));
+                }
+            }
         }
 
-        Ok(())
+        let (expected_children, actual_children) = self.compared_children(expected, actual, levels);
+        Ok(Some(Frame {
+            element: expected,
+            matched: Vec::new(),
+            expected: expected_children,
+            actual: actual_children,
+            shadow_roots,
+            started: false,
+            waiting: None,
+            position: 0,
+            candidate: 0,
+            mark: 0,
+        }))
     }
 
-    /// Compare attributes between two ElementRefs
-    fn compare_attributes(
+    /// Advance the comparison of the children of a pair of elements until
+    /// it needs two child elements compared, or is done
+    fn step_frame<'a>(
         &self,
-        expected: ElementRef,
-        actual: ElementRef,
-    ) -> Result<(), HtmlCompareError> {
-        let expected_attrs: HashSet<_> = expected
-            .value()
-            .attrs()
-            .filter(|(name, _)| !self.options.ignored_attributes.contains(*name))
-            .collect();
-        let actual_attrs: HashSet<_> = actual
-            .value()
-            .attrs()
-            .filter(|(name, _)| !self.options.ignored_attributes.contains(*name))
-            .collect();
-
-        if expected_attrs != actual_attrs {
-            return Err(HtmlCompareError::NodeMismatch(format!(
-                "Attributes mismatch. Expected: {:?}, Actual: {:?}",
-                expected_attrs, actual_attrs
-            )));
+        frame: &mut Frame<'a>,
+        outcome: Option<Result<(), Mismatch<'a>>>,
+        captures: &mut Captures,
+    ) -> Step<'a> {
+        match (frame.waiting.take(), outcome) {
+            (_, None) => {}
+            (Some(Waiting::Child), Some(Err(_))) if self.options.ignore_sibling_order => {
+                captures.rollback(frame.mark);
+                frame.candidate += 1;
+            }
+            (_, Some(Err(err))) => return Step::Done(Err(err)),
+            (Some(Waiting::Child), Some(Ok(()))) => {
+                if self.options.ignore_sibling_order {
+                    frame.matched[frame.candidate] = true;
+                    frame.candidate = 0;
+                }
+                frame.position += 1;
+            }
+            (_, Some(Ok(()))) => {}
+        }
+        if !self.options.light_dom_first {
+            if let Some((expected, actual)) = frame.shadow_roots.take() {
+                frame.waiting = Some(Waiting::ShadowRoot);
+                return Step::Compare(expected, actual);
+            }
+        }
+        if !frame.started {
+            frame.started = true;
+            if frame.expected.len() != frame.actual.len() {
+                return Step::Done(Err(Box::new((
+                    *frame.element,
+                    HtmlCompareError::StructureMismatch {
+                        path: String::new(),
+                        kind: StructureMismatchKind::ChildCount {
+                            expected: frame.expected.len(),
+                            actual: frame.actual.len(),
+                        },
+                    },
+                ))));
+            }
+            if self.options.ignore_sibling_order {
+                frame.matched = vec![false; frame.actual.len()];
+            }
+        }
+        let result = if self.options.ignore_sibling_order {
+            self.step_unordered_nodes(frame, captures)
+        } else {
+            self.step_ordered_nodes(frame, captures)
+        };
+        match result {
+            Ok(Some((expected, actual))) => {
+                frame.waiting = Some(Waiting::Child);
+                frame.mark = captures.mark();
+                Step::Compare(expected, actual)
+            }
+            Ok(None) => match frame.shadow_roots.take() {
+                Some((expected, actual)) => {
+                    frame.waiting = Some(Waiting::ShadowRoot);
+                    Step::Compare(expected, actual)
+                }
+                None => Step::Done(Ok(())),
+            },
+            Err(err) => Step::Done(Err(err)),
         }
-        Ok(())
     }
 
-    /// Compare ordered nodes
-    fn compare_ordered_nodes(
+    /// Compare ordered nodes from the frame's position up to the next pair
+    /// of elements
+    fn step_ordered_nodes<'a>(
         &self,
-        expected: &[NodeRef<Node>],
-        actual: &[NodeRef<Node>],
-    ) -> Result<(), HtmlCompareError> {
-        if expected.len() != actual.len() {
-            return Err(HtmlCompareError::NodeMismatch(format!(
-                "Child count mismatch. Expected: {}, Actual: {}",
-                expected.len(),
-                actual.len()
-            )));
-        }
-
-        for (i, (expected_child, actual_child)) in expected.iter().zip(actual.iter()).enumerate() {
+        frame: &mut Frame<'a>,
+        captures: &mut Captures,
+    ) -> Result<Option<(ElementRef<'a>, ElementRef<'a>)>, Mismatch<'a>> {
+        while let (Some(&expected_child), Some(&actual_child)) = (
+            frame.expected.get(frame.position),
+            frame.actual.get(frame.position),
+        ) {
+            let i = frame.position;
             match (expected_child.value(), actual_child.value()) {
-                (Node::Text(expected_text), Node::Text(actual_text)) => {
+                (Node::Text(_), Node::Text(_)) => {
                     if !self.options.ignore_text {
-                        let expected_str = if self.options.ignore_whitespace {
-                            expected_text.trim()
-                        } else {
-                            expected_text
-                        };
-                        let actual_str = if self.options.ignore_whitespace {
-                            actual_text.trim()
-                        } else {
-                            actual_text
-                        };
-                        if expected_str != actual_str {
-                            return Err(HtmlCompareError::NodeMismatch(format!(
-                                "Text content mismatch at position {}. Expected: '{}', Actual: '{}'",
-                                i, expected_str, actual_str
+                        let expected_str = self.node_text(expected_child);
+                        let actual_str = self.node_text(actual_child);
+                        let matches = self
+                            .values_match(
+                                expected_child,
+                                None,
+                                &expected_str,
+                                &actual_str,
+                                captures,
+                            )
+                            .map_err(|err| Box::new((expected_child, err)))?;
+                        if !matches && code::in_code_block(expected_child) {
+                            return Err(Box::new((
+                                expected_child,
+                                HtmlCompareError::CodeMismatch {
+                                    path: String::new(),
+                                    position: Some(i),
+                                    expected: expected_str.into_owned(),
+                                    actual: actual_str.into_owned(),
+                                },
+                            )));
+                        }
+                        if !matches {
+                            return Err(Box::new((
+                                expected_child,
+                                HtmlCompareError::TextMismatch {
+                                    path: String::new(),
+                                    position: i,
+                                    expected: expected_str.into_owned(),
+                                    actual: actual_str.into_owned(),
+                                },
                             )));
                         }
                     }
                 }
-                (Node::Comment(_), Node::Comment(_)) => {
+                (Node::Comment(expected_comment), Node::Comment(actual_comment)) => {
                     // If we're not ignoring comments, we should compare their content
                     if !self.options.ignore_comments {
-                        let expected_comment = match expected_child.value() {
-                            Node::Comment(c) => c.trim(),
-                            _ => unreachable!(),
-                        };
-                        let actual_comment = match actual_child.value() {
-                            Node::Comment(c) => c.trim(),
-                            _ => unreachable!(),
-                        };
+                        let expected_comment = expected_comment.trim();
+                        let actual_comment = actual_comment.trim();
                         if expected_comment != actual_comment {
-                            return Err(HtmlCompareError::NodeMismatch(format!(
-                                "Comment content mismatch at position {}. Expected: '{}', Actual: '{}'",
-                                i, expected_comment, actual_comment
+                            return Err(Box::new((
+                                expected_child,
+                                HtmlCompareError::CommentMismatch {
+                                    path: String::new(),
+                                    position: i,
+                                    expected: expected_comment.to_string(),
+                                    actual: actual_comment.to_string(),
+                                },
                             )));
                         }
                     }
                 }
                 (Node::Element(_), Node::Element(_)) => {
                     if let (Some(expected_el), Some(actual_el)) = (
-                        ElementRef::wrap(*expected_child),
-                        ElementRef::wrap(*actual_child),
+                        ElementRef::wrap(expected_child),
+                        ElementRef::wrap(actual_child),
                     ) {
-                        self.compare_element_refs(expected_el, actual_el)?;
+                        return Ok(Some((expected_el, actual_el)));
                     }
                 }
                 (expected, actual) => {
-                    return Err(HtmlCompareError::NodeMismatch(format!(
-                        "Node type mismatch at position {}. Expected type: {:?}, Actual type: {:?}",
-                        i,
-                        node_type_name(expected),
-                        node_type_name(actual)
+                    return Err(Box::new((
+                        expected_child,
+                        HtmlCompareError::StructureMismatch {
+                            path: String::new(),
+                            kind: StructureMismatchKind::NodeType {
+                                position: i,
+                                expected: node_type_name(expected),
+                                actual: node_type_name(actual),
+                            },
+                        },
                     )));
                 }
             }
+            frame.position += 1;
         }
-        Ok(())
+        Ok(None)
     }
 
-    fn compare_unordered_nodes(
+    /// Match unordered nodes from the frame's position up to the next pair
+    /// of elements to try
+    fn step_unordered_nodes<'a>(
         &self,
-        expected: &[NodeRef<Node>],
-        actual: &[NodeRef<Node>],
-    ) -> Result<(), HtmlCompareError> {
-        if expected.len() != actual.len() {
-            return Err(HtmlCompareError::NodeMismatch(format!(
-                "Child count mismatch. Expected: {}, Actual: {}",
-                expected.len(),
-                actual.len()
-            )));
-        }
-
-        let mut matched = vec![false; actual.len()];
-
-        for expected_child in expected {
+        frame: &mut Frame<'a>,
+        captures: &mut Captures,
+    ) -> Result<Option<(ElementRef<'a>, ElementRef<'a>)>, Mismatch<'a>> {
+        while let Some(&expected_child) = frame.expected.get(frame.position) {
             let mut found = false;
-            for (i, actual_child) in actual.iter().enumerate() {
-                if !matched[i] {
+            while let Some(actual_child) = frame.actual.get(frame.candidate) {
+                let i = frame.candidate;
+                if !frame.matched[i] {
                     match (expected_child.value(), actual_child.value()) {
-                        (Node::Text(expected_text), Node::Text(actual_text)) => {
+                        (Node::Text(_), Node::Text(_))
                             if self.options.ignore_text
-                                || (!self.options.ignore_whitespace && expected_text == actual_text)
-                                || (self.options.ignore_whitespace
-                                    && expected_text.trim() == actual_text.trim())
-                            {
-                                matched[i] = true;
-                                found = true;
-                                break;
-                            }
+                                || self
+                                    .values_match(
+                                        expected_child,
+                                        None,
+                                        &self.node_text(expected_child),
+                                        &self.node_text(*actual_child),
+                                        captures,
+                                    )
+                                    .unwrap_or(false) =>
+                        {
+                            found = true;
+                            break;
                         }
                         (Node::Element(_), Node::Element(_)) => {
                             if let (Some(expected_el), Some(actual_el)) = (
-                                ElementRef::wrap(*expected_child),
+                                ElementRef::wrap(expected_child),
                                 ElementRef::wrap(*actual_child),
                             ) {
-                                if self.compare_element_refs(expected_el, actual_el).is_ok() {
-                                    matched[i] = true;
-                                    found = true;
-                                    break;
-                                }
+                                return Ok(Some((expected_el, actual_el)));
                             }
                         }
                         (Node::Comment(_), Node::Comment(_)) if self.options.ignore_comments => {
-                            matched[i] = true;
                             found = true;
                             break;
                         }
                         _ => {}
                     }
                 }
+                frame.candidate += 1;
             }
             if !found {
-                return Err(HtmlCompareError::NodeMismatch(format!(
-                    "No matching node found for {:?}",
-                    expected_child.value()
+                return Err(Box::new((
+                    expected_child,
+                    HtmlCompareError::StructureMismatch {
+                        path: String::new(),
+                        kind: StructureMismatchKind::Unmatched {
+                            node: format!("{:?}", expected_child.value()),
+                        },
+                    },
                 )));
             }
+            frame.matched[frame.candidate] = true;
+            frame.candidate = 0;
+            frame.position += 1;
+        }
+        Ok(None)
+    }
+
+    /// Compare attributes between two ElementRefs
+    fn compare_attributes(
+        &self,
+        expected: ElementRef,
+        actual: ElementRef,
+        captures: &mut Captures,
+    ) -> Result<(), HtmlCompareError> {
+        let mut expected_attrs = self.compared_attributes(expected);
+        let mut actual_attrs = self.compared_attributes(actual);
+        expected_attrs.sort_unstable();
+        actual_attrs.sort_unstable();
+
+        let mut matches = expected_attrs == actual_attrs;
+        if !matches && (self.options.capture_placeholders || !self.options.matchers.is_empty()) {
+            let mark = captures.mark();
+            matches = expected_attrs.len() == actual_attrs.len();
+            for ((expected_name, expected_value), (actual_name, actual_value)) in
+                expected_attrs.iter().zip(&actual_attrs)
+            {
+                if !matches {
+                    break;
+                }
+                matches = expected_name == actual_name
+                    && self.values_match(
+                        *expected,
+                        Some(expected_name),
+                        expected_value,
+                        actual_value,
+                        captures,
+                    )?;
+            }
+            if !matches {
+                captures.rollback(mark);
+            }
+        }
+        if !matches {
+            // Ordered sets so that the error message is stable across runs
+            let owned = |attrs: Attributes| {
+                attrs
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value.into_owned()))
+                    .collect()
+            };
+            return Err(HtmlCompareError::AttributeMismatch {
+                path: String::new(),
+                expected: owned(expected_attrs),
+                actual: owned(actual_attrs),
+            });
         }
         Ok(())
     }
 
+    /// The children of `element` that take part in the comparison
+    ///
+    /// `<template>` contents are listed in place of their document fragment.
+    /// A declarative shadow root is left out in [`ShadowDomMode::Separate`]
+    /// (see [`Self::shadow_root`]), replaced by its contents in
+    /// [`ShadowDomMode::Flatten`] and replaces the light children in
+    /// [`ShadowDomMode::Composed`].
+    pub(crate) fn child_nodes<'a>(&self, element: ElementRef<'a>) -> ChildNodes<'a> {
+        let children = self.element_child_nodes(element);
+        match self.options.flatten_slots && self.options.shadow_dom == ShadowDomMode::Composed {
+            true => self.flatten_slots(children),
+            false => children,
+        }
+    }
+
+    /// [`Self::child_nodes`] with `<slot>` elements kept
+    fn element_child_nodes<'a>(&self, element: ElementRef<'a>) -> ChildNodes<'a> {
+        if self.options.turbo_streams && wire::is_turbo_stream(element) {
+            return self.turbo_stream_children(element);
+        }
+        if self.options.shadow_dom == ShadowDomMode::Composed {
+            if let Some(shadow_root) = self.shadow_root(element) {
+                return self.child_nodes(shadow_root);
+            }
+            if let Some(assigned) = self.assigned_nodes(element) {
+                return assigned;
+            }
+        }
+        let shadow_root = self.shadow_root(element).map(|root| root.id());
+        let mut children = ChildNodes::new();
+        let mut shadow_children = ChildNodes::new();
+        for child in element.children() {
+            if Some(child.id()) == shadow_root {
+                if self.options.shadow_dom == ShadowDomMode::Flatten {
+                    let contents = self.child_nodes(ElementRef::wrap(child).unwrap());
+                    match self.options.light_dom_first {
+                        true => shadow_children = contents,
+                        false => children.extend(contents),
+                    }
+                }
+            } else if let Node::Fragment = child.value() {
+                children.extend(child.children().filter(|n| self.should_include_node(n)));
+            } else if let Some(wrapper) = ElementRef::wrap(child).filter(|element| {
+                markdown::is_heading_wrapper(&self.options, *element)
+                    || self.is_unwrapped_element(*element)
+            }) {
+                children.extend(self.child_nodes(wrapper));
+            } else if self.should_include_node(&child) {
+                children.push(child);
+            }
+        }
+        children.extend(shadow_children);
+        if let Some(paragraph) = markdown::sole_paragraph(&self.options, element, &children) {
+            return self.child_nodes(paragraph);
+        }
+        if self.options.mathml && mathml::in_math(element) {
+            return self.normalize_math_children(element, children);
+        }
+        if self.options.normalize_head_order && element.value().name() == "head" {
+            // A stable sort keeps inline scripts and styles in source order
+            children.sort_by_cached_key(head_order_key);
+        }
+        sources::sort_sources(&self.options, element, &mut children);
+        if let Ok(rules) = &self.sort_rules {
+            sorting::sort_children(rules, element, &mut children);
+        }
+        children
+    }
+
+    /// The level of the topmost heading in the document of `node`, or 0
+    /// unless [`HtmlCompareOptions::relative_heading_levels`] is set
+    pub(crate) fn top_heading_level(&self, node: NodeRef<Node>) -> usize {
+        match self.options.relative_heading_levels {
+            true => top_heading_level(node),
+            false => 0,
+        }
+    }
+
+    /// The [`HeadingLevels`] of the documents of `expected` and `actual`
+    pub(crate) fn heading_levels(
+        &self,
+        expected: NodeRef<Node>,
+        actual: NodeRef<Node>,
+    ) -> HeadingLevels {
+        HeadingLevels {
+            expected: self.top_heading_level(expected),
+            actual: self.top_heading_level(actual),
+        }
+    }
+
+    /// The tag name of `element` as compared, in a document whose topmost
+    /// heading is at level `top_heading`
+    pub(crate) fn element_name<'a>(&self, element: ElementRef<'a>, top_heading: usize) -> &'a str {
+        let name = element.value().name();
+        if self.options.relative_heading_levels {
+            if let Some(level) = heading_level(name) {
+                return HEADINGS[level - top_heading];
+            }
+        }
+        if self.options.mathml && mathml::in_math(element) {
+            mathml::local_name(name)
+        } else {
+            name
+        }
+    }
+
+    /// Whether two tag names count as the same tag
+    pub(crate) fn tags_match(&self, expected: &str, actual: &str) -> bool {
+        expected == actual || self.canonical_tag(expected) == self.canonical_tag(actual)
+    }
+
+    /// The tag that `name` is compared as: the first tag of the
+    /// [`HtmlCompareOptions::equivalent_tags`] (or presentational) pair it
+    /// appears in, if any
+    pub(crate) fn canonical_tag<'a>(&'a self, name: &'a str) -> &'a str {
+        let presentational: &[(&str, &str)] =
+            match self.options.treat_presentational_tags_as_equivalent {
+                true => PRESENTATIONAL_EQUIVALENTS,
+                false => &[],
+            };
+        self.options
+            .equivalent_tags
+            .iter()
+            .map(|(first, second)| (first.as_str(), second.as_str()))
+            .chain(presentational.iter().copied())
+            .find(|(first, second)| *first == name || *second == name)
+            .map_or(name, |(first, _)| first)
+    }
+
+    /// The attributes of `element` that take part in the comparison
+    pub(crate) fn compared_attributes<'a>(&self, element: ElementRef<'a>) -> Attributes<'a> {
+        let math = self.options.mathml && mathml::in_math(element);
+        let turbo_stream = self.options.turbo_streams && wire::is_turbo_stream(element);
+        let scoped = match &self.scoped_attributes {
+            Ok(entries) if !entries.is_empty() => scoped_attributes::ignored_on(entries, element),
+            _ => Vec::new(),
+        };
+        element
+            .value()
+            .attrs()
+            .filter(|(name, _)| !self.options.ignored_attributes.contains(*name))
+            .filter(|(name, _)| !scoped.iter().any(|ignored| ignored.contains(*name)))
+            .filter(|(name, _)| {
+                !self
+                    .options
+                    .ignored_attribute_prefixes
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix.as_str()))
+            })
+            .filter(|(name, _)| !turbo_stream || wire::TURBO_STREAM_ATTRIBUTES.contains(name))
+            .filter(|(name, _)| !csp::is_ignored(&self.options, name))
+            .filter(|(name, _)| {
+                !self
+                    .options
+                    .ignore_framework_markers
+                    .is_marker_attribute(name)
+            })
+            .filter(|(name, value)| {
+                !markdown::is_ignored_heading_id(&self.options, element, name, value)
+            })
+            .filter(|(name, _)| !(math && mathml::is_namespace_declaration(name)))
+            .filter(|(name, _)| !slots::is_ignored(&self.options, name))
+            .map(|(name, value)| {
+                let value = if self.options.compare_media_queries && media::is_media_attribute(name)
+                {
+                    Cow::Owned(media::normalize(name, value))
+                } else if name == "srcset" && sources::is_unordered_srcset(&self.options, element) {
+                    Cow::Owned(sources::normalize_srcset(value))
+                } else if (self.options.normalize_boolean_attributes
+                    && minify::BOOLEAN_ATTRIBUTES.contains(&name))
+                    || csp::is_valid_format_only(&self.options, name, value)
+                    || sources::is_media_flag(&self.options, element, name)
+                {
+                    Cow::Borrowed("")
+                } else if let Some(content) = self.options.attribute_content.get(name) {
+                    Cow::Owned(content.normalize(self, value))
+                } else if self.options.normalize_inline_js && inline_js::is_event_handler(name) {
+                    inline_js::normalize(value).map_or(Cow::Borrowed(value), Cow::Owned)
+                } else if query_params::applies(&self.options, name) {
+                    query_params::normalize(&self.options, value)
+                } else {
+                    tokens::normalize_attribute(&self.options.tokenized_attributes, name, value)
+                };
+                (name, value)
+            })
+            .map(|(name, value)| {
+                match self.options.ignore_attribute_wrapping && wrapping::applies(name) {
+                    true => (name, wrapping::unwrap_value(value)),
+                    false => (name, value),
+                }
+            })
+            .map(
+                |(name, value)| match self.options.decode_attribute_entities {
+                    true => (name, entities::decode(value)),
+                    false => (name, value),
+                },
+            )
+            .filter(|(name, value)| {
+                !(value.is_empty() && self.options.empty_attribute_equals_missing.contains(*name))
+            })
+            .collect()
+    }
+
+    /// Whether the compared contents of two text nodes are equal, under
+    /// [`HtmlCompareOptions::text_collation`] if it is set
+    pub(crate) fn texts_equal(&self, expected: &str, actual: &str) -> bool {
+        #[cfg(feature = "collation")]
+        if let Some(collation) = &self.options.text_collation {
+            return collation.equal(expected, actual);
+        }
+        expected == actual
+    }
+
+    /// The content of a text or comment node as compared
+    pub(crate) fn node_text<'a>(&self, node: NodeRef<'a, Node>) -> Cow<'a, str> {
+        let text = match node.value() {
+            Node::Text(text) => &**text,
+            Node::Comment(comment) => &**comment,
+            _ => "",
+        };
+        if self.options.exact_code_whitespace && code::in_code_block(node) {
+            return Cow::Borrowed(text);
+        }
+        let mut text = text;
+        if self.options.ignore_whitespace {
+            text = text.trim();
+        }
+        if self.options.normalize_optional_tags {
+            text = optional_tags::trim_relocated_whitespace(node, text);
+        }
+        if self.options.ignore_block_whitespace {
+            text = pretty::trim_block_whitespace(node, text);
+        }
+        text = markdown::trim_code_newline(&self.options, node, text);
+        match node.parent().and_then(ElementRef::wrap) {
+            Some(parent) if self.options.mathml && mathml::in_math(parent) => {
+                mathml::math_text(parent, text)
+            }
+            _ if self.options.collapse_whitespace && minify::collapsible(node) => {
+                minify::collapse_whitespace(text)
+            }
+            _ => Cow::Borrowed(text),
+        }
+    }
+
+    /// The `<template shadowrootmode>` child that declares the shadow root of
+    /// `element`, if any
+    pub(crate) fn shadow_root<'a>(&self, element: ElementRef<'a>) -> Option<ElementRef<'a>> {
+        element
+            .children()
+            .filter_map(ElementRef::wrap)
+            .find(|child| {
+                child.value().name() == "template" && child.value().attr("shadowrootmode").is_some()
+            })
+    }
+
     /// Determine if a node should be included in comparison
-    fn should_include_node(&self, node: &NodeRef<Node>) -> bool {
+    pub(crate) fn should_include_node(&self, node: &NodeRef<Node>) -> bool {
         match node.value() {
+            Node::Text(_)
+                if self
+                    .ignored_text_selectors
+                    .as_ref()
+                    .is_ok_and(|selectors| ignored_text::is_ignored(selectors, *node)) =>
+            {
+                false
+            }
+            Node::Text(_) if self.options.exact_code_whitespace && code::in_code_block(*node) => {
+                !self.options.ignore_text
+            }
             Node::Text(text) => {
                 !self.options.ignore_text
                     && (!self.options.ignore_whitespace || !text.trim().is_empty())
+                    && (!self.options.normalize_optional_tags
+                        || !optional_tags::is_relocated_whitespace(*node))
+                    && (!self.options.ignore_block_whitespace
+                        || !pretty::trim_block_whitespace(*node, text).is_empty())
             }
             Node::Comment(_) => !self.options.ignore_comments,
+            Node::Element(element) => {
+                (!self.options.ignore_hidden_elements || !hidden::is_hidden(element))
+                    && !markdown::is_heading_anchor(&self.options, *node)
+                    && !self.is_ignored_element(*node)
+            }
             _ => true,
         }
     }
@@ -439,6 +2170,27 @@ impl HtmlComparer {
 pub mod presets {
     use super::*;
 
+    /// Look up a preset by the name of its function, e.g. `"relaxed"`
+    pub fn from_name(name: &str) -> Option<HtmlCompareOptions> {
+        match name {
+            "relaxed" => Some(relaxed()),
+            "strict" => Some(strict()),
+            "markdown" => Some(markdown()),
+            "gfm" => Some(gfm()),
+            "github_readme" => Some(github_readme()),
+            "mathml" => Some(mathml()),
+            "minified" => Some(minified()),
+            "htmx" => Some(htmx()),
+            "turbo" => Some(turbo()),
+            "csp" => Some(csp()),
+            "wasm" => Some(wasm()),
+            "zola" => Some(zola()),
+            "hugo" => Some(hugo()),
+            "mdbook" => Some(mdbook()),
+            _ => None,
+        }
+    }
+
     /// Create a comparer that ignores all formatting differences
     pub fn relaxed() -> HtmlCompareOptions {
         HtmlCompareOptions {
@@ -449,6 +2201,68 @@ pub mod presets {
             ignore_comments: true,
             ignore_sibling_order: true,
             ignore_style_contents: true,
+            exhaustive: false,
+            asset_resolver: None,
+            known_classes: None,
+            shadow_dom: ShadowDomMode::Separate,
+            mathml: false,
+            normalize_head_order: false,
+            sort_children: Vec::new(),
+            equivalent_tags: Vec::new(),
+            treat_presentational_tags_as_equivalent: false,
+            relative_heading_levels: false,
+            empty_attribute_equals_missing: HashSet::new(),
+            tokenized_attributes: HashMap::new(),
+            compare_media_queries: false,
+            collapse_whitespace: false,
+            normalize_boolean_attributes: false,
+            normalize_optional_tags: false,
+            ignore_block_whitespace: false,
+            ignored_attribute_prefixes: Vec::new(),
+            turbo_streams: false,
+            component_boundaries: Vec::new(),
+            lint_rules: Vec::new(),
+            security_checks: false,
+            ignore_nonces: false,
+            integrity: IntegrityMode::Exact,
+            ignore_hidden_elements: false,
+            unordered_picture_sources: false,
+            unordered_media_sources: false,
+            on_node_compared: None,
+            parser: Parser::default(),
+            ignore_framework_markers: FrameworkMarkers::None,
+            markdown: None,
+            normalize_list_tightness: false,
+            ignore_code_highlighting: false,
+            exact_code_whitespace: false,
+            cache: None,
+            ignore_text_in: Vec::new(),
+            ignored_attributes_for: Vec::new(),
+            attribute_content: HashMap::new(),
+            tolerate_prolog_junk: false,
+            root_selector: None,
+            capture_placeholders: false,
+            matchers: Vec::new(),
+            diff_granularity: DiffGranularity::Node,
+            error_formatter: None,
+            dump_on_failure: None,
+            light_dom_first: false,
+            flatten_slots: false,
+            attribute_order: format::default_attribute_order(),
+            invalid_utf8: InvalidUtf8::Replace,
+            decode_attribute_entities: true,
+            ignore_attribute_wrapping: true,
+            ignore_void_trailing_slash: true,
+            strict_attribute_names: false,
+            normalize_inline_js: false,
+            unordered_query_params: false,
+            ignored_query_params: HashSet::new(),
+            #[cfg(feature = "collation")]
+            text_collation: None,
+            #[cfg(feature = "http")]
+            max_decompressed_size: http::MAX_DECOMPRESSED_SIZE,
+            ignore_elements: Vec::new(),
+            unwrap_elements: Vec::new(),
         }
     }
 
@@ -462,23 +2276,198 @@ pub mod presets {
             ignore_comments: false,
             ignore_sibling_order: false,
             ignore_style_contents: false,
+            exhaustive: false,
+            asset_resolver: None,
+            known_classes: None,
+            shadow_dom: ShadowDomMode::Separate,
+            mathml: false,
+            normalize_head_order: false,
+            sort_children: Vec::new(),
+            equivalent_tags: Vec::new(),
+            treat_presentational_tags_as_equivalent: false,
+            relative_heading_levels: false,
+            empty_attribute_equals_missing: HashSet::new(),
+            tokenized_attributes: HashMap::new(),
+            compare_media_queries: false,
+            collapse_whitespace: false,
+            normalize_boolean_attributes: false,
+            normalize_optional_tags: false,
+            ignore_block_whitespace: false,
+            ignored_attribute_prefixes: Vec::new(),
+            turbo_streams: false,
+            component_boundaries: Vec::new(),
+            lint_rules: Vec::new(),
+            security_checks: false,
+            ignore_nonces: false,
+            integrity: IntegrityMode::Exact,
+            ignore_hidden_elements: false,
+            unordered_picture_sources: false,
+            unordered_media_sources: false,
+            on_node_compared: None,
+            parser: Parser::default(),
+            ignore_framework_markers: FrameworkMarkers::None,
+            markdown: None,
+            normalize_list_tightness: false,
+            ignore_code_highlighting: false,
+            exact_code_whitespace: false,
+            cache: None,
+            ignore_text_in: Vec::new(),
+            ignored_attributes_for: Vec::new(),
+            attribute_content: HashMap::new(),
+            tolerate_prolog_junk: false,
+            root_selector: None,
+            capture_placeholders: false,
+            matchers: Vec::new(),
+            diff_granularity: DiffGranularity::Node,
+            error_formatter: None,
+            dump_on_failure: None,
+            light_dom_first: false,
+            flatten_slots: false,
+            attribute_order: format::default_attribute_order(),
+            invalid_utf8: InvalidUtf8::Replace,
+            decode_attribute_entities: false,
+            ignore_attribute_wrapping: false,
+            ignore_void_trailing_slash: true,
+            strict_attribute_names: false,
+            normalize_inline_js: false,
+            unordered_query_params: false,
+            ignored_query_params: HashSet::new(),
+            #[cfg(feature = "collation")]
+            text_collation: None,
+            #[cfg(feature = "http")]
+            max_decompressed_size: http::MAX_DECOMPRESSED_SIZE,
+            ignore_elements: Vec::new(),
+            unwrap_elements: Vec::new(),
         }
     }
 
-    /// Create a comparer that is suitable for testing markdown output
+    /// Create a comparer that is suitable for testing markdown output,
+    /// tolerating the differences between CommonMark renderers (see
+    /// [`MarkdownProfile`])
     pub fn markdown() -> HtmlCompareOptions {
+        MarkdownProfile::commonmark().options()
+    }
+
+    /// Create a comparer for GitHub Flavored Markdown output
+    pub fn gfm() -> HtmlCompareOptions {
+        MarkdownProfile::gfm().options()
+    }
+
+    /// Create a comparer for checking rendered README HTML against what
+    /// github.com serves, ignoring the heading permalink anchors, `dir="auto"`
+    /// and `rel="nofollow"` GitHub adds and the spelling of code fence
+    /// language classes (`language-rust`, `lang-rust`, `highlight-source-rust`)
+    pub fn github_readme() -> HtmlCompareOptions {
+        let mut options = MarkdownProfile::github().options();
+        options.ignored_attributes.insert("dir".to_string());
+        options.ignored_attributes_for.push((
+            "h1, h2, h3, h4, h5, h6".to_string(),
+            HashSet::from(["class".to_string()]),
+        ));
+        options.attribute_content.insert(
+            "rel".to_string(),
+            AttributeContent::custom("rel-without-nofollow", |rel| {
+                let mut tokens: Vec<&str> = rel
+                    .split_ascii_whitespace()
+                    .filter(|token| !token.eq_ignore_ascii_case("nofollow"))
+                    .collect();
+                tokens.sort_unstable();
+                tokens.join(" ")
+            }),
+        );
+        options
+            .empty_attribute_equals_missing
+            .insert("rel".to_string());
+        options.attribute_content.insert(
+            "class".to_string(),
+            AttributeContent::custom("code-fence-classes", markdown::normalize_fence_classes),
+        );
+        options
+    }
+
+    /// Create a comparer for MathML produced by different converters
+    pub fn mathml() -> HtmlCompareOptions {
+        HtmlCompareOptions {
+            mathml: true,
+            ..Default::default()
+        }
+    }
+
+    /// Create a comparer for htmx responses, ignoring `hx-*` attributes
+    pub fn htmx() -> HtmlCompareOptions {
+        HtmlCompareOptions {
+            ignored_attribute_prefixes: wire::HTMX_ATTRIBUTE_PREFIXES
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a comparer for Turbo responses, ignoring `data-turbo-*`
+    /// attributes and comparing `<turbo-stream>` actions
+    pub fn turbo() -> HtmlCompareOptions {
+        HtmlCompareOptions {
+            ignored_attribute_prefixes: wire::TURBO_ATTRIBUTE_PREFIXES
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect(),
+            turbo_streams: true,
+            ..Default::default()
+        }
+    }
+
+    /// Create a comparer for pages behind a Content-Security-Policy,
+    /// ignoring nonces and checking only the format of `integrity` hashes
+    pub fn csp() -> HtmlCompareOptions {
+        HtmlCompareOptions {
+            ignore_nonces: true,
+            integrity: IntegrityMode::Format,
+            ..Default::default()
+        }
+    }
+
+    /// Create a comparer for components rendered by Yew, Leptos and other
+    /// wasm frameworks, ignoring comment anchors and hydration ids and
+    /// parsing the output as a fragment
+    pub fn wasm() -> HtmlCompareOptions {
         HtmlCompareOptions {
-            ignore_whitespace: true,
-            ignore_attributes: false,
-            ignored_attributes: {
-                let mut set = HashSet::new();
-                set.insert("id".to_string());
-                set
-            },
-            ignore_text: false,
             ignore_comments: true,
-            ignore_sibling_order: false,
-            ignore_style_contents: true,
+            ignored_attribute_prefixes: crate::wasm::HYDRATION_ATTRIBUTE_PREFIXES
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect(),
+            parser: Parser::from_name("fragment").unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a comparer for sites built by Zola, ignoring heading anchors
+    /// and the styles of highlighted code (see [`ssg`])
+    pub fn zola() -> HtmlCompareOptions {
+        ssg::zola()
+    }
+
+    /// Create a comparer for sites built by Hugo, ignoring heading anchors
+    /// and Chroma's wrappers, attributes and line numbers around code (see
+    /// [`ssg`])
+    pub fn hugo() -> HtmlCompareOptions {
+        ssg::hugo()
+    }
+
+    /// Create a comparer for mdBook chapters, comparing `<main>` only and
+    /// ignoring heading links and code block buttons (see [`ssg`])
+    pub fn mdbook() -> HtmlCompareOptions {
+        ssg::mdbook()
+    }
+
+    /// Create a comparer for checking a minifier's output against its input
+    pub fn minified() -> HtmlCompareOptions {
+        HtmlCompareOptions {
+            collapse_whitespace: true,
+            normalize_boolean_attributes: true,
+            tokenized_attributes: tokens::html_token_lists(),
+            ..Default::default()
         }
     }
 }
@@ -786,7 +2775,7 @@ mod tests {
     #[test]
     fn test_error_messages() {
         // Test tag mismatch error
-        let result = HtmlComparer::new().compare("<div>Test</div>", "<span>Test</span>");
+        let result = HtmlComparer::new().verify("<div>Test</div>", "<span>Test</span>");
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -794,7 +2783,7 @@ mod tests {
         );
 
         // Test attribute mismatch error
-        let result = HtmlComparer::new().compare(
+        let result = HtmlComparer::new().verify(
             "<div class='test'>Content</div>",
             "<div class='different'>Content</div>",
         );
@@ -804,8 +2793,18 @@ mod tests {
             "Node mismatch: Attributes mismatch. Expected: {(\"class\", \"test\")}, Actual: {(\"class\", \"different\")}"
         );
 
+        // Attributes are listed by name, whatever their source order
+        let result = HtmlComparer::new().verify(
+            "<a title='t' href='/a' id='x'>Link</a>",
+            "<a id='x' title='t' href='/b'>Link</a>",
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Node mismatch: Attributes mismatch. Expected: {(\"href\", \"/a\"), (\"id\", \"x\"), (\"title\", \"t\")}, Actual: {(\"href\", \"/b\"), (\"id\", \"x\"), (\"title\", \"t\")}"
+        );
+
         // Test content mismatch error
-        let result = HtmlComparer::new().compare("<div>Hello</div>", "<div>World</div>");
+        let result = HtmlComparer::new().verify("<div>Hello</div>", "<div>World</div>");
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -813,7 +2812,7 @@ mod tests {
         );
 
         // Test structure mismatch error
-        let result = HtmlComparer::new().compare("<div><p>Text</p></div>", "<div>Text</div>");
+        let result = HtmlComparer::new().verify("<div><p>Text</p></div>", "<div>Text</div>");
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -821,6 +2820,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_structured_errors() {
+        let comparer = HtmlComparer::new();
+        let err = comparer
+            .verify(
+                "<ul><li>One</li><li><b>Two</b></li></ul>",
+                "<ul><li>One</li><li><b>Three</b></li></ul>",
+            )
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            HtmlCompareError::TextMismatch { position: 0, expected, actual, .. }
+                if expected == "Two" && actual == "Three"
+        ));
+        assert_eq!(err.path(), Some("/html/body/ul/li[2]/b/text()"));
+
+        let err = comparer
+            .verify("<p id='a'>Hi</p>", "<p id='b'>Hi</p>")
+            .unwrap_err();
+        let HtmlCompareError::AttributeMismatch { path, actual, .. } = &err else {
+            panic!("{:?}", err);
+        };
+        assert_eq!(path, "/html/body/p");
+        assert!(actual.contains(&("id".to_string(), "b".to_string())));
+
+        let err = comparer
+            .verify("<div><p>A</p></div>", "<div><p>A</p><p>B</p></div>")
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            HtmlCompareError::StructureMismatch {
+                kind: StructureMismatchKind::ChildCount {
+                    expected: 1,
+                    actual: 2
+                },
+                ..
+            }
+        ));
+        assert_eq!(err.path(), Some("/html/body/div"));
+    }
+
+    #[test]
+    fn test_is_equal_and_verify() {
+        let comparer = HtmlComparer::new();
+        assert!(comparer.is_equal("<p>Hi</p>", "<p> Hi </p>").unwrap());
+        assert!(!comparer.is_equal("<p>Hi</p>", "<p>Bye</p>").unwrap());
+        assert!(comparer.verify("<p>Hi</p>", "<p> Hi </p>").is_ok());
+        assert!(matches!(
+            comparer.verify("<p>Hi</p>", "<p>Bye</p>"),
+            Err(HtmlCompareError::TextMismatch { .. })
+        ));
+
+        // Options that can't be applied are still errors
+        let invalid = HtmlComparer::with_options(HtmlCompareOptions {
+            sort_children: vec![("ul[".to_string(), SortKey::Text)],
+            ..Default::default()
+        });
+        assert!(matches!(
+            invalid.is_equal("<ul></ul>", "<ul></ul>"),
+            Err(HtmlCompareError::InvalidSelector(_))
+        ));
+
+        #[allow(deprecated)]
+        let result = comparer.compare("<p>Hi</p>", "<p> Hi </p>");
+        assert!(result.unwrap());
+    }
+
     #[test]
     fn test_preset_configurations() {
         // Test relaxed preset
@@ -945,6 +3011,121 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_arbitrary_input() {
+        let inputs = [
+            "",
+            "\u{0}\u{fffd}<",
+            "<<<>>></</>",
+            "<div <p>><!-- <!-- -->",
+            "<table><td><select><option><table>",
+            "<template shadowrootmode=open><template shadowrootmode=open>",
+            "<math><mi><svg><foreignObject><p>",
+            "</html><html></html><body>",
+        ];
+        let deep = format!("{}x", "<span>".repeat(2000));
+        for options in [
+            HtmlCompareOptions::default(),
+            presets::relaxed(),
+            presets::strict(),
+            HtmlCompareOptions {
+                ignore_sibling_order: true,
+                ..Default::default()
+            },
+        ] {
+            let comparer = HtmlComparer::with_options(options);
+            for expected in inputs.iter().copied().chain([deep.as_str()]) {
+                for actual in inputs.iter().copied().chain([deep.as_str()]) {
+                    let _ = comparer.verify(expected, actual);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_extreme_depth() {
+        // Some generators emit documents nested thousands of levels deep,
+        // far more than a recursive walk fits in a test thread's stack
+        let depth = 10_000;
+        let nested = |leaf: &str| {
+            format!(
+                "{}{}{}",
+                "<div><section>".repeat(depth / 2),
+                leaf,
+                "</section></div>".repeat(depth / 2)
+            )
+        };
+        let (expected_html, actual_html) = (
+            nested("<p>leaf</p><p>other</p>"),
+            nested("<p>other</p><p>leaf!</p>"),
+        );
+        let expected = Html::parse_document(&expected_html);
+        let actual = Html::parse_document(&actual_html);
+
+        let ordered = HtmlComparer::new();
+        assert!(ordered
+            .compare_element_refs(
+                expected.root_element(),
+                expected.root_element(),
+                &mut Captures::default(),
+            )
+            .is_ok());
+        let err = ordered
+            .compare_element_refs(
+                expected.root_element(),
+                actual.root_element(),
+                &mut Captures::default(),
+            )
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Expected: 'leaf', Actual: 'other'"));
+
+        let unordered = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_sibling_order: true,
+            ..Default::default()
+        });
+        let err = unordered
+            .compare_element_refs(
+                expected.root_element(),
+                actual.root_element(),
+                &mut Captures::default(),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("No matching node found"));
+
+        // Parsing is what takes time here, so the documents parsed above
+        // are diffed directly
+        let diff = unordered.diff_parsed(
+            &expected,
+            &actual,
+            expected.root_element(),
+            actual.root_element(),
+            &mut Captures::default(),
+        );
+        assert_eq!(diff.len(), 1);
+        let diff = ordered.diff_parsed(
+            &expected,
+            &actual,
+            expected.root_element(),
+            actual.root_element(),
+            &mut Captures::default(),
+        );
+        assert_eq!(diff.len(), 2);
+        assert!(diff.iter().next().unwrap().path().ends_with("/p[1]/text()"));
+
+        // The public entry points, down to the exhaustive comparison
+        assert!(ordered.diff(&expected_html, &expected_html).is_empty());
+        let exhaustive = HtmlComparer::with_options(HtmlCompareOptions {
+            exhaustive: true,
+            ..Default::default()
+        });
+        match exhaustive.verify(&expected_html, &actual_html) {
+            Err(HtmlCompareError::Differences(diff)) => assert_eq!(diff.len(), 2),
+            other => panic!("expected differences, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_malformed_html() {
         // Unclosed tags (should be handled by HTML parser)
@@ -954,7 +3135,7 @@ mod tests {
         assert_html_ne!("<p>Text</p></p>", "<p>Text</p>");
 
         // Test the specific error we get with extra closing tags
-        let result = HtmlComparer::new().compare("<p>Text</p></p>", "<p>Text</p>");
+        let result = HtmlComparer::new().verify("<p>Text</p></p>", "<p>Text</p>");
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -963,7 +3144,7 @@ mod tests {
 
         // Mismatched tags are typically corrected by the parser
         // Let's verify the actual behavior
-        let result = HtmlComparer::new().compare(
+        let result = HtmlComparer::new().verify(
             "<p><strong>Text</p></strong>",
             "<p><strong>Text</strong></p>",
         );
@@ -1046,4 +3227,216 @@ mod tests {
             ignore_both
         );
     }
+
+    #[test]
+    fn test_macro_options_shorthand() {
+        assert_html_eq!(
+            "<p class='a'>Hi</p>",
+            "<p class='b'>Hi</p>",
+            preset = "relaxed"
+        );
+        assert_html_ne!(
+            "<p class='a'>Hi</p>",
+            "<p class='b'>Hi</p>",
+            preset = "strict",
+        );
+        assert_html_eq!(
+            "<ul id='a' class='x'><li>1</li><li>2</li></ul>",
+            "<ul id='b' class='y'><li>2</li><li>1</li></ul>",
+            ignore_sibling_order,
+            ignore_attributes("class", "id"),
+        );
+        assert_html_ne!(
+            "<p>Hi <!-- a --></p>",
+            "<p>Hi <!-- b --></p>",
+            ignore_comments = false
+        );
+        assert_html_ne!(
+            "<p class='a'>Hi</p>",
+            "<p class='b'>Hi</p>",
+            preset = "relaxed",
+            ignore_attributes = false
+        );
+
+        let options = presets::relaxed();
+        assert_html_eq!("<p class='a'>Hi</p>", "<p class='b'>Hi</p>", options);
+        assert!(options.ignore_attributes);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown HTML comparison preset")]
+    fn test_macro_unknown_preset() {
+        assert_html_eq!("<p>Hi</p>", "<p>Hi</p>", preset = "lenient");
+    }
+
+    #[test]
+    fn test_template_contents() {
+        assert_html_eq!(
+            "<template><p>Row</p></template>",
+            "<template>\n  <p>Row</p>\n</template>"
+        );
+        assert_html_ne!(
+            "<template><p>Row</p></template>",
+            "<template><p>Column</p></template>"
+        );
+    }
+
+    #[test]
+    fn test_normalize_head_order() {
+        let expected = r#"<html><head>
+            <meta charset="utf-8">
+            <title>Post</title>
+            <link rel="stylesheet" href="/a.css">
+            <link rel="stylesheet" href="/b.css">
+            <meta name="description" content="About">
+            <script>first()</script>
+            <script>second()</script>
+        </head><body></body></html>"#;
+        let actual = r#"<html><head>
+            <script>first()</script>
+            <link rel="stylesheet" href="/b.css">
+            <meta name="description" content="About">
+            <title>Post</title>
+            <script>second()</script>
+            <meta charset="utf-8">
+            <link rel="stylesheet" href="/a.css">
+        </head><body></body></html>"#;
+
+        assert_html_ne!(expected, actual);
+        assert_html_eq!(expected, actual, normalize_head_order);
+
+        // Scripts without a src keep their relative order
+        assert_html_ne!(
+            "<head><script>first()</script><script>second()</script></head>",
+            "<head><script>second()</script><script>first()</script></head>",
+            normalize_head_order
+        );
+    }
+
+    #[test]
+    fn test_presentational_tags() {
+        let editor = "<p><b>Bold</b>, <i>italic</i> and <s>gone</s></p>";
+        let renderer = "<p><strong>Bold</strong>, <em>italic</em> and <del>gone</del></p>";
+
+        assert_html_ne!(editor, renderer);
+        assert_html_eq!(editor, renderer, treat_presentational_tags_as_equivalent);
+        assert_html_ne!(
+            "<p><b>Bold</b></p>",
+            "<p><em>Bold</em></p>",
+            treat_presentational_tags_as_equivalent
+        );
+
+        // Extra pairs extend the built-in ones
+        let options = HtmlCompareOptions {
+            treat_presentational_tags_as_equivalent: true,
+            equivalent_tags: vec![("u".to_string(), "ins".to_string())],
+            ..Default::default()
+        };
+        assert_html_eq!(
+            "<p><u>a</u><b>b</b></p>",
+            "<p><ins>a</ins><strong>b</strong></p>",
+            options
+        );
+    }
+
+    #[test]
+    fn test_relative_heading_levels() {
+        let page = "<h2>Card</h2><p>Intro</p><h3>Details</h3><p>More</p>";
+        let nested = "<h3>Card</h3><p>Intro</p><h4>Details</h4><p>More</p>";
+
+        assert_html_ne!(page, nested);
+        assert_html_eq!(page, nested, relative_heading_levels);
+
+        // The hierarchy itself still has to match
+        assert_html_ne!(
+            page,
+            "<h3>Card</h3><p>Intro</p><h5>Details</h5><p>More</p>",
+            relative_heading_levels
+        );
+
+        // Each document counts from its own topmost heading on every walk
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            relative_heading_levels: true,
+            ignore_sibling_order: true,
+            ..Default::default()
+        });
+        comparer.verify(page, nested).unwrap();
+        assert!(comparer.diff(page, nested).is_empty());
+        assert!(comparer
+            .diff(page, nested)
+            .edit_script()
+            .iter()
+            .all(|edit| !matches!(edit, Edit::Relabel { .. })));
+        assert_eq!(comparer.analyze(nested).tags["h2"], 1);
+    }
+
+    #[test]
+    fn test_empty_attribute_equals_missing() {
+        let options = HtmlCompareOptions {
+            empty_attribute_equals_missing: HashSet::from(["alt".to_string(), "value".to_string()]),
+            ..Default::default()
+        };
+
+        assert_html_ne!(r#"<img src="a.png" alt="">"#, r#"<img src="a.png">"#);
+        assert_html_eq!(
+            r#"<img src="a.png" alt=""><input value="">"#,
+            r#"<img src="a.png"><input>"#,
+            options.clone()
+        );
+        assert_html_ne!(r#"<img alt="Logo">"#, r#"<img>"#, options.clone());
+        assert_html_ne!(r#"<a title="">x</a>"#, r#"<a>x</a>"#, options);
+    }
+
+    #[test]
+    fn test_decode_attribute_entities() {
+        // Decoded by the parser regardless of the option
+        assert_html_eq!(r#"<p title="it's">x</p>"#, r#"<p title='it&#39;s'>x</p>"#);
+        assert_html_eq!(r#"<p data-x="a&amp;b">x</p>"#, r#"<p data-x='a&b'>x</p>"#);
+
+        let escaped_twice = r#"<p title="it&amp;#39;s" data-x="a&amp;amp;b">x</p>"#;
+        let escaped_once = r#"<p title="it's" data-x="a&b">x</p>"#;
+        assert_html_ne!(escaped_twice, escaped_once);
+        assert_html_eq!(escaped_twice, escaped_once, decode_attribute_entities);
+        assert_html_ne!(
+            r#"<p title="&lt;b&gt;">x</p>"#,
+            r#"<p title="b">x</p>"#,
+            decode_attribute_entities
+        );
+    }
+
+    #[test]
+    fn test_declarative_shadow_dom() {
+        let expected = r#"<my-card>
+            <template shadowrootmode="open"><h2><slot name="title"></slot></h2></template>
+            <span slot="title">Hello</span>
+        </my-card>"#;
+        let moved = r#"<my-card>
+            <span slot="title">Hello</span>
+            <template shadowrootmode="open"><h2><slot name="title"></slot></h2></template>
+        </my-card>"#;
+        let changed = r#"<my-card>
+            <template shadowrootmode="open"><h3><slot name="title"></slot></h3></template>
+            <span slot="title">Hello</span>
+        </my-card>"#;
+
+        // By default the shadow tree is compared on its own
+        assert_html_eq!(expected, moved);
+        assert_html_ne!(expected, changed);
+        assert_html_ne!(
+            expected,
+            "<my-card><span slot='title'>Hello</span></my-card>"
+        );
+
+        // Flattened, the shadow contents become part of the light tree
+        let flatten = HtmlCompareOptions {
+            shadow_dom: ShadowDomMode::Flatten,
+            ..Default::default()
+        };
+        assert_html_ne!(expected, moved, flatten.clone());
+        assert_html_eq!(
+            expected,
+            r#"<my-card><h2><slot name="title"></slot></h2><span slot="title">Hello</span></my-card>"#,
+            flatten
+        );
+    }
 }
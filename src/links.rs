@@ -0,0 +1,321 @@
+//! Link extraction and link-graph comparison.
+//!
+//! Navigation regression tests usually care about where a page links to, what
+//! the links say and how they are marked up for crawlers (`rel`), not about the
+//! elements wrapping them. [`extract_links`] reduces a document to exactly that.
+
+use std::fmt;
+
+use scraper::{ElementRef, Html, Selector};
+
+use crate::HtmlCompareError;
+
+/// A hyperlink found in a document
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Link {
+    /// Target URL, resolved against the document base
+    pub href: String,
+    /// Anchor text with whitespace collapsed, or the `alt` text of images
+    /// when the link contains no text
+    pub text: String,
+    /// `rel` tokens, lowercased and sorted
+    pub rel: Vec<String>,
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} -> {}", self.text, self.href)?;
+        if !self.rel.is_empty() {
+            write!(f, " (rel={})", self.rel.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Extract all `<a href>` and `<area href>` links in document order
+///
+/// Relative URLs are resolved against the document's `<base href>` (if any),
+/// which is itself resolved against `base_url`. Pass an empty `base_url` to
+/// keep relative URLs as written.
+pub fn extract_links(html: &str, base_url: &str) -> Vec<Link> {
+    let document = Html::parse_document(html);
+
+    let base_selector = Selector::parse("base[href]").unwrap();
+    let base = match document.select(&base_selector).next() {
+        Some(base) => resolve_url(base_url, base.value().attr("href").unwrap_or_default()),
+        None => base_url.to_string(),
+    };
+
+    let selector = Selector::parse("a[href], area[href]").unwrap();
+    document
+        .select(&selector)
+        .map(|element| {
+            let href = element.value().attr("href").unwrap_or_default();
+            let mut rel: Vec<String> = element
+                .value()
+                .attr("rel")
+                .unwrap_or_default()
+                .split_ascii_whitespace()
+                .map(str::to_ascii_lowercase)
+                .collect();
+            rel.sort();
+            rel.dedup();
+
+            Link {
+                href: resolve_url(&base, href),
+                text: link_text(element),
+                rel,
+            }
+        })
+        .collect()
+}
+
+/// Compare the links of two documents, in order
+///
+/// Two documents match when they contain the same sequence of links, each with
+/// the same resolved target, text and `rel` tokens.
+pub fn compare_links(expected: &str, actual: &str, base_url: &str) -> Result<(), HtmlCompareError> {
+    let expected = extract_links(expected, base_url);
+    let actual = extract_links(actual, base_url);
+
+    for (i, (expected_link, actual_link)) in expected.iter().zip(&actual).enumerate() {
+        if expected_link != actual_link {
+            return Err(HtmlCompareError::LinkMismatch(format!(
+                "Link mismatch at position {}. Expected: {}, Actual: {}",
+                i, expected_link, actual_link
+            )));
+        }
+    }
+    if expected.len() != actual.len() {
+        return Err(HtmlCompareError::LinkMismatch(format!(
+            "Link count mismatch. Expected: {}, Actual: {}",
+            expected.len(),
+            actual.len()
+        )));
+    }
+    Ok(())
+}
+
+fn link_text(element: ElementRef) -> String {
+    let text = element.text().collect::<String>();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !text.is_empty() {
+        return text;
+    }
+
+    let images = Selector::parse("img[alt]").unwrap();
+    element
+        .select(&images)
+        .filter_map(|img| img.value().attr("alt"))
+        .map(str::trim)
+        .filter(|alt| !alt.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Resolve `href` against `base` following the common URL reference forms
+///
+/// This covers absolute URLs, scheme-relative (`//host`), root-relative
+/// (`/path`), query, fragment and path-relative references including `.` and
+/// `..` segments. It does not percent-encode or otherwise normalize the URL.
+pub fn resolve_url(base: &str, href: &str) -> String {
+    let href = href.trim();
+    if base.is_empty() || has_scheme(href) {
+        return href.to_string();
+    }
+
+    let (scheme, rest) = match base.split_once("://") {
+        Some((scheme, rest)) => (scheme, rest),
+        None => return href.to_string(),
+    };
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let authority = &rest[..authority_end];
+    let base_path = &rest[authority_end..];
+    let base_path = &base_path[..base_path.find(['?', '#']).unwrap_or(base_path.len())];
+
+    if let Some(scheme_relative) = href.strip_prefix("//") {
+        return format!("{}://{}", scheme, scheme_relative);
+    }
+    if href.is_empty() || href.starts_with('#') || href.starts_with('?') {
+        let base_without_fragment = &base[..base.find('#').unwrap_or(base.len())];
+        if href.starts_with('?') {
+            let without_query = &base_without_fragment[..base_without_fragment
+                .find('?')
+                .unwrap_or(base_without_fragment.len())];
+            return format!("{}{}", without_query, href);
+        }
+        return format!("{}{}", base_without_fragment, href);
+    }
+
+    let (path, suffix) = match href.find(['?', '#']) {
+        Some(index) => href.split_at(index),
+        None => (href, ""),
+    };
+    let joined = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        let directory = &base_path[..base_path.rfind('/').map_or(0, |index| index + 1)];
+        let directory = if directory.is_empty() { "/" } else { directory };
+        format!("{}{}", directory, path)
+    };
+
+    format!(
+        "{}://{}{}{}",
+        scheme,
+        authority,
+        remove_dot_segments(&joined),
+        suffix
+    )
+}
+
+fn has_scheme(href: &str) -> bool {
+    match href.find(':') {
+        Some(index) if index > 0 => href[..index]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')),
+        _ => false,
+    }
+}
+
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut parts = path.split('/').skip(1).peekable();
+    while let Some(segment) = parts.next() {
+        let last = parts.peek().is_none();
+        match segment {
+            "." => {
+                if last {
+                    segments.push("");
+                }
+            }
+            ".." => {
+                segments.pop();
+                if last {
+                    segments.push("");
+                }
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_url() {
+        let base = "https://example.com/docs/guide/intro.html?x=1#top";
+        assert_eq!(
+            resolve_url(base, "https://other.org/"),
+            "https://other.org/"
+        );
+        assert_eq!(resolve_url(base, "mailto:a@b.c"), "mailto:a@b.c");
+        assert_eq!(
+            resolve_url(base, "//cdn.example.com/a.js"),
+            "https://cdn.example.com/a.js"
+        );
+        assert_eq!(resolve_url(base, "/about"), "https://example.com/about");
+        assert_eq!(
+            resolve_url(base, "setup.html"),
+            "https://example.com/docs/guide/setup.html"
+        );
+        assert_eq!(
+            resolve_url(base, "../api/"),
+            "https://example.com/docs/api/"
+        );
+        assert_eq!(
+            resolve_url(base, "./a/../b#c"),
+            "https://example.com/docs/guide/b#c"
+        );
+        assert_eq!(
+            resolve_url(base, "#section"),
+            "https://example.com/docs/guide/intro.html?x=1#section"
+        );
+        assert_eq!(
+            resolve_url(base, "?y=2"),
+            "https://example.com/docs/guide/intro.html?y=2"
+        );
+        assert_eq!(
+            resolve_url("https://example.com", "a"),
+            "https://example.com/a"
+        );
+        assert_eq!(resolve_url("", "a/b"), "a/b");
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let links = extract_links(
+            r#"<nav>
+                <a href="/home" rel="Nofollow noopener">  Home
+                    page </a>
+                <a href="blog/"><img src="b.png" alt="Blog"></a>
+                <a name="anchor-without-href">Skip me</a>
+                <map><area href="map.html" alt="Map"></map>
+            </nav>"#,
+            "https://example.com/en/",
+        );
+
+        assert_eq!(
+            links,
+            vec![
+                Link {
+                    href: "https://example.com/home".to_string(),
+                    text: "Home page".to_string(),
+                    rel: vec!["nofollow".to_string(), "noopener".to_string()],
+                },
+                Link {
+                    href: "https://example.com/en/blog/".to_string(),
+                    text: "Blog".to_string(),
+                    rel: vec![],
+                },
+                Link {
+                    href: "https://example.com/en/map.html".to_string(),
+                    text: String::new(),
+                    rel: vec![],
+                },
+            ]
+        );
+
+        // <base href> takes precedence over the supplied base URL
+        let links = extract_links(
+            r#"<head><base href="/v2/"></head><body><a href="page">Page</a></body>"#,
+            "https://example.com/v1/",
+        );
+        assert_eq!(links[0].href, "https://example.com/v2/page");
+
+        // Text split across inline elements is joined as written
+        let links = extract_links(
+            r#"<a href="/x">Hel<b>lo</b> <i>world</i></a>"#,
+            "https://example.com/",
+        );
+        assert_eq!(links[0].text, "Hello world");
+    }
+
+    #[test]
+    fn test_compare_links() {
+        assert!(compare_links(
+            r#"<ul><li><a href="/a">A</a></li><li><a href="/b" rel="next">B</a></li></ul>"#,
+            r#"<div class="menu"><a href="https://example.com/a"><span>A</span></a> <a rel="next" href="b">B</a></div>"#,
+            "https://example.com/",
+        )
+        .is_ok());
+
+        assert_eq!(
+            compare_links(
+                r#"<a href="/a">A</a>"#,
+                r#"<a href="/a" rel="nofollow">A</a>"#,
+                "https://example.com/",
+            )
+            .unwrap_err()
+            .to_string(),
+            "Link mismatch: Link mismatch at position 0. Expected: \"A\" -> https://example.com/a, Actual: \"A\" -> https://example.com/a (rel=nofollow)"
+        );
+
+        assert!(matches!(
+            compare_links(r#"<a href="/a">A</a>"#, "<p>No links</p>", ""),
+            Err(HtmlCompareError::LinkMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! Lint checks on the documents being compared.
+//!
+//! Test suites that compare rendered pages often want a few sanity checks
+//! alongside equality: images without alternative text, inline event
+//! handlers, deprecated elements and duplicate ids. [`lint`] runs
+//! [`LintRule`]s on a document on its own, and setting
+//! [`HtmlCompareOptions::lint_rules`](crate::HtmlCompareOptions) reports the
+//! findings of the actual document as [`Warning::Lint`](crate::diff::Warning)s
+//! of [`HtmlComparer::diff`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use scraper::{ElementRef, Html};
+
+use crate::HtmlComparer;
+
+/// Elements that are obsolete in the HTML standard
+pub const DEPRECATED_TAGS: &[&str] = &[
+    "acronym",
+    "applet",
+    "basefont",
+    "bgsound",
+    "big",
+    "blink",
+    "center",
+    "dir",
+    "font",
+    "frame",
+    "frameset",
+    "isindex",
+    "keygen",
+    "listing",
+    "marquee",
+    "menuitem",
+    "nobr",
+    "noembed",
+    "noframes",
+    "plaintext",
+    "spacer",
+    "strike",
+    "tt",
+    "xmp",
+];
+
+/// A custom check of one element, given its path, returning a message for
+/// offending elements
+pub type LintCheck = dyn Fn(ElementRef, &str) -> Option<String> + Send + Sync;
+
+/// A check run on every element of a document
+#[derive(Clone)]
+pub enum LintRule {
+    /// `<img>` without an `alt` attribute (`img-missing-alt`)
+    ImgMissingAlt,
+    /// `on*` event handler attributes (`inline-event-handlers`)
+    InlineEventHandlers,
+    /// Elements listed in [`DEPRECATED_TAGS`] (`deprecated-tags`)
+    DeprecatedTags,
+    /// An `id` used by an earlier element (`duplicate-ids`)
+    DuplicateIds,
+    /// A check of one element (see [`LintRule::custom`])
+    Custom { name: String, check: Arc<LintCheck> },
+}
+
+impl LintRule {
+    /// A rule named `name` that reports the message `check` returns
+    pub fn custom(
+        name: impl Into<String>,
+        check: impl Fn(ElementRef, &str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        LintRule::Custom {
+            name: name.into(),
+            check: Arc::new(check),
+        }
+    }
+
+    /// All built-in rules
+    pub fn all() -> Vec<LintRule> {
+        vec![
+            LintRule::ImgMissingAlt,
+            LintRule::InlineEventHandlers,
+            LintRule::DeprecatedTags,
+            LintRule::DuplicateIds,
+        ]
+    }
+
+    /// The built-in rule called `name`, e.g. `img-missing-alt`
+    pub fn from_name(name: &str) -> Option<LintRule> {
+        LintRule::all().into_iter().find(|rule| rule.name() == name)
+    }
+
+    /// The name findings are reported under
+    pub fn name(&self) -> &str {
+        match self {
+            LintRule::ImgMissingAlt => "img-missing-alt",
+            LintRule::InlineEventHandlers => "inline-event-handlers",
+            LintRule::DeprecatedTags => "deprecated-tags",
+            LintRule::DuplicateIds => "duplicate-ids",
+            LintRule::Custom { name, .. } => name,
+        }
+    }
+}
+
+impl fmt::Debug for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintRule::Custom { name, .. } => write!(f, "Custom({:?})", name),
+            rule => f.write_str(rule.name()),
+        }
+    }
+}
+
+/// A finding of a [`LintRule`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// Name of the rule that reported it
+    pub rule: String,
+    /// Path of the offending element
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.path, self.message, self.rule)
+    }
+}
+
+/// Run `rules` on `html`, returning the findings in document order
+///
+/// # Examples
+/// ```ignore
+/// use html_compare_rs::lint::{lint, LintRule};
+///
+/// let lints = lint("<img src='a.png'>", &LintRule::all());
+/// assert_eq!(lints[0].rule, "img-missing-alt");
+/// ```
+pub fn lint(html: &str, rules: &[LintRule]) -> Vec<Lint> {
+    HtmlComparer::new().lint_document(&Html::parse_document(html), rules)
+}
+
+impl HtmlComparer {
+    /// Run the configured [`HtmlCompareOptions::lint_rules`](crate::HtmlCompareOptions)
+    /// on `html`
+    pub fn lint(&self, html: &str) -> Vec<Lint> {
+        self.lint_document(&self.parse(html), &self.options.lint_rules)
+    }
+
+    /// Run `rules` on the elements of `document` that take part in the
+    /// comparison
+    pub(crate) fn lint_document(&self, document: &Html, rules: &[LintRule]) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        if rules.is_empty() {
+            return lints;
+        }
+        let mut ids = HashMap::new();
+        self.walk_elements(document.root_element(), "/html", &mut |element, path| {
+            let mut report = |rule: &LintRule, message: String| {
+                lints.push(Lint {
+                    rule: rule.name().to_string(),
+                    path: path.to_string(),
+                    message,
+                })
+            };
+            for rule in rules {
+                match rule {
+                    LintRule::ImgMissingAlt => {
+                        if element.value().name() == "img" && element.attr("alt").is_none() {
+                            report(rule, "image without alt text".to_string());
+                        }
+                    }
+                    LintRule::InlineEventHandlers => {
+                        let mut handlers: Vec<_> = element
+                            .value()
+                            .attrs()
+                            .map(|(name, _)| name)
+                            .filter(|name| name.starts_with("on"))
+                            .collect();
+                        handlers.sort_unstable();
+                        for handler in handlers {
+                            report(rule, format!("inline event handler {}", handler));
+                        }
+                    }
+                    LintRule::DeprecatedTags => {
+                        let name = element.value().name();
+                        if DEPRECATED_TAGS.contains(&name) {
+                            report(rule, format!("deprecated element <{}>", name));
+                        }
+                    }
+                    LintRule::DuplicateIds => {
+                        let Some(id) = element.attr("id") else {
+                            continue;
+                        };
+                        match ids.get(id) {
+                            Some(first) => report(
+                                rule,
+                                format!("duplicate id {:?}, first used at {}", id, first),
+                            ),
+                            None => {
+                                ids.insert(id.to_string(), path.to_string());
+                            }
+                        }
+                    }
+                    LintRule::Custom { check, .. } => {
+                        if let Some(message) = check(element, path) {
+                            report(rule, message);
+                        }
+                    }
+                }
+            }
+        });
+        lints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::Warning;
+    use crate::HtmlCompareOptions;
+
+    const PAGE: &str = r#"<center id="top"><img src="logo.png"></center>
+        <button id="top" onclick="go()" onmouseover="hover()">Go</button>
+        <img src="photo.jpg" alt="">"#;
+
+    #[test]
+    fn test_builtin_rules() {
+        let lints: Vec<_> = lint(PAGE, &LintRule::all())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lints,
+            [
+                "/html/body/center: deprecated element <center> (deprecated-tags)",
+                "/html/body/center/img: image without alt text (img-missing-alt)",
+                "/html/body/button: inline event handler onclick (inline-event-handlers)",
+                "/html/body/button: inline event handler onmouseover (inline-event-handlers)",
+                "/html/body/button: duplicate id \"top\", first used at /html/body/center (duplicate-ids)",
+            ]
+        );
+        assert!(lint(PAGE, &[]).is_empty());
+        assert!(LintRule::from_name("img-missing-alt").is_some());
+    }
+
+    #[test]
+    fn test_lint_warnings_in_diff() {
+        let external = LintRule::custom("external-links", |element, _| {
+            let href = element.attr("href")?;
+            href.starts_with("http:")
+                .then(|| format!("insecure link {}", href))
+        });
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            lint_rules: vec![external, LintRule::ImgMissingAlt],
+            ..Default::default()
+        });
+        let html = r#"<a href="http://example.com">Example</a><img src="a.png">"#;
+        let diff = comparer.diff(html, html);
+        assert!(diff.is_empty());
+        let warnings: Vec<_> = diff.warnings().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            warnings,
+            [
+                "/html/body/a: insecure link http://example.com (external-links)",
+                "/html/body/img: image without alt text (img-missing-alt)",
+            ]
+        );
+        assert!(
+            matches!(&diff.warnings()[0], Warning::Lint(lint) if lint.rule == "external-links")
+        );
+        assert_eq!(comparer.lint(html).len(), 2);
+    }
+}
@@ -0,0 +1,354 @@
+//! Checking Markdown renderer output.
+//!
+//! Markdown renderers that agree on the document still differ in details: a
+//! list item may or may not wrap its text in `<p>` depending on how loose the
+//! renderer considers the list, code blocks may end with a newline, and
+//! headings get `id` slugs generated by renderer-specific rules. A
+//! [`MarkdownProfile`] tolerates exactly these differences for a Markdown
+//! flavor, and [`presets::markdown`](crate::presets::markdown) uses the
+//! CommonMark profile.
+
+use ego_tree::NodeRef;
+use scraper::{CaseSensitivity, ElementRef, Node};
+
+use crate::{tokens, HtmlCompareOptions};
+
+/// A Markdown dialect
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MarkdownFlavor {
+    #[default]
+    CommonMark,
+    /// GitHub Flavored Markdown, with task lists, tables and footnotes
+    Gfm,
+}
+
+/// Differences between Markdown renderers that are tolerated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownProfile {
+    pub flavor: MarkdownFlavor,
+    /// Treat `<li><p>x</p></li>` as `<li>x</li>`, i.e. loose and tight lists
+    /// as equal (see
+    /// [`HtmlCompareOptions::normalize_list_tightness`](crate::HtmlCompareOptions))
+    pub list_tightness: bool,
+    /// Ignore a newline at the end of `<pre><code>` blocks
+    pub code_trailing_newline: bool,
+    /// Ignore heading `id`s matching this pattern, where `*` matches any
+    /// run of characters, e.g. `user-content-*`
+    pub heading_ids: Option<String>,
+    /// Ignore the permalink anchors GitHub injects into headings,
+    /// `<a class="anchor" href="#...">`, and the `<div class="markdown-heading">`
+    /// that wraps a heading and its anchor
+    pub heading_anchors: bool,
+}
+
+impl Default for MarkdownProfile {
+    fn default() -> Self {
+        MarkdownProfile::commonmark()
+    }
+}
+
+impl MarkdownProfile {
+    /// Tolerate the differences between CommonMark renderers
+    pub fn commonmark() -> Self {
+        MarkdownProfile {
+            flavor: MarkdownFlavor::CommonMark,
+            list_tightness: true,
+            code_trailing_newline: true,
+            heading_ids: Some("*".to_string()),
+            heading_anchors: false,
+        }
+    }
+
+    /// Tolerate the differences between GitHub Flavored Markdown renderers
+    pub fn gfm() -> Self {
+        MarkdownProfile {
+            flavor: MarkdownFlavor::Gfm,
+            ..MarkdownProfile::commonmark()
+        }
+    }
+
+    /// Tolerate the differences between a GitHub Flavored Markdown renderer
+    /// and the HTML github.com serves for READMEs
+    pub fn github() -> Self {
+        MarkdownProfile {
+            heading_anchors: true,
+            ..MarkdownProfile::gfm()
+        }
+    }
+
+    /// Comparison options that tolerate exactly the differences of this
+    /// profile
+    pub fn options(&self) -> HtmlCompareOptions {
+        let mut options = HtmlCompareOptions {
+            ignore_whitespace: true,
+            ignore_comments: true,
+            ignore_style_contents: true,
+            markdown: Some(self.clone()),
+            ..Default::default()
+        };
+        if self.flavor == MarkdownFlavor::Gfm {
+            // Task list checkboxes are written as `disabled` or
+            // `disabled=""`, footnotes carry renderer bookkeeping and table
+            // and task list classes come in any order
+            options.normalize_boolean_attributes = true;
+            options
+                .ignored_attribute_prefixes
+                .push("data-footnote".to_string());
+            options.tokenized_attributes = tokens::html_token_lists();
+        }
+        options
+    }
+}
+
+/// Whether `node` is a heading permalink anchor ignored by the profile
+pub(crate) fn is_heading_anchor(options: &HtmlCompareOptions, node: NodeRef<Node>) -> bool {
+    if !heading_anchors(options) {
+        return false;
+    }
+    let Some(anchor) = ElementRef::wrap(node) else {
+        return false;
+    };
+    let in_heading = node
+        .parent()
+        .and_then(ElementRef::wrap)
+        .is_some_and(|parent| {
+            is_heading(parent.value().name()) || is_heading_wrapper(options, parent)
+        });
+    in_heading
+        && anchor.value().name() == "a"
+        && anchor
+            .value()
+            .has_class("anchor", CaseSensitivity::CaseSensitive)
+        && anchor
+            .value()
+            .attr("href")
+            .is_some_and(|href| href.starts_with('#'))
+}
+
+/// Whether `element` is a `<div class="markdown-heading">` compared as its
+/// children
+pub(crate) fn is_heading_wrapper(options: &HtmlCompareOptions, element: ElementRef) -> bool {
+    heading_anchors(options)
+        && element.value().name() == "div"
+        && element
+            .value()
+            .has_class("markdown-heading", CaseSensitivity::CaseSensitive)
+}
+
+fn heading_anchors(options: &HtmlCompareOptions) -> bool {
+    options
+        .markdown
+        .as_ref()
+        .is_some_and(|profile| profile.heading_anchors)
+}
+
+fn is_heading(name: &str) -> bool {
+    matches!(name, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+/// The class list `class` with code fence language classes spelled as
+/// `language-*`, as a sorted set
+pub(crate) fn normalize_fence_classes(class: &str) -> String {
+    let mut classes: Vec<String> = class
+        .split_ascii_whitespace()
+        .map(|class| {
+            ["lang-", "highlight-source-"]
+                .iter()
+                .find_map(|prefix| class.strip_prefix(prefix))
+                .map_or(class.to_string(), |language| {
+                    format!("language-{}", language)
+                })
+        })
+        .collect();
+    classes.sort_unstable();
+    classes.dedup();
+    classes.join(" ")
+}
+
+/// The only child of a list item, if it is a paragraph to unwrap
+pub(crate) fn sole_paragraph<'a>(
+    options: &HtmlCompareOptions,
+    element: ElementRef,
+    children: &[NodeRef<'a, Node>],
+) -> Option<ElementRef<'a>> {
+    let tightness = options.normalize_list_tightness
+        || options
+            .markdown
+            .as_ref()
+            .is_some_and(|profile| profile.list_tightness);
+    if !tightness || element.value().name() != "li" {
+        return None;
+    }
+    match children {
+        [child] => ElementRef::wrap(*child).filter(|child| child.value().name() == "p"),
+        _ => None,
+    }
+}
+
+/// `text` without the newline ending a code block, if `node` is the last
+/// text of one
+pub(crate) fn trim_code_newline<'a>(
+    options: &HtmlCompareOptions,
+    node: NodeRef<Node>,
+    text: &'a str,
+) -> &'a str {
+    if !options
+        .markdown
+        .as_ref()
+        .is_some_and(|profile| profile.code_trailing_newline)
+        || node.next_sibling().is_some()
+    {
+        return text;
+    }
+    let in_code_block = node
+        .parent()
+        .and_then(ElementRef::wrap)
+        .is_some_and(|code| {
+            code.value().name() == "code"
+                && code
+                    .parent()
+                    .and_then(ElementRef::wrap)
+                    .is_some_and(|pre| pre.value().name() == "pre")
+        });
+    match in_code_block {
+        true => text.strip_suffix('\n').unwrap_or(text),
+        false => text,
+    }
+}
+
+/// Whether the attribute `name` of `element` is a heading id ignored by the
+/// profile
+pub(crate) fn is_ignored_heading_id(
+    options: &HtmlCompareOptions,
+    element: ElementRef,
+    name: &str,
+    value: &str,
+) -> bool {
+    let Some(pattern) = options
+        .markdown
+        .as_ref()
+        .and_then(|profile| profile.heading_ids.as_deref())
+    else {
+        return false;
+    };
+    name == "id" && is_heading(element.value().name()) && matches_pattern(pattern, value)
+}
+
+/// Whether `value` matches `pattern`, where `*` matches any run of characters
+pub(crate) fn matches_pattern(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<_> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlComparer;
+
+    #[test]
+    fn test_commonmark_profile() {
+        let comparer = HtmlComparer::with_options(MarkdownProfile::commonmark().options());
+        comparer
+            .verify(
+                "<h2 id='install'>Install</h2><ul><li>One</li><li><p>Two</p></li></ul><pre><code>cargo add x\n</code></pre>",
+                "<h2 id='installation'>Install</h2><ul><li><p>One</p></li><li>Two</li></ul><pre><code>cargo add x</code></pre>",
+            )
+            .unwrap();
+        assert!(comparer
+            .verify("<p id='a'>Text</p>", "<p id='b'>Text</p>")
+            .is_err());
+        assert!(comparer
+            .verify(
+                "<ul><li><p>One</p><p>More</p></li></ul>",
+                "<ul><li>One More</li></ul>"
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_gfm_profile() {
+        let profile = MarkdownProfile {
+            heading_ids: Some("user-content-*".to_string()),
+            ..MarkdownProfile::gfm()
+        };
+        let comparer = HtmlComparer::with_options(profile.options());
+        comparer
+            .verify(
+                r#"<h1 id="user-content-intro">Intro</h1><ul class="contains-task-list"><li class="task-list-item"><input type="checkbox" disabled=""> Done</li></ul>"#,
+                r#"<h1 id="user-content-introduction">Intro</h1><ul class="contains-task-list"><li class="task-list-item"><input disabled type="checkbox"> Done</li></ul>"#,
+            )
+            .unwrap();
+        assert!(comparer
+            .verify("<h1 id='intro'>Intro</h1>", "<h1 id='other'>Intro</h1>")
+            .is_err());
+    }
+
+    #[test]
+    fn test_github_readme_preset() {
+        let comparer = HtmlComparer::with_options(crate::presets::github_readme());
+        comparer
+            .verify(
+                r##"<h2 id="usage">Usage</h2>
+                <p>See <a href="https://docs.rs" rel="noopener">the docs</a>.</p>
+                <pre><code class="language-rust">let x = 1;</code></pre>
+                <p>Plain <a href="/x">link</a></p>"##,
+                r##"<div class="markdown-heading" dir="auto"><h2 class="heading-element">Usage</h2><a id="user-content-usage" class="anchor" aria-label="Permalink: Usage" href="#usage"><svg class="octicon octicon-link"><path d="M0 0"></path></svg></a></div>
+                <p dir="auto">See <a href="https://docs.rs" rel="nofollow noopener">the docs</a>.</p>
+                <pre><code class="lang-rust">let x = 1;</code></pre>
+                <p dir="auto">Plain <a href="/x" rel="nofollow">link</a></p>"##,
+            )
+            .unwrap();
+        assert!(!comparer
+            .is_equal(
+                r#"<a href="/x" rel="noopener">x</a>"#,
+                r#"<a href="/x" rel="nofollow">x</a>"#,
+            )
+            .unwrap());
+        assert!(!comparer
+            .is_equal(
+                r#"<pre><code class="language-rust">x</code></pre>"#,
+                r#"<pre><code class="lang-go">x</code></pre>"#,
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn test_normalize_list_tightness() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            normalize_list_tightness: true,
+            ..Default::default()
+        });
+        let tight = "<ol><li>First</li><li>Second <em>item</em></li></ol>";
+        let loose =
+            "<ol>\n<li>\n<p>First</p>\n</li>\n<li>\n<p>Second <em>item</em></p>\n</li>\n</ol>";
+        comparer.verify(tight, loose).unwrap();
+        assert!(HtmlComparer::new().verify(tight, loose).is_err());
+        assert!(comparer
+            .verify("<ul><li><p>a</p>b</li></ul>", "<ul><li>a b</li></ul>")
+            .is_err());
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*", "anything"));
+        assert!(matches_pattern("user-content-*", "user-content-x"));
+        assert!(matches_pattern("*-*-1", "a-b-1"));
+        assert!(!matches_pattern("user-content-*", "intro"));
+        assert!(!matches_pattern("a*a", "a"));
+        assert!(matches_pattern("exact", "exact"));
+    }
+}
@@ -0,0 +1,97 @@
+//! HTML from typed builders in the assertion macros.
+//!
+//! [`assert_html_eq!`](crate::assert_html_eq) and
+//! [`assert_html_ne!`](crate::assert_html_ne) accept anything implementing
+//! [`RenderToHtml`], not only strings. With the `maud` feature that includes
+//! `maud::Markup`, so builder output is passed as is and failure messages
+//! name the type it came from.
+
+use std::borrow::Cow;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A value that renders to an HTML string
+pub trait RenderToHtml {
+    /// The HTML
+    fn render_to_html(&self) -> Cow<'_, str>;
+
+    /// The type named in failure messages, `None` for plain strings
+    fn type_label(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<Self>())
+    }
+}
+
+impl<T: RenderToHtml + ?Sized> RenderToHtml for &T {
+    fn render_to_html(&self) -> Cow<'_, str> {
+        (**self).render_to_html()
+    }
+
+    fn type_label(&self) -> Option<&'static str> {
+        (**self).type_label()
+    }
+}
+
+macro_rules! impl_for_strings {
+    ($($string:ty),*) => {$(
+        impl RenderToHtml for $string {
+            fn render_to_html(&self) -> Cow<'_, str> {
+                Cow::Borrowed(self)
+            }
+
+            fn type_label(&self) -> Option<&'static str> {
+                None
+            }
+        }
+    )*};
+}
+
+impl_for_strings!(str, String, Cow<'_, str>, Box<str>, Rc<str>, Arc<str>);
+
+#[cfg(feature = "maud")]
+impl<T: AsRef<str>> RenderToHtml for maud::PreEscaped<T> {
+    fn render_to_html(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.0.as_ref())
+    }
+
+    fn type_label(&self) -> Option<&'static str> {
+        Some("maud::Markup")
+    }
+}
+
+/// ` (type)` for values that are not plain strings, for failure messages
+#[doc(hidden)]
+pub fn __label<T: RenderToHtml + ?Sized>(value: &T) -> String {
+    match value.type_label() {
+        Some(label) => format!(" ({})", label),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stand-in for the output of an HTML builder
+    struct Page(&'static str);
+
+    impl RenderToHtml for Page {
+        fn render_to_html(&self) -> Cow<'_, str> {
+            Cow::Borrowed(self.0)
+        }
+    }
+
+    #[test]
+    fn test_macros_accept_render_to_html() {
+        assert_html_eq!(Page("<p>Hi</p>"), "<p>Hi</p>");
+        assert_html_eq!(String::from("<p>Hi</p>"), Page("<p> Hi </p>"));
+        assert_html_ne!(Page("<p>Hi</p>"), Arc::<str>::from("<p>Bye</p>"));
+        assert_eq!(__label(&"<p>"), "");
+        assert_eq!(__label(&&String::new()), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "left HTML (html_compare_rs::markup::tests::Page):")]
+    fn test_failure_names_the_type() {
+        assert_html_eq!(Page("<p>Hi</p>"), "<p>Bye</p>");
+    }
+}
@@ -0,0 +1,482 @@
+//! Checking volatile values by their shape instead of ignoring them.
+//!
+//! Rendered timestamps change on every run. Ignoring them with
+//! [`HtmlCompareOptions::ignore_text_in`](crate::HtmlCompareOptions) also
+//! accepts a page that prints `Invalid Date`. A [`Matcher`] registered for a
+//! selector in [`HtmlCompareOptions::matchers`](crate::HtmlCompareOptions)
+//! instead accepts any actual value it approves of, whatever the expected
+//! document says:
+//!
+//! ```ignore
+//! options.matchers = vec![
+//!     ("time".to_string(), Matcher::iso8601_within(Duration::from_secs(60)).on_attribute("datetime")),
+//!     ("time".to_string(), Matcher::relative_time_text()),
+//! ];
+//! ```
+//!
+//! A matcher checks the text inside elements matching its selector in the
+//! expected document or, with [`Matcher::on_attribute`], the value of that
+//! attribute on them. Besides times, [`Matcher::one_of`] and
+//! [`Matcher::catalog`] accept the messages of a translation catalog, so one
+//! fixture checks the structure of every locale. Matchers are applied by
+//! [`HtmlComparer::verify`](crate::HtmlComparer::verify),
+//! [`HtmlComparer::is_equal`](crate::HtmlComparer::is_equal) and the
+//! exhaustive [`HtmlComparer::diff`](crate::HtmlComparer::diff) alike.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node, Selector};
+
+/// Check of a [`Matcher`]
+pub type MatcherCheck = dyn Fn(&str) -> bool + Send + Sync;
+
+/// A predicate on actual values, for the text or an attribute of selected
+/// elements
+#[derive(Clone)]
+pub struct Matcher {
+    name: String,
+    check: Arc<MatcherCheck>,
+    attribute: Option<String>,
+}
+
+impl Matcher {
+    /// A matcher named `name` that accepts the values `check` returns true
+    /// for
+    pub fn new(
+        name: impl Into<String>,
+        check: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Matcher {
+            name: name.into(),
+            check: Arc::new(check),
+            attribute: None,
+        }
+    }
+
+    /// An ISO 8601 date or date and time, e.g. `2024-05-01T12:30:00Z`, at
+    /// most `tolerance` away from the time of the comparison
+    ///
+    /// Times without an offset are taken as UTC.
+    pub fn iso8601_within(tolerance: Duration) -> Self {
+        Matcher::new(format!("iso8601_within({:?})", tolerance), move |value| {
+            let Some(time) = parse_iso8601(value.trim()) else {
+                return false;
+            };
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs() as i64);
+            (time - now).unsigned_abs() <= tolerance.as_secs()
+        })
+    }
+
+    /// Relative time as written by humanizing libraries: `just now`,
+    /// `3 minutes ago`, `in an hour`, `yesterday`
+    pub fn relative_time_text() -> Self {
+        Matcher::new("relative_time_text", is_relative_time)
+    }
+
+    /// Any of `messages`, e.g. the translations of a message into every
+    /// supported locale and its plural forms
+    ///
+    /// Variables in the messages match any text: `{ $count }` as in Fluent
+    /// and `%s`, `%d` or `%1$s` as in gettext.
+    pub fn one_of<I, S>(messages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let patterns: Vec<Vec<String>> = messages
+            .into_iter()
+            .map(|message| message_pattern(&message.into()))
+            .collect();
+        Matcher::new("one_of", move |value| {
+            let value = value.trim();
+            patterns
+                .iter()
+                .any(|pattern| matches_message(pattern, value))
+        })
+    }
+
+    /// Any of the messages `load` returns, as in [`Matcher::one_of`], for
+    /// messages read from a Fluent or gettext catalog
+    pub fn catalog<I, S>(name: impl Into<String>, load: impl FnOnce() -> I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Matcher {
+            name: name.into(),
+            ..Matcher::one_of(load())
+        }
+    }
+
+    /// Check the value of the attribute `name` instead of the text
+    pub fn on_attribute(mut self, name: impl Into<String>) -> Self {
+        self.attribute = Some(name.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The attribute checked, if not the text
+    pub fn attribute(&self) -> Option<&str> {
+        self.attribute.as_deref()
+    }
+
+    /// Whether `value` is accepted
+    pub fn matches(&self, value: &str) -> bool {
+        (self.check)(value)
+    }
+}
+
+impl fmt::Debug for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.attribute {
+            Some(attribute) => write!(f, "{}@{}", self.name, attribute),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// Parse the selectors of `matchers`, or describe the first invalid one
+pub(crate) fn compile_matchers(
+    matchers: &[(String, Matcher)],
+) -> Result<Vec<(Selector, Matcher)>, String> {
+    matchers
+        .iter()
+        .map(|(selector, matcher)| {
+            Selector::parse(selector)
+                .map(|compiled| (compiled, matcher.clone()))
+                .map_err(|err| format!("{}: {}", selector, err))
+        })
+        .collect()
+}
+
+/// The matcher for the text node or element `node` of the expected
+/// document, or for its attribute `attribute`
+pub(crate) fn matcher_for<'m>(
+    matchers: &'m [(Selector, Matcher)],
+    node: NodeRef<Node>,
+    attribute: Option<&str>,
+) -> Option<&'m Matcher> {
+    if matchers.is_empty() {
+        return None;
+    }
+    let matches = |element: ElementRef| {
+        matchers.iter().find(|(selector, matcher)| {
+            matcher.attribute.as_deref() == attribute && selector.matches(&element)
+        })
+    };
+    let found = match attribute {
+        Some(_) => ElementRef::wrap(node).and_then(matches),
+        None => node
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .find_map(matches),
+    };
+    found.map(|(_, matcher)| matcher)
+}
+
+/// Seconds since the Unix epoch of an ISO 8601 date or date and time
+fn parse_iso8601(value: &str) -> Option<i64> {
+    let number = |text: &str| -> Option<i64> {
+        text.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| text.parse().ok())?
+    };
+    let date = value.get(..10)?;
+    let year = number(date.get(..4)?)?;
+    let month = number(date.get(5..7)?)?;
+    let day = number(date.get(8..10)?)?;
+    if &date[4..5] != "-"
+        || &date[7..8] != "-"
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+    {
+        return None;
+    }
+    let mut seconds = days_from_civil(year, month, day) * 86_400;
+    let rest = &value[10..];
+    if rest.is_empty() {
+        return Some(seconds);
+    }
+    let time = rest.strip_prefix(['T', 't', ' '])?;
+    let hour = number(time.get(..2)?)?;
+    let minute = number(time.get(3..5)?)?;
+    if &time[2..3] != ":" || hour > 23 || minute > 59 {
+        return None;
+    }
+    seconds += hour * 3600 + minute * 60;
+    let mut zone = &time[5..];
+    if let Some(with_seconds) = zone.strip_prefix(':') {
+        let second = number(with_seconds.get(..2)?)?;
+        if second > 60 {
+            return None;
+        }
+        seconds += second;
+        zone = &with_seconds[2..];
+        if let Some(fraction) = zone.strip_prefix(['.', ',']) {
+            let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                return None;
+            }
+            zone = &fraction[digits..];
+        }
+    }
+    match zone {
+        "" | "Z" | "z" => Some(seconds),
+        _ => {
+            let sign = match zone.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let offset = zone[1..].replace(':', "");
+            if offset.len() != 4 {
+                return None;
+            }
+            let hours = number(offset.get(..2)?)?;
+            let minutes = number(offset.get(2..)?)?;
+            Some(seconds - sign * (hours * 3600 + minutes * 60))
+        }
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The literal parts of a localized message, between its variables
+fn message_pattern(message: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = message.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // Fluent placeables, `{ $count }`
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+                parts.push(String::new());
+            }
+            // gettext conversions, `%s`, `%d`, `%1$s`; `%%` is a literal `%`
+            '%' if chars.peek() == Some(&'%') => {
+                chars.next();
+                parts.last_mut().unwrap().push('%');
+            }
+            '%' if chars.peek().is_some_and(char::is_ascii_alphanumeric) => {
+                while chars.next_if(|c| c.is_ascii_digit() || *c == '$').is_some() {}
+                chars.next();
+                parts.push(String::new());
+            }
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// Whether `value` spells the message of `parts` with some text for each
+/// variable
+fn matches_message(parts: &[String], value: &str) -> bool {
+    let Some((first, rest)) = parts.split_first() else {
+        return value.is_empty();
+    };
+    let Some(mut value) = value.strip_prefix(first.as_str()) else {
+        return false;
+    };
+    let Some((last, middle)) = rest.split_last() else {
+        return value.is_empty();
+    };
+    for part in middle {
+        match value.find(part.as_str()) {
+            Some(index) => value = &value[index + part.len()..],
+            None => return false,
+        }
+    }
+    value.ends_with(last.as_str())
+}
+
+const TIME_UNITS: &[&str] = &["second", "minute", "hour", "day", "week", "month", "year"];
+
+fn is_relative_time(value: &str) -> bool {
+    let words: Vec<String> = value
+        .split_ascii_whitespace()
+        .map(|word| word.to_ascii_lowercase())
+        .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let amount = |word: &str| {
+        matches!(word, "a" | "an" | "one" | "few" | "several")
+            || word.bytes().all(|b| b.is_ascii_digit())
+    };
+    let unit = |word: &str| {
+        TIME_UNITS
+            .iter()
+            .any(|unit| word == *unit || word.strip_suffix('s') == Some(unit))
+    };
+    match words.as_slice() {
+        ["now"]
+        | ["just", "now"]
+        | ["moments", "ago"]
+        | ["yesterday"]
+        | ["today"]
+        | ["tomorrow"] => true,
+        [count, length, "ago"] => amount(count) && unit(length),
+        ["a", "few", length, "ago"] => unit(length),
+        ["in", count, length] => amount(count) && unit(length),
+        ["in", "a", "few", length] => unit(length),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    /// `seconds` since the epoch as `YYYY-MM-DDTHH:MM:SSZ`
+    fn format_iso8601(seconds: i64) -> String {
+        let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+        // Inverse of days_from_civil
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year,
+            month,
+            day,
+            time / 3600,
+            time % 3600 / 60,
+            time % 60
+        )
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        assert_eq!(parse_iso8601("1970-01-01"), Some(0));
+        assert_eq!(parse_iso8601("2000-03-01T00:00:00Z"), Some(951_868_800));
+        assert_eq!(
+            parse_iso8601("2000-03-01T02:30:00.123+02:30"),
+            Some(951_868_800)
+        );
+        assert_eq!(parse_iso8601("2000-03-01 00:00"), Some(951_868_800));
+        assert_eq!(parse_iso8601("2000-13-01"), None);
+        assert_eq!(parse_iso8601("yesterday"), None);
+        assert_eq!(
+            parse_iso8601(&format_iso8601(951_868_800)),
+            Some(951_868_800)
+        );
+
+        assert!(is_relative_time("3 minutes ago"));
+        assert!(is_relative_time("In an hour"));
+        assert!(is_relative_time("just now"));
+        assert!(!is_relative_time("3 bananas ago"));
+        assert!(!is_relative_time("Invalid Date"));
+    }
+
+    #[test]
+    fn test_localized_messages() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            matchers: vec![(
+                ".cart-count".to_string(),
+                Matcher::catalog("cart-count", || {
+                    [
+                        "{ $count } item in your cart",
+                        "{ $count } items in your cart",
+                        "%d article dans votre panier",
+                        "%1$d articles dans votre panier",
+                        "Ihr Warenkorb ist leer",
+                    ]
+                }),
+            )],
+            ..Default::default()
+        });
+        let expected = "<p class='cart-count'>1 item in your cart</p>";
+        for actual in [
+            "<p class='cart-count'>3 items in your cart</p>",
+            "<p class='cart-count'>2 articles dans votre panier</p>",
+            "<p class='cart-count'>Ihr Warenkorb ist leer</p>",
+        ] {
+            comparer.verify(expected, actual).unwrap();
+            assert!(comparer.diff(expected, actual).is_empty(), "{}", actual);
+        }
+        assert!(!comparer
+            .is_equal(expected, "<p class='cart-count'>cart.count</p>")
+            .unwrap());
+        assert_eq!(
+            comparer
+                .diff(expected, "<p class='cart-count'>cart.count</p>")
+                .len(),
+            1
+        );
+        assert!(!comparer
+            .is_equal(
+                expected,
+                "<div class='cart-count'>3 items in your cart</div>"
+            )
+            .unwrap());
+        assert!(matches_message(
+            &message_pattern("100%% of %s"),
+            "100% of it"
+        ));
+    }
+
+    #[test]
+    fn test_time_matchers() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            matchers: vec![
+                (
+                    "time".to_string(),
+                    Matcher::iso8601_within(Duration::from_secs(300)).on_attribute("datetime"),
+                ),
+                ("time".to_string(), Matcher::relative_time_text()),
+            ],
+            ..Default::default()
+        });
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let expected =
+            r#"<p>Posted <time datetime="2020-01-01T00:00:00Z">5 minutes ago</time></p>"#;
+        let actual = |time: i64, text: &str| {
+            format!(
+                r#"<p>Posted <time datetime="{}">{}</time></p>"#,
+                format_iso8601(time),
+                text
+            )
+        };
+        comparer
+            .verify(expected, &actual(now - 60, "a minute ago"))
+            .unwrap();
+        assert!(!comparer
+            .is_equal(expected, &actual(now - 3600, "an hour ago"))
+            .unwrap());
+        assert!(!comparer
+            .is_equal(expected, &actual(now, "Invalid Date"))
+            .unwrap());
+        assert!(!comparer
+            .is_equal(expected, &actual(now, "now").replace("Posted", "Edited"))
+            .unwrap());
+    }
+}
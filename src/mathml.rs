@@ -0,0 +1,155 @@
+//! Normalization of incidental MathML structure.
+//!
+//! LaTeX-to-MathML converters agree on what a formula means but not on how
+//! to spell it: some wrap every group in `<mrow>`, some write namespace
+//! prefixes such as `<m:mi>`, some emit invisible operators like
+//! `&InvisibleTimes;` and others leave them out. With
+//! [`HtmlCompareOptions::mathml`](crate::HtmlCompareOptions) set, these
+//! variations compare as equal inside `<math>` elements.
+
+use std::borrow::Cow;
+
+use scraper::{ElementRef, Node};
+
+use crate::{ChildNodes, HtmlComparer};
+
+/// Elements whose children form an implicit `<mrow>`, so an explicit one
+/// directly inside them only groups what is already grouped
+const INFERRED_MROW_ELEMENTS: &[&str] = &[
+    "math", "mrow", "msqrt", "mstyle", "merror", "mpadded", "mphantom", "menclose", "mtd",
+];
+
+/// Token elements, whose text content is the content of the formula
+const TOKEN_ELEMENTS: &[&str] = &["mi", "mn", "mo", "ms", "mtext"];
+
+/// Strip a namespace prefix such as `m:` from an element name
+pub(crate) fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
+
+/// Whether `element` is a `<math>` element or inside one
+pub(crate) fn in_math(element: ElementRef) -> bool {
+    element
+        .ancestors()
+        .chain(std::iter::once(*element))
+        .filter_map(ElementRef::wrap)
+        .any(|el| local_name(el.value().name()) == "math")
+}
+
+/// Whether `name` is a namespace declaration rather than a real attribute
+pub(crate) fn is_namespace_declaration(name: &str) -> bool {
+    name == "xmlns" || name.starts_with("xmlns:")
+}
+
+/// Normalize the text of a token element
+///
+/// Invisible operators and zero-width spaces are dropped, the Unicode minus
+/// sign is treated as `-`, and whitespace (including the named MathML spaces
+/// such as `&ThinSpace;`) is trimmed and collapsed.
+pub(crate) fn normalize_token_text(text: &str) -> String {
+    let mapped: String = text
+        .chars()
+        .filter(|c| !is_invisible(*c))
+        .map(|c| if c == '\u{2212}' { '-' } else { c })
+        .collect();
+    mapped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The text of a child of the MathML element `parent`, as compared
+pub(crate) fn math_text<'a>(parent: ElementRef, text: &'a str) -> Cow<'a, str> {
+    if TOKEN_ELEMENTS.contains(&local_name(parent.value().name())) {
+        Cow::Owned(normalize_token_text(text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{2061}'..='\u{2064}' | '\u{200B}')
+}
+
+impl HtmlComparer {
+    /// Remove incidental structure from the children of a MathML element
+    ///
+    /// Redundant `<mrow>` wrappers are replaced by their contents, operators
+    /// that only hold invisible characters and whitespace between elements
+    /// are dropped.
+    pub(crate) fn normalize_math_children<'a>(
+        &self,
+        parent: ElementRef<'a>,
+        children: ChildNodes<'a>,
+    ) -> ChildNodes<'a> {
+        let inferred_mrow = INFERRED_MROW_ELEMENTS.contains(&local_name(parent.value().name()));
+        let mut normalized = ChildNodes::with_capacity(children.len());
+        for child in children {
+            let Some(element) = ElementRef::wrap(child) else {
+                if !matches!(child.value(), Node::Text(text) if text.trim().is_empty()) {
+                    normalized.push(child);
+                }
+                continue;
+            };
+            match local_name(element.value().name()) {
+                "mrow" => {
+                    let contents = self.child_nodes(element);
+                    if inferred_mrow || contents.len() == 1 {
+                        normalized.extend(contents);
+                    } else {
+                        normalized.push(child);
+                    }
+                }
+                "mo" if normalize_token_text(&element.text().collect::<String>()).is_empty() => {}
+                _ => normalized.push(child),
+            }
+        }
+        normalized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{presets, HtmlCompareOptions};
+
+    #[test]
+    fn test_normalize_token_text() {
+        assert_eq!(normalize_token_text(" \u{2212} "), "-");
+        assert_eq!(normalize_token_text("sin\u{2061}"), "sin");
+        assert_eq!(normalize_token_text("a\u{2009}\u{2009}b"), "a b");
+    }
+
+    #[test]
+    fn test_mathml_converter_variations() {
+        let comparer = HtmlComparer::with_options(presets::mathml());
+
+        // Redundant mrows, entities and invisible operators
+        let expected = r#"<math><mi>x</mi><mo>&minus;</mo><mn>1</mn></math>"#;
+        let actual = r#"<math xmlns="http://www.w3.org/1998/Math/MathML">
+            <mrow><mrow><mi> x </mi></mrow><mo>-</mo><mn>1</mn></mrow>
+        </math>"#;
+        comparer.verify(expected, actual).unwrap();
+
+        let expected = "<math><mn>2</mn><mi>x</mi></math>";
+        let actual = "<math><mn>2</mn><mo>&InvisibleTimes;</mo><mi>x</mi></math>";
+        comparer.verify(expected, actual).unwrap();
+
+        // An mrow that groups a fraction's numerator is meaningful
+        let expected =
+            "<math><mfrac><mrow><mi>a</mi><mo>+</mo><mi>b</mi></mrow><mi>c</mi></mfrac></math>";
+        let actual = "<math><mfrac><mi>a</mi><mo>+</mo><mi>b</mi><mi>c</mi></mfrac></math>";
+        assert!(comparer.verify(expected, actual).is_err());
+        let actual = "<math><mfrac><mrow><mrow><mi>a</mi><mo>+</mo><mi>b</mi></mrow></mrow><mrow><mi>c</mi></mrow></mfrac></math>";
+        comparer.verify(expected, actual).unwrap();
+
+        // Namespace prefixes from XML converters
+        let actual = r#"<m:math xmlns:m="http://www.w3.org/1998/Math/MathML"><m:mfrac><m:mrow><m:mi>a</m:mi><m:mo>+</m:mo><m:mi>b</m:mi></m:mrow><m:mi>c</m:mi></m:mfrac></m:math>"#;
+        comparer.verify(expected, actual).unwrap();
+
+        // Outside <math> nothing changes
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            mathml: true,
+            ..Default::default()
+        });
+        assert!(comparer.verify("<p>a\u{2212}b</p>", "<p>a-b</p>").is_err());
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+//! Comparison of mdBook builds.
+//!
+//! Upgrading mdBook or switching highlighters rebuilds every chapter, and
+//! what matters is which chapters changed. [`diff_books`] walks two build
+//! directories, pairs the chapter pages found at the same relative path,
+//! compares them with [`presets::mdbook`] and collects the differences per
+//! chapter, along with the chapters that only one build has. The pages
+//! mdBook generates besides the chapters, `print.html`, `toc.html` and
+//! `404.html`, are skipped, as `print.html` repeats every chapter.
+//! [`HtmlComparer::diff_book_dirs`] does the same with other options.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{presets, HtmlComparer, HtmlDiff};
+
+/// Pages of a build's root directory that are not chapters
+const GENERATED_PAGES: &[&str] = &["print.html", "toc.html", "404.html"];
+
+/// The differences between two builds of a book, per chapter
+///
+/// Chapters are identified by their path relative to the build directory,
+/// e.g. `guide/setup.html`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookDiff {
+    chapters: Vec<(PathBuf, HtmlDiff)>,
+    compared: usize,
+    missing: Vec<PathBuf>,
+    extra: Vec<PathBuf>,
+}
+
+impl BookDiff {
+    /// Whether both builds have the same chapters and all of them are
+    /// equivalent
+    pub fn is_empty(&self) -> bool {
+        self.chapters.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+
+    /// Number of chapters found in both builds and compared
+    pub fn compared(&self) -> usize {
+        self.compared
+    }
+
+    /// The differences of the chapter at `path`, if it has any
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&HtmlDiff> {
+        self.chapters
+            .iter()
+            .find(|(chapter, _)| chapter == path.as_ref())
+            .map(|(_, diff)| diff)
+    }
+
+    /// Iterate over the paths and differences of the chapters that differ,
+    /// in order of their paths
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &HtmlDiff)> {
+        self.chapters
+            .iter()
+            .map(|(path, diff)| (path.as_path(), diff))
+    }
+
+    /// Chapters of the expected build missing from the actual one
+    pub fn missing(&self) -> &[PathBuf] {
+        &self.missing
+    }
+
+    /// Chapters of the actual build missing from the expected one
+    pub fn extra(&self) -> &[PathBuf] {
+        &self.extra
+    }
+
+    /// A bounded report: which chapters differ, each with its
+    /// [`HtmlDiff::summary`], and which only one build has
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} of {} chapter{} differ",
+            self.chapters.len(),
+            self.compared,
+            if self.compared == 1 { "" } else { "s" }
+        );
+        for (label, paths) in [("missing", &self.missing), ("unexpected", &self.extra)] {
+            if !paths.is_empty() {
+                let paths: Vec<_> = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                summary.push_str(&format!("; {}: {}", label, paths.join(", ")));
+            }
+        }
+        for (path, diff) in &self.chapters {
+            summary.push_str(&format!("\n{}: ", path.display()));
+            summary.push_str(&diff.summary().replace('\n', "\n  "));
+        }
+        summary
+    }
+}
+
+impl fmt::Display for BookDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for path in &self.missing {
+            writeln!(f, "missing chapter {}", path.display())?;
+        }
+        for path in &self.extra {
+            writeln!(f, "unexpected chapter {}", path.display())?;
+        }
+        for (path, diff) in &self.chapters {
+            writeln!(f, "{}:", path.display())?;
+            for line in diff.to_string().lines() {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare the chapters of two mdBook build directories, e.g. `book/`,
+/// using [`presets::mdbook`]
+///
+/// # Examples
+/// ```ignore
+/// let diff = html_compare::mdbook::diff_books("book-0.4.36", "book")?;
+/// assert!(diff.is_empty(), "{}", diff.summary());
+/// ```
+pub fn diff_books(expected: impl AsRef<Path>, actual: impl AsRef<Path>) -> io::Result<BookDiff> {
+    HtmlComparer::with_options(presets::mdbook()).diff_book_dirs(expected, actual)
+}
+
+/// The paths of the chapter pages under `root`, relative to it and sorted
+fn chapter_paths(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    let mut directories = vec![PathBuf::new()];
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(root.join(&directory))? {
+            let entry = entry?;
+            let path = directory.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                directories.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "html")
+                && !GENERATED_PAGES.iter().any(|page| path.as_os_str() == *page)
+            {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+impl HtmlComparer {
+    /// Compare the chapters of two mdBook build directories as
+    /// [`diff_books`] does, with the options of this comparer
+    pub fn diff_book_dirs(
+        &self,
+        expected: impl AsRef<Path>,
+        actual: impl AsRef<Path>,
+    ) -> io::Result<BookDiff> {
+        let (expected, actual) = (expected.as_ref(), actual.as_ref());
+        let expected_paths = chapter_paths(expected)?;
+        let actual_paths = chapter_paths(actual)?;
+        let mut diff = BookDiff {
+            extra: actual_paths
+                .iter()
+                .filter(|path| !expected_paths.contains(path))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+        for path in expected_paths {
+            if !actual_paths.contains(&path) {
+                diff.missing.push(path);
+                continue;
+            }
+            let chapter = self.diff(
+                &std::fs::read_to_string(expected.join(&path))?,
+                &std::fs::read_to_string(actual.join(&path))?,
+            );
+            diff.compared += 1;
+            if !chapter.is_empty() {
+                diff.chapters.push((path, chapter));
+            }
+        }
+        Ok(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `pages` into a fresh build directory named after `name`
+    fn build(name: &str, pages: &[(&str, &str)]) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("html-compare-book-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        for (path, main) in pages {
+            let path = directory.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let page = format!(r#"<nav id="sidebar">{}</nav><main>{}</main>"#, name, main);
+            std::fs::write(path, page).unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn test_diff_books() {
+        let old = build(
+            "old",
+            &[
+                (
+                    "intro.html",
+                    r##"<h1 id="intro"><a class="header" href="#intro">Intro</a></h1>"##,
+                ),
+                ("guide/setup.html", "<p>Install it</p>"),
+                ("guide/usage.html", "<p>Run it</p>"),
+                ("print.html", "<p>Everything</p>"),
+            ],
+        );
+        let new = build(
+            "new",
+            &[
+                (
+                    "intro.html",
+                    r##"<h1 id="introduction"><a class="header" href="#introduction">Intro</a></h1>"##,
+                ),
+                ("guide/setup.html", "<p>Install it first</p>"),
+                ("guide/faq.html", "<p>Ask</p>"),
+                ("print.html", "<p>Everything, twice</p>"),
+            ],
+        );
+
+        let diff = diff_books(&old, &new).unwrap();
+        assert!(!diff.is_empty());
+        assert_eq!(diff.compared(), 2);
+        assert_eq!(
+            diff.iter().map(|(path, _)| path).collect::<Vec<_>>(),
+            [Path::new("guide/setup.html")]
+        );
+        assert!(diff.get("intro.html").is_none());
+        assert_eq!(diff.missing(), [PathBuf::from("guide/usage.html")]);
+        assert_eq!(diff.extra(), [PathBuf::from("guide/faq.html")]);
+        assert!(diff.summary().starts_with(
+            "1 of 2 chapters differ; missing: guide/usage.html; unexpected: guide/faq.html\nguide/setup.html: 1 difference"
+        ));
+        assert!(diff_books(&old, &old).unwrap().is_empty());
+        assert!(diff_books(&old, old.join("absent")).is_err());
+
+        std::fs::remove_dir_all(&old).unwrap();
+        std::fs::remove_dir_all(&new).unwrap();
+    }
+}
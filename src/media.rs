@@ -0,0 +1,209 @@
+//! Semantic comparison of `media` and `sizes` attributes.
+//!
+//! `media="screen and (min-width:600px), print"` and
+//! `media="print, SCREEN and (min-width: 600px)"` select the same media. With
+//! [`HtmlCompareOptions::compare_media_queries`](crate::HtmlCompareOptions)
+//! set, both attributes are parsed into media conditions and compared in a
+//! canonical form: case and whitespace are normalized, the features joined by
+//! `and` are sorted, and so are the queries of a `media` list. The entries of
+//! a `sizes` list keep their order, since the first matching one wins.
+
+/// Whether the attribute `name` holds media conditions
+pub(crate) fn is_media_attribute(name: &str) -> bool {
+    matches!(name, "media" | "sizes")
+}
+
+/// The canonical form of the value of a `media` or `sizes` attribute
+pub(crate) fn normalize(name: &str, value: &str) -> String {
+    let tokens = tokenize(value);
+    let groups = groups(&tokens);
+    let mut entries: Vec<String> = groups
+        .split(|group| is_token(group, ","))
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            if name == "sizes" {
+                size_entry(entry)
+            } else {
+                condition(entry)
+            }
+        })
+        .collect();
+    if name == "media" {
+        entries.sort_unstable();
+        entries.dedup();
+    }
+    entries.join(", ")
+}
+
+/// Split a value into lowercase words, punctuation and comparison operators
+///
+/// A function name is kept together with its opening parenthesis, as in
+/// `min(`.
+fn tokenize(value: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = value.chars().flat_map(char::to_lowercase).peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' | ')' | ',' | ':' | '/' => tokens.push(c.to_string()),
+            '<' | '>' | '=' => {
+                let mut operator = c.to_string();
+                while let Some(&next @ ('<' | '>' | '=')) = chars.peek() {
+                    operator.push(next);
+                    chars.next();
+                }
+                tokens.push(operator);
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || "),:/<>=".contains(next) {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                    if next == '(' {
+                        break;
+                    }
+                }
+                tokens.push(word);
+            }
+        }
+    }
+    tokens
+}
+
+/// Split tokens into top-level groups: single tokens and parenthesized
+/// blocks or function calls
+fn groups(tokens: &[String]) -> Vec<&[String]> {
+    let mut groups = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.ends_with('(') {
+            depth += 1;
+        } else if token == ")" {
+            depth = depth.saturating_sub(1);
+        }
+        if depth == 0 {
+            groups.push(&tokens[start..=i]);
+            start = i + 1;
+        }
+    }
+    // An unclosed parenthesis runs to the end of the value
+    if start < tokens.len() {
+        groups.push(&tokens[start..]);
+    }
+    groups
+}
+
+fn is_token(group: &[String], token: &str) -> bool {
+    matches!(group, [only] if only == token)
+}
+
+/// Join tokens with canonical spacing
+fn serialize(tokens: &[String]) -> String {
+    let mut serialized = String::new();
+    let mut previous: Option<&str> = None;
+    for token in tokens {
+        let glued = previous.is_none_or(|previous| {
+            previous.ends_with('(')
+                || previous == "/"
+                || matches!(token.as_str(), ")" | ":" | "," | "/")
+        });
+        if !glued {
+            serialized.push(' ');
+        }
+        serialized.push_str(token);
+        previous = Some(token);
+    }
+    serialized
+}
+
+/// A media query or condition with the features joined by `and` sorted; a
+/// leading media type such as `only screen` stays in front
+fn condition(groups: &[&[String]]) -> String {
+    let mut conjuncts: Vec<String> = groups
+        .split(|group| is_token(group, "and"))
+        .map(|conjunct| {
+            conjunct
+                .iter()
+                .map(|group| serialize(group))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+    let media_type = conjuncts
+        .first()
+        .is_some_and(|first| !first.starts_with('('));
+    conjuncts[usize::from(media_type)..].sort_unstable();
+    conjuncts.join(" and ")
+}
+
+/// An entry of a `sizes` list: an optional media condition and a length
+fn size_entry(groups: &[&[String]]) -> String {
+    match groups.split_last() {
+        Some((length, [])) => serialize(length),
+        Some((length, media)) => format!("{} {}", condition(media), serialize(length)),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_normalize_media_attributes() {
+        assert_eq!(
+            normalize(
+                "media",
+                "print,SCREEN and (max-width:600px) and (min-width : 200px)"
+            ),
+            "print, screen and (max-width: 600px) and (min-width: 200px)"
+        );
+        assert_eq!(
+            normalize("media", "(min-width: 200px) and (max-width: 600px), print"),
+            normalize("media", "print, (max-width:600px)and (min-width:200px)")
+        );
+        assert_eq!(
+            normalize("media", "(width>=600px)"),
+            normalize("media", "( width >= 600px )")
+        );
+        assert_eq!(
+            normalize(
+                "sizes",
+                "(max-width:600px) min(100vw,480px),calc(50vw - 2em)"
+            ),
+            "(max-width: 600px) min(100vw, 480px), calc(50vw - 2em)"
+        );
+    }
+
+    #[test]
+    fn test_compare_media_queries() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            compare_media_queries: true,
+            ..Default::default()
+        });
+
+        let expected = r#"<picture>
+            <source media="(min-width: 800px) and (orientation: landscape), print" srcset="wide.jpg">
+            <img sizes="(max-width: 600px) 480px, 800px" src="small.jpg">
+        </picture>"#;
+        let actual = r#"<picture>
+            <source media="print,(orientation:landscape) and (min-width:800px)" srcset="wide.jpg">
+            <img sizes="(max-width:600px) 480px,800px" src="small.jpg">
+        </picture>"#;
+        comparer.verify(expected, actual).unwrap();
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+
+        // The first matching size wins, so reordering entries is a change
+        assert!(comparer
+            .verify(
+                r#"<img sizes="(max-width: 600px) 480px, 800px">"#,
+                r#"<img sizes="800px, (max-width: 600px) 480px">"#,
+            )
+            .is_err());
+    }
+}
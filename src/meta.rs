@@ -0,0 +1,181 @@
+//! Extraction of `<meta>` tags for Open Graph and SEO assertions.
+//!
+//! Social cards and search snippets are driven by `<meta property=... content=...>`
+//! and `<meta name=... content=...>` pairs. Their position in `<head>` and the
+//! presence of unrelated metas rarely matter, so [`MetaTags`] indexes them by
+//! name and [`assert_meta_eq!`](crate::assert_meta_eq) checks only the names
+//! a test cares about.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use scraper::{Html, Selector};
+
+use crate::HtmlCompareError;
+
+/// The `<meta>` tags of a document, keyed by their `property` or `name` attribute
+///
+/// Names may appear several times (e.g. multiple `og:image` tags); all values
+/// are kept in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetaTags {
+    tags: BTreeMap<String, Vec<String>>,
+}
+
+/// Extract all `<meta>` tags that have a `property` or `name` attribute
+///
+/// `property` takes precedence when both are present. Tags without a
+/// `content` attribute are recorded with an empty value.
+pub fn extract_meta(html: &str) -> MetaTags {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("meta").unwrap();
+
+    let mut tags: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for meta in document.select(&selector) {
+        let element = meta.value();
+        let Some(name) = element.attr("property").or_else(|| element.attr("name")) else {
+            continue;
+        };
+        let content = element.attr("content").unwrap_or_default();
+        tags.entry(name.trim().to_string())
+            .or_default()
+            .push(content.to_string());
+    }
+
+    MetaTags { tags }
+}
+
+impl MetaTags {
+    /// The first value recorded for `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.tags
+            .get(name)
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    /// All values recorded for `name`, in document order
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.tags.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Iterate over names and their values, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.tags
+            .iter()
+            .map(|(name, values)| (name.as_str(), values.as_slice()))
+    }
+
+    /// Number of distinct names
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Whether no named meta tags were found
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Check that every name in `expected` has exactly the expected values
+    ///
+    /// Names that are not mentioned in `expected` are ignored. When a name is
+    /// listed several times, the actual tags must carry the same values in the
+    /// same order.
+    pub fn check<I, K, V>(&self, expected: I) -> Result<(), HtmlCompareError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut grouped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, content) in expected {
+            grouped
+                .entry(name.as_ref().to_string())
+                .or_default()
+                .push(content.as_ref().to_string());
+        }
+
+        for (name, expected_values) in grouped {
+            let actual_values = self.get_all(&name);
+            if expected_values != actual_values {
+                return Err(HtmlCompareError::MetaMismatch {
+                    name,
+                    expected: expected_values,
+                    actual: actual_values.to_vec(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MetaTags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, values) in self.iter() {
+            for value in values {
+                writeln!(f, "{} = {:?}", name, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><head>
+        <meta charset="utf-8">
+        <meta name="description" content="A page">
+        <meta property="og:title" content="Hello">
+        <meta property="og:image" content="one.png">
+        <meta name="viewport" content="width=device-width">
+        <meta property="og:image" content="two.png">
+        <meta name="twitter:card" content="summary">
+    </head><body></body></html>"#;
+
+    #[test]
+    fn test_extract_meta() {
+        let meta = extract_meta(PAGE);
+        assert_eq!(meta.len(), 5);
+        assert_eq!(meta.get("og:title"), Some("Hello"));
+        assert_eq!(meta.get("og:image"), Some("one.png"));
+        assert_eq!(meta.get_all("og:image"), ["one.png", "two.png"]);
+        assert_eq!(meta.get("og:missing"), None);
+        assert!(meta.get_all("og:missing").is_empty());
+    }
+
+    #[test]
+    fn test_check_meta() {
+        let meta = extract_meta(PAGE);
+        assert!(meta
+            .check([("twitter:card", "summary"), ("og:title", "Hello")])
+            .is_ok());
+        assert!(meta
+            .check([("og:image", "one.png"), ("og:image", "two.png")])
+            .is_ok());
+
+        let result = meta.check([("og:image", "two.png"), ("og:image", "one.png")]);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            r#"Meta tag mismatch for og:image: expected ["two.png", "one.png"], actual ["one.png", "two.png"]"#
+        );
+
+        assert!(meta.check(vec![("og:description", "Missing")]).is_err());
+    }
+
+    #[test]
+    fn test_assert_meta_eq() {
+        assert_meta_eq!(PAGE, [("og:title", "Hello"), ("description", "A page")]);
+        assert_meta_eq!(
+            String::from("<meta name='robots' content='noindex'>"),
+            vec![("robots".to_string(), "noindex".to_string())],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Meta tag comparison failed")]
+    fn test_assert_meta_eq_failure() {
+        assert_meta_eq!(PAGE, [("og:title", "Goodbye")]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Metrics of the comparisons run, for services that compare HTML.
+//!
+//! With the `metrics` feature, every [`HtmlComparer::verify`](crate::HtmlComparer::verify),
+//! and everything built on it, reports through the [`metrics`](::metrics)
+//! facade to whichever recorder the application installed, e.g. a
+//! Prometheus exporter:
+//!
+//! - `html_compare_comparisons_total`, a counter labeled with the `result`:
+//!   `equal`, `mismatch` or `error`
+//! - `html_compare_failures_total`, a counter of the comparisons that didn't
+//!   find the documents equal, labeled with the `kind` of the error, e.g.
+//!   `text_mismatch` or `invalid_selector`
+//! - `html_compare_duration_seconds`, a histogram of the time comparisons
+//!   take
+//! - `html_compare_document_nodes`, a histogram of the number of nodes of
+//!   the parsed documents, labeled with the `document`: `expected` or
+//!   `actual`
+//!
+//! Without a recorder installed the calls do nothing. [`describe`] registers
+//! the descriptions and units of the metrics with the recorder.
+
+use std::time::Duration;
+
+use ::metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+
+use crate::HtmlCompareError;
+
+const COMPARISONS: &str = "html_compare_comparisons_total";
+const FAILURES: &str = "html_compare_failures_total";
+const DURATION: &str = "html_compare_duration_seconds";
+const DOCUMENT_NODES: &str = "html_compare_document_nodes";
+
+/// Describe the metrics to the installed recorder
+pub fn describe() {
+    describe_counter!(COMPARISONS, Unit::Count, "HTML comparisons run");
+    describe_counter!(
+        FAILURES,
+        Unit::Count,
+        "HTML comparisons that didn't find the documents equal, by kind"
+    );
+    describe_histogram!(DURATION, Unit::Seconds, "Time taken by HTML comparisons");
+    describe_histogram!(
+        DOCUMENT_NODES,
+        Unit::Count,
+        "Number of nodes of the compared HTML documents"
+    );
+}
+
+/// Record a comparison that took `duration` and ended with `result`
+pub(crate) fn record_comparison(result: &Result<(), HtmlCompareError>, duration: Duration) {
+    let outcome = match result {
+        Ok(()) => "equal",
+        Err(err) if err.is_mismatch() => "mismatch",
+        Err(_) => "error",
+    };
+    counter!(COMPARISONS, "result" => outcome).increment(1);
+    if let Err(err) = result {
+        counter!(FAILURES, "kind" => error_kind(err)).increment(1);
+    }
+    histogram!(DURATION).record(duration.as_secs_f64());
+}
+
+/// Record the number of nodes of the `document` parsed for a comparison
+pub(crate) fn record_document(document: &'static str, nodes: usize) {
+    histogram!(DOCUMENT_NODES, "document" => document).record(nodes as f64);
+}
+
+/// The label of `err` in `html_compare_failures_total`
+fn error_kind(err: &HtmlCompareError) -> &'static str {
+    match err {
+        HtmlCompareError::TagMismatch { .. } => "tag_mismatch",
+        HtmlCompareError::AttributeMismatch { .. } => "attribute_mismatch",
+        HtmlCompareError::TextMismatch { .. } => "text_mismatch",
+        HtmlCompareError::CodeMismatch { .. } => "code_mismatch",
+        HtmlCompareError::CommentMismatch { .. } => "comment_mismatch",
+        HtmlCompareError::CaptureMismatch { .. } => "capture_mismatch",
+        HtmlCompareError::StructureMismatch { .. } => "structure_mismatch",
+        HtmlCompareError::VoidSyntaxMismatch { .. } => "void_syntax_mismatch",
+        HtmlCompareError::InvalidAttributeName { .. } => "invalid_attribute_name",
+        HtmlCompareError::MissingNode { .. } => "missing_node",
+        HtmlCompareError::ExtraNode { .. } => "extra_node",
+        HtmlCompareError::StructuredDataMismatch(_) => "structured_data_mismatch",
+        HtmlCompareError::InvalidJsonLd(_) => "invalid_json_ld",
+        HtmlCompareError::MetaMismatch { .. } => "meta_mismatch",
+        HtmlCompareError::FeedMismatch(_) => "feed_mismatch",
+        HtmlCompareError::LinkMismatch(_) => "link_mismatch",
+        HtmlCompareError::FormMismatch(_) => "form_mismatch",
+        HtmlCompareError::InvalidSelector(_) => "invalid_selector",
+        HtmlCompareError::InvalidOptions(_) => "invalid_options",
+        HtmlCompareError::RootNotFound { .. } => "root_not_found",
+        HtmlCompareError::Differences(_) => "differences",
+        HtmlCompareError::BrokenReference(_) => "broken_reference",
+        HtmlCompareError::InvalidEdit(_) => "invalid_edit",
+        HtmlCompareError::DocumentDifferences(_) => "document_differences",
+        HtmlCompareError::InvalidDocumentStream(_) => "invalid_document_stream",
+        HtmlCompareError::SecurityRisk(_) => "security_risk",
+        HtmlCompareError::Fetch(_) => "fetch",
+        HtmlCompareError::ResponseMismatch(_) => "response_mismatch",
+        HtmlCompareError::InvalidResponse(_) => "invalid_response",
+        HtmlCompareError::InvalidUtf8 { .. } => "invalid_utf8",
+        HtmlCompareError::InputTooLarge { .. } => "input_too_large",
+        HtmlCompareError::Internal(_) => "internal",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlComparer;
+
+    #[test]
+    fn test_error_kind() {
+        let comparer = HtmlComparer::new();
+        let err = comparer.verify("<p>a</p>", "<p>b</p>").unwrap_err();
+        assert_eq!(error_kind(&err), "text_mismatch");
+        let err = comparer.verify("<p>a</p>", "<div>a</div>").unwrap_err();
+        assert_eq!(error_kind(&err), "tag_mismatch");
+        assert_eq!(
+            error_kind(&HtmlCompareError::InvalidSelector("p[".to_string())),
+            "invalid_selector"
+        );
+    }
+
+    #[test]
+    fn test_comparisons_without_recorder() {
+        record_comparison(&Ok(()), Duration::from_millis(3));
+        record_document("expected", 12);
+        assert!(HtmlComparer::new()
+            .is_equal("<p>a</p>", "<p>a</p>")
+            .unwrap());
+    }
+}
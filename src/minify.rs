@@ -0,0 +1,129 @@
+//! Normalizations for checking minifier output.
+//!
+//! An HTML minifier is correct when its output parses to the same document
+//! as its input. The parser already undoes omitted optional tags such as
+//! `</li>` and `</p>` and removed attribute quotes; [`presets::minified`]
+//! additionally collapses whitespace runs in text, compares boolean
+//! attributes by presence only (`disabled="disabled"` versus `disabled`) and
+//! compares `class` and the other token list attributes as sets, so that
+//! [`assert_minified_equivalent!`] can serve as a minifier's correctness
+//! oracle.
+//!
+//! [`presets::minified`]: crate::presets::minified
+//! [`assert_minified_equivalent!`]: crate::assert_minified_equivalent
+
+use std::borrow::Cow;
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+/// The boolean attributes defined by HTML, whose value is irrelevant
+pub(crate) const BOOLEAN_ATTRIBUTES: &[&str] = &[
+    "allowfullscreen",
+    "async",
+    "autofocus",
+    "autoplay",
+    "checked",
+    "controls",
+    "default",
+    "defer",
+    "disabled",
+    "formnovalidate",
+    "hidden",
+    "inert",
+    "ismap",
+    "itemscope",
+    "loop",
+    "multiple",
+    "muted",
+    "nomodule",
+    "novalidate",
+    "open",
+    "playsinline",
+    "readonly",
+    "required",
+    "reversed",
+    "selected",
+];
+
+/// Elements whose whitespace minifiers must preserve
+const WHITESPACE_SENSITIVE_ELEMENTS: &[&str] = &["pre", "textarea"];
+
+/// Whether the text node `node` may have its whitespace collapsed
+pub(crate) fn collapsible(node: NodeRef<Node>) -> bool {
+    !node
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|element| WHITESPACE_SENSITIVE_ELEMENTS.contains(&element.value().name()))
+}
+
+/// Collapse each run of whitespace in `text` into a single space, borrowing
+/// `text` when it is already collapsed
+pub(crate) fn collapse_whitespace(text: &str) -> Cow<'_, str> {
+    let mut previous = 'x';
+    let collapsed = text.chars().all(|c| {
+        let single = (c == ' ' && previous != ' ') || !c.is_whitespace();
+        previous = c;
+        single
+    });
+    if collapsed {
+        return Cow::Borrowed(text);
+    }
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    Cow::Owned(collapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{presets, HtmlComparer};
+
+    #[test]
+    fn test_collapse_whitespace() {
+        assert_eq!(collapse_whitespace("a \n\t b"), "a b");
+        assert_eq!(collapse_whitespace("  a  "), " a ");
+    }
+
+    #[test]
+    fn test_assert_minified_equivalent() {
+        let original = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>Shop</title>
+  </head>
+  <body>
+    <!-- navigation -->
+    <ul class="nav  main">
+      <li>Home</li>
+      <li>About   us</li>
+    </ul>
+    <p>Pick
+       one:</p>
+    <input type="checkbox" checked="checked" disabled="">
+    <pre>  keep
+   this  </pre>
+  </body>
+</html>"#;
+        let minified = r#"<!doctype html><title>Shop</title><ul class="main nav"><li>Home<li>About us</ul><p>Pick one:</p><input type=checkbox checked disabled><pre>  keep
+   this  </pre>"#;
+        assert_minified_equivalent!(original, minified);
+
+        // Whitespace in <pre> is content, so collapsing it is a bug
+        let broken = minified.replace("  keep\n   this  ", " keep this ");
+        let comparer = HtmlComparer::with_options(presets::minified());
+        assert!(comparer.verify(original, &broken).is_err());
+        assert!(HtmlComparer::new().verify(original, minified).is_err());
+    }
+}
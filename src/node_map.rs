@@ -0,0 +1,139 @@
+//! Which node of the actual document each expected node was compared with.
+//!
+//! [`HtmlComparer::diff`] pairs up children before comparing them: by tag
+//! in order, or by content with
+//! [`HtmlCompareOptions::ignore_sibling_order`](crate::HtmlCompareOptions::ignore_sibling_order).
+//! [`HtmlDiff::node_map`](crate::HtmlDiff::node_map) lists the resulting
+//! pairs as expected path and actual path, so tools can act on the
+//! counterpart of an expected node, e.g. copy its attributes into a
+//! fixture. Nodes without a counterpart are left out, as are the insides of
+//! code blocks compared by their text.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::diff::{child_paths, Alignment};
+use crate::{HeadingLevels, HtmlComparer, ShadowDomMode};
+
+/// Two paired nodes with their expected and actual paths
+type Pair<'a> = (NodeRef<'a, Node>, NodeRef<'a, Node>, String, String);
+
+impl HtmlComparer {
+    /// The expected and actual paths of the nodes paired up below and
+    /// including `expected` and `actual`, in the order the diff visits them
+    pub(crate) fn node_map(
+        &self,
+        expected: ElementRef,
+        actual: ElementRef,
+    ) -> Vec<(String, String)> {
+        let mut map = Vec::new();
+        // An explicit stack, so that deep documents can't overflow the call
+        // stack; entries are pushed in reverse to pop in order
+        let mut stack = vec![(
+            *expected,
+            *actual,
+            self.node_path(*expected),
+            self.node_path(*actual),
+        )];
+        let levels = self.heading_levels(*expected, *actual);
+        while let Some((expected, actual, expected_path, actual_path)) = stack.pop() {
+            let pairs = match (ElementRef::wrap(expected), ElementRef::wrap(actual)) {
+                (Some(expected), Some(actual)) => {
+                    self.paired_children(expected, actual, &expected_path, &actual_path, levels)
+                }
+                _ => Vec::new(),
+            };
+            map.push((expected_path, actual_path));
+            stack.extend(pairs.into_iter().rev());
+        }
+        map
+    }
+
+    /// The children and shadow roots of two paired elements that are
+    /// paired up in turn, with their paths
+    fn paired_children<'a>(
+        &self,
+        expected: ElementRef<'a>,
+        actual: ElementRef<'a>,
+        expected_path: &str,
+        actual_path: &str,
+        levels: HeadingLevels,
+    ) -> Vec<Pair<'a>> {
+        let mut pairs = Vec::new();
+        if (self.options.ignore_style_contents && expected.value().name() == "style")
+            || self.is_highlighted_code(expected)
+        {
+            return pairs;
+        }
+        let shadow_roots = match self.options.shadow_dom {
+            ShadowDomMode::Separate => self.shadow_root(expected).zip(self.shadow_root(actual)),
+            _ => None,
+        };
+        let shadow_pair = shadow_roots.map(|(expected_root, actual_root)| {
+            (
+                *expected_root,
+                *actual_root,
+                format!("{}/#shadow-root", expected_path),
+                format!("{}/#shadow-root", actual_path),
+            )
+        });
+        if !self.options.light_dom_first {
+            pairs.extend(shadow_pair.clone());
+        }
+
+        let (expected_children, actual_children) = self.compared_children(expected, actual, levels);
+        let expected_paths = child_paths(expected_path, &expected_children);
+        let actual_paths = child_paths(actual_path, &actual_children);
+        for alignment in self.align_children(&expected_children, &actual_children, levels) {
+            if let Alignment::Pair(i, j) = alignment {
+                pairs.push((
+                    expected_children[i],
+                    actual_children[j],
+                    expected_paths[i].clone(),
+                    actual_paths[j].clone(),
+                ));
+            }
+        }
+
+        if self.options.light_dom_first {
+            pairs.extend(shadow_pair);
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_node_map() {
+        let diff = HtmlComparer::new().diff(
+            r#"<ul><li>One</li><li class="b">Two</li></ul>"#,
+            r#"<p>New</p><ul><li>One</li><li class="c">Two</li><li>Three</li></ul>"#,
+        );
+        let map = diff.node_map();
+        assert_eq!(map[0], ("/html".to_string(), "/html".to_string()));
+        assert_eq!(diff.actual_path("/html/body/ul"), Some("/html/body/ul"));
+        assert_eq!(
+            diff.actual_path("/html/body/ul/li[2]/text()"),
+            Some("/html/body/ul/li[2]/text()")
+        );
+        assert!(map
+            .iter()
+            .all(|(_, actual)| actual != "/html/body/p" && actual != "/html/body/ul/li[3]"));
+
+        let unordered = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_sibling_order: true,
+            ..Default::default()
+        })
+        .diff(
+            "<ul><li>a</li><li>b</li></ul>",
+            "<ul><li>b</li><li>a</li></ul>",
+        );
+        assert_eq!(
+            unordered.actual_path("/html/body/ul/li[1]"),
+            Some("/html/body/ul/li[2]")
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Whitespace moved around by omitted end tags.
+//!
+//! The HTML parser closes elements whose end tag is optional, so
+//! `<li>one<li>two` and `<li>one</li><li>two</li>` build the same elements.
+//! What differs is the whitespace: in `<li>one\n<li>two` the newline ends up
+//! inside the first `<li>`, while with `</li>` written out it becomes a text
+//! node between the two. With whitespace ignored that never matters; with
+//! [`HtmlCompareOptions::normalize_optional_tags`](crate::HtmlCompareOptions)
+//! such whitespace is dropped so the two forms also compare equal when
+//! whitespace is significant.
+
+use ego_tree::NodeRef;
+use scraper::Node;
+
+/// Elements whose end tag may be omitted
+const OPTIONAL_END_TAGS: &[&str] = &[
+    "html", "head", "body", "li", "dt", "dd", "p", "rt", "rp", "optgroup", "option", "colgroup",
+    "caption", "thead", "tbody", "tfoot", "tr", "td", "th",
+];
+
+fn has_optional_end_tag(node: Option<NodeRef<Node>>) -> bool {
+    matches!(
+        node.as_ref().map(NodeRef::value),
+        Some(Node::Element(element)) if OPTIONAL_END_TAGS.contains(&element.name())
+    )
+}
+
+/// Whether `node` ends an element whose end tag may be omitted, so that its
+/// trailing whitespace would follow the element had the end tag been written
+fn ends_optional_element(node: NodeRef<Node>) -> bool {
+    node.next_sibling().is_none() && has_optional_end_tag(node.parent())
+}
+
+/// Whether `node` is whitespace whose position depends on an omitted end
+/// tag: it follows an element with an optional end tag or ends one
+pub(crate) fn is_relocated_whitespace(node: NodeRef<Node>) -> bool {
+    match node.value() {
+        Node::Text(text) if text.trim().is_empty() => {
+            has_optional_end_tag(node.prev_sibling()) || ends_optional_element(node)
+        }
+        _ => false,
+    }
+}
+
+/// The text of `node` without whitespace that an omitted end tag moved into
+/// it
+pub(crate) fn trim_relocated_whitespace<'a>(node: NodeRef<Node>, text: &'a str) -> &'a str {
+    if ends_optional_element(node) {
+        text.trim_end()
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{presets, HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_parser_closes_optional_tags() {
+        // The parser inserts the omitted tags, so the trees match even when
+        // comments and attributes are compared
+        let comparer = HtmlComparer::with_options(presets::strict());
+        let pairs = [
+            (
+                "<ul><li>one<li>two</ul>",
+                "<ul><li>one</li><li>two</li></ul>",
+            ),
+            ("<p>text<div>block</div>", "<p>text</p><div>block</div>"),
+            (
+                "<table><tr><td>a<td>b</table>",
+                "<table><tbody><tr><td>a</td><td>b</td></tr></tbody></table>",
+            ),
+            (
+                "<dl><dt>term<dd>definition</dl>",
+                "<dl><dt>term</dt><dd>definition</dd></dl>",
+            ),
+            (
+                "<select><option>a<option>b</select>",
+                "<select><option>a</option><option>b</option></select>",
+            ),
+            (
+                "<title>x</title><p>body",
+                "<html><head><title>x</title></head><body><p>body</p></body></html>",
+            ),
+        ];
+        for (omitted, closed) in pairs {
+            assert!(comparer.is_equal(omitted, closed).unwrap(), "{}", omitted);
+        }
+    }
+
+    #[test]
+    fn test_normalize_optional_tags() {
+        let omitted = "<ul>\n  <li>one\n  <li>two\n</ul>\n<p>para\n<div>block</div>";
+        let closed = "<ul>\n  <li>one</li>\n  <li>two</li>\n</ul>\n<p>para</p>\n<div>block</div>";
+
+        let options = HtmlCompareOptions {
+            ignore_whitespace: false,
+            ..Default::default()
+        };
+        assert!(HtmlComparer::with_options(options.clone())
+            .verify(omitted, closed)
+            .is_err());
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            normalize_optional_tags: true,
+            ..options
+        });
+        comparer.verify(omitted, closed).unwrap();
+        assert!(comparer
+            .verify(
+                "<ul><li>one <li>two</ul>",
+                "<ul><li>one</li><li> two</li></ul>"
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,318 @@
+//! Choice of the parser that builds the compared trees.
+//!
+//! Parsers recover from invalid markup differently, and a test comparing
+//! what production sees should parse like production does.
+//! [`HtmlCompareOptions::parser`](crate::HtmlCompareOptions) selects an
+//! [`HtmlParserBackend`]: scraper's html5ever document parser by default,
+//! its fragment parser, with the `quick-xml` feature an XML parser for
+//! XHTML output, or any implementation that builds a scraper [`Html`] tree.
+//!
+//! There is no html5gum backend yet: html5gum is a tokenizer without tree
+//! construction, so a backend needs its own tree builder on top of it.
+//! Until one ships, callers who need html5gum's recovery can implement
+//! [`HtmlParserBackend`] with an adapter of their own.
+
+use std::fmt;
+use std::sync::Arc;
+
+use scraper::Html;
+
+/// Builds the tree compared for a document
+pub trait HtmlParserBackend: Send + Sync {
+    fn parse_document(&self, html: &str) -> Html;
+}
+
+/// html5ever's document parser, as used by browsers: adds the implied
+/// `<html>`, `<head>` and `<body>` elements
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Html5ever;
+
+impl HtmlParserBackend for Html5ever {
+    fn parse_document(&self, html: &str) -> Html {
+        Html::parse_document(html)
+    }
+}
+
+/// html5ever's fragment parser: the input's nodes become the children of
+/// the root `<html>` element, without an implied `<head>` or `<body>`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fragment;
+
+impl HtmlParserBackend for Fragment {
+    fn parse_document(&self, html: &str) -> Html {
+        Html::parse_fragment(html)
+    }
+}
+
+/// quick-xml's XML parser, for pipelines that produce XHTML: builds the
+/// elements as written, without implied elements or any error recovery
+///
+/// Parsing stops at the first error, such as a mismatched end tag, keeping
+/// the nodes read so far. Top-level nodes are wrapped in an `<html>` element
+/// unless the document has one. Text with entities other than XML's five
+/// predefined ones is kept as written.
+#[cfg(feature = "quick-xml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuickXml;
+
+#[cfg(feature = "quick-xml")]
+impl HtmlParserBackend for QuickXml {
+    fn parse_document(&self, html: &str) -> Html {
+        use ego_tree::NodeId;
+        use html5ever::{tendril::StrTendril, Attribute, LocalName, Namespace, QualName};
+        use quick_xml::events::{BytesStart, Event};
+        use scraper::node::{Comment, Element, Text};
+        use scraper::Node;
+
+        let element = |start: &BytesStart| {
+            let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+            let attributes = start
+                .html_attributes()
+                .flatten()
+                .map(|attribute| Attribute {
+                    name: QualName::new(
+                        None,
+                        Namespace::from(""),
+                        LocalName::from(&*String::from_utf8_lossy(attribute.key.as_ref())),
+                    ),
+                    value: StrTendril::from(
+                        attribute
+                            .unescape_value()
+                            .unwrap_or_else(|_| String::from_utf8_lossy(&attribute.value))
+                            .as_ref(),
+                    ),
+                })
+                .collect();
+            Node::Element(Element::new(
+                QualName::new(
+                    None,
+                    Namespace::from("http://www.w3.org/1999/xhtml"),
+                    LocalName::from(name),
+                ),
+                attributes,
+            ))
+        };
+
+        let mut document = Html::new_document();
+        let mut open: Vec<NodeId> = vec![document.tree.root().id()];
+        let append = |document: &mut Html, open: &[NodeId], node: Node| {
+            let parent = *open.last().expect("the root stays open");
+            let mut parent = document.tree.get_mut(parent).expect("open nodes exist");
+            // Adjacent text and CDATA make one text node, as in the parser
+            if let (Node::Text(text), Some(mut last)) = (&node, parent.last_child()) {
+                if let Node::Text(previous) = last.value() {
+                    previous.text.push_tendril(&text.text);
+                    return last.id();
+                }
+            }
+            parent.append(node).id()
+        };
+        let text = |text: &str| {
+            Node::Text(Text {
+                text: StrTendril::from(text),
+            })
+        };
+
+        let mut reader = quick_xml::Reader::from_str(html);
+        reader.config_mut().check_end_names = true;
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(start)) => {
+                    let id = append(&mut document, &open, element(&start));
+                    open.push(id);
+                }
+                Ok(Event::Empty(start)) => {
+                    append(&mut document, &open, element(&start));
+                }
+                Ok(Event::End(_)) => {
+                    if open.len() > 1 {
+                        open.pop();
+                    }
+                }
+                Ok(Event::Text(content)) => {
+                    let content = content
+                        .unescape()
+                        .unwrap_or_else(|_| String::from_utf8_lossy(&content).into_owned().into());
+                    append(&mut document, &open, text(&content));
+                }
+                Ok(Event::CData(content)) => {
+                    append(
+                        &mut document,
+                        &open,
+                        text(&String::from_utf8_lossy(&content)),
+                    );
+                }
+                Ok(Event::Comment(content)) => {
+                    let comment = Node::Comment(Comment {
+                        comment: StrTendril::from(&*String::from_utf8_lossy(&content)),
+                    });
+                    append(&mut document, &open, comment);
+                }
+                Ok(Event::Decl(_) | Event::PI(_) | Event::DocType(_)) => {}
+                Ok(Event::Eof) | Err(_) => break,
+            }
+        }
+
+        let root = document.tree.root();
+        let has_html = root
+            .children()
+            .any(|child| matches!(child.value(), Node::Element(e) if e.name() == "html"));
+        if !has_html {
+            let children: Vec<NodeId> = root.children().map(|child| child.id()).collect();
+            let html = element(&BytesStart::new("html"));
+            let html = document.tree.root_mut().append(html).id();
+            let mut html = document.tree.get_mut(html).expect("just appended");
+            for child in children {
+                html.append_id(child);
+            }
+        }
+        document
+    }
+}
+
+impl<F: Fn(&str) -> Html + Send + Sync> HtmlParserBackend for F {
+    fn parse_document(&self, html: &str) -> Html {
+        self(html)
+    }
+}
+
+/// The parser backend of a comparison
+#[derive(Clone)]
+pub struct Parser {
+    name: String,
+    backend: Arc<dyn HtmlParserBackend>,
+    /// Whether the backend is built in, so that its name identifies it
+    builtin: bool,
+}
+
+impl Parser {
+    /// Parse with `backend`, reported as `name` in debug output
+    pub fn new(name: impl Into<String>, backend: impl HtmlParserBackend + 'static) -> Self {
+        Parser {
+            name: name.into(),
+            backend: Arc::new(backend),
+            builtin: false,
+        }
+    }
+
+    /// A built-in backend by name: `html5ever`, `fragment`, or `quick-xml`
+    /// with that feature
+    pub fn from_name(name: &str) -> Option<Parser> {
+        let parser = match name {
+            "html5ever" => Parser::new(name, Html5ever),
+            "fragment" => Parser::new(name, Fragment),
+            #[cfg(feature = "quick-xml")]
+            "quick-xml" => Parser::new(name, QuickXml),
+            _ => return None,
+        };
+        Some(Parser {
+            builtin: true,
+            ..parser
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn parse_document(&self, html: &str) -> Html {
+        self.backend.parse_document(html)
+    }
+
+    /// Whether this is one of the backends of [`Parser::from_name`], rather
+    /// than one only its name describes
+    pub(crate) fn is_builtin(&self) -> bool {
+        self.builtin
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            builtin: true,
+            ..Parser::new("html5ever", Html5ever)
+        }
+    }
+}
+
+impl fmt::Debug for Parser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Parser").field(&self.name).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_fragment_parser() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            parser: Parser::from_name("fragment").unwrap(),
+            exhaustive: true,
+            ..Default::default()
+        });
+        let diff = comparer.diff("<p>One</p>", "<p>Two</p>");
+        assert_eq!(diff.iter().next().unwrap().path(), "/html/p/text()");
+        assert_eq!(
+            HtmlComparer::new()
+                .diff("<p>One</p>", "<p>Two</p>")
+                .iter()
+                .next()
+                .unwrap()
+                .path(),
+            "/html/body/p/text()"
+        );
+        assert_eq!(
+            Parser::from_name("quick-xml").is_some(),
+            cfg!(feature = "quick-xml")
+        );
+        assert!(Parser::from_name("lol_html").is_none());
+    }
+
+    #[cfg(feature = "quick-xml")]
+    #[test]
+    fn test_quick_xml_parser() {
+        let xml = HtmlComparer::with_options(HtmlCompareOptions {
+            parser: Parser::from_name("quick-xml").unwrap(),
+            ..Default::default()
+        });
+        let xhtml = "<?xml version=\"1.0\"?>\n<html><body><p class=\"a\">A &amp; B<![CDATA[ & C]]></p><br/></body></html>";
+        xml.verify(
+            xhtml,
+            "<html><body><p class='a'>A &amp; B &amp; C</p><br></br></body></html>",
+        )
+        .unwrap();
+        // html5ever reads the CDATA section as a comment
+        assert!(!HtmlComparer::new()
+            .is_equal(xhtml, "<p class=a>A &amp; B &amp; C</p><br>")
+            .unwrap());
+
+        // No recovery: a block in a paragraph stays there
+        let nested = "<p><div>x</div></p>";
+        let recovered = "<p></p><div>x</div><p></p>";
+        HtmlComparer::new().verify(nested, recovered).unwrap();
+        assert!(!xml.is_equal(nested, recovered).unwrap());
+
+        // Fragments get a root, and unknown entities are kept as written
+        let diff = xml.diff("<p>One</p>", "<p>Two&nbsp;</p>");
+        assert_eq!(
+            diff.iter().next().unwrap().to_string(),
+            "/html/p/text(): expected 'One', found 'Two&nbsp;'"
+        );
+    }
+
+    #[test]
+    fn test_custom_backend() {
+        // A production pipeline that strips a BOM-like prefix before parsing
+        let backend = |html: &str| Html::parse_document(html.trim_start_matches("<!--x-->"));
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            parser: Parser::new("stripping", backend),
+            ignore_comments: false,
+            ..Default::default()
+        });
+        comparer.verify("<!--x--><p>Hi</p>", "<p>Hi</p>").unwrap();
+        assert!(format!("{:?}", comparer).contains("Parser(\"stripping\")"));
+    }
+}
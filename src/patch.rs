@@ -0,0 +1,786 @@
+//! Applying an edit script to the source of the expected document.
+//!
+//! [`HtmlComparer::apply_diff`] rewrites an expected fixture so that it
+//! matches the actual output, e.g. for an "update fixtures" mode of a test
+//! suite. Only the markup that an edit touches is rewritten: indentation,
+//! attribute quoting, entity references and omitted tags elsewhere in the
+//! fixture are kept as written.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use ego_tree::{NodeId, NodeRef};
+use scraper::{ElementRef, Html, Node};
+
+use crate::diff::child_paths;
+use crate::format::VOID_ELEMENTS;
+use crate::{Edit, HtmlCompareError, HtmlComparer, HtmlDiff, ShadowDomMode};
+
+/// Elements whose content is raw text rather than markup
+pub(crate) const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "script", "style", "textarea", "title", "xmp", "iframe", "noembed", "noframes",
+];
+
+/// Elements the parser creates even when their start tag is omitted
+const IMPLIED_ELEMENTS: &[&str] = &["html", "head", "body", "tbody", "colgroup"];
+
+impl HtmlComparer {
+    /// Rewrite `expected_html` so that it matches the actual document of
+    /// `diff`, which must have been produced by this comparer from
+    /// `expected_html`
+    ///
+    /// The rewritten fixture is compared with the actual document again,
+    /// and an edit that doesn't carry over to the source is reported as
+    /// [`HtmlCompareError::InvalidEdit`] rather than returned.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let comparer = HtmlComparer::new();
+    /// let diff = comparer.diff(&fixture, &rendered);
+    /// if !diff.is_empty() {
+    ///     std::fs::write(fixture_path, comparer.apply_diff(&fixture, &diff)?)?;
+    /// }
+    /// ```
+    pub fn apply_diff(
+        &self,
+        expected_html: &str,
+        diff: &HtmlDiff,
+    ) -> Result<String, HtmlCompareError> {
+        let document = self.parse(expected_html);
+        let source = SourceMap::new(expected_html, &document);
+
+        let mut nodes = HashMap::new();
+        self.index_paths(document.root_element(), "/html", &mut nodes);
+        let node = |path: &str| {
+            nodes
+                .get(path)
+                .and_then(|id| document.tree.get(*id))
+                .ok_or_else(|| HtmlCompareError::InvalidEdit(format!("no node at {}", path)))
+        };
+        let span = |node: NodeRef<Node>| {
+            source.span(node).ok_or_else(|| {
+                HtmlCompareError::InvalidEdit(format!("{} has no source markup", describe(node)))
+            })
+        };
+
+        let mut changes = Changes::default();
+        let mut removed = HashSet::new();
+        // Inserted markup by parent path, with its index after the edits
+        let mut placements: Vec<(String, usize, String)> = Vec::new();
+        // End tags of void elements relabeled to hold content, by path, which
+        // go after whatever is placed in them
+        let mut end_tags: Vec<(&str, usize, String)> = Vec::new();
+
+        // Elements relabeled to void elements lose their content along with
+        // their end tag, and with them the edits inside
+        let voided: Vec<&str> = diff
+            .edit_script()
+            .iter()
+            .filter_map(|edit| match edit {
+                Edit::Relabel { path, from, to } if is_void(to) && !is_void(from) => {
+                    Some(path.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        let inside_voided = |path: &str| {
+            voided.iter().any(|voided| {
+                path.strip_prefix(voided)
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+        };
+
+        for edit in diff.edit_script() {
+            match edit {
+                Edit::Move {
+                    path,
+                    parent,
+                    index,
+                } => {
+                    let target = node(path)?;
+                    let range = span(target)?;
+                    if !inside_voided(parent) && !voided.contains(&parent.as_str()) {
+                        placements.push((
+                            parent.clone(),
+                            *index,
+                            expected_html[range.clone()].to_string(),
+                        ));
+                    }
+                    if !inside_voided(path) {
+                        changes.replace(range, String::new());
+                    }
+                    removed.insert(target.id());
+                }
+                Edit::Delete { path }
+                | Edit::Relabel { path, .. }
+                | Edit::SetAttribute { path, .. }
+                | Edit::SetText { path, .. }
+                    if inside_voided(path) => {}
+                Edit::Insert { parent, .. }
+                    if inside_voided(parent) || voided.contains(&parent.as_str()) => {}
+                Edit::Delete { path } => {
+                    let target = node(path)?;
+                    changes.replace(span(target)?, String::new());
+                    removed.insert(target.id());
+                }
+                Edit::Insert {
+                    parent,
+                    index,
+                    html,
+                } => placements.push((parent.clone(), *index, html.clone())),
+                Edit::Relabel { path, from, to } => {
+                    let target = node(path)?;
+                    let tags = source.tags(target);
+                    let start = tags.start.ok_or_else(|| missing_tag(path))?;
+                    changes.replace(start.name.clone(), to.clone());
+                    match (is_void(from), is_void(to)) {
+                        (false, true) => {
+                            let end = match tags.end {
+                                Some(end) => end.range.end,
+                                None => span(target)?.end,
+                            };
+                            changes.replace(start.range.end..end, String::new());
+                        }
+                        (true, false) => {
+                            end_tags.push((path, start.range.end, format!("</{}>", to)))
+                        }
+                        _ => {
+                            if let Some(end) = tags.end {
+                                changes.replace(end.name, to.clone());
+                            }
+                        }
+                    }
+                }
+                Edit::SetAttribute { path, name, value } => {
+                    let start = source
+                        .tags(node(path)?)
+                        .start
+                        .ok_or_else(|| missing_tag(path))?;
+                    let markup = value
+                        .as_ref()
+                        .map(|value| format!(" {}=\"{}\"", name, escape_attribute(value)));
+                    match (start.attributes.iter().find(|(n, _)| n == name), markup) {
+                        (Some((_, range)), markup) => {
+                            changes.replace(range.clone(), markup.unwrap_or_default())
+                        }
+                        (None, Some(markup)) => changes.insert(start.attributes_end, markup),
+                        (None, None) => {}
+                    }
+                }
+                Edit::SetText { path, text } => {
+                    let target = node(path)?;
+                    let range = span(target)?;
+                    match source.comments.get(&target.id()) {
+                        Some(Some(content)) => changes.replace(content.clone(), text.clone()),
+                        Some(None) => changes.replace(range, format!("<!--{}-->", text)),
+                        None => {
+                            let raw = target
+                                .parent()
+                                .and_then(ElementRef::wrap)
+                                .is_some_and(|p| RAW_TEXT_ELEMENTS.contains(&p.value().name()));
+                            let text = if raw { text.clone() } else { escape_text(text) };
+                            changes.replace(range, text)
+                        }
+                    }
+                }
+            }
+        }
+
+        // Place inserted and moved markup next to the surviving children of
+        // its new parent
+        let mut parents: Vec<&str> = Vec::new();
+        for (parent, _, _) in &placements {
+            if !parents.contains(&parent.as_str()) {
+                parents.push(parent);
+            }
+        }
+        // An end tag goes right after the content placed in its element, and
+        // before the siblings placed after it
+        for (path, offset, tag) in &end_tags {
+            if !parents.contains(path) {
+                changes.insert(*offset, tag.clone());
+            }
+        }
+        for parent_path in parents {
+            let parent = ElementRef::wrap(node(parent_path)?).ok_or_else(|| {
+                HtmlCompareError::InvalidEdit(format!("{} is not an element", parent_path))
+            })?;
+            let mut children: Vec<Result<NodeRef<Node>, &str>> = self
+                .child_nodes(parent)
+                .into_iter()
+                .filter(|child| !removed.contains(&child.id()))
+                .map(Ok)
+                .collect();
+            for (_, index, markup) in placements.iter().filter(|(p, _, _)| p == parent_path) {
+                children.insert((*index).min(children.len()), Err(markup.as_str()));
+            }
+
+            for (position, child) in children.iter().enumerate() {
+                let Err(markup) = child else { continue };
+                let following = children[position..].iter().find_map(|c| c.ok());
+                let preceding = children[..position].iter().rev().find_map(|c| c.ok());
+                let offset = match (following, preceding) {
+                    (Some(following), _) => span(following)?.start,
+                    (None, Some(preceding)) => span(preceding)?.end,
+                    (None, None) => source
+                        .content_start(*parent)
+                        .ok_or_else(|| missing_tag(parent_path))?,
+                };
+                changes.insert(offset, markup.to_string());
+            }
+            for (_, offset, tag) in end_tags.iter().filter(|(p, _, _)| *p == parent_path) {
+                changes.insert(*offset, tag.clone());
+            }
+        }
+
+        let updated = changes.apply(expected_html)?;
+        if let Some(actual) = diff.actual_source() {
+            match self.verify(&updated, actual) {
+                Err(err) if err.is_mismatch() => {
+                    return Err(HtmlCompareError::InvalidEdit(format!(
+                        "the rewritten fixture doesn't match the actual document: {}",
+                        err
+                    )))
+                }
+                result => result?,
+            }
+        }
+        Ok(updated)
+    }
+
+    /// Map the path of every compared node below `element` to its id
+    fn index_paths(&self, element: ElementRef, path: &str, nodes: &mut HashMap<String, NodeId>) {
+        nodes.insert(path.to_string(), element.id());
+        if self.options.shadow_dom == ShadowDomMode::Separate {
+            if let Some(shadow_root) = self.shadow_root(element) {
+                self.index_paths(shadow_root, &format!("{}/#shadow-root", path), nodes);
+            }
+        }
+        let children = self.child_nodes(element);
+        for (child, child_path) in children.iter().zip(child_paths(path, &children)) {
+            match ElementRef::wrap(*child) {
+                Some(child) => self.index_paths(child, &child_path, nodes),
+                None => {
+                    nodes.insert(child_path, child.id());
+                }
+            }
+        }
+    }
+}
+
+fn is_void(name: &str) -> bool {
+    VOID_ELEMENTS.contains(&name)
+}
+
+fn missing_tag(path: &str) -> HtmlCompareError {
+    HtmlCompareError::InvalidEdit(format!("{} has no tag in the source", path))
+}
+
+fn describe(node: NodeRef<Node>) -> String {
+    match node.value() {
+        Node::Element(element) => format!("<{}>", element.name()),
+        Node::Text(_) => "text".to_string(),
+        Node::Comment(_) => "comment".to_string(),
+        _ => "node".to_string(),
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+/// Replacements of source ranges, applied all at once
+#[derive(Default)]
+struct Changes {
+    changes: Vec<(Range<usize>, String)>,
+}
+
+impl Changes {
+    fn replace(&mut self, range: Range<usize>, text: String) {
+        self.changes.push((range, text));
+    }
+
+    fn insert(&mut self, offset: usize, text: String) {
+        self.changes.push((offset..offset, text));
+    }
+
+    fn apply(mut self, source: &str) -> Result<String, HtmlCompareError> {
+        // Stable, so insertions at the same offset keep their order
+        self.changes
+            .sort_by_key(|(range, _)| (range.start, range.end));
+        let mut output = String::with_capacity(source.len());
+        let mut copied = 0;
+        for (range, text) in self.changes {
+            if range.start < copied {
+                return Err(HtmlCompareError::InvalidEdit(
+                    "edits overlap in the source".to_string(),
+                ));
+            }
+            output.push_str(&source[copied..range.start]);
+            output.push_str(&text);
+            copied = range.end;
+        }
+        output.push_str(&source[copied..]);
+        Ok(output)
+    }
+}
+
+/// A start tag in the source
+#[derive(Debug, Clone)]
+struct StartTag {
+    range: Range<usize>,
+    name: Range<usize>,
+    /// Lowercased attribute names with the range of `name="value"`,
+    /// including the whitespace before it
+    attributes: Vec<(String, Range<usize>)>,
+    /// Where a new attribute goes
+    attributes_end: usize,
+}
+
+/// An end tag in the source
+#[derive(Debug, Clone)]
+struct EndTag {
+    range: Range<usize>,
+    name: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    StartTag(StartTag),
+    EndTag(EndTag),
+    Text(Range<usize>),
+    /// A comment and the range of its content, unless it closes abruptly
+    Comment(Range<usize>, Option<Range<usize>>),
+}
+
+/// The tags of an element found in the source; either can be omitted
+#[derive(Debug, Default, Clone)]
+struct Tags {
+    start: Option<StartTag>,
+    end: Option<EndTag>,
+}
+
+/// Where the nodes of a parsed document come from in its source
+struct SourceMap {
+    tags: HashMap<NodeId, Tags>,
+    ranges: HashMap<NodeId, Range<usize>>,
+    /// The content of comments, between `<!--` and `-->`
+    comments: HashMap<NodeId, Option<Range<usize>>>,
+}
+
+impl SourceMap {
+    /// Match the tokens of `source` to the nodes of `document` in document
+    /// order, skipping elements the parser implied and markup it dropped
+    fn new(source: &str, document: &Html) -> Self {
+        let nodes: Vec<NodeRef<Node>> = document
+            .tree
+            .root()
+            .descendants()
+            .filter(|node| {
+                matches!(
+                    node.value(),
+                    Node::Element(_) | Node::Text(_) | Node::Comment(_)
+                )
+            })
+            .collect();
+
+        let mut map = SourceMap {
+            tags: HashMap::new(),
+            ranges: HashMap::new(),
+            comments: HashMap::new(),
+        };
+        let mut cursor = 0;
+        let mut open: Vec<(String, NodeId)> = Vec::new();
+
+        for token in tokenize(source) {
+            // Look ahead past elements the parser may have implied
+            let next = |cursor: usize, wanted: &dyn Fn(&Node) -> bool| {
+                nodes[cursor..]
+                    .iter()
+                    .position(|node| {
+                        wanted(node.value())
+                            || !matches!(node.value(), Node::Element(e) if IMPLIED_ELEMENTS.contains(&e.name()))
+                    })
+                    .map(|offset| cursor + offset)
+                    .filter(|&index| wanted(nodes[index].value()))
+            };
+
+            match token {
+                Token::StartTag(tag) => {
+                    let name = source[tag.name.clone()].to_ascii_lowercase();
+                    let wanted = |node: &Node| matches!(node, Node::Element(e) if e.name().eq_ignore_ascii_case(&name));
+                    if let Some(index) = next(cursor, &wanted) {
+                        let id = nodes[index].id();
+                        open.push((name, id));
+                        map.tags.entry(id).or_default().start = Some(tag);
+                        cursor = index + 1;
+                    }
+                }
+                Token::EndTag(tag) => {
+                    let name = source[tag.name.clone()].to_ascii_lowercase();
+                    if let Some(position) =
+                        open.iter().rposition(|(open_name, _)| *open_name == name)
+                    {
+                        let (_, id) = open[position];
+                        open.truncate(position);
+                        map.tags.entry(id).or_default().end = Some(tag);
+                    }
+                }
+                Token::Text(range) => {
+                    if let Some(index) = next(cursor, &|node| matches!(node, Node::Text(_))) {
+                        map.ranges.insert(nodes[index].id(), range);
+                        cursor = index + 1;
+                    }
+                }
+                Token::Comment(range, content) => {
+                    if let Some(index) = next(cursor, &|node| matches!(node, Node::Comment(_))) {
+                        map.ranges.insert(nodes[index].id(), range);
+                        map.comments.insert(nodes[index].id(), content);
+                        cursor = index + 1;
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    fn tags(&self, node: NodeRef<Node>) -> Tags {
+        self.tags.get(&node.id()).cloned().unwrap_or_default()
+    }
+
+    /// The source range of a node and its subtree
+    fn span(&self, node: NodeRef<Node>) -> Option<Range<usize>> {
+        if let Some(range) = self.ranges.get(&node.id()) {
+            return Some(range.clone());
+        }
+        let tags = self.tags.get(&node.id());
+        let children: Vec<_> = node
+            .children()
+            .filter_map(|child| self.span(child))
+            .collect();
+        let start = tags
+            .and_then(|tags| tags.start.as_ref())
+            .map(|tag| tag.range.start)
+            .or_else(|| children.first().map(|range| range.start))?;
+        let end = match tags.and_then(|tags| tags.end.as_ref()) {
+            Some(end) => end.range.end,
+            None => children
+                .iter()
+                .map(|range| range.end)
+                .chain(
+                    tags.and_then(|tags| tags.start.as_ref())
+                        .map(|tag| tag.range.end),
+                )
+                .max()?,
+        };
+        Some(start..end)
+    }
+
+    /// Where the content of `element` starts in the source
+    fn content_start(&self, element: NodeRef<Node>) -> Option<usize> {
+        match self
+            .tags
+            .get(&element.id())
+            .and_then(|tags| tags.start.as_ref())
+        {
+            Some(start) => Some(start.range.end),
+            None => self.span(element).map(|range| range.start),
+        }
+    }
+}
+
+/// Split HTML source into tags, text and comments; doctypes and processing
+/// instructions are skipped
+fn tokenize(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let find = |from: usize, needle: &str| source[from..].find(needle).map(|i| from + i);
+    let is_name = |b: u8| !b.is_ascii_whitespace() && b != b'>' && b != b'/' && b != b'=';
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if rest.starts_with(b"<!--") {
+            let (content, end) = comment(source, i);
+            tokens.push(Token::Comment(i..end, content));
+            i = end;
+        } else if rest.starts_with(b"</") && rest.get(2).is_some_and(u8::is_ascii_alphabetic) {
+            let name_end = (i + 2..bytes.len())
+                .find(|&j| !is_name(bytes[j]))
+                .unwrap_or(bytes.len());
+            let end = find(i, ">").map_or(bytes.len(), |end| end + 1);
+            tokens.push(Token::EndTag(EndTag {
+                range: i..end,
+                name: i + 2..name_end,
+            }));
+            i = end;
+        } else if rest[0] == b'<' && rest.get(1).is_some_and(u8::is_ascii_alphabetic) {
+            let tag = start_tag(bytes, i);
+            let name = source[tag.name.clone()].to_ascii_lowercase();
+            i = tag.range.end;
+            tokens.push(Token::StartTag(tag));
+
+            if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                let close = format!("</{}", name);
+                let end = source[i..]
+                    .to_ascii_lowercase()
+                    .find(&close)
+                    .map_or(bytes.len(), |end| i + end);
+                if end > i {
+                    tokens.push(Token::Text(i..end));
+                }
+                i = end;
+            }
+        } else if rest[0] == b'<' && matches!(rest.get(1), Some(b'!' | b'?')) {
+            i = find(i, ">").map_or(bytes.len(), |end| end + 1);
+        } else {
+            let end = (i + 1..bytes.len())
+                .find(|&j| {
+                    bytes[j] == b'<'
+                        && bytes.get(j + 1).is_some_and(|b| {
+                            b.is_ascii_alphabetic() || matches!(b, b'/' | b'!' | b'?')
+                        })
+                })
+                .unwrap_or(bytes.len());
+            tokens.push(Token::Text(i..end));
+            i = end;
+        }
+    }
+    tokens
+}
+
+/// The content of the comment beginning at `start` and where it ends
+///
+/// As in the parser, `<!-->` and `<!--->` close an empty comment, which has
+/// no content to edit, and a comment left open runs to the end of the
+/// source, without the dashes it ends in.
+fn comment(source: &str, start: usize) -> (Option<Range<usize>>, usize) {
+    let content_start = start + 4;
+    let rest = &source[content_start..];
+    if rest.starts_with('>') {
+        return (None, content_start + 1);
+    }
+    if rest.starts_with("->") {
+        return (None, content_start + 2);
+    }
+    match rest.find("-->") {
+        Some(end) => (
+            Some(content_start..content_start + end),
+            content_start + end + 3,
+        ),
+        None => {
+            let content = rest.strip_suffix('-').unwrap_or(rest);
+            let content = content.strip_suffix('-').unwrap_or(content);
+            (
+                Some(content_start..content_start + content.len()),
+                source.len(),
+            )
+        }
+    }
+}
+
+/// Parse the start tag beginning at `start`
+fn start_tag(bytes: &[u8], start: usize) -> StartTag {
+    let is_name = |b: u8| !b.is_ascii_whitespace() && b != b'>' && b != b'/' && b != b'=';
+    let skip_whitespace = |mut j: usize| {
+        while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+            j += 1;
+        }
+        j
+    };
+
+    let name_end = (start + 1..bytes.len())
+        .find(|&j| !is_name(bytes[j]))
+        .unwrap_or(bytes.len());
+    let mut attributes = Vec::new();
+    let mut attributes_end = name_end;
+    let mut j = name_end;
+    loop {
+        let attribute_start = j;
+        j = skip_whitespace(j);
+        match bytes.get(j) {
+            None => break,
+            Some(b'>') => {
+                j += 1;
+                break;
+            }
+            Some(b'/') => {
+                j += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let name_start = j;
+        while j < bytes.len() && is_name(bytes[j]) {
+            j += 1;
+        }
+        let name = String::from_utf8_lossy(&bytes[name_start..j]).to_ascii_lowercase();
+        let after_name = j;
+        j = skip_whitespace(j);
+        if bytes.get(j) == Some(&b'=') {
+            j = skip_whitespace(j + 1);
+            match bytes.get(j) {
+                Some(&quote @ (b'"' | b'\'')) => {
+                    j = (j + 1..bytes.len())
+                        .find(|&k| bytes[k] == quote)
+                        .map_or(bytes.len(), |k| k + 1);
+                }
+                _ => {
+                    while j < bytes.len() && !bytes[j].is_ascii_whitespace() && bytes[j] != b'>' {
+                        j += 1;
+                    }
+                }
+            }
+        } else {
+            j = after_name;
+        }
+        attributes.push((name, attribute_start..j));
+        attributes_end = j;
+    }
+
+    StartTag {
+        range: start..j,
+        name: start + 1..name_end,
+        attributes,
+        attributes_end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    #[test]
+    fn test_apply_diff_preserves_untouched_markup() {
+        let expected = "<ul class='menu' data-x=1>\n  <li>One &amp; only</li>\n  <li>Two</li>\n  <li>Three\n</ul>\n<p>Keep <b>this</b></p>\n";
+        let actual = "<ol class=\"menu\" data-x=\"1\" id=\"m\"><li>Three</li><li>One &amp; only</li><li>2</li></ol><p>Keep <b>this</b></p>";
+
+        let comparer = HtmlComparer::new();
+        let diff = comparer.diff(expected, actual);
+        let updated = comparer.apply_diff(expected, &diff).unwrap();
+
+        comparer.verify(&updated, actual).unwrap();
+        assert!(updated.starts_with("<ol class='menu' data-x=1 id=\"m\">"));
+        assert!(updated.contains("<li>One &amp; only</li>"));
+        assert!(updated.ends_with("</ol>\n<p>Keep <b>this</b></p>\n"));
+    }
+
+    #[test]
+    fn test_apply_diff_attributes_and_text() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_comments: false,
+            ..Default::default()
+        });
+        let expected = "<!DOCTYPE html>\n<html>\n<head><title>Old</title></head>\n<body>\n  <!-- v1 -->\n  <a href=\"/a\" hidden target=_blank>Go</a>\n  <div>\n    <span>Remove me</span>\n  </div>\n</body>\n</html>\n";
+        let actual = "<!DOCTYPE html><html><head><title>New &lt;3</title></head><body><!-- v2 --><a href=\"/b\">Go</a><div></div></body></html>";
+
+        let diff = comparer.diff(expected, actual);
+        let updated = comparer.apply_diff(expected, &diff).unwrap();
+        comparer.verify(&updated, actual).unwrap();
+        assert_eq!(
+            updated,
+            "<!DOCTYPE html>\n<html>\n<head><title>New <3</title></head>\n<body>\n  <!-- v2 -->\n  <a href=\"/b\">Go</a>\n  <div>\n    \n  </div>\n</body>\n</html>\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_diff_comments() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_comments: false,
+            ..Default::default()
+        });
+        // Comments left open at the end of the source, or closed abruptly
+        for (expected, actual, updated) in [
+            ("<p>A</p><!--open", "<p>A</p><!--new-->", "<p>A</p><!--new"),
+            ("<p>A<!--é--", "<p>A<!--new-->", "<p>A<!--new--"),
+            ("\u{FEFF}<p><!--éé", "<p><!--new-->", "\u{FEFF}<p><!--new"),
+            (
+                "<p>A<!-->B</p>",
+                "<p>A<!--new-->B</p>",
+                "<p>A<!--new-->B</p>",
+            ),
+            (
+                "<p>A<!--->B</p>",
+                "<p>A<!--new-->B</p>",
+                "<p>A<!--new-->B</p>",
+            ),
+        ] {
+            let diff = comparer.diff(expected, actual);
+            assert_eq!(comparer.apply_diff(expected, &diff).unwrap(), updated);
+            comparer.verify(updated, actual).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_apply_diff_with_parser() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            parser: crate::Parser::from_name("fragment").unwrap(),
+            ..Default::default()
+        });
+        let diff = comparer.diff("<p>One</p>", "<p>Two</p>");
+        assert_eq!(
+            comparer.apply_diff("<p>One</p>", &diff).unwrap(),
+            "<p>Two</p>"
+        );
+    }
+
+    #[test]
+    fn test_apply_diff_void_and_raw_text() {
+        let comparer = HtmlComparer::new();
+        for (expected, actual, updated) in [
+            (
+                "<p>a<br>b</p>",
+                "<p>a <b>b</b> c</p>",
+                // Whitespace the comparison ignores is left as it was
+                "<p>a<b>b</b> c</p>",
+            ),
+            (
+                "<div><p>1</p>text<p>2</p></div>",
+                "<p>a<br>b</p>",
+                "<p>a<br>b</p>",
+            ),
+            (
+                "<div><hr></div>",
+                "<div><p>1</p>text<p>2</p></div>",
+                "<div><p>1</p>text<p>2</p></div>",
+            ),
+            (
+                "<script></script>",
+                "<script>if (a<b) x()</script>",
+                "<script>if (a<b) x()</script>",
+            ),
+        ] {
+            let diff = comparer.diff(expected, actual);
+            assert_eq!(comparer.apply_diff(expected, &diff).unwrap(), updated);
+        }
+    }
+
+    #[test]
+    fn test_apply_diff_checks_the_result() {
+        let comparer = HtmlComparer::new();
+        // Edits made for another fixture land on the same paths but leave
+        // the text as it was
+        let diff = comparer.diff("<p class=a>A</p>", "<p class=b>A</p>");
+        assert!(matches!(
+            comparer.apply_diff("<p class=a>Z</p>", &diff),
+            Err(HtmlCompareError::InvalidEdit(_))
+        ));
+    }
+
+    #[test]
+    fn test_apply_diff_rejects_unknown_paths() {
+        let comparer = HtmlComparer::new();
+        let diff = comparer.diff("<p>A</p><p>B</p>", "<p>A</p>");
+        assert!(matches!(
+            comparer.apply_diff("<div></div>", &diff),
+            Err(HtmlCompareError::InvalidEdit(_))
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+//! Patterns over the node paths of differences.
+//!
+//! A pattern is a path whose steps may hold wildcards: `*` matches any run
+//! of characters within a step, and a step of its own `**` matches any
+//! number of steps, none included. A step without an index matches the
+//! step with any index, so `/html/body/ul/li` matches `/html/body/ul/li[2]`
+//! while `li[2]` matches the second item only. `/html/body/footer/**`
+//! matches the footer and everything inside it. As in XPath, `//` stands for
+//! `/**/`, so `//footer//**` matches every footer and its contents wherever
+//! it is.
+
+use crate::markdown::matches_pattern;
+
+/// Whether `path` matches the pattern `glob`
+pub(crate) fn matches(glob: &str, path: &str) -> bool {
+    // Empty steps after the first come from `//`
+    let glob: Vec<_> = glob
+        .split('/')
+        .enumerate()
+        .map(|(index, step)| match index > 0 && step.is_empty() {
+            true => "**",
+            false => step,
+        })
+        .collect();
+    let path: Vec<_> = path.split('/').collect();
+    matches_steps(&glob, &path)
+}
+
+fn matches_steps(glob: &[&str], path: &[&str]) -> bool {
+    match glob.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => {
+            (0..=path.len()).any(|skipped| matches_steps(rest, &path[skipped..]))
+        }
+        Some((step, rest)) => path.split_first().is_some_and(|(first, others)| {
+            matches_step(step, first) && matches_steps(rest, others)
+        }),
+    }
+}
+
+/// Whether the path step `step` matches the pattern step `glob`
+fn matches_step(glob: &str, step: &str) -> bool {
+    let step = match glob.contains('[') {
+        true => step,
+        false => step.split_once('[').map_or(step, |(name, _)| name),
+    };
+    matches_pattern(glob, step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("/html/body/ul/li", "/html/body/ul/li[2]"));
+        assert!(matches("/html/body/ul/li[2]", "/html/body/ul/li[2]"));
+        assert!(!matches("/html/body/ul/li[1]", "/html/body/ul/li[2]"));
+        assert!(!matches("/html/body/ul", "/html/body/ul/li[2]"));
+        assert!(matches("/html/body/*/li[*]", "/html/body/ol/li[3]"));
+        assert!(matches("/html/body/footer/**", "/html/body/footer"));
+        assert!(matches(
+            "/html/body/footer/**",
+            "/html/body/footer/p[2]/text()"
+        ));
+        assert!(matches("/html/**/a", "/html/body/nav/ul/li[2]/a"));
+        assert!(!matches("/html/**/a", "/html/body/nav/abbr"));
+        assert!(matches("**/text()", "/html/body/p/text()"));
+        assert!(matches("//footer//**", "/html/body/div[2]/footer/p/text()"));
+        assert!(matches("//footer//**", "/html/body/footer"));
+        assert!(!matches("//footer//**", "/html/body/main/p"));
+        assert!(matches("/html/body//a", "/html/body/nav/a[3]"));
+    }
+}
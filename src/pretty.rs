@@ -0,0 +1,190 @@
+//! Checking HTML formatter output.
+//!
+//! A formatter may only add whitespace where the browser ignores it: between
+//! block-level elements and at the start and end of their content. Where it
+//! also breaks up inline content or wraps long lines, it relies on whitespace
+//! runs rendering as a single space. A [`PrettyProfile`] states which of
+//! these a formatter does, and
+//! [`assert_prettified_equivalent!`](crate::assert_prettified_equivalent)
+//! checks its output under exactly those allowances, so inserting a newline
+//! between `a<b>b</b>` is caught unless the profile permits it.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::HtmlCompareOptions;
+
+/// Elements around which, and at the edges of whose content, whitespace is
+/// not rendered
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "caption",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "meta",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "script",
+    "section",
+    "style",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "ul",
+];
+
+/// Elements whose content keeps its whitespace
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "textarea"];
+
+/// What a formatter is allowed to change besides whitespace around blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrettyProfile {
+    /// Put inline content on lines of its own, e.g. `<p>Hi <b>you</b></p>`
+    /// as `<p>`, `Hi`, `<b>you</b>`, `</p>` on separate lines
+    pub indent_inline: bool,
+    /// Wrap long text, replacing spaces with line breaks and indentation
+    pub wrap_text: bool,
+}
+
+impl PrettyProfile {
+    /// Comparison options that tolerate exactly the changes of this profile
+    pub fn options(&self) -> HtmlCompareOptions {
+        HtmlCompareOptions {
+            ignore_whitespace: self.indent_inline,
+            ignore_block_whitespace: true,
+            collapse_whitespace: self.wrap_text,
+            normalize_optional_tags: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Whether `node` is a block-level element
+pub(crate) fn is_block(node: Option<NodeRef<Node>>) -> bool {
+    matches!(
+        node.as_ref().map(NodeRef::value),
+        Some(Node::Element(element)) if BLOCK_ELEMENTS.contains(&element.name())
+    )
+}
+
+/// The closest sibling before (or after) `node` that is not a comment
+fn sibling(node: NodeRef<Node>, before: bool) -> Option<NodeRef<Node>> {
+    let mut current = node;
+    loop {
+        current = match before {
+            true => current.prev_sibling()?,
+            false => current.next_sibling()?,
+        };
+        if !matches!(current.value(), Node::Comment(_)) {
+            return Some(current);
+        }
+    }
+}
+
+/// The text of `node` without whitespace that borders on a block boundary
+pub(crate) fn trim_block_whitespace<'a>(node: NodeRef<Node>, text: &'a str) -> &'a str {
+    let parent = node.parent();
+    let preformatted = node
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|element| PREFORMATTED_ELEMENTS.contains(&element.value().name()));
+    if preformatted {
+        return text;
+    }
+    let mut text = text;
+    match sibling(node, true) {
+        Some(previous) if is_block(Some(previous)) => text = text.trim_start(),
+        None if is_block(parent) => text = text.trim_start(),
+        _ => {}
+    }
+    match sibling(node, false) {
+        Some(next) if is_block(Some(next)) => text = text.trim_end(),
+        None if is_block(parent) => text = text.trim_end(),
+        _ => {}
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlComparer;
+
+    const ORIGINAL: &str =
+        r#"<div class="card"><h2>Title</h2><p>Some <b>bold</b> text that is long</p></div>"#;
+
+    #[test]
+    fn test_block_indentation() {
+        let pretty = "<div class=\"card\">\n  <h2>Title</h2>\n  <p>Some <b>bold</b> text that is long</p>\n</div>\n";
+        assert_prettified_equivalent!(ORIGINAL, pretty);
+
+        // A space inserted inside inline content renders
+        let comparer = HtmlComparer::with_options(PrettyProfile::default().options());
+        let spaced = pretty.replace("<b>bold</b>", "<b> bold</b>");
+        assert!(comparer.verify(ORIGINAL, &spaced).is_err());
+        let wrapped = pretty.replace("that is long", "that\n    is long");
+        assert!(comparer.verify(ORIGINAL, &wrapped).is_err());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let wrapped = "<div class=\"card\">\n  <h2>Title</h2>\n  <p>Some <b>bold</b> text\n    that is long</p>\n</div>";
+        assert_prettified_equivalent!(
+            ORIGINAL,
+            wrapped,
+            PrettyProfile {
+                wrap_text: true,
+                ..Default::default()
+            }
+        );
+
+        let inline = "<div class=\"card\">\n  <h2>Title</h2>\n  <p>\n    Some\n    <b>bold</b>\n    text that is long\n  </p>\n</div>";
+        let profile = PrettyProfile {
+            indent_inline: true,
+            ..Default::default()
+        };
+        assert_prettified_equivalent!(ORIGINAL, inline, profile);
+        assert!(
+            HtmlComparer::with_options(PrettyProfile::default().options())
+                .verify(ORIGINAL, inline)
+                .is_err()
+        );
+    }
+}
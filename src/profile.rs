@@ -0,0 +1,182 @@
+//! Timing of the comparison per subtree.
+//!
+//! Assertions on large pages can get slow, and the whole-document time
+//! doesn't say why. [`HtmlCompareOptions::on_node_compared`](crate::HtmlCompareOptions)
+//! is called after every pair of elements [`HtmlComparer::diff`](crate::HtmlComparer::diff)
+//! compares, with the time spent on their subtrees; a [`Profiler`] collects
+//! these calls and reports the slowest subtrees.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type HookFn = dyn Fn(&str, Duration, bool) + Send + Sync;
+
+/// Callback receiving the path of each compared element, the time spent on
+/// its subtree and whether the subtrees were equal
+#[derive(Clone)]
+pub struct NodeHook(Arc<HookFn>);
+
+impl NodeHook {
+    /// Call `hook` after every compared element
+    pub fn new(hook: impl Fn(&str, Duration, bool) + Send + Sync + 'static) -> Self {
+        NodeHook(Arc::new(hook))
+    }
+
+    pub(crate) fn call(&self, path: &str, duration: Duration, equal: bool) {
+        (self.0)(path, duration, equal)
+    }
+}
+
+impl fmt::Debug for NodeHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("NodeHook(..)")
+    }
+}
+
+/// The time spent comparing one subtree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeTiming {
+    pub path: String,
+    /// Time spent on the element and its descendants
+    pub duration: Duration,
+    /// Whether the subtrees compared equal
+    pub equal: bool,
+}
+
+impl fmt::Display for NodeTiming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>10.3?}  {}", self.duration, self.path)?;
+        if !self.equal {
+            f.write_str(" (differs)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the timings of every comparison made with its [`Profiler::hook`]
+///
+/// # Examples
+/// ```ignore
+/// let profiler = Profiler::default();
+/// let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+///     on_node_compared: Some(profiler.hook()),
+///     ..Default::default()
+/// });
+/// comparer.diff(&expected, &actual);
+/// println!("{}", profiler.report(10));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    timings: Arc<Mutex<Vec<NodeTiming>>>,
+}
+
+impl Profiler {
+    /// A hook recording into this profiler
+    pub fn hook(&self) -> NodeHook {
+        let timings = Arc::clone(&self.timings);
+        NodeHook::new(move |path, duration, equal| {
+            timings.lock().unwrap().push(NodeTiming {
+                path: path.to_string(),
+                duration,
+                equal,
+            })
+        })
+    }
+
+    /// The `limit` slowest subtrees, slowest first
+    ///
+    /// Timings include descendants, so the root and the ancestors of slow
+    /// subtrees come first.
+    pub fn slowest(&self, limit: usize) -> Vec<NodeTiming> {
+        let mut timings = self.timings.lock().unwrap().clone();
+        timings.sort_by(|a, b| {
+            b.duration
+                .cmp(&a.duration)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        timings.truncate(limit);
+        timings
+    }
+
+    /// Number of element pairs compared so far
+    pub fn len(&self) -> usize {
+        self.timings.lock().unwrap().len()
+    }
+
+    /// Whether nothing was compared yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget the recorded timings
+    pub fn clear(&self) {
+        self.timings.lock().unwrap().clear()
+    }
+
+    /// A table of the `limit` slowest subtrees
+    pub fn report(&self, limit: usize) -> String {
+        let slowest = self.slowest(limit);
+        let mut report = format!("slowest {} of {} subtrees:", slowest.len(), self.len());
+        for timing in slowest {
+            report.push_str(&format!("\n{}", timing));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_profiler() {
+        let profiler = Profiler::default();
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            on_node_compared: Some(profiler.hook()),
+            ..Default::default()
+        });
+        comparer.diff(
+            "<ul><li>a</li><li>b</li></ul>",
+            "<ul><li>a</li><li>c</li></ul>",
+        );
+
+        // html, head, body, ul and two items
+        assert_eq!(profiler.len(), 6);
+        let slowest = profiler.slowest(10);
+        assert_eq!(slowest[0].path, "/html");
+        assert!(slowest
+            .windows(2)
+            .all(|pair| pair[0].duration >= pair[1].duration));
+        let differing: Vec<_> = slowest
+            .iter()
+            .filter(|timing| !timing.equal)
+            .map(|timing| timing.path.as_str())
+            .collect();
+        assert_eq!(differing.len(), 4);
+        assert!(differing.contains(&"/html/body/ul/li[2]"));
+        assert!(profiler.report(2).starts_with("slowest 2 of 6 subtrees:\n"));
+
+        profiler.clear();
+        assert!(profiler.is_empty());
+    }
+
+    #[test]
+    fn test_node_hook_in_exhaustive_compare() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&calls);
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            exhaustive: true,
+            on_node_compared: Some(NodeHook::new(move |path, _, equal| {
+                recorded.lock().unwrap().push((path.to_string(), equal))
+            })),
+            ..Default::default()
+        });
+        comparer.verify("<p>Hi</p>", "<p>Hi</p>").unwrap();
+        assert!(calls
+            .lock()
+            .unwrap()
+            .contains(&("/html/body/p".to_string(), true)));
+    }
+}
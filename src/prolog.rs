@@ -0,0 +1,83 @@
+//! Invisible prefixes of exported files.
+//!
+//! Editors and export tools prepend byte order marks, `<?xml ...?>`
+//! declarations, blank lines and comments to documents. Most of them vanish
+//! in parsing, but a byte order mark after a newline becomes text in
+//! `<body>`, and fragments keep all of them. With
+//! [`HtmlCompareOptions::tolerate_prolog_junk`](crate::HtmlCompareOptions)
+//! they are stripped from the source before it is parsed.
+
+/// `html` without the byte order marks, processing instructions and
+/// whitespace before its content, and without the comments before its
+/// doctype
+pub(crate) fn strip_prolog(html: &str) -> &str {
+    let mut rest = html;
+    let mut first_comment = None;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == '\u{feff}' || c.is_ascii_whitespace());
+        // Parsers end processing instructions at the first `>`, so do the
+        // same
+        let after = if let Some(instruction) = rest.strip_prefix("<?") {
+            instruction.split_once('>').map(|(_, after)| after)
+        } else if let Some(comment) = rest.strip_prefix("<!--") {
+            first_comment.get_or_insert(rest);
+            comment.split_once("-->").map(|(_, after)| after)
+        } else {
+            break;
+        };
+        match after {
+            Some(after) => rest = after,
+            None => break,
+        }
+    }
+    match first_comment {
+        Some(comment) if !starts_with_doctype(rest) => comment,
+        _ => rest,
+    }
+}
+
+fn starts_with_doctype(html: &str) -> bool {
+    html.get(..9)
+        .is_some_and(|start| start.eq_ignore_ascii_case("<!doctype"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer, Parser};
+
+    #[test]
+    fn test_strip_prolog() {
+        assert_eq!(
+            strip_prolog(
+                "\u{feff}<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!-- saved by Tool -->\n<!DOCTYPE html><p>"
+            ),
+            "<!DOCTYPE html><p>"
+        );
+        assert_eq!(strip_prolog("\n \u{feff}<p>Hi</p>"), "<p>Hi</p>");
+        assert_eq!(
+            strip_prolog("<!-- kept --><p>Hi</p>"),
+            "<!-- kept --><p>Hi</p>"
+        );
+        assert_eq!(strip_prolog("<!-- unclosed"), "<!-- unclosed");
+    }
+
+    #[test]
+    fn test_tolerate_prolog_junk() {
+        let comparer = |tolerate_prolog_junk| {
+            HtmlComparer::with_options(HtmlCompareOptions {
+                tolerate_prolog_junk,
+                ignore_comments: false,
+                parser: Parser::from_name("fragment").unwrap(),
+                ..Default::default()
+            })
+        };
+        let exported =
+            "\u{feff}<?xml version=\"1.0\"?>\n<!-- exported -->\n<!DOCTYPE html><p>Hi</p>";
+        comparer(true).verify("<p>Hi</p>", exported).unwrap();
+        assert!(!comparer(false).is_equal("<p>Hi</p>", exported).unwrap());
+        assert!(!comparer(true)
+            .is_equal("<p>Hi</p>", "<!-- note --><p>Hi</p>")
+            .unwrap());
+    }
+}
@@ -0,0 +1,159 @@
+//! Query strings of URL attributes.
+//!
+//! Servers and routers rebuild query strings in whatever order their
+//! parameter maps iterate, so `/search?q=a&page=2` comes back as
+//! `/search?page=2&q=a`, and cache busters like `?v=1718` change with every
+//! build. With
+//! [`HtmlCompareOptions::unordered_query_params`](crate::HtmlCompareOptions::unordered_query_params)
+//! the query of `href`, `src`, `action`, `formaction` and `xlink:href` values
+//! is parsed into names and values, percent-decoded with `+` as a space, and
+//! compared byte for byte as a multimap: order doesn't matter, but a parameter given twice
+//! must be given twice in both. Parameters named in
+//! [`HtmlCompareOptions::ignored_query_params`](crate::HtmlCompareOptions::ignored_query_params)
+//! are left out, whether or not the order is ignored. The rest of the URL,
+//! fragment included, is compared as written.
+
+use std::borrow::Cow;
+
+use crate::assets::percent_decode_bytes;
+use crate::security::URL_ATTRIBUTES;
+use crate::HtmlCompareOptions;
+
+/// Whether the query of the value of attribute `name` is normalized under
+/// `options`
+pub(crate) fn applies(options: &HtmlCompareOptions, name: &str) -> bool {
+    (options.unordered_query_params || !options.ignored_query_params.is_empty())
+        && URL_ATTRIBUTES.contains(&name)
+}
+
+/// `url` with its query parameters decoded, those ignored by `options`
+/// removed and, if their order is ignored, sorted
+pub(crate) fn normalize<'a>(options: &HtmlCompareOptions, url: &'a str) -> Cow<'a, str> {
+    let (url_without_fragment, fragment) = url.split_at(url.find('#').unwrap_or(url.len()));
+    let Some((base, query)) = url_without_fragment.split_once('?') else {
+        return Cow::Borrowed(url);
+    };
+    let mut params: Vec<_> = query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let (name, value) = param.split_once('=').unwrap_or((param, ""));
+            (decode(name), decode(value))
+        })
+        .filter(|(name, _)| {
+            !std::str::from_utf8(name).is_ok_and(|name| options.ignored_query_params.contains(name))
+        })
+        .collect();
+    if options.unordered_query_params {
+        params.sort_unstable();
+    }
+
+    let mut normalized = base.to_string();
+    for (index, (name, value)) in params.iter().enumerate() {
+        normalized.push(if index == 0 { '?' } else { '&' });
+        normalized.push_str(&encode(name));
+        normalized.push('=');
+        normalized.push_str(&encode(value));
+    }
+    normalized.push_str(fragment);
+    Cow::Owned(normalized)
+}
+
+/// The bytes of a query component with `+` read as a space and
+/// percent-decoded
+///
+/// Escapes that don't decode to UTF-8 are kept as bytes, so that `%ff` and
+/// `%fe` stay apart.
+fn decode(component: &str) -> Vec<u8> {
+    percent_decode_bytes(&component.replace('+', " "))
+}
+
+/// `decoded` with the characters that delimit query components, and bytes
+/// that aren't UTF-8, escaped, so that distinct parameters can't normalize
+/// to the same string
+fn encode(decoded: &[u8]) -> String {
+    let mut encoded = String::with_capacity(decoded.len());
+    for chunk in decoded.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '%' | '&' | '=' | '#' | '+' | ' ' => encoded.push_str(&format!("%{:02X}", c as u8)),
+                _ => encoded.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlComparer;
+
+    #[test]
+    fn test_normalize() {
+        let options = HtmlCompareOptions {
+            unordered_query_params: true,
+            ignored_query_params: ["v".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
+        let normalize = |url| normalize(&options, url).into_owned();
+        assert_eq!(
+            normalize("/s?q=a+b&page=2&v=9#top"),
+            "/s?page=2&q=a%20b#top"
+        );
+        assert_eq!(normalize("/s?page=2&q=a%20b"), "/s?page=2&q=a%20b");
+        assert_eq!(normalize("/s?tag=b&tag=a&flag"), "/s?flag=&tag=a&tag=b");
+        assert_eq!(normalize("/s?a=x%26b%3Dy"), "/s?a=x%26b%3Dy");
+        assert_ne!(normalize("/s?a=x%26b%3Dy"), normalize("/s?a=x&b=y"));
+        assert_eq!(normalize("/s?v=1"), "/s");
+        assert_eq!(normalize("/s#a?b"), "/s#a?b");
+        assert_eq!(normalize("/s?a=%ff"), "/s?a=%FF");
+        assert_ne!(normalize("/s?a=%ff"), normalize("/s?a=%fe"));
+        assert_eq!(normalize("/s?a=%e9"), "/s?a=%E9");
+        assert_eq!(normalize("/s?a=%C3%A9"), "/s?a=é");
+        // A sign isn't a hex digit
+        assert_eq!(normalize("/s?a=%+1"), "/s?a=%25%201");
+        assert_ne!(normalize("/s?a=%+1"), normalize("/s?a=%01"));
+    }
+
+    #[test]
+    fn test_unordered_query_params() {
+        let expected =
+            r#"<a href="/search?q=rust+html&page=2&sort=new">Next</a><img src="/logo.png?v=1">"#;
+        let actual =
+            r#"<a href="/search?sort=new&page=2&q=rust%20html">Next</a><img src="/logo.png?v=2">"#;
+        assert!(!HtmlComparer::new().is_equal(expected, actual).unwrap());
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            unordered_query_params: true,
+            ignored_query_params: ["v".to_string()].into_iter().collect(),
+            ..Default::default()
+        });
+        comparer.verify(expected, actual).unwrap();
+        for changed in [
+            actual.replace("page=2", "page=3"),
+            actual.replace("page=2", "page=2&page=2"),
+            actual.replace("/search", "/find"),
+        ] {
+            assert!(
+                !comparer.is_equal(expected, &changed).unwrap(),
+                "{}",
+                changed
+            );
+        }
+        // Escapes that aren't UTF-8 are compared as bytes
+        assert!(!comparer
+            .is_equal(r#"<a href="/s?a=%ff">x</a>"#, r#"<a href="/s?a=%fe">x</a>"#)
+            .unwrap());
+        // Only URL attributes are parsed
+        assert!(!comparer
+            .is_equal(
+                r#"<div data-query="a=1&b=2"></div>"#,
+                r#"<div data-query="b=2&a=1"></div>"#
+            )
+            .unwrap());
+    }
+}
@@ -0,0 +1,100 @@
+//! Comparing one part of each document.
+//!
+//! Pages wrap the interesting content in navigation, footers and scripts
+//! that a test usually does not care about. With
+//! [`HtmlCompareOptions::root_selector`](crate::HtmlCompareOptions) set to
+//! e.g. `main#content`, both documents are reduced to the first element
+//! matching the selector before they are compared, and a document without
+//! such an element fails the comparison.
+
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{HtmlCompareError, HtmlComparer};
+
+/// Parse the `root_selector` option, or describe why it is invalid
+pub(crate) fn compile_root_selector(selector: Option<&str>) -> Result<Option<Selector>, String> {
+    selector
+        .map(|selector| Selector::parse(selector).map_err(|err| format!("{}: {}", selector, err)))
+        .transpose()
+}
+
+impl HtmlComparer {
+    /// The element of `document` that is compared: the first match of the
+    /// root selector, or the `<html>` element without one. `which` names the
+    /// document in the error
+    pub(crate) fn compared_root<'a>(
+        &self,
+        document: &'a Html,
+        which: &'static str,
+    ) -> Result<ElementRef<'a>, HtmlCompareError> {
+        let Ok(Some(selector)) = &self.root_selector else {
+            return Ok(document.root_element());
+        };
+        document
+            .select(selector)
+            .next()
+            .ok_or_else(|| HtmlCompareError::RootNotFound {
+                selector: self.options.root_selector.clone().unwrap_or_default(),
+                document: which,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HtmlCompareError, HtmlCompareOptions, HtmlComparer};
+
+    fn comparer(selector: &str) -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            root_selector: Some(selector.to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_root_selector() {
+        let comparer = comparer("main#content");
+        let expected = "<main id='content'><h1>Title</h1></main>";
+        comparer
+            .verify(
+                expected,
+                "<nav>Home</nav><main id='content'><h1>Title</h1></main><footer>2026</footer>",
+            )
+            .unwrap();
+        assert!(!comparer
+            .is_equal(expected, "<main id='content'><h1>Other</h1></main>")
+            .unwrap());
+        assert_eq!(
+            comparer
+                .diff(expected, "<main id='content'><h1>Other</h1></main>")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_missing_root() {
+        let comparer = comparer("main");
+        let err = comparer.verify("<main></main>", "<div></div>").unwrap_err();
+        assert!(matches!(
+            err,
+            HtmlCompareError::RootNotFound {
+                document: "actual",
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "No element matches the root selector `main` in the actual document"
+        );
+        assert!(!comparer.diff("<main></main>", "<div></div>").is_empty());
+        assert!(matches!(
+            HtmlComparer::with_options(HtmlCompareOptions {
+                root_selector: Some("[".to_string()),
+                ..Default::default()
+            })
+            .verify("", ""),
+            Err(HtmlCompareError::InvalidSelector(_))
+        ));
+    }
+}
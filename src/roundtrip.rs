@@ -0,0 +1,84 @@
+//! Checking that a document survives being parsed and serialized.
+//!
+//! Tools that load HTML with scraper or html5ever, edit the tree and write it
+//! back rely on serialization preserving the document. Mis-nested markup,
+//! foster-parented table content or a hand-written serializer can break that
+//! invariant; [`HtmlComparer::verify_roundtrip`] reports where.
+
+use scraper::Html;
+
+use crate::{Difference, HtmlComparer};
+
+impl HtmlComparer {
+    /// Check that `html` compares equal to itself after being parsed and
+    /// serialized with scraper, returning the first difference otherwise
+    ///
+    /// # Examples
+    /// ```ignore
+    /// use html_compare::HtmlComparer;
+    ///
+    /// assert!(HtmlComparer::new().verify_roundtrip("<p>One<p>Two").is_ok());
+    /// ```
+    pub fn verify_roundtrip(&self, html: &str) -> Result<(), Difference> {
+        self.verify_roundtrip_with(html, Html::html)
+    }
+
+    /// Check that `html` compares equal to itself after being parsed and
+    /// written back by `serialize`, returning the first difference otherwise
+    pub fn verify_roundtrip_with(
+        &self,
+        html: &str,
+        serialize: impl Fn(&Html) -> String,
+    ) -> Result<(), Difference> {
+        let serialized = serialize(&self.parse(html));
+        match self.diff(html, &serialized).into_iter().next() {
+            Some(difference) => Err(difference),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format_html, FormatOptions, HtmlCompareOptions};
+
+    const PAGE: &str = r#"<title>Docs</title>
+        <table><tr><td>a<td>b</table>
+        <p>Some <b>bold <i>and</b> italic</i> text
+        <ul><li>One<li class="x">Two</ul>"#;
+
+    #[test]
+    fn test_verify_roundtrip() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_whitespace: false,
+            ignore_comments: false,
+            ..Default::default()
+        });
+        assert_eq!(comparer.verify_roundtrip(PAGE), Ok(()));
+        assert_eq!(
+            HtmlComparer::new().verify_roundtrip_with(PAGE, |document| {
+                format_html(&document.html(), &FormatOptions::default())
+            }),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_verify_roundtrip_reports_divergence() {
+        // A serializer that forgets attributes
+        let lossy = |document: &Html| document.html().replace(r#" class="x""#, "");
+        let Err(Difference::AttributeMismatch {
+            path,
+            name,
+            expected,
+            actual,
+        }) = HtmlComparer::new().verify_roundtrip_with(PAGE, lossy)
+        else {
+            panic!("expected an attribute mismatch");
+        };
+        assert!(path.ends_with("/ul/li[2]"), "{}", path);
+        assert_eq!(name, "class");
+        assert_eq!((expected.as_deref(), actual), (Some("x"), None));
+    }
+}
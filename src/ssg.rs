@@ -0,0 +1,133 @@
+//! Output of static site generators.
+//!
+//! A site rebuilt with another generator, or another version of the same
+//! one, keeps its content but not the markup around it. The presets
+//! [`presets::zola`](crate::presets::zola),
+//! [`presets::hugo`](crate::presets::hugo) and
+//! [`presets::mdbook`](crate::presets::mdbook) leave out what each generator
+//! adds on its own:
+//!
+//! - Zola: the `<a class="zola-anchor">` of `insert_anchor_links` and the
+//!   inline styles and `data-lang` of highlighted code blocks
+//! - Hugo: heading anchors added by render hooks, the `<div class="highlight">`
+//!   around code blocks, Chroma's attributes on `<pre>` and its line number
+//!   spans
+//! - mdBook: the page around `<main>`, the `<a class="header">` wrapping
+//!   heading text and the clipboard and playground buttons of code blocks
+//!
+//! All three compare code blocks by their text (see
+//! [`HtmlCompareOptions::ignore_code_highlighting`]) and ignore heading
+//! `id`s, whose slugs each generator derives its own way.
+
+use std::collections::HashSet;
+
+use crate::HtmlCompareOptions;
+
+const HEADINGS: &str = "h1, h2, h3, h4, h5, h6";
+
+/// Options shared by the presets: code compared by its text and heading
+/// `id`s ignored
+fn site_options() -> HtmlCompareOptions {
+    HtmlCompareOptions {
+        ignore_code_highlighting: true,
+        ignored_attributes_for: vec![(HEADINGS.to_string(), attributes(&["id"]))],
+        ..Default::default()
+    }
+}
+
+fn attributes(names: &[&str]) -> HashSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn selectors(selectors: &[&str]) -> Vec<String> {
+    selectors
+        .iter()
+        .map(|selector| selector.to_string())
+        .collect()
+}
+
+pub(crate) fn zola() -> HtmlCompareOptions {
+    let mut options = site_options();
+    options.ignore_elements = selectors(&["a.zola-anchor"]);
+    options.ignored_attributes_for.push((
+        "pre".to_string(),
+        attributes(&["class", "style", "data-lang"]),
+    ));
+    options
+}
+
+pub(crate) fn hugo() -> HtmlCompareOptions {
+    let mut options = site_options();
+    options.ignore_elements = selectors(&[
+        ":is(h1, h2, h3, h4, h5, h6) > a:is(.anchor, .heading-anchor, .hanchor)",
+        "pre .ln",
+    ]);
+    options.unwrap_elements = selectors(&["div.highlight"]);
+    options.ignored_attributes_for.push((
+        "pre".to_string(),
+        attributes(&["class", "style", "tabindex"]),
+    ));
+    options
+}
+
+pub(crate) fn mdbook() -> HtmlCompareOptions {
+    let mut options = site_options();
+    options.root_selector = Some("main".to_string());
+    options.ignore_elements = selectors(&["pre > .buttons", "pre .clip-button"]);
+    options.unwrap_elements = selectors(&[":is(h1, h2, h3, h4, h5, h6) > a.header"]);
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{presets, HtmlComparer};
+
+    #[test]
+    fn test_zola_preset() {
+        let plain =
+            r#"<h2 id="setup">Setup</h2><pre><code class="language-sh">cargo build</code></pre>"#;
+        let zola = r##"<h2 id="setup-1"><a class="zola-anchor" href="#setup-1" aria-label="Anchor link for: setup-1">🔗</a>Setup</h2><pre data-lang="sh" style="background-color:#2b303b;color:#c0c5ce;" class="language-sh "><code class="language-sh" data-lang="sh"><span style="color:#bf616a;">cargo</span><span> build</span></code></pre>"##;
+        let comparer = HtmlComparer::with_options(presets::zola());
+        comparer.verify(plain, zola).unwrap();
+        assert!(!comparer
+            .is_equal(plain, &zola.replace(">Setup<", ">Install<"))
+            .unwrap());
+        assert!(HtmlComparer::new().verify(plain, zola).is_err());
+    }
+
+    #[test]
+    fn test_hugo_preset() {
+        let plain = r#"<h2 id="setup">Setup</h2><pre><code>go build
+go test</code></pre>"#;
+        let hugo = r##"<h2 id="setup-step"><a class="anchor" href="#setup-step">#</a>Setup</h2><div class="highlight"><pre tabindex="0" class="chroma"><code class="language-sh" data-lang="sh"><span class="line"><span class="ln">1</span><span class="cl">go build
+</span></span><span class="line"><span class="ln">2</span><span class="cl">go test</span></span></code></pre></div>"##;
+        let comparer = HtmlComparer::with_options(presets::hugo());
+        comparer.verify(plain, hugo).unwrap();
+        assert!(!comparer
+            .is_equal(plain, &hugo.replace("go test", "go vet"))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_mdbook_preset() {
+        let page = |main: &str, sidebar: &str| {
+            format!(
+                r#"<nav id="sidebar"><ol class="chapter">{}</ol></nav><div id="page-wrapper"><main>{}</main></div>"#,
+                sidebar, main
+            )
+        };
+        let old = page(
+            r##"<h1 id="intro"><a class="header" href="#intro">Intro</a></h1><pre class="playground"><code class="language-rust">fn main() {}</code></pre>"##,
+            "<li>Intro</li>",
+        );
+        let new = page(
+            r##"<h1 id="introduction"><a class="header" href="#introduction">Intro</a></h1><pre class="playground"><code class="language-rust hljs"><span class="hljs-keyword">fn</span> <span class="hljs-title function_">main</span>() {}</code><div class="buttons"><button class="clip-button" title="Copy to clipboard"><i class="tooltiptext"></i></button></div></pre>"##,
+            "<li>Intro</li><li>Setup</li>",
+        );
+        let comparer = HtmlComparer::with_options(presets::mdbook());
+        comparer.verify(&old, &new).unwrap();
+        assert!(!comparer
+            .is_equal(&old, &new.replace(">Intro</a>", ">Overview</a>"))
+            .unwrap());
+    }
+}
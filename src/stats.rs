@@ -0,0 +1,199 @@
+//! Size and complexity metrics of documents.
+//!
+//! [`HtmlComparer::analyze`] counts what a document holds as compared: the
+//! same nodes and attributes the comparison sees, after the options filter
+//! out comments, whitespace or ignored attributes. [`HtmlDiff`] carries the
+//! statistics of both sides, and its summary points out when one side is
+//! much larger than the other, which often explains a long list of
+//! differences at a glance.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::{HtmlComparer, HtmlDiff, ShadowDomMode};
+
+/// How many times larger one document must be for the summary to say so
+const SIZE_RATIO_HINT: f64 = 2.0;
+
+/// Node counts and sizes of a document
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentStats {
+    pub elements: usize,
+    pub text_nodes: usize,
+    pub comments: usize,
+    /// Number of elements per tag name
+    pub tags: BTreeMap<String, usize>,
+    /// Nesting depth of the deepest node, counting `<html>` as 1
+    pub max_depth: usize,
+    /// Number of compared attributes over all elements
+    pub attributes: usize,
+    /// Length in bytes of the compared text
+    pub text_bytes: usize,
+}
+
+impl DocumentStats {
+    /// Number of nodes of any type
+    pub fn nodes(&self) -> usize {
+        self.elements + self.text_nodes + self.comments
+    }
+
+    /// Count the contents of `other` as well, keeping the larger depth
+    pub(crate) fn add(&mut self, other: &DocumentStats) {
+        self.elements += other.elements;
+        self.text_nodes += other.text_nodes;
+        self.comments += other.comments;
+        for (tag, count) in &other.tags {
+            *self.tags.entry(tag.clone()).or_default() += count;
+        }
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.attributes += other.attributes;
+        self.text_bytes += other.text_bytes;
+    }
+}
+
+impl fmt::Display for DocumentStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes ({} elements, {} text, {} comments), depth {}, {} attributes, {} bytes of text",
+            self.nodes(),
+            self.elements,
+            self.text_nodes,
+            self.comments,
+            self.max_depth,
+            self.attributes,
+            self.text_bytes
+        )
+    }
+}
+
+impl HtmlComparer {
+    /// Count the nodes, attributes and text of a document as compared
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let stats = HtmlComparer::new().analyze("<ul><li>One</li><li>Two</li></ul>");
+    /// assert_eq!(stats.tags["li"], 2);
+    /// ```
+    pub fn analyze(&self, html: &str) -> DocumentStats {
+        self.document_stats(self.parse(html).root_element())
+    }
+
+    pub(crate) fn document_stats(&self, root: ElementRef) -> DocumentStats {
+        let mut stats = DocumentStats::default();
+        let top_heading = self.top_heading_level(*root);
+        let mut stack: Vec<(NodeRef<Node>, usize)> = vec![(*root, 1)];
+        while let Some((node, depth)) = stack.pop() {
+            stats.max_depth = stats.max_depth.max(depth);
+            match node.value() {
+                Node::Text(_) => {
+                    stats.text_nodes += 1;
+                    stats.text_bytes += self.node_text(node).len();
+                }
+                Node::Comment(_) => stats.comments += 1,
+                _ => {
+                    let Some(element) = ElementRef::wrap(node) else {
+                        continue;
+                    };
+                    stats.elements += 1;
+                    *stats
+                        .tags
+                        .entry(self.element_name(element, top_heading).to_string())
+                        .or_default() += 1;
+                    if !self.options.ignore_attributes {
+                        stats.attributes += self.compared_attributes(element).len();
+                    }
+                    stack.extend(
+                        self.child_nodes(element)
+                            .into_iter()
+                            .map(|child| (child, depth + 1)),
+                    );
+                    if self.options.shadow_dom == ShadowDomMode::Separate {
+                        if let Some(shadow_root) = self.shadow_root(element) {
+                            stack.push((*shadow_root, depth + 1));
+                        }
+                    }
+                }
+            }
+        }
+        stats
+    }
+}
+
+impl HtmlDiff {
+    /// Statistics of the expected document
+    pub fn expected_stats(&self) -> &DocumentStats {
+        &self.stats.0
+    }
+
+    /// Statistics of the actual document
+    pub fn actual_stats(&self) -> &DocumentStats {
+        &self.stats.1
+    }
+
+    /// A note on the sizes of the documents when one has at least
+    /// [`SIZE_RATIO_HINT`] times the nodes of the other
+    pub(crate) fn size_summary(&self) -> Option<String> {
+        let (expected, actual) = (self.stats.0.nodes(), self.stats.1.nodes());
+        let (larger, smaller) = (expected.max(actual), expected.min(actual));
+        if smaller == 0 || (larger as f64) < smaller as f64 * SIZE_RATIO_HINT {
+            return None;
+        }
+        Some(format!(
+            "actual has {:.1}x {} nodes than expected ({} vs {})",
+            larger as f64 / smaller as f64,
+            if actual > expected { "more" } else { "fewer" },
+            actual,
+            expected
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HtmlCompareError, HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_analyze() {
+        let html =
+            "<ul class='menu' id='nav'><!-- nav -->\n  <li>One</li>\n  <li><b>Two</b></li>\n</ul>";
+        let stats = HtmlComparer::new().analyze(html);
+        // html, head, body, ul, two li and b
+        assert_eq!(stats.elements, 7);
+        assert_eq!((stats.text_nodes, stats.text_bytes), (2, 6));
+        assert_eq!((stats.comments, stats.attributes), (0, 2));
+        assert_eq!(stats.tags["li"], 2);
+        assert_eq!(stats.max_depth, 6);
+
+        let with_comments = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_comments: false,
+            ..Default::default()
+        });
+        assert_eq!(with_comments.analyze(html).comments, 1);
+        let relaxed = HtmlComparer::with_options(crate::presets::relaxed()).analyze(html);
+        assert_eq!(relaxed.attributes, 0);
+    }
+
+    #[test]
+    fn test_summary_notes_size() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            exhaustive: true,
+            ..Default::default()
+        });
+        let list = |items: usize| format!("<ul>{}</ul>", "<li>Item</li>".repeat(items));
+
+        let err = comparer.verify(&list(2), &list(12)).unwrap_err();
+        let HtmlCompareError::Differences(diff) = &err else {
+            panic!("{}", err);
+        };
+        assert_eq!(diff.actual_stats().tags["li"], 12);
+        let message = err.to_string();
+        assert!(message.contains("actual has 3.5x more nodes than expected (28 vs 8)"));
+
+        let err = comparer.verify(&list(2), &list(3)).unwrap_err();
+        assert!(!err.to_string().contains("nodes than expected"));
+    }
+}
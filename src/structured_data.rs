@@ -0,0 +1,418 @@
+//! Extraction and comparison of schema.org structured data.
+//!
+//! Search engines read microdata (`itemscope`/`itemprop`) and JSON-LD blocks
+//! without caring about the markup around them, so this module compares the
+//! extracted data graphs instead of the HTML itself.
+//!
+//! # Example
+//! ```ignore
+//! use html_compare_rs::structured_data;
+//!
+//! structured_data::compare(
+//!     r#"<div itemscope itemtype="https://schema.org/Person"><span itemprop="name">Ada</span></div>"#,
+//!     r#"<section itemscope itemtype="https://schema.org/Person"><p><b itemprop="name">Ada</b></p></section>"#,
+//! )
+//! .unwrap();
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use ego_tree::NodeId;
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Map, Value};
+
+use crate::HtmlCompareError;
+
+/// Structured data extracted from a document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StructuredData {
+    /// Top-level microdata items, in the JSON form described by the HTML spec
+    /// (`{"type": [...], "id": ..., "properties": {...}}`)
+    pub microdata: Vec<Value>,
+    /// Nodes declared in `<script type="application/ld+json">` blocks, with
+    /// top-level arrays and `@graph` containers flattened
+    pub json_ld: Vec<Value>,
+}
+
+/// Extract microdata items and JSON-LD nodes from an HTML document
+pub fn extract(html: &str) -> Result<StructuredData, HtmlCompareError> {
+    let document = Html::parse_document(html);
+
+    // Document order, to list the properties of an item in
+    let order: HashMap<NodeId, usize> = document
+        .tree
+        .root()
+        .descendants()
+        .enumerate()
+        .map(|(index, node)| (node.id(), index))
+        .collect();
+    let item_selector = Selector::parse("[itemscope]:not([itemprop])").unwrap();
+    let microdata = document
+        .select(&item_selector)
+        .map(|element| microdata_item(&document, &order, element))
+        .collect();
+
+    let script_selector = Selector::parse("script[type]").unwrap();
+    let mut json_ld = Vec::new();
+    for script in document.select(&script_selector) {
+        let script_type = script.value().attr("type").unwrap_or_default();
+        if !script_type
+            .trim()
+            .to_ascii_lowercase()
+            .starts_with("application/ld+json")
+        {
+            continue;
+        }
+        let source = script.text().collect::<String>();
+        let value: Value = serde_json::from_str(&source)
+            .map_err(|err| HtmlCompareError::InvalidJsonLd(err.to_string()))?;
+        flatten_json_ld(value, &mut json_ld);
+    }
+
+    Ok(StructuredData { microdata, json_ld })
+}
+
+/// Compare the structured data of two HTML documents
+///
+/// Microdata items and JSON-LD nodes are compared as unordered collections,
+/// and arrays inside them are compared ignoring order (a single value is
+/// treated the same as a one-element array). Everything outside the data
+/// graph, including the elements carrying it, is ignored.
+pub fn compare(expected: &str, actual: &str) -> Result<(), HtmlCompareError> {
+    let expected = extract(expected)?;
+    let actual = extract(actual)?;
+
+    compare_graphs("microdata", expected.microdata, actual.microdata)?;
+    compare_graphs("JSON-LD", expected.json_ld, actual.json_ld)
+}
+
+/// An item whose properties are being built by [`microdata_item`]
+struct PendingItem<'a> {
+    element: ElementRef<'a>,
+    /// The elements declaring its properties still to add
+    properties: std::vec::IntoIter<ElementRef<'a>>,
+    values: Map<String, Value>,
+}
+
+impl<'a> PendingItem<'a> {
+    fn new(document: &'a Html, order: &HashMap<NodeId, usize>, element: ElementRef<'a>) -> Self {
+        PendingItem {
+            element,
+            properties: item_properties(document, order, element).into_iter(),
+            values: Map::new(),
+        }
+    }
+
+    fn into_value(self) -> Value {
+        let mut item = Map::new();
+        if let Some(types) = self.element.value().attr("itemtype") {
+            let types = types.split_ascii_whitespace().map(Value::from).collect();
+            item.insert("type".to_string(), Value::Array(types));
+        }
+        if let Some(id) = self.element.value().attr("itemid") {
+            item.insert("id".to_string(), Value::from(id.trim()));
+        }
+        item.insert("properties".to_string(), Value::Object(self.values));
+        Value::Object(item)
+    }
+}
+
+/// Build the JSON form of the microdata item rooted at `element`
+///
+/// Nested items are built on an explicit stack rather than by recursion, so
+/// deeply nested items can't overflow the call stack. As in the HTML spec,
+/// an item that is a property of itself, through `itemref`, has the value
+/// `"ERROR"` there.
+fn microdata_item(document: &Html, order: &HashMap<NodeId, usize>, element: ElementRef) -> Value {
+    let mut stack = vec![PendingItem::new(document, order, element)];
+    loop {
+        let top = stack.last_mut().expect("the stack holds the root item");
+        match top.properties.next() {
+            Some(property) if property.value().attr("itemscope").is_some() => {
+                if stack.iter().any(|item| item.element.id() == property.id()) {
+                    let top = stack.last_mut().expect("the stack holds the root item");
+                    add_property(property, Value::from("ERROR"), &mut top.values);
+                } else {
+                    stack.push(PendingItem::new(document, order, property));
+                }
+            }
+            Some(property) => add_property(property, property_value(property), &mut top.values),
+            None => {
+                let item = stack.pop().expect("the stack holds the root item");
+                let element = item.element;
+                let value = item.into_value();
+                match stack.last_mut() {
+                    Some(parent) => add_property(element, value, &mut parent.values),
+                    None => return value,
+                }
+            }
+        }
+    }
+}
+
+/// The elements declaring properties of the item `root`, in tree order,
+/// following the HTML spec's algorithm to crawl the properties of an item
+///
+/// Each element is visited once, so `itemref`s referring back to an element
+/// already crawled, like two items referring to each other, are skipped.
+fn item_properties<'a>(
+    document: &'a Html,
+    order: &HashMap<NodeId, usize>,
+    root: ElementRef<'a>,
+) -> Vec<ElementRef<'a>> {
+    let mut visited = HashSet::from([root.id()]);
+    let mut pending: Vec<ElementRef> = root.children().filter_map(ElementRef::wrap).collect();
+    if let Some(refs) = root.value().attr("itemref") {
+        pending.extend(
+            refs.split_ascii_whitespace()
+                .filter_map(|id| find_by_id(document, id)),
+        );
+    }
+
+    let mut properties = Vec::new();
+    while let Some(current) = pending.pop() {
+        if !visited.insert(current.id()) {
+            continue;
+        }
+        if current.value().attr("itemscope").is_none() {
+            pending.extend(current.children().filter_map(ElementRef::wrap));
+        }
+        if current.value().attr("itemprop").is_some() {
+            properties.push(current);
+        }
+    }
+    properties.sort_by_key(|element| order.get(&element.id()).copied());
+    properties
+}
+
+fn add_property(element: ElementRef, value: Value, properties: &mut Map<String, Value>) {
+    let Some(names) = element.value().attr("itemprop") else {
+        return;
+    };
+    for name in names.split_ascii_whitespace() {
+        match properties.get_mut(name) {
+            Some(Value::Array(values)) => values.push(value.clone()),
+            _ => {
+                properties.insert(name.to_string(), Value::Array(vec![value.clone()]));
+            }
+        }
+    }
+}
+
+/// Determine the value of a property that isn't an item according to the
+/// element it is declared on
+fn property_value(element: ElementRef) -> Value {
+    let el = element.value();
+    let attr = |name: &str| Value::from(el.attr(name).unwrap_or_default().trim());
+    match el.name() {
+        "meta" => attr("content"),
+        "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => attr("src"),
+        "a" | "area" | "link" => attr("href"),
+        "object" => attr("data"),
+        "data" | "meter" => attr("value"),
+        "time" if el.attr("datetime").is_some() => attr("datetime"),
+        _ => Value::from(collapse_whitespace(&element.text().collect::<String>())),
+    }
+}
+
+fn find_by_id<'a>(document: &'a Html, id: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse("[id]").unwrap();
+    document
+        .select(&selector)
+        .find(|element| element.value().attr("id") == Some(id))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Push the nodes of a JSON-LD block, unwrapping top-level arrays and `@graph`
+fn flatten_json_ld(value: Value, nodes: &mut Vec<Value>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                flatten_json_ld(item, nodes);
+            }
+        }
+        Value::Object(mut object) => match object.remove("@graph") {
+            Some(graph) => flatten_json_ld(graph, nodes),
+            None => nodes.push(Value::Object(object)),
+        },
+        other => nodes.push(other),
+    }
+}
+
+/// Normalize a value so that structurally equal graphs serialize identically
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Array(items) => {
+            let mut items: Vec<_> = items.into_iter().map(canonicalize).collect();
+            if items.len() == 1 {
+                return items.remove(0);
+            }
+            items.sort_by_cached_key(|item| item.to_string());
+            Value::Array(items)
+        }
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, canonicalize(value)))
+                .collect(),
+        ),
+        Value::String(text) => Value::String(text.trim().to_string()),
+        other => other,
+    }
+}
+
+fn compare_graphs(
+    kind: &str,
+    expected: Vec<Value>,
+    actual: Vec<Value>,
+) -> Result<(), HtmlCompareError> {
+    let expected: Vec<_> = expected.into_iter().map(canonicalize).collect();
+    let mut unmatched: Vec<_> = actual.into_iter().map(canonicalize).collect();
+
+    for item in &expected {
+        match unmatched.iter().position(|candidate| candidate == item) {
+            Some(index) => {
+                unmatched.remove(index);
+            }
+            None => {
+                return Err(HtmlCompareError::StructuredDataMismatch(format!(
+                    "Missing {} item: {}",
+                    kind, item
+                )));
+            }
+        }
+    }
+
+    if let Some(extra) = unmatched.first() {
+        return Err(HtmlCompareError::StructuredDataMismatch(format!(
+            "Unexpected {} item: {}",
+            kind, extra
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_microdata() {
+        let data = extract(
+            r#"<div itemscope itemtype="https://schema.org/Movie">
+                <h1 itemprop="name">Avatar</h1>
+                <div itemprop="director" itemscope itemtype="https://schema.org/Person">
+                    Director: <span itemprop="name">James   Cameron</span>
+                </div>
+                <a itemprop="trailer" href="trailer.html">Trailer</a>
+                <meta itemprop="duration" content="PT2H42M">
+            </div>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            data.microdata,
+            vec![json!({
+                "type": ["https://schema.org/Movie"],
+                "properties": {
+                    "name": ["Avatar"],
+                    "director": [{
+                        "type": ["https://schema.org/Person"],
+                        "properties": { "name": ["James Cameron"] }
+                    }],
+                    "trailer": ["trailer.html"],
+                    "duration": ["PT2H42M"]
+                }
+            })]
+        );
+        assert!(data.json_ld.is_empty());
+    }
+
+    #[test]
+    fn test_microdata_ignores_markup() {
+        assert!(compare(
+            r#"<div itemscope itemtype="https://schema.org/Person">
+                <span itemprop="name">Ada</span><span itemprop="jobTitle">Engineer</span>
+            </div>"#,
+            r#"<section class="card" itemscope itemtype="https://schema.org/Person">
+                <p><b itemprop="jobTitle">Engineer</b></p><h2 itemprop="name">Ada</h2>
+            </section>"#,
+        )
+        .is_ok());
+
+        let result = compare(
+            r#"<div itemscope><span itemprop="name">Ada</span></div>"#,
+            r#"<div itemscope><span itemprop="name">Grace</span></div>"#,
+        );
+        assert!(matches!(
+            result,
+            Err(HtmlCompareError::StructuredDataMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_microdata_itemref_cycle() {
+        // Two items that are properties of each other through itemref
+        let data = extract(
+            r#"<div itemscope itemtype="https://schema.org/Thing">
+                <div itemprop="a" itemscope id="a" itemref="b"><span itemprop="name">A</span></div>
+            </div>
+            <div itemprop="b" itemscope id="b" itemref="a"><span itemprop="name">B</span></div>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            data.microdata,
+            vec![json!({
+                "type": ["https://schema.org/Thing"],
+                "properties": {
+                    "a": [{
+                        "properties": {
+                            "name": ["A"],
+                            "b": [{
+                                "properties": { "name": ["B"], "a": ["ERROR"] }
+                            }]
+                        }
+                    }]
+                }
+            })]
+        );
+    }
+
+    #[test]
+    fn test_json_ld_comparison() {
+        assert!(compare(
+            r#"<script type="application/ld+json">
+                {"@context": "https://schema.org", "@type": "Organization", "name": "Acme", "sameAs": ["a", "b"]}
+            </script>"#,
+            r#"<p>Unrelated</p><script type="application/ld+json">{"sameAs":["b","a"],"name":"Acme","@type":"Organization","@context":"https://schema.org"}</script>"#,
+        )
+        .is_ok());
+
+        // @graph containers and top-level arrays are flattened
+        assert!(compare(
+            r#"<script type="application/ld+json">{"@graph": [{"@id": "_:a"}, {"@id": "_:b"}]}</script>"#,
+            r#"<script type="application/ld+json">[{"@id": "_:b"}, {"@id": "_:a"}]</script>"#,
+        )
+        .is_ok());
+
+        let result = compare(
+            r#"<script type="application/ld+json">{"@type": "Organization", "name": "Acme"}</script>"#,
+            r#"<script type="application/ld+json">{"@type": "Organization", "name": "Other"}</script>"#,
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            r#"Structured data mismatch: Missing JSON-LD item: {"@type":"Organization","name":"Acme"}"#
+        );
+    }
+
+    #[test]
+    fn test_invalid_json_ld() {
+        let result = extract(r#"<script type="application/ld+json">{"name": </script>"#);
+        assert!(matches!(result, Err(HtmlCompareError::InvalidJsonLd(_))));
+    }
+}
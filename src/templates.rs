@@ -0,0 +1,116 @@
+//! Rendering templates and comparing the result in one step.
+//!
+//! [`assert_template_renders!`](crate::assert_template_renders) renders a
+//! template with a context, compares the output against the expected HTML
+//! and, on a mismatch, panics with a [`pretty_diff`](crate::HtmlComparer::pretty_diff)
+//! of the two. Templates are anything implementing [`RenderTemplate`]:
+//!
+//! - with the `tera` feature, a `(&tera::Tera, "template name")` pair
+//!   rendered with a `tera::Context`
+//! - with the `askama` feature, any `askama::Template`, rendered with `()`
+//!   since the template value carries its own context
+//! - a closure wrapped in [`from_fn`], for other engines
+
+use std::fmt::{self, Display};
+
+/// A template that renders to HTML given a context of type `C`
+pub trait RenderTemplate<C: ?Sized> {
+    /// Render the template, or describe why it could not be rendered
+    fn render_template(&self, context: &C) -> Result<String, String>;
+}
+
+/// A [`RenderTemplate`] calling a closure, see [`from_fn`]
+pub struct FnTemplate<F>(F);
+
+/// Use `render` as a template
+///
+/// # Examples
+/// ```ignore
+/// let greeting = templates::from_fn(|name: &str| Ok::<_, String>(format!("<p>Hi {}</p>", name)));
+/// assert_template_renders!(greeting, "Ada", "<p>Hi Ada</p>");
+/// ```
+pub fn from_fn<F>(render: F) -> FnTemplate<F> {
+    FnTemplate(render)
+}
+
+impl<C, F, E> RenderTemplate<C> for FnTemplate<F>
+where
+    C: ?Sized,
+    F: Fn(&C) -> Result<String, E>,
+    E: Display,
+{
+    fn render_template(&self, context: &C) -> Result<String, String> {
+        (self.0)(context).map_err(|err| err.to_string())
+    }
+}
+
+impl<F> fmt::Debug for FnTemplate<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("FnTemplate")
+    }
+}
+
+#[cfg(feature = "tera")]
+impl RenderTemplate<tera::Context> for (&tera::Tera, &str) {
+    fn render_template(&self, context: &tera::Context) -> Result<String, String> {
+        let (tera, name) = *self;
+        tera.render(name, context).map_err(|err| error_chain(&err))
+    }
+}
+
+#[cfg(feature = "askama")]
+impl<T: askama::Template> RenderTemplate<()> for T {
+    fn render_template(&self, _context: &()) -> Result<String, String> {
+        self.render().map_err(|err| error_chain(&err))
+    }
+}
+
+/// `err` followed by its sources, which carry the useful part of template
+/// errors such as the failing line
+#[cfg(any(feature = "tera", feature = "askama"))]
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str(&format!("\ncaused by: {}", err));
+        source = err.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assert_template_renders() {
+        let list = from_fn(|items: &[&str]| {
+            let items: String = items
+                .iter()
+                .map(|item| format!("\n  <li>{}</li>", item))
+                .collect();
+            Ok::<_, String>(format!("<ul>{}\n</ul>", items))
+        });
+        assert_template_renders!(list, ["a", "b"][..], "<ul><li>a</li><li>b</li></ul>");
+        assert_template_renders!(
+            list,
+            ["b", "a"][..],
+            "<ul><li>a</li><li>b</li></ul>",
+            ignore_sibling_order
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Rendered template does not match")]
+    fn test_assert_template_renders_mismatch() {
+        let page = from_fn(|title: &str| Ok::<_, String>(format!("<h1>{}</h1>", title)));
+        assert_template_renders!(page, "Draft", "<h1>Final</h1>");
+    }
+
+    #[test]
+    #[should_panic(expected = "Template failed to render:\nmissing variable `title`")]
+    fn test_assert_template_renders_error() {
+        let page = from_fn(|_: &()| Err::<String, _>("missing variable `title`"));
+        assert_template_renders!(page, (), "<h1>Title</h1>");
+    }
+}
@@ -0,0 +1,155 @@
+//! Attributes whose values are lists of tokens.
+//!
+//! `class="a b"` and `class="b  a"` mean the same thing, and so do
+//! `accept="image/png, image/gif"` and `accept="image/gif,image/png"`.
+//! [`HtmlCompareOptions::tokenized_attributes`](crate::HtmlCompareOptions)
+//! splits the listed attributes into tokens before comparing them, either as
+//! a sequence or as a set.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// What separates the tokens of an attribute value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    /// Any run of ASCII whitespace, as in `class` or `rel`
+    Whitespace,
+    /// A character, with whitespace around tokens ignored, as in `accept`
+    Char(char),
+}
+
+/// How to compare the value of a token list attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenRule {
+    pub delimiter: Delimiter,
+    /// Whether the order of the tokens matters; unordered lists also ignore
+    /// repeated tokens
+    pub ordered: bool,
+}
+
+impl TokenRule {
+    /// A list whose token order matters
+    pub fn ordered(delimiter: Delimiter) -> Self {
+        TokenRule {
+            delimiter,
+            ordered: true,
+        }
+    }
+
+    /// A set of tokens
+    pub fn unordered(delimiter: Delimiter) -> Self {
+        TokenRule {
+            delimiter,
+            ordered: false,
+        }
+    }
+
+    /// Bring `value` into a canonical form, so equivalent token lists are
+    /// equal strings
+    pub fn normalize(&self, value: &str) -> String {
+        let mut tokens: Vec<&str> = match self.delimiter {
+            Delimiter::Whitespace => value.split_ascii_whitespace().collect(),
+            Delimiter::Char(delimiter) => value
+                .split(delimiter)
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .collect(),
+        };
+        if !self.ordered {
+            tokens.sort_unstable();
+            tokens.dedup();
+        }
+        match self.delimiter {
+            Delimiter::Whitespace => tokens.join(" "),
+            Delimiter::Char(delimiter) => tokens.join(&format!("{} ", delimiter)),
+        }
+    }
+
+    /// Whether `value` holds exactly one token and nothing else
+    fn is_single_token(&self, value: &str) -> bool {
+        !value.is_empty()
+            && match self.delimiter {
+                Delimiter::Whitespace => !value.bytes().any(|b| b.is_ascii_whitespace()),
+                Delimiter::Char(delimiter) => !value.contains(delimiter) && value.trim() == value,
+            }
+    }
+}
+
+/// Rules for the token list attributes defined by HTML: `class`, `rel`,
+/// `sandbox`, `headers` and `ping` are whitespace-separated sets, `accept`
+/// is a comma-separated set and `sizes` a comma-separated list
+pub fn html_token_lists() -> HashMap<String, TokenRule> {
+    let whitespace_set = TokenRule::unordered(Delimiter::Whitespace);
+    [
+        ("class", whitespace_set),
+        ("rel", whitespace_set),
+        ("sandbox", whitespace_set),
+        ("headers", whitespace_set),
+        ("ping", whitespace_set),
+        ("accept", TokenRule::unordered(Delimiter::Char(','))),
+        ("sizes", TokenRule::ordered(Delimiter::Char(','))),
+    ]
+    .into_iter()
+    .map(|(name, rule)| (name.to_string(), rule))
+    .collect()
+}
+
+/// The value of attribute `name` as compared under `rules`
+pub(crate) fn normalize_attribute<'a>(
+    rules: &HashMap<String, TokenRule>,
+    name: &str,
+    value: &'a str,
+) -> Cow<'a, str> {
+    match rules.get(name) {
+        // A single token is its own normal form
+        Some(rule) if rule.is_single_token(value) => Cow::Borrowed(value),
+        Some(rule) => Cow::Owned(rule.normalize(value)),
+        None => Cow::Borrowed(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_token_rules() {
+        let set = TokenRule::unordered(Delimiter::Whitespace);
+        assert_eq!(set.normalize("  nav  main nav\n"), "main nav");
+
+        let list = TokenRule::ordered(Delimiter::Char(','));
+        assert_eq!(
+            list.normalize("(max-width: 600px) 480px,800px"),
+            "(max-width: 600px) 480px, 800px"
+        );
+        assert_eq!(
+            TokenRule::unordered(Delimiter::Char(',')).normalize("image/png , .gif"),
+            ".gif, image/png"
+        );
+    }
+
+    #[test]
+    fn test_tokenized_attributes() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            tokenized_attributes: html_token_lists(),
+            ..Default::default()
+        });
+
+        comparer
+            .verify(
+                r#"<a class="btn primary" rel="noopener nofollow" href="/">Go</a>"#,
+                r#"<a class=" primary btn" rel="nofollow  noopener" href="/">Go</a>"#,
+            )
+            .unwrap();
+        assert!(comparer
+            .verify(
+                r#"<img sizes="(min-width: 40em) 50vw, 100vw">"#,
+                r#"<img sizes="100vw, (min-width: 40em) 50vw">"#,
+            )
+            .is_err());
+        assert!(HtmlComparer::new()
+            .verify(r#"<p class="a b">x</p>"#, r#"<p class="b a">x</p>"#)
+            .is_err());
+    }
+}
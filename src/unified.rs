@@ -0,0 +1,288 @@
+//! A readable side-by-side rendering of two documents.
+//!
+//! [`HtmlComparer::pretty_diff`] serializes both documents in their compared
+//! form, one node per line and indented by depth, and marks lines of the
+//! expected document with `-` and of the actual document with `+`. Children
+//! are paired up the same way [`HtmlComparer::diff`] pairs them, so only
+//! subtrees that really differ are expanded, and long runs of identical
+//! siblings far from any change collapse into `… 37 identical nodes …`.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::diff::Alignment;
+use crate::format::sort_attributes;
+use crate::{Attributes, HeadingLevels, HtmlComparer};
+
+/// Longest line written for an unchanged node before it is cut off
+const CONTEXT_WIDTH: usize = 100;
+
+/// One line of the rendering, before identical runs are collapsed
+enum Line {
+    /// An unchanged node, which may be collapsed
+    Same(String),
+    /// Anything else, kept along with its context
+    Kept(String),
+}
+
+impl HtmlComparer {
+    /// Render the differences between two documents, keeping `context`
+    /// identical siblings around each change
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let rendered = HtmlComparer::new().pretty_diff(&expected, &actual, 2);
+    /// //   <ul>
+    /// //     … 37 identical nodes …
+    /// //     <li>38</li>
+    /// //     <li>39</li>
+    /// //     <li>
+    /// // -     40
+    /// // +     forty
+    /// //     </li>
+    /// //   </ul>
+    /// ```
+    pub fn pretty_diff(&self, expected: &str, actual: &str, context: usize) -> String {
+        let expected_doc = self.parse(expected);
+        let actual_doc = self.parse(actual);
+        let mut lines = Vec::new();
+        let expected_root = self
+            .compared_root(&expected_doc, "expected")
+            .unwrap_or_else(|_| expected_doc.root_element());
+        let actual_root = self
+            .compared_root(&actual_doc, "actual")
+            .unwrap_or_else(|_| actual_doc.root_element());
+        let levels = self.heading_levels(*expected_root, *actual_root);
+        self.render_pair(*expected_root, *actual_root, 0, context, &mut lines, levels);
+        let mut out = String::new();
+        for line in lines {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Render a pair of aligned nodes of documents whose topmost headings
+    /// are at `levels`
+    fn render_pair(
+        &self,
+        expected: NodeRef<Node>,
+        actual: NodeRef<Node>,
+        depth: usize,
+        context: usize,
+        lines: &mut Vec<String>,
+        levels: HeadingLevels,
+    ) {
+        let indent = "  ".repeat(depth);
+        if self.identical(expected, actual, levels) {
+            lines.push(format!("  {}{}", indent, self.serialize(expected)));
+            return;
+        }
+        let (Some(expected_el), Some(actual_el)) =
+            (ElementRef::wrap(expected), ElementRef::wrap(actual))
+        else {
+            lines.push(format!("- {}{}", indent, self.serialize(expected)));
+            lines.push(format!("+ {}{}", indent, self.serialize(actual)));
+            return;
+        };
+        if !self.tags_match(
+            self.element_name(expected_el, levels.expected),
+            self.element_name(actual_el, levels.actual),
+        ) {
+            lines.push(format!("- {}{}", indent, self.serialize(expected)));
+            lines.push(format!("+ {}{}", indent, self.serialize(actual)));
+            return;
+        }
+
+        let (expected_tag, actual_tag) = (self.start_tag(expected_el), self.start_tag(actual_el));
+        if expected_tag == actual_tag || self.options.ignore_attributes {
+            lines.push(format!("  {}{}", indent, expected_tag));
+        } else {
+            lines.push(format!("- {}{}", indent, expected_tag));
+            lines.push(format!("+ {}{}", indent, actual_tag));
+        }
+        let (expected_children, actual_children) =
+            self.compared_children(expected_el, actual_el, levels);
+        let mut children = Vec::new();
+        for alignment in self.align_children(&expected_children, &actual_children, levels) {
+            match alignment {
+                Alignment::Pair(i, j)
+                    if self.identical(expected_children[i], actual_children[j], levels) =>
+                {
+                    children.push(Line::Same(format!(
+                        "  {}  {}",
+                        indent,
+                        truncate(&self.serialize(expected_children[i]))
+                    )));
+                }
+                Alignment::Pair(i, j) => {
+                    let mut nested = Vec::new();
+                    self.render_pair(
+                        expected_children[i],
+                        actual_children[j],
+                        depth + 1,
+                        context,
+                        &mut nested,
+                        levels,
+                    );
+                    children.extend(nested.into_iter().map(Line::Kept));
+                }
+                Alignment::Missing(i) => children.push(Line::Kept(format!(
+                    "- {}  {}",
+                    indent,
+                    self.serialize(expected_children[i])
+                ))),
+                Alignment::Extra(j) => children.push(Line::Kept(format!(
+                    "+ {}  {}",
+                    indent,
+                    self.serialize(actual_children[j])
+                ))),
+            }
+        }
+        collapse(children, context, &format!("  {}  ", indent), lines);
+        lines.push(format!("  {}</{}>", indent, expected_el.value().name()));
+    }
+
+    /// The start tag of `element` with its compared attributes in
+    /// `attribute_order`
+    fn start_tag(&self, element: ElementRef) -> String {
+        let mut attributes = match self.options.ignore_attributes {
+            true => Attributes::new(),
+            false => self.compared_attributes(element),
+        };
+        sort_attributes(&mut attributes, &self.options.attribute_order);
+        let mut tag = format!("<{}", element.value().name());
+        for (name, value) in attributes {
+            tag.push_str(&format!(" {}=\"{}\"", name, value.replace('"', "&quot;")));
+        }
+        tag.push('>');
+        tag
+    }
+
+    /// `node` and its subtree on one line, as compared
+    pub(crate) fn serialize(&self, node: NodeRef<Node>) -> String {
+        match node.value() {
+            Node::Text(_) => self.node_text(node).replace('\n', "\\n"),
+            Node::Comment(_) => format!("<!--{}-->", self.node_text(node)),
+            _ => match ElementRef::wrap(node) {
+                Some(element) => {
+                    let mut out = self.start_tag(element);
+                    for child in self.child_nodes(element) {
+                        out.push_str(&self.serialize(child));
+                    }
+                    out.push_str(&format!("</{}>", element.value().name()));
+                    out
+                }
+                None => String::new(),
+            },
+        }
+    }
+}
+
+/// Append `children` to `lines`, replacing unchanged lines further than
+/// `context` lines from a change with their count
+fn collapse(children: Vec<Line>, context: usize, indent: &str, lines: &mut Vec<String>) {
+    let changes: Vec<usize> = children
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| matches!(line, Line::Kept(_)))
+        .map(|(index, _)| index)
+        .collect();
+    // Under an element that only differs in its start tag, the first
+    // children are the context
+    let near_change = |index: usize| match changes.is_empty() {
+        true => index < context,
+        false => changes
+            .iter()
+            .any(|&change| change.abs_diff(index) <= context),
+    };
+
+    let mut hidden = 0;
+    for (index, line) in children.into_iter().enumerate() {
+        match line {
+            Line::Same(_) if !near_change(index) => hidden += 1,
+            Line::Same(text) | Line::Kept(text) => {
+                flush(&mut hidden, indent, lines);
+                lines.push(text);
+            }
+        }
+    }
+    flush(&mut hidden, indent, lines);
+}
+
+/// Write the count of collapsed nodes, if any
+fn flush(hidden: &mut usize, indent: &str, lines: &mut Vec<String>) {
+    match *hidden {
+        0 => {}
+        1 => lines.push(format!("{}… 1 identical node …", indent)),
+        count => lines.push(format!("{}… {} identical nodes …", indent, count)),
+    }
+    *hidden = 0;
+}
+
+/// `line` cut off after [`CONTEXT_WIDTH`] characters
+fn truncate(line: &str) -> String {
+    match line.char_indices().nth(CONTEXT_WIDTH) {
+        Some((index, _)) => format!("{}…", &line[..index]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HtmlComparer;
+
+    fn list(items: impl Iterator<Item = String>) -> String {
+        format!("<ul>{}</ul>", items.collect::<String>())
+    }
+
+    #[test]
+    fn test_identical_runs_collapse() {
+        let expected = list((1..=40).map(|i| format!("<li>{}</li>", i)));
+        let actual = expected.replace("<li>40</li>", "<li>forty</li>");
+        let rendered = HtmlComparer::new().pretty_diff(&expected, &actual, 2);
+        assert_eq!(
+            rendered,
+            "  <html>\n\
+             \x20   <head></head>\n\
+             \x20   <body>\n\
+             \x20     <ul>\n\
+             \x20       … 37 identical nodes …\n\
+             \x20       <li>38</li>\n\
+             \x20       <li>39</li>\n\
+             \x20       <li>\n\
+             -         40\n\
+             +         forty\n\
+             \x20       </li>\n\
+             \x20     </ul>\n\
+             \x20   </body>\n\
+             \x20 </html>\n"
+        );
+    }
+
+    #[test]
+    fn test_context_size() {
+        let expected = list((1..=9).map(|i| format!("<li>{}</li>", i)));
+        let actual = expected.replace("<li>5</li>", "<li class='x'>5</li>");
+        let rendered = HtmlComparer::new().pretty_diff(&expected, &actual, 1);
+        let lines: Vec<_> = rendered.lines().skip(4).collect();
+        assert_eq!(
+            lines,
+            [
+                "        … 3 identical nodes …",
+                "        <li>4</li>",
+                "-       <li>",
+                "+       <li class=\"x\">",
+                "          5",
+                "        </li>",
+                "        <li>6</li>",
+                "        … 3 identical nodes …",
+                "      </ul>",
+                "    </body>",
+                "  </html>",
+            ]
+        );
+        let full = HtmlComparer::new().pretty_diff(&expected, &actual, usize::MAX);
+        assert!(!full.contains("identical"));
+    }
+}
@@ -0,0 +1,320 @@
+//! Validation of submitted HTML against a template inside long-running
+//! services.
+//!
+//! The assertion macros are made for tests: they panic, print both
+//! documents and stop at the first mismatch. A content pipeline checking
+//! user-submitted HTML against a template needs the opposite. An
+//! [`HtmlValidator`] holds the template and its compiled options, is
+//! `Send + Sync` to be shared between request handlers, and returns every
+//! outcome as a value:
+//!
+//! - a [`Validation`] listing the differences found, at most
+//!   [`ValidatorLimits::max_differences`] of them; the comparison stops
+//!   once it found more, and skips the edit script, node map and statistics
+//!   of an [`HtmlDiff`](crate::HtmlDiff)
+//! - [`HtmlCompareError::InputTooLarge`] for a submission over
+//!   [`ValidatorLimits::max_input_bytes`], rejected before it is parsed, so
+//!   that the memory a validation takes is bounded by the limits rather than
+//!   by what users send
+//! - [`HtmlCompareError::Internal`] should comparing panic, instead of
+//!   unwinding into the service
+//!
+//! Documents are walked with an explicit stack (see
+//! [Robustness](crate#robustness)), so a submission nested thousands of
+//! levels deep is compared like any other rather than overflowing the
+//! stack of the thread validating it.
+//!
+//! Submissions given as bytes are decoded according to
+//! [`HtmlCompareOptions::invalid_utf8`](crate::HtmlCompareOptions::invalid_utf8)
+//! into buffers that are kept in a pool and reused by later validations.
+//!
+//! ```ignore
+//! let validator = HtmlValidator::new(TEMPLATE, options)?;
+//! let validation = validator.validate(&submission)?;
+//! if !validation.is_valid() {
+//!     return reject(validation.differences());
+//! }
+//! ```
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+use crate::{Difference, HtmlCompareError, HtmlCompareOptions, HtmlComparer};
+
+/// Bounds on the resources an [`HtmlValidator`] uses per validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidatorLimits {
+    /// Largest submission accepted, in bytes
+    pub max_input_bytes: usize,
+    /// Most differences reported; the comparison stops once it found more
+    pub max_differences: usize,
+    /// Most decoding buffers kept for reuse
+    pub pooled_buffers: usize,
+}
+
+impl Default for ValidatorLimits {
+    fn default() -> Self {
+        ValidatorLimits {
+            max_input_bytes: 1 << 20,
+            max_differences: 100,
+            pooled_buffers: 16,
+        }
+    }
+}
+
+/// The outcome of validating a submission
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validation {
+    differences: Vec<Difference>,
+    truncated: bool,
+}
+
+impl Validation {
+    /// Whether the submission is equivalent to the template
+    pub fn is_valid(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// The differences found, up to [`ValidatorLimits::max_differences`]
+    pub fn differences(&self) -> &[Difference] {
+        &self.differences
+    }
+
+    /// Whether there were more differences than
+    /// [`ValidatorLimits::max_differences`], left out without being looked
+    /// for
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+/// Compares submissions against a template without panicking
+#[derive(Debug)]
+pub struct HtmlValidator {
+    comparer: HtmlComparer,
+    template: String,
+    limits: ValidatorLimits,
+    buffers: Mutex<Vec<String>>,
+}
+
+impl HtmlValidator {
+    /// A validator of submissions against `template`, failing if a selector
+    /// of `options` doesn't parse
+    pub fn new(
+        template: impl Into<String>,
+        options: HtmlCompareOptions,
+    ) -> Result<Self, HtmlCompareError> {
+        let comparer = HtmlComparer::with_options(options);
+        comparer.check_selectors()?;
+        Ok(HtmlValidator {
+            comparer,
+            template: template.into(),
+            limits: ValidatorLimits::default(),
+            buffers: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn with_limits(mut self, limits: ValidatorLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn limits(&self) -> &ValidatorLimits {
+        &self.limits
+    }
+
+    /// Compare `html` against the template
+    pub fn validate(&self, html: &str) -> Result<Validation, HtmlCompareError> {
+        self.check_size(html.len())?;
+        self.compare(html)
+    }
+
+    /// Compare `html`, decoded as UTF-8 (see
+    /// [`HtmlComparer::verify_bytes`]), against the template
+    pub fn validate_bytes(&self, html: &[u8]) -> Result<Validation, HtmlCompareError> {
+        self.check_size(html.len())?;
+        if let Ok(html) = std::str::from_utf8(html) {
+            return self.compare(html);
+        }
+        let mut buffer = self.take_buffer();
+        let result = self
+            .decode_into(html, &mut buffer)
+            .and_then(|()| self.compare(&buffer));
+        self.return_buffer(buffer);
+        result
+    }
+
+    fn compare(&self, html: &str) -> Result<Validation, HtmlCompareError> {
+        let (differences, truncated) = panic::catch_unwind(AssertUnwindSafe(|| {
+            self.comparer
+                .differences_up_to(&self.template, html, self.limits.max_differences)
+        }))
+        .map_err(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "comparison panicked".to_string());
+            HtmlCompareError::Internal(message)
+        })?;
+        Ok(Validation {
+            differences,
+            truncated,
+        })
+    }
+
+    fn check_size(&self, size: usize) -> Result<(), HtmlCompareError> {
+        match size > self.limits.max_input_bytes {
+            true => Err(HtmlCompareError::InputTooLarge {
+                size,
+                limit: self.limits.max_input_bytes,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// Decode `html` into the cleared `buffer` by the options' policy
+    fn decode_into(&self, html: &[u8], buffer: &mut String) -> Result<(), HtmlCompareError> {
+        self.comparer
+            .options
+            .invalid_utf8
+            .decode_into(html, "actual", buffer)
+    }
+
+    fn take_buffer(&self) -> String {
+        self.buffers
+            .lock()
+            .ok()
+            .and_then(|mut buffers| buffers.pop())
+            .unwrap_or_default()
+    }
+
+    /// Keep `buffer` for reuse, unless the pool is full or the buffer
+    /// outgrew the input limit
+    fn return_buffer(&self, mut buffer: String) {
+        if buffer.capacity() > self.limits.max_input_bytes.saturating_mul(3) {
+            return;
+        }
+        buffer.clear();
+        if let Ok(mut buffers) = self.buffers.lock() {
+            if buffers.len() < self.limits.pooled_buffers {
+                buffers.push(buffer);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InvalidUtf8;
+
+    const TEMPLATE: &str = "<article><h1>Title</h1><p>Body</p></article>";
+
+    fn validator() -> HtmlValidator {
+        HtmlValidator::new(TEMPLATE, HtmlCompareOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<HtmlValidator>();
+
+        let validator = validator();
+        assert!(validator
+            .validate("<article><h1>Title</h1> <p>Body</p></article>")
+            .unwrap()
+            .is_valid());
+        let validation = validator
+            .validate("<article><h1>Other</h1><p class='x'>Body</p></article>")
+            .unwrap();
+        assert_eq!(validation.differences().len(), 2);
+        assert!(!validation.is_truncated());
+
+        let strict = validator.with_limits(ValidatorLimits {
+            max_input_bytes: 64,
+            max_differences: 1,
+            ..Default::default()
+        });
+        let validation = strict
+            .validate("<article><h1>Other</h1><p class='x'>Body</p></article>")
+            .unwrap();
+        assert_eq!(
+            (validation.differences().len(), validation.is_truncated()),
+            (1, true)
+        );
+        assert!(matches!(
+            strict.validate(&"<p>".repeat(30)),
+            Err(HtmlCompareError::InputTooLarge {
+                size: 90,
+                limit: 64
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_bytes_reuses_buffers() {
+        let validator = validator();
+        let submission = b"<article><h1>Title</h1><p>Body\xff</p></article>";
+        for _ in 0..3 {
+            let validation = validator.validate_bytes(submission).unwrap();
+            assert_eq!(validation.differences().len(), 1);
+        }
+        assert_eq!(validator.buffers.lock().unwrap().len(), 1);
+
+        let rejecting = HtmlValidator::new(
+            TEMPLATE,
+            HtmlCompareOptions {
+                invalid_utf8: InvalidUtf8::Error,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(matches!(
+            rejecting.validate_bytes(submission),
+            Err(HtmlCompareError::InvalidUtf8 { offset: 30, .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_deep_submission() {
+        // Far deeper than a recursive walk fits in a test thread's stack
+        let depth = 10_000;
+        let nested = |text: &str| {
+            format!(
+                "<article>{}{}{}</article>",
+                "<span>".repeat(depth),
+                text,
+                "</span>".repeat(depth)
+            )
+        };
+        let validator = HtmlValidator::new(nested("Body"), HtmlCompareOptions::default())
+            .unwrap()
+            .with_limits(ValidatorLimits {
+                max_input_bytes: nested("Other").len(),
+                ..Default::default()
+            });
+        assert!(validator.validate(&nested("Body")).unwrap().is_valid());
+        let validation = validator.validate(&nested("Other")).unwrap();
+        match validation.differences() {
+            [Difference::TextMismatch { path, actual, .. }] => {
+                assert_eq!(path.matches("/span").count(), depth);
+                assert_eq!(actual, "Other");
+            }
+            other => panic!("expected one text mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let err = HtmlValidator::new(
+            TEMPLATE,
+            HtmlCompareOptions {
+                root_selector: Some("[".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, HtmlCompareError::InvalidSelector(_)));
+    }
+}
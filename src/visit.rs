@@ -0,0 +1,245 @@
+//! Traversal of a document as the comparer sees it.
+//!
+//! [`walk`] parses a document and reports its nodes to an [`HtmlVisitor`]
+//! after the same normalization the comparison applies: ignored nodes are
+//! pruned, text is whitespace-normalized, attributes are filtered and
+//! normalized and children come in comparison order. Custom checks such as
+//! counting, extraction or linting can build on it instead of reimplementing
+//! the options against raw scraper.
+
+use std::borrow::Cow;
+
+use scraper::{ElementRef, Node};
+
+use crate::diff::child_paths;
+use crate::{HtmlCompareOptions, HtmlComparer, ShadowDomMode};
+
+/// An element as compared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisitedElement<'a> {
+    /// Path of the element, e.g. `/html/body/ul/li[2]`
+    pub path: &'a str,
+    /// Tag name, after `equivalent_tags` and similar options are applied
+    pub name: &'a str,
+    /// Compared attributes with their normalized values, empty when
+    /// attributes are ignored
+    pub attributes: Vec<(&'a str, Cow<'a, str>)>,
+}
+
+impl VisitedElement<'_> {
+    /// The normalized value of attribute `name`
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == name)
+            .map(|(_, value)| value.as_ref())
+    }
+}
+
+/// Callbacks for [`walk`]; every method does nothing by default
+pub trait HtmlVisitor {
+    /// Called before the children of `element`
+    fn enter_element(&mut self, _element: &VisitedElement) {}
+
+    /// Called after the children of `element`
+    fn leave_element(&mut self, _element: &VisitedElement) {}
+
+    /// Called with normalized text content
+    fn text(&mut self, _path: &str, _text: &str) {}
+
+    /// Called with trimmed comment content, unless comments are ignored
+    fn comment(&mut self, _path: &str, _text: &str) {}
+}
+
+impl<V: HtmlVisitor + ?Sized> HtmlVisitor for &mut V {
+    fn enter_element(&mut self, element: &VisitedElement) {
+        (**self).enter_element(element)
+    }
+
+    fn leave_element(&mut self, element: &VisitedElement) {
+        (**self).leave_element(element)
+    }
+
+    fn text(&mut self, path: &str, text: &str) {
+        (**self).text(path, text)
+    }
+
+    fn comment(&mut self, path: &str, text: &str) {
+        (**self).comment(path, text)
+    }
+}
+
+/// Parse `html` and report its nodes to `visitor` in document order, as
+/// normalized by `options`
+///
+/// # Examples
+/// ```ignore
+/// use html_compare_rs::visit::{walk, HtmlVisitor, VisitedElement};
+///
+/// struct Links(usize);
+/// impl HtmlVisitor for Links {
+///     fn enter_element(&mut self, element: &VisitedElement) {
+///         self.0 += (element.name == "a") as usize;
+///     }
+/// }
+///
+/// let mut links = Links(0);
+/// walk("<p><a href='/'>Home</a></p>", &Default::default(), &mut links);
+/// assert_eq!(links.0, 1);
+/// ```
+pub fn walk(html: &str, options: &HtmlCompareOptions, mut visitor: impl HtmlVisitor) {
+    let comparer = HtmlComparer::with_options(options.clone());
+    let document = comparer.parse(html);
+    let root = document.root_element();
+    let top_heading = comparer.top_heading_level(*root);
+    walk_element(&comparer, root, "/html", top_heading, &mut visitor);
+}
+
+fn walk_element(
+    comparer: &HtmlComparer,
+    element: ElementRef,
+    path: &str,
+    top_heading: usize,
+    visitor: &mut impl HtmlVisitor,
+) {
+    let options = &comparer.options;
+    let visited = VisitedElement {
+        path,
+        name: comparer.element_name(element, top_heading),
+        attributes: match options.ignore_attributes {
+            true => Vec::new(),
+            false => comparer.compared_attributes(element).into_vec(),
+        },
+    };
+    visitor.enter_element(&visited);
+
+    if !options.light_dom_first {
+        walk_shadow_root(comparer, element, path, top_heading, visitor);
+    }
+
+    if !(options.ignore_style_contents && element.value().name() == "style") {
+        let children = comparer.child_nodes(element);
+        for (child, child_path) in children.iter().zip(child_paths(path, &children)) {
+            match child.value() {
+                Node::Text(_) => visitor.text(&child_path, &comparer.node_text(*child)),
+                Node::Comment(comment) => visitor.comment(&child_path, comment.trim()),
+                _ => {
+                    if let Some(child) = ElementRef::wrap(*child) {
+                        walk_element(comparer, child, &child_path, top_heading, visitor);
+                    }
+                }
+            }
+        }
+    }
+
+    if options.light_dom_first {
+        walk_shadow_root(comparer, element, path, top_heading, visitor);
+    }
+
+    visitor.leave_element(&visited);
+}
+
+/// Walk the declarative shadow root of `element` in
+/// [`ShadowDomMode::Separate`]
+fn walk_shadow_root(
+    comparer: &HtmlComparer,
+    element: ElementRef,
+    path: &str,
+    top_heading: usize,
+    visitor: &mut impl HtmlVisitor,
+) {
+    if comparer.options.shadow_dom != ShadowDomMode::Separate {
+        return;
+    }
+    if let Some(shadow_root) = comparer.shadow_root(element) {
+        walk_element(
+            comparer,
+            shadow_root,
+            &format!("{}/#shadow-root", path),
+            top_heading,
+            visitor,
+        );
+    }
+}
+
+impl HtmlComparer {
+    /// Report the nodes of `html` to `visitor` as normalized by this
+    /// comparer's options (see [`walk`])
+    pub fn walk(&self, html: &str, visitor: impl HtmlVisitor) {
+        walk(html, &self.options, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl HtmlVisitor for Recorder {
+        fn enter_element(&mut self, element: &VisitedElement) {
+            self.0
+                .push(format!("{} {:?}", element.path, element.attributes));
+        }
+
+        fn leave_element(&mut self, element: &VisitedElement) {
+            self.0.push(format!("/{}", element.name));
+        }
+
+        fn text(&mut self, path: &str, text: &str) {
+            self.0.push(format!("{} {:?}", path, text));
+        }
+
+        fn comment(&mut self, path: &str, text: &str) {
+            self.0.push(format!("{} <!-- {} -->", path, text));
+        }
+    }
+
+    #[test]
+    fn test_walk_applies_normalization() {
+        let html = r#"<ul class="b  a">
+            <li data-id="1"> One </li>
+            <!-- gone -->
+            <li>Two</li>
+        </ul>"#;
+        let options = HtmlCompareOptions {
+            ignored_attributes: ["data-id".to_string()].into(),
+            tokenized_attributes: crate::tokens::html_token_lists(),
+            ..Default::default()
+        };
+        let mut recorder = Recorder::default();
+        walk(html, &options, &mut recorder);
+
+        let body = recorder.0.iter().position(|line| *line == "/head").unwrap();
+        assert_eq!(
+            recorder.0[body + 1..],
+            [
+                "/html/body []",
+                "/html/body/ul [(\"class\", \"a b\")]",
+                "/html/body/ul/li[1] []",
+                "/html/body/ul/li[1]/text() \"One\"",
+                "/li",
+                "/html/body/ul/li[2] []",
+                "/html/body/ul/li[2]/text() \"Two\"",
+                "/li",
+                "/ul",
+                "/body",
+                "/html",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_with_comparer_options() {
+        let comparer = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_comments: false,
+            ..Default::default()
+        });
+        let mut recorder = Recorder::default();
+        comparer.walk("<p>Hi<!-- note --></p>", &mut recorder);
+        assert!(recorder
+            .0
+            .contains(&"/html/body/p/comment() <!-- note -->".to_string()));
+    }
+}
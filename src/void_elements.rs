@@ -0,0 +1,230 @@
+//! The trailing slash of void elements.
+//!
+//! `<br>`, `<br/>` and `<br />` parse to the same element: the slash leaves
+//! no trace in the tree, so they compare equal. Pipelines that keep their
+//! output XHTML-compatible want one spelling throughout, and with
+//! [`HtmlCompareOptions::ignore_void_trailing_slash`](crate::HtmlCompareOptions::ignore_void_trailing_slash)
+//! off the start tags of void elements are read from the sources of both
+//! documents and compared by their slash, in document order per tag name.
+//! A difference fails with [`HtmlCompareError::VoidSyntaxMismatch`] at the
+//! expected element, once the trees themselves compare equal.
+//!
+//! [`HtmlCompareError::VoidSyntaxMismatch`]: crate::HtmlCompareError::VoidSyntaxMismatch
+
+use std::collections::HashMap;
+
+use scraper::Html;
+
+use crate::format::VOID_ELEMENTS;
+use crate::{HtmlCompareError, HtmlComparer};
+
+/// Elements whose content the tokenizer reads as text up to their end tag
+const TEXT_ELEMENTS: &[&str] = &[
+    "script", "style", "xmp", "iframe", "noembed", "noframes", "noscript", "textarea", "title",
+];
+
+/// The void elements' start tags in `html`, in order, as their lowercase
+/// name and whether they end in `/>`
+pub(crate) fn void_tags(html: &str) -> Vec<(String, bool)> {
+    let lowercase = html.to_ascii_lowercase();
+    let source = lowercase.as_bytes();
+    let mut tags = Vec::new();
+    let mut at = 0;
+    while let Some(offset) = lowercase[at..].find('<') {
+        at += offset + 1;
+        let rest = &lowercase[at..];
+        if rest.starts_with("!--") {
+            at += rest.find("-->").map_or(rest.len(), |end| end + 3);
+            continue;
+        }
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        let name_len = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+            .unwrap_or(rest.len());
+        let name = &rest[..name_len];
+        at += name_len;
+        let self_closing = skip_attributes(source, &mut at);
+        if VOID_ELEMENTS.contains(&name) {
+            tags.push((name.to_string(), self_closing));
+        } else if TEXT_ELEMENTS.contains(&name) {
+            let end_tag = format!("</{}", name);
+            at += lowercase[at..]
+                .find(&end_tag)
+                .unwrap_or(lowercase.len() - at);
+        }
+    }
+    tags
+}
+
+/// Move `at` past the attributes and the end of a start tag, returning
+/// whether the tag ends in `/>`
+fn skip_attributes(source: &[u8], at: &mut usize) -> bool {
+    // A slash only closes the tag right before `>`; elsewhere the tokenizer
+    // ignores it, and in an unquoted value it belongs to the value
+    let mut slash = false;
+    while let Some(&byte) = source.get(*at) {
+        *at += 1;
+        match byte {
+            b'>' => return slash,
+            b'/' => slash = true,
+            b'"' | b'\'' => {
+                let quote = byte;
+                while source.get(*at).is_some_and(|&byte| byte != quote) {
+                    *at += 1;
+                }
+                // Past the closing quote, if the source has one
+                *at = (*at + 1).min(source.len());
+                slash = false;
+            }
+            b'=' => {
+                while source.get(*at).is_some_and(u8::is_ascii_whitespace) {
+                    *at += 1;
+                }
+                if !matches!(source.get(*at), Some(b'"' | b'\'')) {
+                    while source
+                        .get(*at)
+                        .is_some_and(|&byte| !byte.is_ascii_whitespace() && byte != b'>')
+                    {
+                        *at += 1;
+                    }
+                }
+                slash = false;
+            }
+            _ => slash = false,
+        }
+    }
+    false
+}
+
+/// How a void tag is written, e.g. `<br/>`
+fn spelling(name: &str, self_closing: bool) -> String {
+    match self_closing {
+        true => format!("<{}/>", name),
+        false => format!("<{}>", name),
+    }
+}
+
+impl HtmlComparer {
+    /// Fail with the first void element written with a trailing slash in
+    /// one source and without in the other, unless the options ignore it
+    pub(crate) fn check_void_syntax(
+        &self,
+        expected: &str,
+        actual: &str,
+        expected_doc: &Html,
+    ) -> Result<(), HtmlCompareError> {
+        if self.options.ignore_void_trailing_slash {
+            return Ok(());
+        }
+        let expected_tags = void_tags(expected);
+        let actual_tags = void_tags(actual);
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (name, self_closing) in &expected_tags {
+            let occurrence = seen.entry(name).or_default();
+            let index = *occurrence;
+            *occurrence += 1;
+            let Some((_, actual_self_closing)) = actual_tags
+                .iter()
+                .filter(|(other, _)| other == name)
+                .nth(index)
+            else {
+                continue;
+            };
+            if self_closing != actual_self_closing {
+                let path = expected_doc
+                    .tree
+                    .root()
+                    .descendants()
+                    .filter(|node| {
+                        node.value()
+                            .as_element()
+                            .is_some_and(|element| element.name() == name)
+                    })
+                    .nth(index)
+                    .map_or_else(|| "/html".to_string(), |node| self.node_path(node));
+                return Err(HtmlCompareError::VoidSyntaxMismatch {
+                    path,
+                    expected: spelling(name, *self_closing),
+                    actual: spelling(name, *actual_self_closing),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlCompareOptions;
+
+    #[test]
+    fn test_void_tags() {
+        let tags = |html| {
+            void_tags(html)
+                .into_iter()
+                .map(|(name, self_closing)| spelling(&name, self_closing))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            tags(r#"<P>a<BR/>b<br >c<img src="a/>b" alt=x/><hr / ><input a/>"#),
+            ["<br/>", "<br>", "<img>", "<hr>", "<input/>"]
+        );
+        // Truncated markup ends the scan
+        for truncated in [
+            r#"<br/><p title="abc"#,
+            "<br/><p title='abc",
+            "<br/><img alt=",
+            "<br/><img",
+            "<br/><script>x",
+            "<br/><!-- x",
+            "<br/><",
+        ] {
+            assert_eq!(tags(truncated).first().unwrap(), "<br/>", "{}", truncated);
+        }
+        assert_eq!(
+            tags("<!-- <br/> --><script>'<br/>'</script><textarea><br></textarea><wbr />"),
+            ["<wbr/>"]
+        );
+    }
+
+    #[test]
+    fn test_ignore_void_trailing_slash() {
+        let xhtml = r#"<p>One<br/>two</p><img src="a.png" alt="A" /><p>Three<br/>four</p>"#;
+        let html = r#"<p>One<br/>two</p><img src="a.png" alt="A" /><p>Three<br>four</p>"#;
+        HtmlComparer::new().verify(xhtml, html).unwrap();
+
+        let strict = |exhaustive| {
+            HtmlComparer::with_options(HtmlCompareOptions {
+                ignore_void_trailing_slash: false,
+                exhaustive,
+                ..Default::default()
+            })
+        };
+        strict(false).verify(xhtml, xhtml).unwrap();
+        for exhaustive in [false, true] {
+            match strict(exhaustive).verify(xhtml, html) {
+                Err(HtmlCompareError::VoidSyntaxMismatch {
+                    path,
+                    expected,
+                    actual,
+                }) => {
+                    assert_eq!(path, "/html/body/p[2]/br");
+                    assert_eq!((expected.as_str(), actual.as_str()), ("<br/>", "<br>"));
+                }
+                other => panic!("expected a void syntax mismatch, got {:?}", other),
+            }
+        }
+        for truncated in [r#"<p title="abc"#, "<p title='a<br/>", "<br/><img src=\"a"] {
+            strict(false).verify(truncated, truncated).unwrap();
+            assert!(strict(true).diff(truncated, truncated).is_empty());
+        }
+        // Differences in the trees are reported first
+        assert!(matches!(
+            strict(false).verify(xhtml, &html.replace("four", "five")),
+            Err(HtmlCompareError::TextMismatch { .. })
+        ));
+    }
+}
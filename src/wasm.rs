@@ -0,0 +1,50 @@
+//! Comparison of components rendered by Rust/wasm frameworks.
+//!
+//! Yew, Leptos, Sycamore and Dioxus render components with hydration
+//! bookkeeping: comment anchors such as `<!--#-->` or `<!--hk=0-0-1-->`
+//! around dynamic content, and `data-hk` or `data-node-hydration` ids on
+//! elements. [`presets::wasm`](crate::presets::wasm) leaves those out and
+//! parses the rendered output as a fragment, and [`compare_rendered`] applies
+//! it to a component's output, whether rendered to a string on the server or
+//! serialized from a `web_sys::Element` with `outer_html()` in a browser test.
+
+use crate::{presets, HtmlCompareError, HtmlComparer};
+
+/// Attribute families set by wasm frameworks for hydration
+pub const HYDRATION_ATTRIBUTE_PREFIXES: &[&str] = &["data-hk", "data-node-hydration"];
+
+/// Compare the rendered output of a component with the expected markup,
+/// using [`presets::wasm`]
+///
+/// # Examples
+/// ```ignore
+/// let rendered = leptos::ssr::render_to_string(|| view! { <Counter initial=1/> });
+/// compare_rendered("<button>1</button>", &rendered)?;
+/// ```
+pub fn compare_rendered(expected: &str, rendered: &str) -> Result<(), HtmlCompareError> {
+    HtmlComparer::with_options(presets::wasm()).verify(expected, rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leptos_hydration_markers() {
+        let rendered = r#"<div data-hk="0-0-0-1"><!--hk=0-0-0-2o|leptos-for-start--><span data-hk="0-0-0-3">1</span><!--#--><!--/--><!--hk=0-0-0-2c|leptos-for-end--></div>"#;
+        compare_rendered("<div><span>1</span></div>", rendered).unwrap();
+        assert!(compare_rendered("<div><span>2</span></div>", rendered).is_err());
+    }
+
+    #[test]
+    fn test_rendered_fragments() {
+        let rendered =
+            r#"<li data-node-hydration="0">One</li><li data-node-hydration="1">Two</li>"#;
+        compare_rendered("<li>One</li>\n<li>Two</li>", rendered).unwrap();
+        crate::assert_html_eq!(
+            "<td>cell</td>",
+            "<!--<[app::Cell]>--><td>cell</td><!--</[app::Cell]>-->",
+            preset = "wasm"
+        );
+    }
+}
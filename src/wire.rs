@@ -0,0 +1,81 @@
+//! Comparison of HTML-over-the-wire responses.
+//!
+//! htmx and Turbo responses carry attributes that drive client-side behavior,
+//! such as `hx-target` or `data-turbo-frame`, and often differ between
+//! renders in ways that don't matter to the markup being tested.
+//! [`HtmlCompareOptions::ignored_attribute_prefixes`](crate::HtmlCompareOptions)
+//! ignores whole attribute families, and with
+//! [`HtmlCompareOptions::turbo_streams`](crate::HtmlCompareOptions) a
+//! `<turbo-stream>` is compared by what it does: its action, its target and
+//! the content of its `<template>`.
+
+use scraper::ElementRef;
+
+use crate::{ChildNodes, HtmlComparer};
+
+/// The attributes that determine what a `<turbo-stream>` does
+pub(crate) const TURBO_STREAM_ATTRIBUTES: &[&str] = &["action", "target", "targets"];
+
+/// Attribute families set by htmx
+pub const HTMX_ATTRIBUTE_PREFIXES: &[&str] = &["hx-", "data-hx-"];
+
+/// Attribute families set by Turbo
+pub const TURBO_ATTRIBUTE_PREFIXES: &[&str] = &["data-turbo-"];
+
+/// Whether `element` is a `<turbo-stream>`
+pub(crate) fn is_turbo_stream(element: ElementRef) -> bool {
+    element.value().name() == "turbo-stream"
+}
+
+impl HtmlComparer {
+    /// The children of a `<turbo-stream>` as compared: the contents of its
+    /// templates
+    pub(crate) fn turbo_stream_children<'a>(&self, stream: ElementRef<'a>) -> ChildNodes<'a> {
+        stream
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|child| child.value().name() == "template")
+            .flat_map(|template| self.child_nodes(template))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{presets, HtmlComparer};
+
+    #[test]
+    fn test_htmx_attribute_noise() {
+        let comparer = HtmlComparer::with_options(presets::htmx());
+        comparer
+            .verify(
+                r##"<li hx-get="/users/1?v=1" hx-swap="outerHTML"><b>Alice</b></li>"##,
+                r##"<li hx-get="/users/1?v=2" data-hx-target="#user-1"><b>Alice</b></li>"##,
+            )
+            .unwrap();
+        assert!(comparer
+            .verify(
+                r#"<button hx-post="/save" class="primary">Save</button>"#,
+                r#"<button hx-post="/save" class="secondary">Save</button>"#,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_turbo_streams() {
+        let comparer = HtmlComparer::with_options(presets::turbo());
+        let expected = r#"<turbo-stream action="append" target="messages">
+            <template><div id="message_1" data-turbo-permanent>Hello</div></template>
+        </turbo-stream>"#;
+        let actual = r#"<turbo-stream target="messages" action="append" request-id="abc123"><template>
+            <div id="message_1">Hello</div>
+        </template></turbo-stream>"#;
+        comparer.verify(expected, actual).unwrap();
+        assert!(HtmlComparer::new().verify(expected, actual).is_err());
+
+        let replace = actual.replace("append", "replace");
+        assert!(comparer.verify(expected, &replace).is_err());
+        let other_content = actual.replace("Hello", "Goodbye");
+        assert!(comparer.verify(expected, &other_content).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Formatting inside start tags.
+//!
+//! Formatters such as prettier and djlint wrap long start tags, putting
+//! each attribute on its own line and breaking long values. Line breaks
+//! between attributes never reach the parsed document, but those inside a
+//! value do:
+//!
+//! ```html
+//! <div
+//!   class="card"
+//!   style="
+//!     color: red;
+//!     margin: 0;
+//!   "
+//! ></div>
+//! ```
+//!
+//! With [`HtmlCompareOptions::ignore_attribute_wrapping`](crate::HtmlCompareOptions::ignore_attribute_wrapping),
+//! on by default, every line break in the value of an attribute where
+//! whitespace carries no meaning, together with the indentation around it,
+//! stands for a single space, and those at the start or end of the value are
+//! dropped, so the `style` above compares equal to
+//! `style="color: red; margin: 0;"` whatever the whitespace option. Values
+//! without line breaks are left as they are, and so are those of attributes
+//! such as `value`, `title` or `data-*`, where a line break is content.
+
+use std::borrow::Cow;
+
+/// Attributes whose values are lists or declarations, where a line break
+/// means no more than a space
+const WRAPPABLE_ATTRIBUTES: &[&str] = &[
+    "class", "style", "srcset", "sizes", "rel", "accept", "sandbox", "headers", "ping",
+];
+
+/// Whether a formatter wrapping the value of attribute `name` leaves its
+/// meaning unchanged
+pub(crate) fn applies(name: &str) -> bool {
+    WRAPPABLE_ATTRIBUTES.contains(&name)
+}
+
+/// `value` with its line breaks and the whitespace around them collapsed
+/// into single spaces, and removed at its ends
+pub(crate) fn unwrap_value(value: Cow<'_, str>) -> Cow<'_, str> {
+    if !value.contains(['\n', '\r']) {
+        return value;
+    }
+    let mut unwrapped = String::with_capacity(value.len());
+    for (index, line) in value.lines().map(str::trim).enumerate() {
+        if index > 0 && !unwrapped.is_empty() && !line.is_empty() {
+            unwrapped.push(' ');
+        }
+        unwrapped.push_str(line);
+    }
+    Cow::Owned(unwrapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HtmlCompareOptions, HtmlComparer};
+
+    #[test]
+    fn test_unwrap_value() {
+        let unwrap = |value: &str| unwrap_value(Cow::Borrowed(value)).into_owned();
+        assert_eq!(
+            unwrap("\n    color: red;\n    margin: 0 auto;\n  "),
+            "color: red; margin: 0 auto;"
+        );
+        assert_eq!(unwrap("Search the\r\n           docs"), "Search the docs");
+        assert_eq!(unwrap("a\n\n  b"), "a b");
+        assert_eq!(unwrap("  spaced  value "), "  spaced  value ");
+    }
+
+    #[test]
+    fn test_formatter_output() {
+        let compact = r#"<div class="card card--featured" data-controller="card" style="color: red; margin: 0 auto;"><a href="/x" title="Go">Link</a><input type="text" name="q" placeholder="Search the docs" required></div>"#;
+        // prettier wraps attributes and hugs the closing brackets so that no
+        // whitespace text is added
+        let prettier = r#"<div
+  class="card card--featured"
+  data-controller="card"
+  style="
+    color: red;
+    margin: 0 auto;
+  "
+><a
+    href="/x"
+    title="Go"
+    >Link</a
+  ><input
+    type="text"
+    name="q"
+    placeholder="Search the docs"
+    required
+/></div>"#;
+        // djlint aligns attributes under the first one and breaks long values
+        let djlint = r#"<div class="card card--featured"
+     data-controller="card"
+     style="color: red;
+            margin: 0 auto;"><a href="/x"
+   title="Go">Link</a><input type="text"
+       name="q"
+       placeholder="Search the docs"
+       required></div>"#;
+
+        let strict_whitespace = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_whitespace: false,
+            ..Default::default()
+        });
+        strict_whitespace.verify(compact, prettier).unwrap();
+        strict_whitespace.verify(compact, djlint).unwrap();
+
+        let exact = HtmlComparer::with_options(HtmlCompareOptions {
+            ignore_whitespace: false,
+            ignore_attribute_wrapping: false,
+            ..Default::default()
+        });
+        assert!(!exact.is_equal(compact, djlint).unwrap());
+    }
+
+    #[test]
+    fn test_wrapped_content_attributes() {
+        let strict = HtmlComparer::with_options(crate::presets::strict());
+        let default = HtmlComparer::new();
+        for comparer in [&strict, &default] {
+            assert!(!comparer
+                .is_equal("<input value='a b'>", "<input value='a\nb'>")
+                .unwrap());
+            assert!(!comparer
+                .is_equal("<div data-x='a b'></div>", "<div data-x='a\n   b'></div>")
+                .unwrap());
+            assert!(!comparer
+                .is_equal(
+                    "<input placeholder='Search the docs'>",
+                    "<input placeholder='Search the\n    docs'>"
+                )
+                .unwrap());
+        }
+        default
+            .verify("<p class='a b'></p>", "<p class='a\n   b'></p>")
+            .unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+//! Allocation benchmark for the comparison of large documents.
+//!
+//! CI jobs run thousands of comparisons, so normalization borrows from the
+//! parsed tree and keeps the children and attributes of an element inline
+//! wherever it can. These tests count the allocations a comparison makes
+//! beyond parsing the two documents, and fail if the per-element cost grows.
+//! They run in a binary of their own so that the counting allocator doesn't
+//! stand in for the one of the library's tests.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use html_compare_rs::{presets, HtmlCompareOptions, HtmlComparer};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// The allocations made by `run`
+fn count_allocations(run: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    run();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+/// A page with `rows` table rows of a few elements, attributes and text runs
+fn large_page(rows: usize) -> String {
+    let mut html = String::from("<table class='data  grid'>");
+    for row in 0..rows {
+        html.push_str(&format!(
+            "<tr id='row-{row}' class='row'>\n  <td>Item   {row}</td>\n  <td><a href='/items/{row}' title='Item {row}'>details</a></td>\n</tr>"
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Allocations per element made by comparing a large page with itself,
+/// leaving out those of parsing it
+fn allocations_per_element(options: HtmlCompareOptions) -> f64 {
+    let html = large_page(1000);
+    let document = options.parser.parse_document(&html);
+    let elements = document
+        .tree
+        .nodes()
+        .filter(|node| node.value().is_element())
+        .count();
+    let parsing = count_allocations(|| {
+        options.parser.parse_document(&html);
+    });
+    let comparer = HtmlComparer::with_options(options);
+    let comparison = count_allocations(|| comparer.verify(&html, &html).unwrap());
+    comparison.saturating_sub(2 * parsing) as f64 / elements as f64
+}
+
+#[test]
+fn test_default_comparison_allocations() {
+    let per_element = allocations_per_element(HtmlCompareOptions::default());
+    assert!(
+        per_element <= 0.25,
+        "{:.2} allocations per element",
+        per_element
+    );
+}
+
+#[test]
+fn test_minified_comparison_allocations() {
+    // Collapsing the whitespace of a text run allocates its new text
+    let per_element = allocations_per_element(presets::minified());
+    assert!(
+        per_element <= 1.0,
+        "{:.2} allocations per element",
+        per_element
+    );
+}